@@ -0,0 +1,94 @@
+// Runs the same scripts on the tree-walking evaluator and on the vm:
+// `cargo bench --bench engines`. Parsing and compiling are done once, only
+// the execution is measured.
+use criterion::{criterion_group, criterion_main, Criterion};
+use monkey::compiler::Compiler;
+use monkey::eval::evaluator::Evaluator;
+use monkey::eval::ObjectWrapper;
+use monkey::lexer::lexer::Lexer;
+use monkey::parser::program::Program;
+use monkey::parser::resolve::resolve;
+use monkey::parser::Parser;
+use monkey::vm::Vm;
+
+const WORKLOADS: [(&str, &str, i64); 5] = [
+    (
+        "fibonacci",
+        "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } };
+        fib(20)",
+        6765,
+    ),
+    (
+        "ackermann",
+        "let ack = fn(m, n) {
+            if (m == 0) {
+                n + 1
+            } else {
+                if (n == 0) { ack(m - 1, 1) } else { ack(m - 1, ack(m, n - 1)) }
+            }
+        };
+        ack(2, 50)",
+        103,
+    ),
+    (
+        "string_building",
+        r#"let build = fn(n, s) { if (n == 0) { s } else { build(n - 1, s + "ab") } };
+        len(build(500, ""))"#,
+        1000,
+    ),
+    (
+        "hash_churn",
+        r#"let churn = fn(n, acc) {
+            if (n == 0) {
+                acc
+            } else {
+                let h = {"a": n, "b": n * 2, n: "n", true: [n]};
+                churn(n - 1, acc + h["a"] + h["b"] + h[true][0])
+            }
+        };
+        churn(500, 0)"#,
+        501000,
+    ),
+    (
+        "array_walk",
+        "let build = fn(n, a) { if (n == 0) { a } else { build(n - 1, push(a, n)) } };
+        let sum = fn(a, i, acc) { if (i == len(a)) { acc } else { sum(a, i + 1, acc + a[i]) } };
+        sum(build(500, []), 0, 0)",
+        125250,
+    ),
+];
+
+fn parse(input: &str) -> Program {
+    let mut program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+    resolve(&mut program);
+    program
+}
+
+fn run_evaluator(program: &Program) -> ObjectWrapper {
+    Evaluator::new(&program.statements).eval().unwrap()
+}
+
+fn run_vm(compiler: &Compiler) -> ObjectWrapper {
+    let mut vm = Vm::new(compiler.bytecode());
+    vm.run().unwrap();
+    vm.last_popped_stack_elem().clone()
+}
+
+fn engines(c: &mut Criterion) {
+    for (name, input, expect) in WORKLOADS {
+        let program = parse(input);
+        let mut compiler = Compiler::new();
+        compiler.compile(&program).unwrap();
+        // both engines have to agree before their speed is compared
+        assert_eq!(run_evaluator(&program), ObjectWrapper::Integer(expect));
+        assert_eq!(run_vm(&compiler), ObjectWrapper::Integer(expect));
+
+        let mut group = c.benchmark_group(name);
+        group.bench_function("evaluator", |b| b.iter(|| run_evaluator(&program)));
+        group.bench_function("vm", |b| b.iter(|| run_vm(&compiler)));
+        group.finish();
+    }
+}
+
+criterion_group!(benches, engines);
+criterion_main!(benches);
@@ -0,0 +1,77 @@
+// Lexes, with and without borrowing names and strings, and parses generated
+// programs: `cargo bench --bench parse`. A large one, one nesting
+// expressions as deep as the parser allows and one with long lists.
+// Evaluating the large one is measured too, a tree laid out differently
+// changes both.
+use criterion::{criterion_group, criterion_main, BenchmarkGroup, Criterion, Throughput};
+use monkey::eval::evaluator::Evaluator;
+use monkey::lexer::lexer::Lexer;
+use monkey::lexer::lexer::{tokenize, tokenize_source};
+use monkey::parser::resolve::resolve;
+use monkey::parser::{Parser, MAX_NESTING};
+
+// `functions` functions with nested arithmetic, conditions, calls and
+// literals, each called once
+fn large(functions: usize) -> String {
+    let mut source = String::new();
+    for i in 0..functions {
+        source.push_str(&format!(
+            "let f{i} = fn(a, b) {{
+                let c = (a + b) * (a - b) / 2 + -a;
+                if ((c > {i}) == !(a == b)) {{ [c, a, b][1] }} else {{ {{\"c\": c}}[\"c\"] }}
+            }};
+            let r{i} = f{i}({i}, {i} + 1) + len([1, 2, 3]);\n"
+        ));
+    }
+    source
+}
+
+// `statements` arrays of parentheses nested just below MAX_NESTING
+fn nested(statements: usize) -> String {
+    let levels = MAX_NESTING / 2 - 1;
+    let expression = format!("{}1{}", "[(".repeat(levels), ")]".repeat(levels));
+    format!("{};\n", expression).repeat(statements)
+}
+
+// an array, a hash and a call with `items` items each
+fn wide(items: usize) -> String {
+    let list = |f: &dyn Fn(usize) -> String| (0..items).map(f).collect::<Vec<_>>().join(", ");
+    format!(
+        "let a = [{}];\nlet h = {{{}}};\nlet c = f({});\n",
+        list(&|i| i.to_string()),
+        list(&|i| format!("\"k{}\": {}", i, i)),
+        list(&|i| format!("a[{}]", i)),
+    )
+}
+
+fn parse_source(group: &mut BenchmarkGroup<'_, criterion::measurement::WallTime>, source: &str) {
+    group.throughput(Throughput::Bytes(source.len() as u64));
+    group.bench_function("lex", |b| b.iter(|| tokenize(source)));
+    group.bench_function("lex_source", |b| b.iter(|| tokenize_source(source)));
+    group.bench_function("parse", |b| {
+        b.iter(|| Parser::new(Lexer::new(source)).parse_program().unwrap())
+    });
+}
+
+fn parse(c: &mut Criterion) {
+    let source = large(2000);
+    let mut group = c.benchmark_group("large");
+    parse_source(&mut group, &source);
+    let mut program = Parser::new(Lexer::new(&source)).parse_program().unwrap();
+    resolve(&mut program);
+    group.bench_function("evaluate", |b| {
+        b.iter(|| Evaluator::new(&program.statements).eval().unwrap())
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("nested");
+    parse_source(&mut group, &nested(200));
+    group.finish();
+
+    let mut group = c.benchmark_group("wide");
+    parse_source(&mut group, &wide(10_000));
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
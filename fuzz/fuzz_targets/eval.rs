@@ -0,0 +1,23 @@
+// Any source evaluates to a value or an error. The limits keep slow
+// scripts from timing out and the host is out of reach. Functions referring
+// to themselves leak their environment, run with `-- -detect_leaks=0`.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use monkey::eval::capabilities::Capabilities;
+use monkey::eval::evaluator::EvalOptions;
+use monkey::Interpreter;
+
+fuzz_target!(|data: &[u8]| {
+    let source = match std::str::from_utf8(data) {
+        Ok(source) => source,
+        Err(_) => return,
+    };
+    let mut interpreter = Interpreter::with_options(EvalOptions {
+        max_steps: Some(100_000),
+        max_memory: Some(1 << 24),
+        capabilities: Capabilities::NONE,
+        ..Default::default()
+    });
+    let _ = interpreter.eval(source);
+});
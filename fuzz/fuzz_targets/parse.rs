@@ -0,0 +1,16 @@
+// Any source parses to a program or an error, programs print.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use monkey::lexer::lexer::Lexer;
+use monkey::parser::Parser;
+
+fuzz_target!(|data: &[u8]| {
+    let source = match std::str::from_utf8(data) {
+        Ok(source) => source,
+        Err(_) => return,
+    };
+    if let Ok(program) = Parser::new(Lexer::new(source)).parse_program() {
+        let _ = program.to_string();
+    }
+});
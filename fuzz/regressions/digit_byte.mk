@@ -0,0 +1,2 @@
+// expect: no prefix parse function for Illegal
+²
@@ -0,0 +1,2 @@
+// expect: integer overflow
+9223372036854775807 + 1
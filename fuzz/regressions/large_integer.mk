@@ -0,0 +1,2 @@
+// expect: no prefix parse function for Illegal
+99999999999999999999
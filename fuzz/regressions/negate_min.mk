@@ -0,0 +1,3 @@
+// expect: integer overflow
+let min = -9223372036854775807 - 1;
+-min
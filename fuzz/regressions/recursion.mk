@@ -0,0 +1,3 @@
+// expect: stack overflow
+let f = fn(n) { f(n + 1) };
+f(0)
@@ -0,0 +1,37 @@
+/* The c api of the monkey interpreter, build the library with
+ * `cargo rustc --release --lib --crate-type cdylib --features capi` and
+ * link to libmonkey. */
+
+#ifndef MONKEY_H
+#define MONKEY_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct MonkeyInterpreter MonkeyInterpreter;
+typedef struct MonkeyValue MonkeyValue;
+
+/* a new interpreter, free it with monkey_free */
+MonkeyInterpreter *monkey_new(void);
+
+/* Evaluates source keeping the bindings of the previous calls. The value
+ * lives until the next call, it is NULL if the source does not parse or
+ * fails, see monkey_last_error. */
+const MonkeyValue *monkey_eval(MonkeyInterpreter *interpreter, const char *source);
+
+/* why the last monkey_eval returned NULL, NULL if it did not, it lives
+ * until the next call */
+const char *monkey_last_error(const MonkeyInterpreter *interpreter);
+
+/* the value as puts prints it, it lives as long as the value */
+const char *monkey_value_to_string(const MonkeyValue *value);
+
+/* frees an interpreter and what it returned */
+void monkey_free(MonkeyInterpreter *interpreter);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
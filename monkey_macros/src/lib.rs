@@ -0,0 +1,93 @@
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Error, FnArg, ItemFn, LitStr, Pat, ReturnType};
+
+// `#[monkey::builtin]` on a Rust function whose parameters convert from
+// objects with `TryFrom` and whose result is an `IntoObject`. The function
+// is kept as it is, next to it `register_<name>(&mut Interpreter)` makes it
+// a host function of the same name, or the one given with
+// `#[monkey::builtin(name = "other")]`.
+#[proc_macro_attribute]
+pub fn builtin(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let func = parse_macro_input!(item as ItemFn);
+    let mut name = func.sig.ident.to_string();
+    let names = syn::meta::parser(|meta| {
+        if meta.path.is_ident("name") {
+            name = meta.value()?.parse::<LitStr>()?.value();
+            Ok(())
+        } else {
+            Err(meta.error("expect `name = \"...\"`"))
+        }
+    });
+    parse_macro_input!(attr with names);
+    match expand(&func, &name) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand(func: &ItemFn, name: &str) -> syn::Result<proc_macro2::TokenStream> {
+    let sig = &func.sig;
+    if let Some(token) = &sig.asyncness {
+        return Err(Error::new(token.span(), "builtins cannot be async"));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(Error::new(
+            sig.generics.span(),
+            "builtins cannot be generic",
+        ));
+    }
+    let mut params = vec![];
+    let mut types = vec![];
+    for arg in &sig.inputs {
+        match arg {
+            FnArg::Typed(arg) if matches!(*arg.pat, Pat::Ident(_)) => {
+                params.push(format_ident!("arg{}", params.len()));
+                types.push(arg.ty.clone());
+            }
+            FnArg::Typed(arg) => {
+                return Err(Error::new(arg.pat.span(), "expect a parameter name"));
+            }
+            FnArg::Receiver(arg) => {
+                return Err(Error::new(arg.span(), "builtins cannot take `self`"));
+            }
+        }
+    }
+    if let ReturnType::Type(_, ty) = &sig.output {
+        if let syn::Type::ImplTrait(_) = **ty {
+            return Err(Error::new(ty.span(), "builtins cannot return `impl Trait`"));
+        }
+    }
+
+    let vis = &func.vis;
+    let ident = &sig.ident;
+    let register = format_ident!("register_{}", ident);
+    let arity = params.len();
+    let positions = (1..=arity).collect::<Vec<usize>>();
+    Ok(quote! {
+        #func
+
+        #vis fn #register(interpreter: &mut ::monkey::Interpreter) {
+            use ::monkey::eval::convert::IntoObject;
+            use ::std::convert::TryFrom;
+            interpreter.register_fn(#name, #arity, |args| {
+                let mut args = args.into_iter();
+                #(
+                    let #params = <#types as TryFrom<::monkey::Value>>::try_from(
+                        args.next().unwrap(),
+                    )
+                    .map_err(|e| {
+                        ::monkey::parser::ParseError::from(format!(
+                            "argument {} of '{}': {}",
+                            #positions,
+                            #name,
+                            e.message()
+                        ))
+                    })?;
+                )*
+                #ident(#(#params),*).into_object()
+            });
+        }
+    })
+}
@@ -0,0 +1,112 @@
+// The functions of include/monkey.h. Pointers given to them are null or
+// come from them and were not freed, strings are nul-terminated.
+#![allow(clippy::missing_safety_doc)]
+
+use crate::diagnostics;
+use crate::eval::output::format_value;
+use crate::eval::ObjectWrapper;
+use crate::Interpreter;
+use std::cell::OnceCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+#[cfg(test)]
+mod test;
+
+pub struct MonkeyInterpreter {
+    interpreter: Interpreter,
+    value: Option<MonkeyValue>,
+    error: Option<CString>,
+}
+
+pub struct MonkeyValue {
+    obj: ObjectWrapper,
+    string: OnceCell<CString>,
+}
+
+// c strings end at the first nul
+fn c_string(s: String) -> CString {
+    CString::new(s.replace('\0', "")).unwrap()
+}
+
+// a new interpreter, free it with `monkey_free`
+#[no_mangle]
+pub extern "C" fn monkey_new() -> *mut MonkeyInterpreter {
+    Box::into_raw(Box::new(MonkeyInterpreter {
+        interpreter: Interpreter::new(),
+        value: None,
+        error: None,
+    }))
+}
+
+// Evaluates `source` keeping the bindings of the previous calls. The value
+// lives until the next call, it is null if the source does not parse or
+// fails, see `monkey_last_error`.
+#[no_mangle]
+pub unsafe extern "C" fn monkey_eval(
+    interpreter: *mut MonkeyInterpreter,
+    source: *const c_char,
+) -> *const MonkeyValue {
+    let interpreter = match interpreter.as_mut() {
+        Some(interpreter) => interpreter,
+        None => return ptr::null(),
+    };
+    interpreter.value = None;
+    interpreter.error = None;
+    if source.is_null() {
+        interpreter.error = Some(c_string("source is null".to_string()));
+        return ptr::null();
+    }
+    let source = match CStr::from_ptr(source).to_str() {
+        Ok(source) => source,
+        Err(e) => {
+            interpreter.error = Some(c_string(format!("source is not utf-8: {}", e)));
+            return ptr::null();
+        }
+    };
+    let ret = interpreter.interpreter.eval(source);
+    let error = match ret {
+        Ok(ObjectWrapper::ErrorObject(e)) => diagnostics::render_message("error", &e, None),
+        Ok(obj) => {
+            let value = interpreter.value.insert(MonkeyValue {
+                obj,
+                string: OnceCell::new(),
+            });
+            return value;
+        }
+        Err(e) => e.render(source, None),
+    };
+    interpreter.error = Some(c_string(error));
+    ptr::null()
+}
+
+// why the last `monkey_eval` returned null, null if it did not, it lives
+// until the next call
+#[no_mangle]
+pub unsafe extern "C" fn monkey_last_error(interpreter: *const MonkeyInterpreter) -> *const c_char {
+    match interpreter.as_ref().and_then(|i| i.error.as_ref()) {
+        Some(error) => error.as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+// the value as `puts` prints it, it lives as long as the value
+#[no_mangle]
+pub unsafe extern "C" fn monkey_value_to_string(value: *const MonkeyValue) -> *const c_char {
+    match value.as_ref() {
+        Some(value) => value
+            .string
+            .get_or_init(|| c_string(format_value(&value.obj)))
+            .as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+// frees an interpreter and what it returned
+#[no_mangle]
+pub unsafe extern "C" fn monkey_free(interpreter: *mut MonkeyInterpreter) {
+    if !interpreter.is_null() {
+        drop(Box::from_raw(interpreter));
+    }
+}
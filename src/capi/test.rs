@@ -0,0 +1,120 @@
+use crate::capi::*;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+
+#[cfg(test)]
+fn to_str(s: *const c_char) -> Option<String> {
+    if s.is_null() {
+        None
+    } else {
+        Some(unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_string())
+    }
+}
+
+#[test]
+fn test_capi() {
+    let interpreter = monkey_new();
+    let cases = [
+        (
+            "let greet = fn(name) { \"hello \" + name };",
+            Some("null"),
+            None,
+        ),
+        ("greet(\"c\")", Some("hello c"), None),
+        ("[1, greet(\"go\")]", Some("[1, \"hello go\"]"), None),
+        ("let = 1;", None, Some("error: not a ident token")),
+        ("1 / 0", None, Some("error: division by zero")),
+    ];
+    for (input, value, error) in cases {
+        let source = CString::new(input).unwrap();
+        unsafe {
+            let ret = monkey_eval(interpreter, source.as_ptr());
+            assert_eq!(to_str(monkey_value_to_string(ret)).as_deref(), value);
+            let e = to_str(monkey_last_error(interpreter));
+            assert_eq!(
+                e.as_deref().map(|e| e.lines().next().unwrap()),
+                error,
+                "{}",
+                input
+            );
+        }
+    }
+    unsafe {
+        let invalid = b"\"\xff\"\0";
+        assert!(monkey_eval(interpreter, invalid.as_ptr() as *const c_char).is_null());
+        assert!(to_str(monkey_last_error(interpreter))
+            .unwrap()
+            .starts_with("source is not utf-8"));
+        assert!(monkey_eval(std::ptr::null_mut(), invalid.as_ptr() as *const c_char).is_null());
+        monkey_free(interpreter);
+        monkey_free(std::ptr::null_mut());
+    }
+}
+
+// the c type of a parameter or return type of the module
+fn c_type(rust: &str) -> String {
+    let (qualifier, pointee) = if let Some(pointee) = rust.strip_prefix("*mut ") {
+        ("", pointee)
+    } else if let Some(pointee) = rust.strip_prefix("*const ") {
+        ("const ", pointee)
+    } else {
+        panic!("no c type for {}", rust)
+    };
+    let pointee = if pointee == "c_char" { "char" } else { pointee };
+    format!("{}{} *", qualifier, pointee)
+}
+
+fn declaration(c_type: &str, name: &str) -> String {
+    if c_type.ends_with('*') {
+        format!("{}{}", c_type, name)
+    } else {
+        format!("{} {}", c_type, name)
+    }
+}
+
+// the header declares the functions of the module, and only them, with the
+// types they have
+#[test]
+fn test_header() {
+    let header = include_str!("../../include/monkey.h");
+    let source = include_str!("mod.rs");
+    let prototypes = source
+        .split("extern \"C\" fn ")
+        .skip(1)
+        .map(|rest| {
+            let (name, rest) = rest.split_at(rest.find('(').unwrap());
+            let (params, rest) = rest[1..].split_at(rest.find(')').unwrap() - 1);
+            let params = params
+                .split(',')
+                .map(str::trim)
+                .filter(|param| !param.is_empty())
+                .map(|param| {
+                    let (name, rust) = param.split_once(": ").unwrap();
+                    declaration(&c_type(rust), name)
+                })
+                .collect::<Vec<String>>();
+            let params = if params.is_empty() {
+                "void".to_string()
+            } else {
+                params.join(", ")
+            };
+            let ret = match rest[1..rest.find('{').unwrap()].trim().strip_prefix("-> ") {
+                Some(rust) => c_type(rust),
+                None => "void".to_string(),
+            };
+            format!("{}({});", declaration(&ret, name), params)
+        })
+        .collect::<Vec<String>>();
+    assert_eq!(prototypes.len(), 5);
+    for prototype in &prototypes {
+        assert!(
+            header.lines().any(|line| line == prototype),
+            "{} is not in the header",
+            prototype
+        );
+    }
+    assert_eq!(
+        header.lines().filter(|line| line.ends_with(");")).count(),
+        prototypes.len()
+    );
+}
@@ -0,0 +1,224 @@
+use crate::eval::{snapshot, ObjectWrapper};
+use std::fmt::Write;
+
+// Bytecode instructions: a one byte opcode followed by its big-endian
+// operands, the widths of which are given by the opcode's definition.
+pub type Instructions = Vec<u8>;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Opcode {
+    Constant,
+    Pop,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    True,
+    False,
+    Null,
+    Equal,
+    NotEqual,
+    GreaterThan,
+    LessThan,
+    Minus,
+    Bang,
+    JumpNotTruthy,
+    Jump,
+    GetGlobal,
+    SetGlobal,
+    GetLocal,
+    SetLocal,
+    Array,
+    Hash,
+    Index,
+    Call,
+    ReturnValue,
+    Return,
+    GetBuiltin,
+    Closure,
+    GetFree,
+    CurrentClosure,
+}
+
+pub struct Definition {
+    pub name: &'static str,
+    pub operand_widths: &'static [usize],
+}
+
+const OPCODES: [Opcode; 31] = [
+    Opcode::Constant,
+    Opcode::Pop,
+    Opcode::Add,
+    Opcode::Sub,
+    Opcode::Mul,
+    Opcode::Div,
+    Opcode::True,
+    Opcode::False,
+    Opcode::Null,
+    Opcode::Equal,
+    Opcode::NotEqual,
+    Opcode::GreaterThan,
+    Opcode::LessThan,
+    Opcode::Minus,
+    Opcode::Bang,
+    Opcode::JumpNotTruthy,
+    Opcode::Jump,
+    Opcode::GetGlobal,
+    Opcode::SetGlobal,
+    Opcode::GetLocal,
+    Opcode::SetLocal,
+    Opcode::Array,
+    Opcode::Hash,
+    Opcode::Index,
+    Opcode::Call,
+    Opcode::ReturnValue,
+    Opcode::Return,
+    Opcode::GetBuiltin,
+    Opcode::Closure,
+    Opcode::GetFree,
+    Opcode::CurrentClosure,
+];
+
+impl Opcode {
+    pub fn from_byte(byte: u8) -> Option<Opcode> {
+        OPCODES.get(byte as usize).copied()
+    }
+
+    pub fn definition(self) -> Definition {
+        let (name, operand_widths): (&'static str, &'static [usize]) = match self {
+            Opcode::Constant => ("OpConstant", &[2]),
+            Opcode::Pop => ("OpPop", &[]),
+            Opcode::Add => ("OpAdd", &[]),
+            Opcode::Sub => ("OpSub", &[]),
+            Opcode::Mul => ("OpMul", &[]),
+            Opcode::Div => ("OpDiv", &[]),
+            Opcode::True => ("OpTrue", &[]),
+            Opcode::False => ("OpFalse", &[]),
+            Opcode::Null => ("OpNull", &[]),
+            Opcode::Equal => ("OpEqual", &[]),
+            Opcode::NotEqual => ("OpNotEqual", &[]),
+            Opcode::GreaterThan => ("OpGreaterThan", &[]),
+            Opcode::LessThan => ("OpLessThan", &[]),
+            Opcode::Minus => ("OpMinus", &[]),
+            Opcode::Bang => ("OpBang", &[]),
+            Opcode::JumpNotTruthy => ("OpJumpNotTruthy", &[2]),
+            Opcode::Jump => ("OpJump", &[2]),
+            Opcode::GetGlobal => ("OpGetGlobal", &[2]),
+            Opcode::SetGlobal => ("OpSetGlobal", &[2]),
+            Opcode::GetLocal => ("OpGetLocal", &[1]),
+            Opcode::SetLocal => ("OpSetLocal", &[1]),
+            Opcode::Array => ("OpArray", &[2]),
+            Opcode::Hash => ("OpHash", &[2]),
+            Opcode::Index => ("OpIndex", &[]),
+            Opcode::Call => ("OpCall", &[1]),
+            Opcode::ReturnValue => ("OpReturnValue", &[]),
+            Opcode::Return => ("OpReturn", &[]),
+            Opcode::GetBuiltin => ("OpGetBuiltin", &[1]),
+            // constant index of the function, number of free variables
+            Opcode::Closure => ("OpClosure", &[2, 1]),
+            Opcode::GetFree => ("OpGetFree", &[1]),
+            Opcode::CurrentClosure => ("OpCurrentClosure", &[]),
+        };
+        Definition {
+            name,
+            operand_widths,
+        }
+    }
+}
+
+// encodes one instruction, operands that do not fit their width are truncated
+pub fn make(op: Opcode, operands: &[usize]) -> Instructions {
+    let definition = op.definition();
+    let mut ret = Vec::with_capacity(1 + definition.operand_widths.iter().sum::<usize>());
+    ret.push(op as u8);
+    for (operand, width) in operands.iter().zip(definition.operand_widths) {
+        match width {
+            2 => ret.extend_from_slice(&(*operand as u16).to_be_bytes()),
+            1 => ret.push(*operand as u8),
+            _ => unreachable!("unsupported operand width {}", width),
+        }
+    }
+    ret
+}
+
+// decodes the operands following an opcode, returns them and the number of
+// bytes read
+pub fn read_operands(definition: &Definition, ins: &[u8]) -> (Vec<usize>, usize) {
+    let mut operands = Vec::with_capacity(definition.operand_widths.len());
+    let mut offset = 0;
+    for width in definition.operand_widths {
+        match width {
+            2 => operands.push(read_u16(&ins[offset..]) as usize),
+            1 => operands.push(ins[offset] as usize),
+            _ => unreachable!("unsupported operand width {}", width),
+        }
+        offset += width;
+    }
+    (operands, offset)
+}
+
+pub fn read_u16(ins: &[u8]) -> u16 {
+    u16::from_be_bytes([ins[0], ins[1]])
+}
+
+// Renders instructions one per line with their offset, `0003 OpConstant 2 ; 42`.
+// Constants are looked up in `constants` to show their value.
+pub fn disassemble(ins: &[u8], constants: &[ObjectWrapper]) -> String {
+    let mut ret = String::new();
+    let mut offset = 0;
+    while offset < ins.len() {
+        let (text, len) = format_instruction(ins, offset, constants);
+        writeln!(ret, "{:04} {}", offset, text).unwrap();
+        offset += len;
+    }
+    ret
+}
+
+// the instruction at `offset` without the offset, and its length in bytes
+pub fn format_instruction(
+    ins: &[u8],
+    offset: usize,
+    constants: &[ObjectWrapper],
+) -> (String, usize) {
+    let op = match Opcode::from_byte(ins[offset]) {
+        Some(op) => op,
+        None => return (format!("unknown opcode {}", ins[offset]), 1),
+    };
+    let definition = op.definition();
+    let width = definition.operand_widths.iter().sum::<usize>();
+    if offset + 1 + width > ins.len() {
+        return (
+            format!("{} <truncated>", definition.name),
+            ins.len() - offset,
+        );
+    }
+    let (operands, read) = read_operands(&definition, &ins[offset + 1..]);
+    let mut ret = definition.name.to_string();
+    for operand in &operands {
+        write!(ret, " {}", operand).unwrap();
+    }
+    match op {
+        Opcode::Constant => match constants.get(operands[0]) {
+            Some(obj) => write!(ret, " ; {}", describe_value(obj)).unwrap(),
+            None => ret.push_str(" ; <missing>"),
+        },
+        Opcode::Closure => write!(ret, " ; fn {}", operands[0]).unwrap(),
+        _ => {}
+    }
+    (ret, 1 + read)
+}
+
+// a short form of a value for listings and traces
+pub fn describe_value(obj: &ObjectWrapper) -> String {
+    match obj {
+        ObjectWrapper::CompiledFn(_) | ObjectWrapper::Closure(_) | ObjectWrapper::Memoized(_) => {
+            "<function>".to_string()
+        }
+        ObjectWrapper::BuiltinFn(..) | ObjectWrapper::HostFn(_) => "<builtin>".to_string(),
+        ObjectWrapper::Handle(handle) => format!("<handle {}>", handle.type_name()),
+        ObjectWrapper::Iterator(_) => "<iterator>".to_string(),
+        ObjectWrapper::Null => "null".to_string(),
+        obj => snapshot::to_source(obj).unwrap_or_else(|| obj.to_string()),
+    }
+}
@@ -0,0 +1,575 @@
+use crate::compiler::code::{disassemble, make, Instructions, Opcode};
+use crate::compiler::optimizer::fold_constant;
+use crate::compiler::symbol_table::{Symbol, SymbolScope, SymbolTable};
+use crate::eval::builtins::Builtins;
+use crate::eval::ObjectWrapper;
+use crate::lexer::span::Span;
+use crate::lexer::token::Token;
+use crate::parser::program::{Expression, Ident, Program, Statement};
+use crate::parser::{ParseError, Result};
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+pub mod code;
+pub mod optimizer;
+pub mod peephole;
+pub mod serialize;
+pub mod symbol_table;
+
+#[cfg(test)]
+mod test;
+
+// operand of the jumps emitted before their target is known
+const PLACEHOLDER: usize = 9999;
+
+// Where the instructions come from, sorted by offset. An entry covers the
+// instructions from its offset up to the next entry.
+pub type SpanTable = Vec<(usize, Span)>;
+
+// the span of the instruction at `offset`
+pub fn span_at(spans: &[(usize, Span)], offset: usize) -> Option<Span> {
+    let i = spans.partition_point(|(start, _)| *start <= offset);
+    i.checked_sub(1).map(|i| spans[i].1)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompiledFunction {
+    pub instructions: Instructions,
+    pub num_locals: usize,
+    pub num_parameters: usize,
+    pub spans: SpanTable,
+}
+
+// a compiled function together with the values of its free variables
+#[derive(Debug, Clone, PartialEq)]
+pub struct Closure {
+    pub func: Arc<CompiledFunction>,
+    pub free: Vec<ObjectWrapper>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bytecode {
+    pub instructions: Instructions,
+    pub constants: Vec<ObjectWrapper>,
+    pub spans: SpanTable,
+}
+
+impl Bytecode {
+    // the program followed by the functions in the constant pool
+    pub fn disassemble(&self) -> String {
+        let mut ret = disassemble(&self.instructions, &self.constants);
+        for (index, obj) in self.constants.iter().enumerate() {
+            if let ObjectWrapper::CompiledFn(func) = obj {
+                ret.push_str(&format!(
+                    "\nfn {} (parameters: {}, locals: {}):\n",
+                    index, func.num_parameters, func.num_locals
+                ));
+                ret.push_str(&disassemble(&func.instructions, &self.constants));
+            }
+        }
+        ret
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CompilerOptions {
+    // evaluate operators on literals at compile time, `2 * 3` becomes `6`
+    pub fold_constants: bool,
+    // drop statements after a `return` and the branch of an `if` whose
+    // condition is constant, each dropped piece is reported as a warning
+    pub eliminate_dead_code: bool,
+    // rewrite the generated instructions, see `peephole::optimize`
+    pub peephole: bool,
+}
+
+impl Default for CompilerOptions {
+    fn default() -> Self {
+        CompilerOptions {
+            fold_constants: true,
+            eliminate_dead_code: true,
+            peephole: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub span: Span,
+    pub message: String,
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: warning: {}", self.span, self.message)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct EmittedInstruction {
+    opcode: Opcode,
+    position: usize,
+}
+
+// the instructions of the program or of the function being compiled
+#[derive(Default)]
+struct CompilationScope {
+    instructions: Instructions,
+    last_instruction: Option<EmittedInstruction>,
+    previous_instruction: Option<EmittedInstruction>,
+    spans: SpanTable,
+}
+
+// Lowers the AST into bytecode for the vm. Globals and constants are kept
+// between `compile` calls, so the REPL can compile one input at a time.
+pub struct Compiler {
+    constants: Vec<ObjectWrapper>,
+    scopes: Vec<CompilationScope>,
+    symbol_table: SymbolTable,
+    options: CompilerOptions,
+    warnings: Vec<Warning>,
+    // of the innermost expression or statement being compiled, recorded for
+    // the instructions emitted for it
+    span: Option<Span>,
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        let mut symbol_table = SymbolTable::new();
+        for (index, name) in Builtins::instance_ref().names().iter().enumerate() {
+            symbol_table.define_builtin(index, name);
+        }
+        Compiler {
+            constants: vec![],
+            scopes: vec![CompilationScope::default()],
+            symbol_table,
+            options: CompilerOptions::default(),
+            warnings: vec![],
+            span: None,
+        }
+    }
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_options(&mut self, options: CompilerOptions) {
+        self.options = options;
+    }
+
+    pub fn compile(&mut self, program: &Program) -> Result<()> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("compile").entered();
+        self.scope_mut().instructions.clear();
+        self.scope_mut().last_instruction = None;
+        self.scope_mut().previous_instruction = None;
+        self.scope_mut().spans.clear();
+        self.warnings.clear();
+        self.compile_statements(&program.statements)?;
+        // the program evaluates to its last expression statement, like in the
+        // evaluator a trailing `let` makes it null
+        if let Some(Statement::LetStatement(..)) = program.statements.last() {
+            self.emit(Opcode::Null, &[]);
+            self.emit(Opcode::Pop, &[]);
+        }
+        if self.options.peephole {
+            let scope = self.scopes.last_mut().unwrap();
+            let (instructions, spans) =
+                peephole::optimize(&scope.instructions, &scope.spans, &self.constants, true);
+            scope.instructions = instructions;
+            scope.spans = spans;
+            scope.last_instruction = None;
+            scope.previous_instruction = None;
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            instructions = self.scope().instructions.len(),
+            constants = self.constants.len(),
+            "compiled"
+        );
+        Ok(())
+    }
+
+    // what the last `compile` call found, like unreachable code
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    pub fn bytecode(&self) -> Bytecode {
+        Bytecode {
+            instructions: self.scopes[0].instructions.clone(),
+            constants: self.constants.clone(),
+            spans: self.scopes[0].spans.clone(),
+        }
+    }
+
+    fn scope(&self) -> &CompilationScope {
+        self.scopes.last().unwrap()
+    }
+
+    fn scope_mut(&mut self) -> &mut CompilationScope {
+        self.scopes.last_mut().unwrap()
+    }
+
+    fn compile_statement(&mut self, statement: &Statement) -> Result<()> {
+        match statement {
+            Statement::ExpressionStatement(expr, _) => {
+                self.compile_expression(expr)?;
+                self.emit(Opcode::Pop, &[]);
+            }
+            Statement::LetStatement(ident, expr, _) => {
+                // defined after the value, like in the evaluator, a function
+                // refers to itself through its own name instead
+                match expr {
+                    Expression::FunctionExpression(params, body, _) => {
+                        self.compile_function_literal(params, body, Some(&ident.0))?;
+                    }
+                    _ => self.compile_expression(expr)?,
+                }
+                let symbol = self.define(ident)?;
+                match symbol.scope {
+                    SymbolScope::Global => self.emit(Opcode::SetGlobal, &[symbol.index]),
+                    _ => self.emit(Opcode::SetLocal, &[symbol.index]),
+                };
+            }
+            Statement::ReturnStatement(expr, _) => {
+                self.compile_expression(expr)?;
+                self.emit(Opcode::ReturnValue, &[]);
+            }
+        }
+        Ok(())
+    }
+
+    fn compile_expression(&mut self, expression: &Expression) -> Result<()> {
+        let outer = self.span;
+        self.span = expression.span().or(outer);
+        let ret = self.compile_expression_node(expression);
+        self.span = outer;
+        ret
+    }
+
+    fn compile_expression_node(&mut self, expression: &Expression) -> Result<()> {
+        if self.options.fold_constants {
+            if let Expression::PrefixExpression(..) | Expression::InfixExpression(..) = expression {
+                if let Some(obj) = fold_constant(expression) {
+                    self.emit_value(obj);
+                    return Ok(());
+                }
+            }
+        }
+        match expression {
+            Expression::IntLiteral(v) => self.emit_constant(ObjectWrapper::Integer(*v)),
+            Expression::FloatLiteral(v) => self.emit_constant(ObjectWrapper::Float(*v)),
+            Expression::StringLiteral(v) => self.emit_constant(ObjectWrapper::String(v.clone())),
+            Expression::BoolLiteral(true) => self.emit(Opcode::True, &[]),
+            Expression::BoolLiteral(false) => self.emit(Opcode::False, &[]),
+            Expression::PrefixExpression(operator, right, span) => {
+                self.compile_expression(right)?;
+                match operator {
+                    Token::Minus => self.emit(Opcode::Minus, &[]),
+                    Token::Bang => self.emit(Opcode::Bang, &[]),
+                    _ => {
+                        let e = format!("unknown prefix operator: {}", operator);
+                        return Err(ParseError::new(e, *span));
+                    }
+                }
+            }
+            Expression::InfixExpression(left, operator, right, span) => {
+                self.compile_expression(left)?;
+                self.compile_expression(right)?;
+                let op = match operator {
+                    Token::Plus => Opcode::Add,
+                    Token::Minus => Opcode::Sub,
+                    Token::Asterisk => Opcode::Mul,
+                    Token::Slash => Opcode::Div,
+                    Token::Eq => Opcode::Equal,
+                    Token::NotEq => Opcode::NotEqual,
+                    Token::GT => Opcode::GreaterThan,
+                    Token::LT => Opcode::LessThan,
+                    _ => {
+                        let e = format!("unknown infix operator: {}", operator);
+                        return Err(ParseError::new(e, *span));
+                    }
+                };
+                self.emit(op, &[])
+            }
+            Expression::IfExpression(condition, consequence, alternative, _) => {
+                self.compile_if_expression(condition, consequence, alternative)?
+            }
+            Expression::Identifier(ident) => self.compile_identifier(ident)?,
+            Expression::ArrayLiteral(elements) => {
+                for element in elements {
+                    self.compile_expression(element)?;
+                }
+                self.emit(Opcode::Array, &[elements.len()])
+            }
+            Expression::HashLiteral(pairs) => {
+                for (key, value) in pairs {
+                    self.compile_expression(key)?;
+                    self.compile_expression(value)?;
+                }
+                self.emit(Opcode::Hash, &[pairs.len() * 2])
+            }
+            Expression::IndexExpression(left, index, _) => {
+                self.compile_expression(left)?;
+                self.compile_expression(index)?;
+                self.emit(Opcode::Index, &[])
+            }
+            Expression::FunctionExpression(params, body, _) => {
+                self.compile_function_literal(params, body, None)?
+            }
+            Expression::CallExpression(func, args, _) => {
+                self.compile_expression(func)?;
+                for arg in args {
+                    self.compile_expression(arg)?;
+                }
+                self.emit(Opcode::Call, &[args.len()])
+            }
+        };
+        Ok(())
+    }
+
+    fn compile_if_expression(
+        &mut self,
+        condition: &Expression,
+        consequence: &[Statement],
+        alternative: &[Statement],
+    ) -> Result<usize> {
+        if self.options.eliminate_dead_code {
+            if let Some(ObjectWrapper::Boolean(taken)) = fold_constant(condition) {
+                let (live, dead) = if taken {
+                    (consequence, alternative)
+                } else {
+                    (alternative, consequence)
+                };
+                self.warn_unreachable(dead);
+                self.compile_block(live)?;
+                return Ok(self.scope().instructions.len());
+            }
+        }
+        self.compile_expression(condition)?;
+        let jump_not_truthy = self.emit(Opcode::JumpNotTruthy, &[PLACEHOLDER]);
+
+        self.compile_block(consequence)?;
+        let jump = self.emit(Opcode::Jump, &[PLACEHOLDER]);
+
+        let after_consequence = self.scope().instructions.len();
+        self.change_operand(jump_not_truthy, after_consequence);
+
+        self.compile_block(alternative)?;
+        let after_alternative = self.scope().instructions.len();
+        self.change_operand(jump, after_alternative);
+        Ok(after_alternative)
+    }
+
+    // a block leaves its value on the stack, null if it has none
+    fn compile_statements(&mut self, statements: &[Statement]) -> Result<()> {
+        for (i, st) in statements.iter().enumerate() {
+            // errors without a more precise span point at the statement
+            let outer = self.span.replace(st.span());
+            let ret = self.compile_statement(st);
+            self.span = outer;
+            ret.map_err(|e| e.or_span(st.span()))?;
+            if !self.options.eliminate_dead_code {
+                continue;
+            }
+            if let Statement::ReturnStatement(..) = st {
+                self.warn_unreachable(&statements[i + 1..]);
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn warn_unreachable(&mut self, statements: &[Statement]) {
+        if let (Some(first), Some(last)) = (statements.first(), statements.last()) {
+            self.warnings.push(Warning {
+                span: first.span().to(last.span()),
+                message: "unreachable code".to_string(),
+            });
+        }
+    }
+
+    fn compile_block(&mut self, statements: &[Statement]) -> Result<()> {
+        self.compile_statements(statements)?;
+        if self.last_instruction_is(Opcode::Pop) {
+            self.remove_last_instruction();
+        } else {
+            self.emit(Opcode::Null, &[]);
+        }
+        Ok(())
+    }
+
+    fn compile_function_literal(
+        &mut self,
+        params: &[Ident],
+        body: &[Statement],
+        name: Option<&str>,
+    ) -> Result<usize> {
+        self.enter_scope();
+        if let Some(name) = name {
+            self.symbol_table.define_function_name(name);
+        }
+        let ret = self.compile_function_body(params, body);
+        let (scope, symbol_table) = self.leave_scope();
+        ret?;
+
+        // the captured values are loaded in the enclosing function
+        for symbol in &symbol_table.free_symbols {
+            self.load_symbol(symbol);
+        }
+        let (instructions, spans) = if self.options.peephole {
+            peephole::optimize(&scope.instructions, &scope.spans, &self.constants, false)
+        } else {
+            (scope.instructions, scope.spans)
+        };
+        let func = CompiledFunction {
+            instructions,
+            num_locals: symbol_table.num_definitions(),
+            num_parameters: params.len(),
+            spans,
+        };
+        self.constants
+            .push(ObjectWrapper::CompiledFn(Arc::new(func)));
+        let index = self.constants.len() - 1;
+        Ok(self.emit(Opcode::Closure, &[index, symbol_table.free_symbols.len()]))
+    }
+
+    fn compile_function_body(&mut self, params: &[Ident], body: &[Statement]) -> Result<()> {
+        for param in params {
+            let symbol = self.symbol_table.define_parameter(&param.0);
+            self.check_slot(param, &symbol)?;
+        }
+        self.compile_statements(body)?;
+        // the last expression is the return value
+        if self.last_instruction_is(Opcode::Pop) {
+            let position = self.scope().last_instruction.unwrap().position;
+            self.scope_mut().instructions[position] = Opcode::ReturnValue as u8;
+            self.scope_mut().last_instruction = Some(EmittedInstruction {
+                opcode: Opcode::ReturnValue,
+                position,
+            });
+        }
+        if !self.last_instruction_is(Opcode::ReturnValue) {
+            self.emit(Opcode::Return, &[]);
+        }
+        Ok(())
+    }
+
+    fn enter_scope(&mut self) {
+        self.scopes.push(CompilationScope::default());
+        let outer = std::mem::take(&mut self.symbol_table);
+        self.symbol_table = SymbolTable::new_enclosed(outer);
+    }
+
+    fn leave_scope(&mut self) -> (CompilationScope, SymbolTable) {
+        let scope = self.scopes.pop().unwrap();
+        let outer = self.symbol_table.outer.take().unwrap();
+        let symbol_table = std::mem::replace(&mut self.symbol_table, *outer);
+        (scope, symbol_table)
+    }
+
+    fn compile_identifier(&mut self, ident: &Ident) -> Result<usize> {
+        match self.symbol_table.resolve(&ident.0) {
+            Some(symbol) => {
+                if symbol.scope == SymbolScope::Free && symbol.index > u8::MAX as usize {
+                    let e = format!("too many free variables, cannot capture {}", ident.0);
+                    return Err(ParseError::new(e, ident.1));
+                }
+                Ok(self.load_symbol(&symbol))
+            }
+            None => Err(ParseError::new(
+                format!("undefined variable: {}", ident.0),
+                ident.1,
+            )),
+        }
+    }
+
+    fn load_symbol(&mut self, symbol: &Symbol) -> usize {
+        match symbol.scope {
+            SymbolScope::Global => self.emit(Opcode::GetGlobal, &[symbol.index]),
+            SymbolScope::Local => self.emit(Opcode::GetLocal, &[symbol.index]),
+            SymbolScope::Builtin => self.emit(Opcode::GetBuiltin, &[symbol.index]),
+            SymbolScope::Free => self.emit(Opcode::GetFree, &[symbol.index]),
+            SymbolScope::Function => self.emit(Opcode::CurrentClosure, &[]),
+        }
+    }
+
+    // binds the name in the current scope
+    fn define(&mut self, ident: &Ident) -> Result<Symbol> {
+        let symbol = self.symbol_table.define(&ident.0);
+        self.check_slot(ident, &symbol)?;
+        Ok(symbol)
+    }
+
+    fn check_slot(&self, ident: &Ident, symbol: &Symbol) -> Result<()> {
+        let max = if self.symbol_table.is_global() {
+            u16::MAX as usize
+        } else {
+            u8::MAX as usize
+        };
+        if symbol.index > max {
+            let e = format!("too many variables, cannot define {}", ident.0);
+            return Err(ParseError::new(e, ident.1));
+        }
+        Ok(())
+    }
+
+    // the folded value of an expression
+    fn emit_value(&mut self, obj: ObjectWrapper) -> usize {
+        match obj {
+            ObjectWrapper::Boolean(true) => self.emit(Opcode::True, &[]),
+            ObjectWrapper::Boolean(false) => self.emit(Opcode::False, &[]),
+            ObjectWrapper::Null => self.emit(Opcode::Null, &[]),
+            obj => self.emit_constant(obj),
+        }
+    }
+
+    fn emit_constant(&mut self, obj: ObjectWrapper) -> usize {
+        self.constants.push(obj);
+        let index = self.constants.len() - 1;
+        self.emit(Opcode::Constant, &[index])
+    }
+
+    // returns the position of the new instruction
+    fn emit(&mut self, op: Opcode, operands: &[usize]) -> usize {
+        let ins = make(op, operands);
+        let span = self.span;
+        let scope = self.scope_mut();
+        let position = scope.instructions.len();
+        scope.instructions.extend_from_slice(&ins);
+        if let Some(span) = span {
+            if scope.spans.last().map(|(_, last)| *last) != Some(span) {
+                scope.spans.push((position, span));
+            }
+        }
+        scope.previous_instruction = scope.last_instruction;
+        scope.last_instruction = Some(EmittedInstruction {
+            opcode: op,
+            position,
+        });
+        position
+    }
+
+    fn last_instruction_is(&self, op: Opcode) -> bool {
+        self.scope()
+            .last_instruction
+            .is_some_and(|last| last.opcode == op)
+    }
+
+    fn remove_last_instruction(&mut self) {
+        let scope = self.scope_mut();
+        if let Some(last) = scope.last_instruction {
+            scope.instructions.truncate(last.position);
+            scope.spans.retain(|(offset, _)| *offset < last.position);
+            scope.last_instruction = scope.previous_instruction;
+        }
+    }
+
+    fn change_operand(&mut self, position: usize, operand: usize) {
+        let op = Opcode::from_byte(self.scope().instructions[position]).unwrap();
+        let ins = make(op, &[operand]);
+        self.scope_mut().instructions[position..position + ins.len()].copy_from_slice(&ins);
+    }
+}
@@ -0,0 +1,45 @@
+use crate::eval::ObjectWrapper;
+use crate::lexer::token::Token;
+use crate::parser::program::Expression;
+
+// Evaluates an expression made only of literals and operators at compile
+// time. Returns None when it is not constant or evaluating it fails, the
+// error is then left to the vm.
+pub fn fold_constant(expression: &Expression) -> Option<ObjectWrapper> {
+    match expression {
+        Expression::IntLiteral(v) => Some(ObjectWrapper::Integer(*v)),
+        Expression::FloatLiteral(v) => Some(ObjectWrapper::Float(*v)),
+        Expression::StringLiteral(v) => Some(ObjectWrapper::String(v.clone())),
+        Expression::BoolLiteral(v) => Some(ObjectWrapper::Boolean(*v)),
+        Expression::PrefixExpression(operator, right, _) => {
+            let right = fold_constant(right)?;
+            match (operator, right) {
+                (Token::Minus, ObjectWrapper::Integer(v)) => {
+                    v.checked_neg().map(ObjectWrapper::Integer)
+                }
+                (Token::Minus, ObjectWrapper::Float(v)) => Some(ObjectWrapper::Float(-v)),
+                (Token::Bang, ObjectWrapper::Boolean(v)) => Some(ObjectWrapper::Boolean(!v)),
+                (Token::Bang, ObjectWrapper::Integer(v)) => Some(ObjectWrapper::Boolean(v == 0)),
+                (Token::Bang, _) => Some(ObjectWrapper::Boolean(false)),
+                _ => None,
+            }
+        }
+        Expression::InfixExpression(left, operator, right, _) => {
+            let left = fold_constant(left)?;
+            let right = fold_constant(right)?;
+            let ret = match operator {
+                Token::Plus => left.add(&right),
+                Token::Minus => left.sub(&right),
+                Token::Asterisk => left.multi(&right),
+                Token::Slash => left.divide(&right),
+                Token::Eq => left.eq(&right),
+                Token::NotEq => left.not_eq(&right),
+                Token::GT => left.great_than(&right),
+                Token::LT => left.less_than(&right),
+                _ => return None,
+            };
+            ret.ok()
+        }
+        _ => None,
+    }
+}
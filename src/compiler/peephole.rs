@@ -0,0 +1,199 @@
+use crate::compiler::code::{make, read_operands, Instructions, Opcode};
+use crate::compiler::SpanTable;
+use crate::eval::ObjectWrapper;
+use crate::lexer::span::Span;
+use std::collections::{HashMap, HashSet};
+
+struct Instruction {
+    offset: usize,
+    op: Opcode,
+    operands: Vec<usize>,
+    removed: bool,
+}
+
+// Rewrites finished instructions:
+//
+// - a jump to an `OpJump` goes straight to where that one leads
+// - a constant that is popped right away is not pushed at all
+// - `!!` on a boolean and `--` on a number are dropped
+//
+// In the program, `keep_last_pop` keeps the final `OpPop`, its value is the
+// result the vm reports. The span table of `ins` is returned moved to the
+// new offsets.
+pub fn optimize(
+    ins: &[u8],
+    spans: &[(usize, Span)],
+    constants: &[ObjectWrapper],
+    keep_last_pop: bool,
+) -> (Instructions, SpanTable) {
+    let mut instructions = decode(ins);
+    let index: HashMap<usize, usize> = instructions
+        .iter()
+        .enumerate()
+        .map(|(i, instruction)| (instruction.offset, i))
+        .collect();
+
+    shorten_jump_chains(&mut instructions, &index);
+
+    let targets: HashSet<usize> = instructions
+        .iter()
+        .filter(|instruction| is_jump(instruction.op))
+        .map(|instruction| instruction.operands[0])
+        .collect();
+    let last_pop = instructions
+        .iter()
+        .rposition(|instruction| instruction.op == Opcode::Pop)
+        .filter(|_| keep_last_pop);
+
+    let mut prev: Option<usize> = None;
+    let mut i = 0;
+    while i < instructions.len() {
+        let removable = i + 1 < instructions.len()
+            && !targets.contains(&instructions[i].offset)
+            && !targets.contains(&instructions[i + 1].offset);
+        let first = &instructions[i];
+        let second = instructions.get(i + 1).map(|instruction| instruction.op);
+        let drop_pair = removable
+            && match (first.op, second) {
+                (Opcode::Constant, Some(Opcode::Pop))
+                | (Opcode::True, Some(Opcode::Pop))
+                | (Opcode::False, Some(Opcode::Pop))
+                | (Opcode::Null, Some(Opcode::Pop)) => last_pop != Some(i + 1),
+                (Opcode::Bang, Some(Opcode::Bang)) => {
+                    prev.is_some_and(|prev| pushes_boolean(instructions[prev].op))
+                }
+                (Opcode::Minus, Some(Opcode::Minus)) => {
+                    prev.is_some_and(|prev| pushes_number(&instructions[prev], constants))
+                }
+                _ => false,
+            };
+        if drop_pair {
+            instructions[i].removed = true;
+            instructions[i + 1].removed = true;
+            i += 2;
+        } else {
+            prev = Some(i);
+            i += 1;
+        }
+    }
+
+    encode(&instructions, spans, ins.len())
+}
+
+fn decode(ins: &[u8]) -> Vec<Instruction> {
+    let mut ret = vec![];
+    let mut offset = 0;
+    while offset < ins.len() {
+        let op = Opcode::from_byte(ins[offset]).expect("compiler emitted an unknown opcode");
+        let (operands, read) = read_operands(&op.definition(), &ins[offset + 1..]);
+        ret.push(Instruction {
+            offset,
+            op,
+            operands,
+            removed: false,
+        });
+        offset += 1 + read;
+    }
+    ret
+}
+
+fn shorten_jump_chains(instructions: &mut [Instruction], index: &HashMap<usize, usize>) {
+    for i in 0..instructions.len() {
+        if !is_jump(instructions[i].op) {
+            continue;
+        }
+        let mut target = instructions[i].operands[0];
+        // bounded, jumps could form a loop
+        for _ in 0..instructions.len() {
+            match index.get(&target).map(|j| &instructions[*j]) {
+                Some(next) if next.op == Opcode::Jump && next.operands[0] != target => {
+                    target = next.operands[0]
+                }
+                _ => break,
+            }
+        }
+        instructions[i].operands[0] = target;
+    }
+}
+
+fn encode(
+    instructions: &[Instruction],
+    spans: &[(usize, Span)],
+    len: usize,
+) -> (Instructions, SpanTable) {
+    // a removed instruction maps to the next one that is kept
+    let mut offsets = HashMap::with_capacity(instructions.len() + 1);
+    let mut new_offset = 0;
+    let mut pending = vec![];
+    for instruction in instructions {
+        pending.push(instruction.offset);
+        if instruction.removed {
+            continue;
+        }
+        for offset in pending.drain(..) {
+            offsets.insert(offset, new_offset);
+        }
+        new_offset += 1 + instruction
+            .op
+            .definition()
+            .operand_widths
+            .iter()
+            .sum::<usize>();
+    }
+    pending.push(len);
+    for offset in pending {
+        offsets.insert(offset, new_offset);
+    }
+
+    let mut ret = Vec::with_capacity(new_offset);
+    for instruction in instructions
+        .iter()
+        .filter(|instruction| !instruction.removed)
+    {
+        if is_jump(instruction.op) {
+            ret.extend(make(instruction.op, &[offsets[&instruction.operands[0]]]));
+        } else {
+            ret.extend(make(instruction.op, &instruction.operands));
+        }
+    }
+
+    // of the entries landing on the same instruction the last one is the
+    // instruction's own
+    let mut new_spans: SpanTable = Vec::with_capacity(spans.len());
+    for (offset, span) in spans {
+        let offset = offsets[offset];
+        match new_spans.last_mut() {
+            Some(last) if last.0 == offset => last.1 = *span,
+            _ => new_spans.push((offset, *span)),
+        }
+    }
+    (ret, new_spans)
+}
+
+fn is_jump(op: Opcode) -> bool {
+    op == Opcode::Jump || op == Opcode::JumpNotTruthy
+}
+
+fn pushes_boolean(op: Opcode) -> bool {
+    matches!(
+        op,
+        Opcode::True
+            | Opcode::False
+            | Opcode::Bang
+            | Opcode::Equal
+            | Opcode::NotEqual
+            | Opcode::GreaterThan
+            | Opcode::LessThan
+    )
+}
+
+fn pushes_number(instruction: &Instruction, constants: &[ObjectWrapper]) -> bool {
+    match instruction.op {
+        Opcode::Minus => true,
+        Opcode::Constant => matches!(
+            constants.get(instruction.operands[0]),
+            Some(ObjectWrapper::Integer(_)) | Some(ObjectWrapper::Float(_))
+        ),
+        _ => false,
+    }
+}
@@ -0,0 +1,195 @@
+use crate::compiler::{Bytecode, CompiledFunction, SpanTable};
+use crate::eval::ObjectWrapper;
+use crate::lexer::span::{Position, Span};
+use crate::parser::Result;
+use std::convert::TryInto;
+use std::sync::Arc;
+
+// Layout of a .monkeyc file, numbers are big-endian:
+//
+//   magic "MNKC", version: u16
+//   instructions: u32 length, bytes
+//   spans: u32 count, each the u32 offset of the first instruction it covers
+//     then offset, line and column: u32 of its start and of its end
+//   constants: u32 count, each a tag byte followed by its value, functions
+//     as their locals, parameters, instructions and spans
+//
+// The version changes whenever the layout, the opcodes or the builtins,
+// which are referred to by index, do, the tests list the builtins of this
+// one. Files of another version are rejected instead of run.
+const MAGIC: &[u8; 4] = b"MNKC";
+pub const FORMAT_VERSION: u16 = 9;
+
+const TAG_INTEGER: u8 = 0;
+const TAG_FLOAT: u8 = 1;
+const TAG_STRING: u8 = 2;
+const TAG_FUNCTION: u8 = 3;
+
+impl Bytecode {
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut ret = Vec::with_capacity(MAGIC.len() + 2 + self.instructions.len());
+        ret.extend_from_slice(MAGIC);
+        ret.extend_from_slice(&FORMAT_VERSION.to_be_bytes());
+        write_bytes(&mut ret, &self.instructions)?;
+        write_spans(&mut ret, &self.spans)?;
+        write_len(&mut ret, self.constants.len())?;
+        for obj in &self.constants {
+            match obj {
+                ObjectWrapper::Integer(v) => {
+                    ret.push(TAG_INTEGER);
+                    ret.extend_from_slice(&v.to_be_bytes());
+                }
+                ObjectWrapper::Float(v) => {
+                    ret.push(TAG_FLOAT);
+                    ret.extend_from_slice(&v.to_bits().to_be_bytes());
+                }
+                ObjectWrapper::String(v) => {
+                    ret.push(TAG_STRING);
+                    write_bytes(&mut ret, v.as_bytes())?;
+                }
+                ObjectWrapper::CompiledFn(func) => {
+                    ret.push(TAG_FUNCTION);
+                    write_len(&mut ret, func.num_locals)?;
+                    write_len(&mut ret, func.num_parameters)?;
+                    write_bytes(&mut ret, &func.instructions)?;
+                    write_spans(&mut ret, &func.spans)?;
+                }
+                _ => {
+                    let e = format!("cannot serialize a {} constant", obj.type_str());
+                    return Err(e.into());
+                }
+            }
+        }
+        Ok(ret)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Bytecode> {
+        let mut reader = Reader { bytes, offset: 0 };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err("not a monkey bytecode file".into());
+        }
+        let version = u16::from_be_bytes(reader.take(2)?.try_into().unwrap());
+        if version != FORMAT_VERSION {
+            return Err(format!(
+                "unsupported bytecode version {}, expect {}, rebuild it from the source",
+                version, FORMAT_VERSION
+            )
+            .into());
+        }
+        let instructions = reader.read_bytes()?.to_vec();
+        let spans = reader.read_spans()?;
+        let len = reader.read_len()?;
+        let mut constants = Vec::new();
+        for _ in 0..len {
+            let obj = match reader.take(1)?[0] {
+                TAG_INTEGER => {
+                    ObjectWrapper::Integer(i64::from_be_bytes(reader.take(8)?.try_into().unwrap()))
+                }
+                TAG_FLOAT => ObjectWrapper::Float(f64::from_bits(u64::from_be_bytes(
+                    reader.take(8)?.try_into().unwrap(),
+                ))),
+                TAG_STRING => {
+                    let v = std::str::from_utf8(reader.read_bytes()?)
+                        .map_err(|_| "invalid utf-8 in string constant")?;
+                    ObjectWrapper::String(v.into())
+                }
+                TAG_FUNCTION => {
+                    let num_locals = reader.read_len()?;
+                    let num_parameters = reader.read_len()?;
+                    let instructions = reader.read_bytes()?.to_vec();
+                    let spans = reader.read_spans()?;
+                    if num_locals < num_parameters {
+                        return Err("function with fewer locals than parameters".into());
+                    }
+                    ObjectWrapper::CompiledFn(Arc::new(CompiledFunction {
+                        instructions,
+                        num_locals,
+                        num_parameters,
+                        spans,
+                    }))
+                }
+                tag => return Err(format!("unknown constant tag: {}", tag).into()),
+            };
+            constants.push(obj);
+        }
+        if reader.offset != bytes.len() {
+            return Err("trailing bytes after bytecode".into());
+        }
+        Ok(Bytecode {
+            instructions,
+            constants,
+            spans,
+        })
+    }
+}
+
+fn write_len(buf: &mut Vec<u8>, len: usize) -> Result<()> {
+    let len: u32 = len.try_into().map_err(|_| "bytecode too large")?;
+    buf.extend_from_slice(&len.to_be_bytes());
+    Ok(())
+}
+
+fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) -> Result<()> {
+    write_len(buf, bytes.len())?;
+    buf.extend_from_slice(bytes);
+    Ok(())
+}
+
+fn write_spans(buf: &mut Vec<u8>, spans: &[(usize, Span)]) -> Result<()> {
+    write_len(buf, spans.len())?;
+    for (offset, span) in spans {
+        write_len(buf, *offset)?;
+        for position in [span.start, span.end] {
+            write_len(buf, position.offset)?;
+            write_len(buf, position.line)?;
+            write_len(buf, position.column)?;
+        }
+    }
+    Ok(())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or("truncated bytecode")?;
+        let ret = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(ret)
+    }
+
+    fn read_len(&mut self) -> Result<usize> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as usize)
+    }
+
+    fn read_bytes(&mut self) -> Result<&'a [u8]> {
+        let len = self.read_len()?;
+        self.take(len)
+    }
+
+    fn read_position(&mut self) -> Result<Position> {
+        Ok(Position {
+            offset: self.read_len()?,
+            line: self.read_len()?,
+            column: self.read_len()?,
+        })
+    }
+
+    fn read_spans(&mut self) -> Result<SpanTable> {
+        let len = self.read_len()?;
+        let mut ret = Vec::new();
+        for _ in 0..len {
+            let offset = self.read_len()?;
+            let span = Span::new(self.read_position()?, self.read_position()?);
+            ret.push((offset, span));
+        }
+        Ok(ret)
+    }
+}
@@ -0,0 +1,1091 @@
+use crate::compiler::code::{disassemble, make, read_operands, Instructions, Opcode};
+use crate::compiler::peephole;
+use crate::compiler::serialize::FORMAT_VERSION;
+use crate::compiler::symbol_table::{Symbol, SymbolScope, SymbolTable};
+use crate::compiler::{Bytecode, CompiledFunction, Compiler, CompilerOptions};
+use crate::eval::builtins::Builtins;
+use crate::eval::ObjectWrapper;
+use crate::lexer::lexer::Lexer;
+use crate::parser::Parser;
+use std::sync::Arc;
+
+// the code is compiled as it is written, the optimizations have their own
+// tests
+#[cfg(test)]
+fn run_compiler_tests(cases: Vec<(&str, Vec<ObjectWrapper>, Vec<Instructions>)>) {
+    run_compiler_tests_with(
+        CompilerOptions {
+            fold_constants: false,
+            eliminate_dead_code: false,
+            peephole: false,
+        },
+        cases,
+    )
+}
+
+#[cfg(test)]
+fn run_compiler_tests_with(
+    options: CompilerOptions,
+    cases: Vec<(&str, Vec<ObjectWrapper>, Vec<Instructions>)>,
+) {
+    for (input, constants, instructions) in cases {
+        let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+        let mut compiler = Compiler::new();
+        compiler.set_options(options.clone());
+        compiler.compile(&program).unwrap();
+        let bytecode = compiler.bytecode();
+
+        // compared as text first, a mismatch is much easier to read
+        let instructions = instructions.concat();
+        assert_eq!(
+            disassemble(&bytecode.instructions, &bytecode.constants),
+            disassemble(&instructions, &bytecode.constants),
+            "{}",
+            input
+        );
+        assert_eq!(bytecode.instructions, instructions, "{}", input);
+        assert_eq!(without_spans(bytecode.constants), constants, "{}", input);
+    }
+}
+
+#[cfg(test)]
+fn compiled_fn(
+    instructions: Vec<Instructions>,
+    num_locals: usize,
+    num_parameters: usize,
+) -> ObjectWrapper {
+    ObjectWrapper::CompiledFn(Arc::new(CompiledFunction {
+        instructions: instructions.concat(),
+        num_locals,
+        num_parameters,
+        spans: vec![],
+    }))
+}
+
+// the span tables have their own tests
+#[cfg(test)]
+fn without_spans(constants: Vec<ObjectWrapper>) -> Vec<ObjectWrapper> {
+    constants
+        .into_iter()
+        .map(|obj| match obj {
+            ObjectWrapper::CompiledFn(func) => {
+                ObjectWrapper::CompiledFn(Arc::new(CompiledFunction {
+                    spans: vec![],
+                    ..(*func).clone()
+                }))
+            }
+            obj => obj,
+        })
+        .collect()
+}
+
+#[test]
+fn test_make() {
+    let cases = [
+        (
+            Opcode::Constant,
+            vec![65534],
+            vec![Opcode::Constant as u8, 255, 254],
+        ),
+        (Opcode::Add, vec![], vec![Opcode::Add as u8]),
+        (
+            Opcode::GetLocal,
+            vec![255],
+            vec![Opcode::GetLocal as u8, 255],
+        ),
+    ];
+
+    for (op, operands, expect) in cases {
+        assert_eq!(make(op, &operands), expect);
+    }
+}
+
+#[test]
+fn test_read_operands() {
+    let cases = [
+        (Opcode::Constant, vec![65535], 2),
+        (Opcode::GetLocal, vec![255], 1),
+        (Opcode::Pop, vec![], 0),
+    ];
+
+    for (op, operands, bytes_read) in cases {
+        let ins = make(op, &operands);
+        let (read, n) = read_operands(&op.definition(), &ins[1..]);
+        assert_eq!(n, bytes_read);
+        assert_eq!(read, operands);
+    }
+}
+
+#[test]
+fn test_disassemble() {
+    let ins = [
+        make(Opcode::Add, &[]),
+        make(Opcode::GetLocal, &[1]),
+        make(Opcode::Constant, &[2]),
+        make(Opcode::Constant, &[65535]),
+        make(Opcode::Closure, &[65535, 255]),
+    ]
+    .concat();
+    let constants = [
+        ObjectWrapper::Integer(1),
+        ObjectWrapper::Integer(2),
+        ObjectWrapper::String("a".into()),
+    ];
+    let expect = "0000 OpAdd
+0001 OpGetLocal 1
+0003 OpConstant 2 ; \"a\"
+0006 OpConstant 65535 ; <missing>
+0009 OpClosure 65535 255 ; fn 65535
+";
+    assert_eq!(disassemble(&ins, &constants), expect);
+}
+
+#[test]
+fn test_disassemble_bytecode() {
+    let input = "let add = fn(a, b) { a + b }; add(40, 2)";
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+    let mut compiler = Compiler::new();
+    compiler.compile(&program).unwrap();
+    let expect = "0000 OpClosure 0 0 ; fn 0
+0004 OpSetGlobal 0
+0007 OpGetGlobal 0
+0010 OpConstant 1 ; 40
+0013 OpConstant 2 ; 2
+0016 OpCall 2
+0018 OpPop
+
+fn 0 (parameters: 2, locals: 2):
+0000 OpGetLocal 0
+0002 OpGetLocal 1
+0004 OpAdd
+0005 OpReturnValue
+";
+    assert_eq!(compiler.bytecode().disassemble(), expect);
+}
+
+#[test]
+fn test_integer_arithmetic() {
+    let cases = vec![
+        (
+            "1 + 2",
+            vec![ObjectWrapper::Integer(1), ObjectWrapper::Integer(2)],
+            vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Add, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+        (
+            "1; 2",
+            vec![ObjectWrapper::Integer(1), ObjectWrapper::Integer(2)],
+            vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Pop, &[]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+        (
+            "2 * 3 - 4 / 2",
+            vec![
+                ObjectWrapper::Integer(2),
+                ObjectWrapper::Integer(3),
+                ObjectWrapper::Integer(4),
+                ObjectWrapper::Integer(2),
+            ],
+            vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Mul, &[]),
+                make(Opcode::Constant, &[2]),
+                make(Opcode::Constant, &[3]),
+                make(Opcode::Div, &[]),
+                make(Opcode::Sub, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+        (
+            "-1",
+            vec![ObjectWrapper::Integer(1)],
+            vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Minus, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+    ];
+    run_compiler_tests(cases);
+}
+
+#[test]
+fn test_boolean_expressions() {
+    let cases = vec![
+        (
+            "true",
+            vec![],
+            vec![make(Opcode::True, &[]), make(Opcode::Pop, &[])],
+        ),
+        (
+            "1 < 2",
+            vec![ObjectWrapper::Integer(1), ObjectWrapper::Integer(2)],
+            vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::LessThan, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+        (
+            "true != false",
+            vec![],
+            vec![
+                make(Opcode::True, &[]),
+                make(Opcode::False, &[]),
+                make(Opcode::NotEqual, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+        (
+            "!true",
+            vec![],
+            vec![
+                make(Opcode::True, &[]),
+                make(Opcode::Bang, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+    ];
+    run_compiler_tests(cases);
+}
+
+#[test]
+fn test_conditionals() {
+    let cases = vec![
+        (
+            "if (true) { 10 }; 3333;",
+            vec![ObjectWrapper::Integer(10), ObjectWrapper::Integer(3333)],
+            vec![
+                // 0000
+                make(Opcode::True, &[]),
+                // 0001
+                make(Opcode::JumpNotTruthy, &[10]),
+                // 0004
+                make(Opcode::Constant, &[0]),
+                // 0007
+                make(Opcode::Jump, &[11]),
+                // 0010
+                make(Opcode::Null, &[]),
+                // 0011
+                make(Opcode::Pop, &[]),
+                // 0012
+                make(Opcode::Constant, &[1]),
+                // 0015
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+        (
+            "if (true) { 10 } else { 20 }; 3333;",
+            vec![
+                ObjectWrapper::Integer(10),
+                ObjectWrapper::Integer(20),
+                ObjectWrapper::Integer(3333),
+            ],
+            vec![
+                // 0000
+                make(Opcode::True, &[]),
+                // 0001
+                make(Opcode::JumpNotTruthy, &[10]),
+                // 0004
+                make(Opcode::Constant, &[0]),
+                // 0007
+                make(Opcode::Jump, &[13]),
+                // 0010
+                make(Opcode::Constant, &[1]),
+                // 0013
+                make(Opcode::Pop, &[]),
+                // 0014
+                make(Opcode::Constant, &[2]),
+                // 0017
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+    ];
+    run_compiler_tests(cases);
+}
+
+#[test]
+fn test_global_let_statements() {
+    let cases = vec![
+        (
+            "let one = 1; let two = 2;",
+            vec![ObjectWrapper::Integer(1), ObjectWrapper::Integer(2)],
+            vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::SetGlobal, &[1]),
+                make(Opcode::Null, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+        (
+            "let one = 1; let two = one; two;",
+            vec![ObjectWrapper::Integer(1)],
+            vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::GetGlobal, &[0]),
+                make(Opcode::SetGlobal, &[1]),
+                make(Opcode::GetGlobal, &[1]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+    ];
+    run_compiler_tests(cases);
+}
+
+#[test]
+fn test_collection_literals() {
+    let cases = vec![
+        (
+            "\"mon\" + \"key\"",
+            vec![
+                ObjectWrapper::String("mon".into()),
+                ObjectWrapper::String("key".into()),
+            ],
+            vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Add, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+        (
+            "[]",
+            vec![],
+            vec![make(Opcode::Array, &[0]), make(Opcode::Pop, &[])],
+        ),
+        (
+            "[1, 2 + 3]",
+            vec![
+                ObjectWrapper::Integer(1),
+                ObjectWrapper::Integer(2),
+                ObjectWrapper::Integer(3),
+            ],
+            vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Constant, &[2]),
+                make(Opcode::Add, &[]),
+                make(Opcode::Array, &[2]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+        (
+            "{1: 2, 3: 4}",
+            vec![
+                ObjectWrapper::Integer(1),
+                ObjectWrapper::Integer(2),
+                ObjectWrapper::Integer(3),
+                ObjectWrapper::Integer(4),
+            ],
+            vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Constant, &[2]),
+                make(Opcode::Constant, &[3]),
+                make(Opcode::Hash, &[4]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+        (
+            "{1: 2}[1]",
+            vec![
+                ObjectWrapper::Integer(1),
+                ObjectWrapper::Integer(2),
+                ObjectWrapper::Integer(1),
+            ],
+            vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Hash, &[2]),
+                make(Opcode::Constant, &[2]),
+                make(Opcode::Index, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+    ];
+    run_compiler_tests(cases);
+}
+
+#[test]
+fn test_functions() {
+    let cases = vec![
+        (
+            "fn() { return 5 + 10 }",
+            vec![
+                ObjectWrapper::Integer(5),
+                ObjectWrapper::Integer(10),
+                compiled_fn(
+                    vec![
+                        make(Opcode::Constant, &[0]),
+                        make(Opcode::Constant, &[1]),
+                        make(Opcode::Add, &[]),
+                        make(Opcode::ReturnValue, &[]),
+                    ],
+                    0,
+                    0,
+                ),
+            ],
+            vec![make(Opcode::Closure, &[2, 0]), make(Opcode::Pop, &[])],
+        ),
+        (
+            "fn() { 1; 2 }",
+            vec![
+                ObjectWrapper::Integer(1),
+                ObjectWrapper::Integer(2),
+                compiled_fn(
+                    vec![
+                        make(Opcode::Constant, &[0]),
+                        make(Opcode::Pop, &[]),
+                        make(Opcode::Constant, &[1]),
+                        make(Opcode::ReturnValue, &[]),
+                    ],
+                    0,
+                    0,
+                ),
+            ],
+            vec![make(Opcode::Closure, &[2, 0]), make(Opcode::Pop, &[])],
+        ),
+        (
+            "fn() { }",
+            vec![compiled_fn(vec![make(Opcode::Return, &[])], 0, 0)],
+            vec![make(Opcode::Closure, &[0, 0]), make(Opcode::Pop, &[])],
+        ),
+        (
+            "let one_arg = fn(a) { let b = a; b }; one_arg(24);",
+            vec![
+                compiled_fn(
+                    vec![
+                        make(Opcode::GetLocal, &[0]),
+                        make(Opcode::SetLocal, &[1]),
+                        make(Opcode::GetLocal, &[1]),
+                        make(Opcode::ReturnValue, &[]),
+                    ],
+                    2,
+                    1,
+                ),
+                ObjectWrapper::Integer(24),
+            ],
+            vec![
+                make(Opcode::Closure, &[0, 0]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::GetGlobal, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Call, &[1]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+    ];
+    run_compiler_tests(cases);
+}
+
+#[test]
+fn test_compile_errors() {
+    let cases = [
+        ("x", "undefined variable: x"),
+        ("let f = fn() { y }", "undefined variable: y"),
+        ("let f = fn() { let a = 1; }; a", "undefined variable: a"),
+    ];
+
+    for (input, expect) in cases {
+        let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+        let err = Compiler::new().compile(&program).unwrap_err();
+        assert_eq!(err.to_string(), expect);
+    }
+}
+
+#[cfg(test)]
+fn symbol(name: &str, scope: SymbolScope, index: usize) -> Symbol {
+    Symbol {
+        name: name.to_string(),
+        scope,
+        index,
+    }
+}
+
+#[test]
+fn test_symbol_table_nested_scopes() {
+    let mut global = SymbolTable::new();
+    assert_eq!(global.define("a"), symbol("a", SymbolScope::Global, 0));
+    assert_eq!(global.define("b"), symbol("b", SymbolScope::Global, 1));
+    global.define_builtin(0, "len");
+
+    let mut first = SymbolTable::new_enclosed(global);
+    assert_eq!(first.define("c"), symbol("c", SymbolScope::Local, 0));
+    assert_eq!(first.define("d"), symbol("d", SymbolScope::Local, 1));
+
+    let mut second = SymbolTable::new_enclosed(first);
+    assert_eq!(second.define("e"), symbol("e", SymbolScope::Local, 0));
+
+    let cases = [
+        ("a", symbol("a", SymbolScope::Global, 0)),
+        ("len", symbol("len", SymbolScope::Builtin, 0)),
+        ("e", symbol("e", SymbolScope::Local, 0)),
+        ("d", symbol("d", SymbolScope::Free, 0)),
+        ("c", symbol("c", SymbolScope::Free, 1)),
+        ("d", symbol("d", SymbolScope::Free, 0)),
+    ];
+    for (name, expect) in cases {
+        assert_eq!(second.resolve(name), Some(expect), "{}", name);
+    }
+    assert_eq!(second.resolve("x"), None);
+    assert_eq!(
+        second.free_symbols,
+        vec![
+            symbol("d", SymbolScope::Local, 1),
+            symbol("c", SymbolScope::Local, 0)
+        ]
+    );
+    // the table in between captures nothing, `c` and `d` are its own locals
+    assert!(second.outer.unwrap().free_symbols.is_empty());
+}
+
+#[test]
+fn test_closures() {
+    let cases = vec![
+        (
+            "fn(a) { fn(b) { a + b } }",
+            vec![
+                compiled_fn(
+                    vec![
+                        make(Opcode::GetFree, &[0]),
+                        make(Opcode::GetLocal, &[0]),
+                        make(Opcode::Add, &[]),
+                        make(Opcode::ReturnValue, &[]),
+                    ],
+                    1,
+                    1,
+                ),
+                compiled_fn(
+                    vec![
+                        make(Opcode::GetLocal, &[0]),
+                        make(Opcode::Closure, &[0, 1]),
+                        make(Opcode::ReturnValue, &[]),
+                    ],
+                    1,
+                    1,
+                ),
+            ],
+            vec![make(Opcode::Closure, &[1, 0]), make(Opcode::Pop, &[])],
+        ),
+        (
+            "let countdown = fn(x) { countdown(x - 1) };",
+            vec![
+                ObjectWrapper::Integer(1),
+                compiled_fn(
+                    vec![
+                        make(Opcode::CurrentClosure, &[]),
+                        make(Opcode::GetLocal, &[0]),
+                        make(Opcode::Constant, &[0]),
+                        make(Opcode::Sub, &[]),
+                        make(Opcode::Call, &[1]),
+                        make(Opcode::ReturnValue, &[]),
+                    ],
+                    1,
+                    1,
+                ),
+            ],
+            vec![
+                make(Opcode::Closure, &[1, 0]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::Null, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+    ];
+    run_compiler_tests(cases);
+}
+
+#[test]
+fn test_builtins() {
+    let len = Builtins::instance_ref()
+        .names()
+        .iter()
+        .position(|name| name == "len")
+        .unwrap();
+    let cases = vec![(
+        "len([]); fn() { len }",
+        vec![compiled_fn(
+            vec![
+                make(Opcode::GetBuiltin, &[len]),
+                make(Opcode::ReturnValue, &[]),
+            ],
+            0,
+            0,
+        )],
+        vec![
+            make(Opcode::GetBuiltin, &[len]),
+            make(Opcode::Array, &[0]),
+            make(Opcode::Call, &[1]),
+            make(Opcode::Pop, &[]),
+            make(Opcode::Closure, &[0, 0]),
+            make(Opcode::Pop, &[]),
+        ],
+    )];
+    run_compiler_tests(cases);
+}
+
+#[test]
+fn test_constant_folding() {
+    let cases = vec![
+        (
+            "2 * 3 + 4",
+            vec![ObjectWrapper::Integer(10)],
+            vec![make(Opcode::Constant, &[0]), make(Opcode::Pop, &[])],
+        ),
+        (
+            "\"a\" + \"b\"",
+            vec![ObjectWrapper::String("ab".into())],
+            vec![make(Opcode::Constant, &[0]), make(Opcode::Pop, &[])],
+        ),
+        (
+            "!true; -(1.5 * 2.0); 1 < 2 == true",
+            vec![ObjectWrapper::Float(-3.0)],
+            vec![
+                make(Opcode::False, &[]),
+                make(Opcode::Pop, &[]),
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Pop, &[]),
+                make(Opcode::True, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+        (
+            "let x = 1; x + 2 * 3",
+            vec![ObjectWrapper::Integer(1), ObjectWrapper::Integer(6)],
+            vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::GetGlobal, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Add, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+        // errors are left for the vm to report
+        (
+            "1 / 0",
+            vec![ObjectWrapper::Integer(1), ObjectWrapper::Integer(0)],
+            vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Div, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+    ];
+    run_compiler_tests_with(
+        CompilerOptions {
+            peephole: false,
+            ..Default::default()
+        },
+        cases,
+    );
+}
+
+#[test]
+fn test_dead_code_elimination() {
+    let cases = vec![
+        (
+            "if (true) { 10 } else { 20 }; 3333;",
+            vec![ObjectWrapper::Integer(10), ObjectWrapper::Integer(3333)],
+            vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Pop, &[]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+        (
+            "if (1 > 2) { 10 }",
+            vec![],
+            vec![make(Opcode::Null, &[]), make(Opcode::Pop, &[])],
+        ),
+        (
+            "fn() { return 1; 2; 3 }",
+            vec![
+                ObjectWrapper::Integer(1),
+                compiled_fn(
+                    vec![make(Opcode::Constant, &[0]), make(Opcode::ReturnValue, &[])],
+                    0,
+                    0,
+                ),
+            ],
+            vec![make(Opcode::Closure, &[1, 0]), make(Opcode::Pop, &[])],
+        ),
+        // not a boolean, the vm reports the invalid condition
+        (
+            "if (1) { 10 }",
+            vec![ObjectWrapper::Integer(1), ObjectWrapper::Integer(10)],
+            vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::JumpNotTruthy, &[12]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Jump, &[13]),
+                make(Opcode::Null, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+    ];
+    run_compiler_tests_with(
+        CompilerOptions {
+            peephole: false,
+            ..Default::default()
+        },
+        cases,
+    );
+}
+
+#[test]
+fn test_unreachable_code_warnings() {
+    let cases = [
+        ("fn() { 1 }", vec![]),
+        ("fn() { return 1; 2; 3 }", vec![(1, 18, 22)]),
+        ("if (false) { 1 } else { 2 }", vec![(1, 14, 15)]),
+        ("if (true) { 1 }", vec![]),
+    ];
+    for (input, expect) in cases {
+        let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+        let mut compiler = Compiler::new();
+        compiler.compile(&program).unwrap();
+        let warnings = compiler
+            .warnings()
+            .iter()
+            .map(|warning| {
+                assert_eq!(warning.message, "unreachable code");
+                (
+                    warning.span.start.line,
+                    warning.span.start.column,
+                    warning.span.end.column,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(warnings, expect, "{}", input);
+    }
+}
+
+#[test]
+fn test_bytecode_round_trip() {
+    let input = r#"let greet = fn(name) { "hello " + name }; greet("monkey"); 1.5; -3"#;
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+    let mut compiler = Compiler::new();
+    compiler.compile(&program).unwrap();
+    let bytecode = compiler.bytecode();
+    assert!(!bytecode.spans.is_empty());
+
+    let bytes = bytecode.to_bytes().unwrap();
+    assert_eq!(&bytes[..6], b"MNKC\x00\x09");
+    assert_eq!(Bytecode::from_bytes(&bytes).unwrap(), bytecode);
+}
+
+// Bytecode refers to builtins by their index in this list, a file of
+// another list calls the wrong ones. Bump the version when it changes.
+#[test]
+fn test_bytecode_builtins() {
+    let names = [
+        "PI",
+        "args",
+        "assert",
+        "assert_eq",
+        "benchmark",
+        "env",
+        "eputs",
+        "error",
+        "exit",
+        "first",
+        "input",
+        "is_error",
+        "last",
+        "len",
+        "map",
+        "memoize",
+        "message",
+        "next",
+        "pmap",
+        "push",
+        "puts",
+        "random",
+        "read_file",
+        "read_line",
+        "rescue",
+        "rest",
+        "set_env",
+        "take",
+        "time",
+        "write_file",
+    ];
+    let builtins = Builtins::instance_ref().names();
+    assert_eq!(
+        (FORMAT_VERSION, builtins),
+        (9, &names.map(String::from)[..])
+    );
+}
+
+#[test]
+fn test_bytecode_from_invalid_bytes() {
+    let valid = Bytecode {
+        instructions: make(Opcode::Constant, &[0]),
+        constants: vec![ObjectWrapper::Integer(7)],
+        spans: vec![],
+    }
+    .to_bytes()
+    .unwrap();
+
+    let mut other_version = valid.clone();
+    other_version[5] = 10;
+    let mut unknown_tag = valid.clone();
+    unknown_tag[valid.len() - 9] = 42;
+    let mut trailing = valid.clone();
+    trailing.push(0);
+    let few_locals = Bytecode {
+        instructions: vec![],
+        constants: vec![ObjectWrapper::CompiledFn(Arc::new(CompiledFunction {
+            instructions: make(Opcode::Return, &[]),
+            num_locals: 1,
+            num_parameters: 2,
+            spans: vec![],
+        }))],
+        spans: vec![],
+    }
+    .to_bytes()
+    .unwrap();
+
+    let cases = [
+        (b"MNK".to_vec(), "truncated bytecode"),
+        (b"ELF\x7f\x00\x01".to_vec(), "not a monkey bytecode file"),
+        (
+            other_version,
+            "unsupported bytecode version 10, expect 9, rebuild it from the source",
+        ),
+        (valid[..valid.len() - 1].to_vec(), "truncated bytecode"),
+        (unknown_tag, "unknown constant tag: 42"),
+        (trailing, "trailing bytes after bytecode"),
+        (few_locals, "function with fewer locals than parameters"),
+    ];
+    for (bytes, expect) in cases {
+        assert_eq!(
+            Bytecode::from_bytes(&bytes).unwrap_err().to_string(),
+            expect
+        );
+    }
+
+    let unsupported = Bytecode {
+        instructions: vec![],
+        constants: vec![ObjectWrapper::Array(vec![].into())],
+        spans: vec![],
+    };
+    assert_eq!(
+        unsupported.to_bytes().unwrap_err().to_string(),
+        "cannot serialize a array constant"
+    );
+}
+
+#[test]
+fn test_peephole() {
+    let constants = [ObjectWrapper::Integer(1), ObjectWrapper::String("a".into())];
+    let cases = vec![
+        // unused constants
+        (
+            vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Pop, &[]),
+                make(Opcode::True, &[]),
+                make(Opcode::Pop, &[]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Pop, &[]),
+            ],
+            vec![make(Opcode::Constant, &[1]), make(Opcode::Pop, &[])],
+        ),
+        // the jumps are moved with the code
+        (
+            vec![
+                make(Opcode::Null, &[]),
+                make(Opcode::Pop, &[]),
+                make(Opcode::True, &[]),
+                make(Opcode::JumpNotTruthy, &[12]),
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Jump, &[13]),
+                make(Opcode::Null, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+            vec![
+                make(Opcode::True, &[]),
+                make(Opcode::JumpNotTruthy, &[10]),
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Jump, &[11]),
+                make(Opcode::Null, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+        // a popped constant a jump leads to stays
+        (
+            vec![
+                make(Opcode::True, &[]),
+                make(Opcode::JumpNotTruthy, &[8]),
+                make(Opcode::Null, &[]),
+                make(Opcode::Jump, &[9]),
+                make(Opcode::Null, &[]),
+                make(Opcode::Pop, &[]),
+                make(Opcode::Null, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+            vec![
+                make(Opcode::True, &[]),
+                make(Opcode::JumpNotTruthy, &[8]),
+                make(Opcode::Null, &[]),
+                make(Opcode::Jump, &[9]),
+                make(Opcode::Null, &[]),
+                make(Opcode::Pop, &[]),
+                make(Opcode::Null, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+        // jump chains
+        (
+            vec![
+                make(Opcode::True, &[]),
+                make(Opcode::JumpNotTruthy, &[7]),
+                make(Opcode::Jump, &[10]),
+                make(Opcode::Jump, &[4]),
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Pop, &[]),
+            ],
+            vec![
+                make(Opcode::True, &[]),
+                make(Opcode::JumpNotTruthy, &[10]),
+                make(Opcode::Jump, &[10]),
+                make(Opcode::Jump, &[10]),
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+        // double negation of booleans and numbers
+        (
+            vec![
+                make(Opcode::True, &[]),
+                make(Opcode::Bang, &[]),
+                make(Opcode::Bang, &[]),
+                make(Opcode::Bang, &[]),
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Minus, &[]),
+                make(Opcode::Minus, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+            vec![
+                make(Opcode::True, &[]),
+                make(Opcode::Bang, &[]),
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+        // unknown operands, !!x turns x into a boolean and --x fails on strings
+        (
+            vec![
+                make(Opcode::GetGlobal, &[0]),
+                make(Opcode::Bang, &[]),
+                make(Opcode::Bang, &[]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Minus, &[]),
+                make(Opcode::Minus, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+            vec![
+                make(Opcode::GetGlobal, &[0]),
+                make(Opcode::Bang, &[]),
+                make(Opcode::Bang, &[]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Minus, &[]),
+                make(Opcode::Minus, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        ),
+    ];
+    for (before, after) in cases {
+        let before = before.concat();
+        let after = after.concat();
+        assert_eq!(
+            disassemble(
+                &peephole::optimize(&before, &[], &constants, true).0,
+                &constants
+            ),
+            disassemble(&after, &constants),
+            "{}",
+            disassemble(&before, &constants)
+        );
+    }
+}
+
+// the inner jump at 0013 led to the outer one at 0019
+#[test]
+fn test_peephole_nested_conditionals() {
+    let input = "fn(a, b) { if (a) { if (b) { 1 } else { 2 } } else { 3 } }";
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+    let mut compiler = Compiler::new();
+    compiler.compile(&program).unwrap();
+    let expect = "0000 OpClosure 3 0 ; fn 3
+0004 OpPop
+
+fn 3 (parameters: 2, locals: 2):
+0000 OpGetLocal 0
+0002 OpJumpNotTruthy 22
+0005 OpGetLocal 1
+0007 OpJumpNotTruthy 16
+0010 OpConstant 0 ; 1
+0013 OpJump 25
+0016 OpConstant 1 ; 2
+0019 OpJump 25
+0022 OpConstant 2 ; 3
+0025 OpReturnValue
+";
+    assert_eq!(compiler.bytecode().disassemble(), expect);
+}
+
+#[test]
+fn test_error_spans() {
+    let cases = [
+        (
+            "let a = 1;\nlet b = a + c;",
+            "undefined variable: c",
+            (2, 13),
+        ),
+        ("fn(x) {\n  x + y\n}", "undefined variable: y", (2, 7)),
+    ];
+    for (input, message, (line, column)) in cases {
+        let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+        let e = Compiler::new().compile(&program).unwrap_err();
+        assert_eq!(e.message(), message, "{}", input);
+        let span = e.span().expect("compile errors have a span");
+        assert_eq!(
+            (span.start.line, span.start.column),
+            (line, column),
+            "{}",
+            input
+        );
+    }
+
+    let input = "let a = 1;\nputs(b);";
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+    let e = Compiler::new().compile(&program).unwrap_err();
+    assert_eq!(
+        e.render(input, Some("main.mk")),
+        "\
+error: undefined variable: b
+  --> main.mk:2:6
+  |
+2 | puts(b);
+  |      ^
+"
+    );
+}
@@ -0,0 +1,179 @@
+use crate::highlight::RESET;
+use crate::lexer::span::Span;
+use crate::parser::CallFrame;
+use std::fmt::Write;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(test)]
+mod test;
+
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const CYAN: &str = "\x1b[36m";
+const BOLD: &str = "\x1b[1m";
+
+// off by default, see `set_color`
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+// Whether `render` colors its output with ANSI escapes, for all threads.
+pub fn set_color(enabled: bool) {
+    COLOR.store(enabled, Ordering::Relaxed);
+}
+
+// diagnostics go to stderr, they are colored if it is a terminal and
+// NO_COLOR is not set, see https://no-color.org
+pub fn color_supported() -> bool {
+    std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+}
+
+// red for errors, yellow for warnings, `warning[rule]` included
+fn level_style(level: &str) -> &'static str {
+    if level.starts_with("warning") {
+        YELLOW
+    } else {
+        RED
+    }
+}
+
+// wraps text in an escape and RESET when colors are on
+struct Paint(bool);
+
+impl Paint {
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.0 {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn level(&self, level: &str) -> String {
+        self.paint(level_style(level), level)
+    }
+
+    fn header(&self, level: &str, message: &str) -> String {
+        format!("{}: {}", self.level(level), self.paint(BOLD, message))
+    }
+}
+
+// a message without a location in the source, `path` names the file it is
+// about
+pub fn render_message(level: &str, message: &str, path: Option<&str>) -> String {
+    let paint = Paint(COLOR.load(Ordering::Relaxed));
+    match path {
+        Some(path) => format!(
+            "{}: {}\n",
+            paint.level(level),
+            paint.paint(BOLD, &format!("{}: {}", path, message))
+        ),
+        None => format!("{}\n", paint.header(level, message)),
+    }
+}
+
+// Renders a message with the source line it points at, `path` names the
+// source if it came from a file:
+//
+//   error: undefined variable: x
+//    --> script.mk:1:9
+//     |
+//   1 | let y = x + 1;
+//     |         ^
+//
+// Spans over several lines are underlined up to the end of the first one.
+pub fn render(level: &str, message: &str, source: &str, span: Span, path: Option<&str>) -> String {
+    render_with(
+        &Paint(COLOR.load(Ordering::Relaxed)),
+        level,
+        message,
+        source,
+        span,
+        path,
+    )
+}
+
+fn render_with(
+    paint: &Paint,
+    level: &str,
+    message: &str,
+    source: &str,
+    span: Span,
+    path: Option<&str>,
+) -> String {
+    let mut ret = format!("{}\n", paint.header(level, message));
+    let location = match path {
+        Some(path) => format!("{}:{}", path, span.start),
+        None => span.start.to_string(),
+    };
+    let number = span.start.line.to_string();
+    let gutter = " ".repeat(number.len());
+    writeln!(
+        ret,
+        "{}{}",
+        gutter,
+        paint.paint(CYAN, &format!(" --> {}", location))
+    )
+    .unwrap();
+    let bar = paint.paint(CYAN, " |");
+    let line = match source.lines().nth(span.start.line.wrapping_sub(1)) {
+        Some(line) => line,
+        None => return ret,
+    };
+    writeln!(ret, "{}{}", gutter, bar).unwrap();
+    writeln!(ret, "{}{} {}", paint.paint(CYAN, &number), bar, line).unwrap();
+
+    let start = span.start.column.max(1) - 1;
+    let len = line.chars().count();
+    let end = if span.end.line == span.start.line {
+        span.end.column.max(1) - 1
+    } else {
+        len
+    };
+    // tabs are kept so the carets line up with the source
+    let indent: String = line
+        .chars()
+        .chain(std::iter::repeat(' '))
+        .take(start)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let carets = "^".repeat(end.saturating_sub(start).max(1));
+    let carets = paint.paint(level_style(level), &carets);
+    writeln!(ret, "{}{} {}{}", gutter, bar, indent, carets).unwrap();
+    ret
+}
+
+// The calls a runtime error was raised in, innermost first, printed under
+// what `render` printed for it:
+//
+//     = in fib, called at script.mk:3:21, 41 times
+//     = in <anonymous>, called at script.mk:7:1
+//
+// The frames of a recursion, the same call again and again, are printed once.
+pub fn render_trace(trace: &[CallFrame], path: Option<&str>) -> String {
+    let paint = Paint(COLOR.load(Ordering::Relaxed));
+    let mut ret = String::new();
+    let mut frames = trace.iter().peekable();
+    while let Some(frame) = frames.next() {
+        let mut times = 1;
+        while frames.next_if_eq(&frame).is_some() {
+            times += 1;
+        }
+        let location = match path {
+            Some(path) => format!("{}:{}", path, frame.span.start),
+            None => frame.span.start.to_string(),
+        };
+        write!(
+            ret,
+            "  {} in {}, called at {}",
+            paint.paint(CYAN, "="),
+            frame.function,
+            location
+        )
+        .unwrap();
+        if times > 1 {
+            write!(ret, ", {} times", times).unwrap();
+        }
+        ret.push('\n');
+    }
+    ret
+}
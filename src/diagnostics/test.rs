@@ -0,0 +1,108 @@
+use crate::diagnostics::{render, render_trace, render_with, Paint};
+use crate::lexer::span::{Position, Span};
+use crate::parser::CallFrame;
+
+#[cfg(test)]
+fn span(start: (usize, usize), end: (usize, usize)) -> Span {
+    Span::new(
+        Position {
+            offset: 0,
+            line: start.0,
+            column: start.1,
+        },
+        Position {
+            offset: 0,
+            line: end.0,
+            column: end.1,
+        },
+    )
+}
+
+#[test]
+fn test_render() {
+    let source = "let a = 1;\nlet b = a + c;\n\tfoo(1,\n2)";
+    let cases = [
+        (
+            span((2, 13), (2, 14)),
+            None,
+            "\
+error: oops
+  --> 2:13
+  |
+2 | let b = a + c;
+  |             ^
+",
+        ),
+        (
+            span((2, 9), (2, 14)),
+            Some("main.mk"),
+            "\
+error: oops
+  --> main.mk:2:9
+  |
+2 | let b = a + c;
+  |         ^^^^^
+",
+        ),
+        (
+            span((3, 2), (4, 3)),
+            None,
+            "\
+error: oops
+  --> 3:2
+  |
+3 | \tfoo(1,
+  | \t^^^^^^
+",
+        ),
+        // past the end of the source, at EOF
+        (
+            span((5, 1), (5, 1)),
+            None,
+            "\
+error: oops
+  --> 5:1
+",
+        ),
+    ];
+    for (span, path, expect) in cases {
+        assert_eq!(render("error", "oops", source, span, path), expect);
+    }
+}
+
+#[test]
+fn test_render_color() {
+    let source = "let x = y;";
+    let expect = "\
+\x1b[1;33mwarning\x1b[0m: \x1b[1munused\x1b[0m
+ \x1b[36m --> a.mk:1:5\x1b[0m
+ \x1b[36m |\x1b[0m
+\x1b[36m1\x1b[0m\x1b[36m |\x1b[0m let x = y;
+ \x1b[36m |\x1b[0m     \x1b[1;33m^\x1b[0m
+";
+    let ret = render_with(
+        &Paint(true),
+        "warning",
+        "unused",
+        source,
+        span((1, 5), (1, 6)),
+        Some("a.mk"),
+    );
+    assert_eq!(ret, expect);
+}
+
+#[test]
+fn test_render_trace() {
+    let frame = |function: &str, line| CallFrame {
+        function: function.to_string(),
+        span: span((line, 3), (line, 7)),
+    };
+    let trace = [frame("f", 1), frame("f", 1), frame("f", 1), frame("g", 5)];
+    let expect = concat!(
+        "  = in f, called at a.mk:1:3, 3 times\n",
+        "  = in g, called at a.mk:5:3\n",
+    );
+    assert_eq!(render_trace(&trace, Some("a.mk")), expect);
+    assert_eq!(render_trace(&trace[3..], None), "  = in g, called at 5:3\n");
+    assert_eq!(render_trace(&[], None), "");
+}
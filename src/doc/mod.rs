@@ -0,0 +1,121 @@
+use crate::lexer::span::Span;
+use crate::parser::program::{Expression, Program, Statement};
+use std::fmt::Write;
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocFormat {
+    Markdown,
+    // a standalone page
+    Html,
+}
+
+// a documented top-level binding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocItem {
+    pub name: String,
+    // None when the binding is not a function
+    pub params: Option<Vec<String>>,
+    // its `///` comments, see `Program::doc`
+    pub doc: Option<String>,
+    pub span: Span,
+}
+
+impl DocItem {
+    // `add(a, b)` for functions, the name for the rest
+    pub fn signature(&self) -> String {
+        match &self.params {
+            Some(params) => format!("{}({})", self.name, params.join(", ")),
+            None => self.name.clone(),
+        }
+    }
+}
+
+// The top-level functions, and the other top-level bindings that have doc
+// comments, in the order they are defined.
+pub fn extract(program: &Program) -> Vec<DocItem> {
+    let mut ret = vec![];
+    for statement in &program.statements {
+        let (ident, value, span) = match statement {
+            Statement::LetStatement(ident, value, span) => (ident, value, span),
+            _ => continue,
+        };
+        let params = match value {
+            Expression::FunctionExpression(params, _, _) => {
+                Some(params.iter().map(|param| param.0.to_string()).collect())
+            }
+            _ => None,
+        };
+        let doc = program.doc(statement);
+        if params.is_some() || doc.is_some() {
+            ret.push(DocItem {
+                name: ident.0.to_string(),
+                params,
+                doc,
+                span: *span,
+            });
+        }
+    }
+    ret
+}
+
+// the items of each file under a heading with the file's name
+pub fn render(files: &[(String, Vec<DocItem>)], format: DocFormat) -> String {
+    match format {
+        DocFormat::Markdown => markdown(files),
+        DocFormat::Html => html(files),
+    }
+}
+
+fn markdown(files: &[(String, Vec<DocItem>)]) -> String {
+    let mut ret = String::new();
+    for (name, items) in files {
+        if !ret.is_empty() {
+            ret.push('\n');
+        }
+        writeln!(ret, "# {}", name).unwrap();
+        for item in items {
+            write!(ret, "\n## `{}`\n", item.signature()).unwrap();
+            if let Some(doc) = &item.doc {
+                write!(ret, "\n{}\n", doc).unwrap();
+            }
+        }
+    }
+    ret
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn html(files: &[(String, Vec<DocItem>)]) -> String {
+    let mut ret = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Documentation</title>\n</head>\n<body>\n",
+    );
+    for (name, items) in files {
+        writeln!(ret, "<h1>{}</h1>", escape(name)).unwrap();
+        for item in items {
+            writeln!(
+                ret,
+                "<h2 id=\"{}\"><code>{}</code></h2>",
+                escape(&item.name),
+                escape(&item.signature())
+            )
+            .unwrap();
+            // blank lines separate paragraphs
+            let doc = item.doc.as_deref().unwrap_or_default();
+            for paragraph in doc.split("\n\n").filter(|p| !p.trim().is_empty()) {
+                let lines = paragraph.lines().map(escape).collect::<Vec<String>>();
+                writeln!(ret, "<p>{}</p>", lines.join("<br>\n")).unwrap();
+            }
+        }
+    }
+    ret.push_str("</body>\n</html>\n");
+    ret
+}
@@ -0,0 +1,59 @@
+use crate::doc::{extract, render, DocFormat};
+use crate::lexer::lexer::Lexer;
+use crate::parser::Parser;
+
+#[cfg(test)]
+fn parse_docs(input: &str) -> Vec<(String, Vec<crate::doc::DocItem>)> {
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+    vec![("math.mk".to_string(), extract(&program))]
+}
+
+#[test]
+fn test_extract() {
+    let input = "/// Adds two numbers.
+///
+/// Returns a < b.
+let add = fn(a, b) { a + b };
+// not a doc comment
+let sub = fn(a, b) { a - b };
+/// The ratio.
+let ratio = 1.5;
+let hidden = 2; /// trailing, not a doc comment
+let shown = fn() { 1 };
+";
+    let docs = parse_docs(input);
+    let items = docs[0]
+        .1
+        .iter()
+        .map(|item| (item.signature(), item.doc.as_deref(), item.span.start.line))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        items,
+        vec![
+            (
+                "add(a, b)".to_string(),
+                Some("Adds two numbers.\n\nReturns a < b."),
+                4
+            ),
+            ("sub(a, b)".to_string(), None, 6),
+            ("ratio".to_string(), Some("The ratio."), 8),
+            ("shown()".to_string(), None, 10),
+        ]
+    );
+}
+
+#[test]
+fn test_render() {
+    let docs =
+        parse_docs("/// Adds.\n/// Fast.\nlet add = fn(a, b) { a + b };\nlet one = fn() { 1 };");
+    assert_eq!(
+        render(&docs, DocFormat::Markdown),
+        "# math.mk\n\n## `add(a, b)`\n\nAdds.\nFast.\n\n## `one()`\n"
+    );
+    let html = render(&docs, DocFormat::Html);
+    assert!(html.starts_with("<!DOCTYPE html>\n"));
+    assert!(html.contains(
+        "<h1>math.mk</h1>\n<h2 id=\"add\"><code>add(a, b)</code></h2>\n<p>Adds.<br>\nFast.</p>\n\
+         <h2 id=\"one\"><code>one()</code></h2>\n</body>"
+    ));
+}
@@ -0,0 +1,93 @@
+use crate::eval::evaluator::Evaluator;
+use crate::eval::{HashKey, HashMap, ObjectWrapper};
+use crate::parser::Result;
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+// how long the calls of a function took
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub iterations: usize,
+    pub min: Duration,
+    pub mean: Duration,
+    pub max: Duration,
+}
+
+// the keys of the hash `benchmark` returns, durations are in milliseconds
+const KEYS: [&str; 4] = ["iterations", "min", "mean", "max"];
+
+impl Stats {
+    // `{"iterations": 100, "min": 0.12, "mean": 0.13, "max": 0.3}`
+    pub fn to_object(&self) -> ObjectWrapper {
+        let millis = |duration: Duration| ObjectWrapper::Float(duration.as_secs_f64() * 1000.0);
+        let values = [
+            ObjectWrapper::Integer(self.iterations as i64),
+            millis(self.min),
+            millis(self.mean),
+            millis(self.max),
+        ];
+        let hash = KEYS
+            .iter()
+            .map(|key| HashKey::String((*key).into()))
+            .zip(values)
+            .collect::<HashMap<HashKey, ObjectWrapper>>();
+        ObjectWrapper::Hash(hash)
+    }
+
+    // the stats `to_object` made
+    pub fn from_object(obj: &ObjectWrapper) -> Option<Self> {
+        let hash = match obj {
+            ObjectWrapper::Hash(hash) => hash,
+            _ => return None,
+        };
+        let get = |key: &str| hash.get(&HashKey::String(key.into()));
+        let millis = |key: &str| match get(key) {
+            Some(ObjectWrapper::Float(v)) => Some(Duration::from_secs_f64(v / 1000.0)),
+            _ => None,
+        };
+        match get(KEYS[0]) {
+            Some(ObjectWrapper::Integer(iterations)) => Some(Stats {
+                iterations: *iterations as usize,
+                min: millis(KEYS[1])?,
+                mean: millis(KEYS[2])?,
+                max: millis(KEYS[3])?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "min {:.3?}, mean {:.3?}, max {:.3?} ({} iterations)",
+            self.min, self.mean, self.max, self.iterations
+        )
+    }
+}
+
+// Calls `function` without arguments `iterations` times, which is not 0,
+// timing each call. It stops at the first call raising an error or
+// evaluating to one.
+pub fn measure(ev: &mut Evaluator, function: &ObjectWrapper, iterations: usize) -> Result<Stats> {
+    let mut total = Duration::ZERO;
+    let (mut min, mut max) = (Duration::MAX, Duration::ZERO);
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let ret = ev.apply_function(function, vec![])?;
+        let elapsed = start.elapsed();
+        if let ObjectWrapper::ErrorObject(message) = ret {
+            return Err(message.into());
+        }
+        total += elapsed;
+        min = min.min(elapsed);
+        max = max.max(elapsed);
+    }
+    Ok(Stats {
+        iterations,
+        min,
+        mean: total.div_f64(iterations as f64),
+        max,
+    })
+}
@@ -1,37 +1,25 @@
-use crate::eval::ObjectWrapper;
+use crate::eval::capabilities::Capabilities;
+use crate::eval::evaluator::Evaluator;
+use crate::eval::iterator::Iter;
+use crate::eval::memo::Memo;
+use crate::eval::output::format_value;
+use crate::eval::printer::ObjectPrinter;
+use crate::eval::{benchmark, parallel};
+use crate::eval::{HashMap, ObjectWrapper};
 use crate::parser::Result;
 use lazy_static::lazy_static;
-use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::convert::TryFrom;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
-
-impl Into<ObjectWrapper> for bool {
-    fn into(self) -> ObjectWrapper {
-        ObjectWrapper::Boolean(self)
-    }
-}
-
-impl Into<ObjectWrapper> for i64 {
-    fn into(self) -> ObjectWrapper {
-        ObjectWrapper::Integer(self)
-    }
-}
-
-impl Into<ObjectWrapper> for f64 {
-    fn into(self) -> ObjectWrapper {
-        ObjectWrapper::Float(self)
-    }
-}
-
-impl Into<ObjectWrapper> for String {
-    fn into(self) -> ObjectWrapper {
-        ObjectWrapper::String(self)
-    }
-}
+use std::time::{SystemTime, UNIX_EPOCH};
 
 lazy_static! {
     static ref BUILTINS_INS: Builtins = {
-        let ret = Builtins {
+        let mut ret = Builtins {
             builtins: Arc::new(Mutex::new(HashMap::default())),
+            names: vec![],
         };
         let builtins = ret.builtins.clone();
         let mut maps = builtins.lock().unwrap();
@@ -39,109 +27,654 @@ lazy_static! {
 
         maps.insert(
             "len".to_string(),
-            ObjectWrapper::BuiltinFn(1, |args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
-                if args.len() != 1 {
-                    return Err(
-                        format!("Wrong number of arguments, expect 1 got {}", args.len()).into(),
-                    );
-                }
-                match &args[0] {
-                    ObjectWrapper::String(v) => Ok(ObjectWrapper::Integer(v.len() as i64)),
-                    ObjectWrapper::Array(array) => Ok(ObjectWrapper::Integer(array.len() as i64)),
-                    _ => Err(format!(
-                        "Argument to `len` not supported, got {}",
-                        args[0].type_str()
-                    )
-                    .into()),
-                }
-            }),
+            ObjectWrapper::BuiltinFn(
+                1,
+                |_: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    if args.len() != 1 {
+                        return Err(format!(
+                            "Wrong number of arguments, expect 1 got {}",
+                            args.len()
+                        )
+                        .into());
+                    }
+                    match &args[0] {
+                        ObjectWrapper::String(v) => Ok(ObjectWrapper::Integer(v.len() as i64)),
+                        ObjectWrapper::Array(array) => {
+                            Ok(ObjectWrapper::Integer(array.len() as i64))
+                        }
+                        ObjectWrapper::Hash(hash) => Ok(ObjectWrapper::Integer(hash.len() as i64)),
+                        _ => Err(format!(
+                            "Argument to `len` not supported, got {}",
+                            args[0].type_str()
+                        )
+                        .into()),
+                    }
+                },
+            ),
         );
 
         maps.insert(
             "first".to_string(),
-            ObjectWrapper::BuiltinFn(1, |args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
-                if args.len() != 1 {
-                    return Err(
-                        format!("Wrong number of arguments, expect 1 got {}", args.len()).into(),
-                    );
-                }
-
-                if let ObjectWrapper::Array(array) = &args[0] {
-                    if array.len() > 0 {
-                        Ok(array[0].clone())
+            ObjectWrapper::BuiltinFn(
+                1,
+                |_: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    if args.len() != 1 {
+                        return Err(format!(
+                            "Wrong number of arguments, expect 1 got {}",
+                            args.len()
+                        )
+                        .into());
+                    }
+
+                    if let ObjectWrapper::Array(array) = &args[0] {
+                        if !array.is_empty() {
+                            Ok(array[0].clone())
+                        } else {
+                            Ok(ObjectWrapper::Null)
+                        }
                     } else {
-                        Ok(ObjectWrapper::Null)
+                        Err(format!(
+                            "Argument to 'first' must be ARRAY, got {:?}",
+                            args[0].type_str()
+                        )
+                        .into())
                     }
-                } else {
-                    return Err(format!(
-                        "Argument to 'first' must be ARRAY, got {:?}",
-                        args[0].type_str()
-                    )
-                    .into());
-                }
-            }),
+                },
+            ),
         );
 
         maps.insert(
             "last".to_string(),
-            ObjectWrapper::BuiltinFn(1, |args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
-                if args.len() != 1 {
-                    return Err(
-                        format!("Wrong number of arguments, expect 1 got {}", args.len()).into(),
-                    );
-                }
-
-                if let ObjectWrapper::Array(array) = &args[0] {
-                    if array.len() > 0 {
-                        Ok(array[array.len() - 1].clone())
+            ObjectWrapper::BuiltinFn(
+                1,
+                |_: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    if args.len() != 1 {
+                        return Err(format!(
+                            "Wrong number of arguments, expect 1 got {}",
+                            args.len()
+                        )
+                        .into());
+                    }
+
+                    if let ObjectWrapper::Array(array) = &args[0] {
+                        if !array.is_empty() {
+                            Ok(array[array.len() - 1].clone())
+                        } else {
+                            Ok(ObjectWrapper::Null)
+                        }
                     } else {
-                        Ok(ObjectWrapper::Null)
+                        Err(format!(
+                            "Argument to 'last' must be ARRAY, got {:?}",
+                            args[0].type_str()
+                        )
+                        .into())
                     }
-                } else {
-                    return Err(format!(
-                        "Argument to 'last' must be ARRAY, got {:?}",
-                        args[0].type_str()
-                    )
-                    .into());
-                }
-            }),
+                },
+            ),
         );
 
         maps.insert(
             "rest".to_string(),
-            ObjectWrapper::BuiltinFn(1, |args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
-                if args.len() != 1 {
-                    return Err(
-                        format!("Wrong number of arguments, expect 1 got {}", args.len()).into(),
-                    );
-                }
-
-                if let ObjectWrapper::Array(array) = &args[0] {
-                    if array.len() > 0 {
-                        Ok(ObjectWrapper::Array(
-                            array[0..array.len() - 1]
-                                .iter()
-                                .map(|ele| ele.clone())
-                                .collect(),
-                        ))
+            ObjectWrapper::BuiltinFn(
+                1,
+                |_: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    if args.len() != 1 {
+                        return Err(format!(
+                            "Wrong number of arguments, expect 1 got {}",
+                            args.len()
+                        )
+                        .into());
+                    }
+
+                    if let ObjectWrapper::Array(array) = &args[0] {
+                        if !array.is_empty() {
+                            Ok(ObjectWrapper::Array(array[0..array.len() - 1].to_vec().into()))
+                        } else {
+                            Ok(ObjectWrapper::Null)
+                        }
                     } else {
-                        Ok(ObjectWrapper::Null)
+                        Err(format!(
+                            "Argument to 'rest' must be ARRAY, got {:?}",
+                            args[0].type_str()
+                        )
+                        .into())
+                    }
+                },
+            ),
+        );
+
+        // returns a new array, the argument is left untouched, it is only
+        // copied when it is used elsewhere
+        maps.insert(
+            "push".to_string(),
+            ObjectWrapper::BuiltinFn(
+                2,
+                |_: &mut Evaluator, mut args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    let value = args.pop().unwrap();
+                    match args.pop().unwrap() {
+                        ObjectWrapper::Array(mut array) => {
+                            Arc::make_mut(&mut array).push(value);
+                            Ok(ObjectWrapper::Array(array))
+                        }
+                        other => Err(format!(
+                            "Argument to 'push' must be ARRAY, got {:?}",
+                            other.type_str()
+                        )
+                        .into()),
+                    }
+                },
+            ),
+        );
+
+        // next(iterator): its next item, null once it is exhausted
+        maps.insert(
+            "next".to_string(),
+            ObjectWrapper::BuiltinFn(
+                1,
+                |ev: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    let item = expect_iterator("next", &args[0])?.next(ev)?;
+                    Ok(item.unwrap_or(ObjectWrapper::Null))
+                },
+            ),
+        );
+
+        // take(iterator, n): an array of its next n items, fewer once it is
+        // exhausted
+        maps.insert(
+            "take".to_string(),
+            ObjectWrapper::BuiltinFn(
+                2,
+                |ev: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    let iterator = expect_iterator("take", &args[0])?;
+                    let n = match &args[1] {
+                        ObjectWrapper::Integer(n) if *n >= 0 => *n,
+                        _ => {
+                            return Err(format!(
+                                "Argument to `take` must be a non-negative INTEGER, got {}",
+                                ObjectPrinter::new().print(&args[1])
+                            )
+                            .into())
+                        }
+                    };
+                    let mut items = vec![];
+                    for _ in 0..n {
+                        match iterator.next(ev)? {
+                            Some(item) => items.push(item),
+                            None => break,
+                        }
+                    }
+                    Ok(ObjectWrapper::Array(items.into()))
+                },
+            ),
+        );
+
+        // map(iterator, function): an iterator calling `function` on the
+        // items as they are read
+        maps.insert(
+            "map".to_string(),
+            ObjectWrapper::BuiltinFn(
+                2,
+                |_: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    let iterator = expect_iterator("map", &args[0])?;
+                    Ok(ObjectWrapper::Iterator(iterator.map(args[1].clone())))
+                },
+            ),
+        );
+
+        // pmap(array, function): the array of what `function` returns for
+        // each item, called on all cores with the `parallel` feature
+        maps.insert(
+            "pmap".to_string(),
+            ObjectWrapper::BuiltinFn(
+                2,
+                |ev: &mut Evaluator, mut args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    let function = args.pop().unwrap();
+                    match args.pop().unwrap() {
+                        ObjectWrapper::Array(items) => {
+                            let items = Arc::unwrap_or_clone(items);
+                            Ok(ObjectWrapper::Array(parallel::map(ev, items, &function)?.into()))
+                        }
+                        other => Err(format!(
+                            "Argument to 'pmap' must be ARRAY, got {:?}",
+                            other.type_str()
+                        )
+                        .into()),
+                    }
+                },
+            ),
+        );
+
+        // memoize(function): the function remembering what it returned for
+        // the arguments it was called with
+        maps.insert(
+            "memoize".to_string(),
+            ObjectWrapper::BuiltinFn(
+                1,
+                |_: &mut Evaluator, mut args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    match args.pop().unwrap() {
+                        function @ (ObjectWrapper::FunctionObject(..)
+                        | ObjectWrapper::Closure(_)
+                        | ObjectWrapper::BuiltinFn(..)
+                        | ObjectWrapper::HostFn(_)) => {
+                            Ok(ObjectWrapper::Memoized(Memo::new(function)))
+                        }
+                        memo @ ObjectWrapper::Memoized(_) => Ok(memo),
+                        other => Err(format!(
+                            "Argument to `memoize` must be FUNCTION, got {}",
+                            other.type_str()
+                        )
+                        .into()),
+                    }
+                },
+            ),
+        );
+
+        maps.insert(
+            "error".to_string(),
+            ObjectWrapper::BuiltinFn(
+                1,
+                |_: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    match &args[0] {
+                        ObjectWrapper::String(msg) => Ok(ObjectWrapper::ErrorObject(msg.to_string())),
+                        _ => Err(format!(
+                            "Argument to 'error' must be STRING, got {:?}",
+                            args[0].type_str()
+                        )
+                        .into()),
+                    }
+                },
+            ),
+        );
+
+        // fail the script, for tests run by `monkey test`
+        maps.insert(
+            "assert".to_string(),
+            ObjectWrapper::BuiltinFn(
+                2,
+                |_: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    match (&args[0], &args[1]) {
+                        (ObjectWrapper::Boolean(true), _) => Ok(ObjectWrapper::Null),
+                        (ObjectWrapper::Boolean(false), message) => {
+                            Err(format!("assertion failed: {}", format_value(message)).into())
+                        }
+                        _ => Err(format!(
+                            "Argument to 'assert' must be BOOLEAN, got {:?}",
+                            args[0].type_str()
+                        )
+                        .into()),
+                    }
+                },
+            ),
+        );
+
+        maps.insert(
+            "assert_eq".to_string(),
+            ObjectWrapper::BuiltinFn(
+                2,
+                |_: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    if args[0] == args[1] {
+                        return Ok(ObjectWrapper::Null);
                     }
-                } else {
-                    return Err(format!(
-                        "Argument to 'rest' must be ARRAY, got {:?}",
-                        args[0].type_str()
+                    let printer = ObjectPrinter::new();
+                    Err(format!(
+                        "assertion failed: expect {}, got {}",
+                        printer.print(&args[1]),
+                        printer.print(&args[0])
                     )
-                    .into());
-                }
-            }),
+                    .into())
+                },
+            ),
+        );
+
+        maps.insert(
+            "is_error".to_string(),
+            ObjectWrapper::BuiltinFn(
+                1,
+                |_: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    Ok(matches!(args[0], ObjectWrapper::ErrorObject(_)).into())
+                },
+            ),
+        );
+
+        // the text of an error, as `error` was given it
+        maps.insert(
+            "message".to_string(),
+            ObjectWrapper::BuiltinFn(
+                1,
+                |_: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    match &args[0] {
+                        ObjectWrapper::ErrorObject(msg) => Ok(ObjectWrapper::String(msg.as_str().into())),
+                        _ => Err(format!(
+                            "Argument to 'message' must be ERROR, got {:?}",
+                            args[0].type_str()
+                        )
+                        .into()),
+                    }
+                },
+            ),
         );
+
+        // rescue(body, handler): calls `body` with no arguments, if it raises an
+        // error or evaluates to an error value, `handler` is called with that
+        // error and its result is returned instead
+        maps.insert(
+            "rescue".to_string(),
+            ObjectWrapper::BuiltinFn(
+                2,
+                |ev: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    let err = match ev.apply_function(&args[0], vec![]) {
+                        Ok(ObjectWrapper::ErrorObject(msg)) => msg,
+                        Ok(obj) => return Ok(obj),
+                        Err(e) if ev.exit_code().is_some() => return Err(e),
+                        Err(e) => e.to_string(),
+                    };
+                    ev.apply_function(&args[1], vec![ObjectWrapper::ErrorObject(err)])
+                },
+            ),
+        );
+
+        // prints its argument and a newline to the script's stdout
+        maps.insert(
+            "puts".to_string(),
+            ObjectWrapper::BuiltinFn(
+                1,
+                |ev: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    ev.require(Capabilities::OUTPUT, "puts")?;
+                    ev.output()
+                        .stdout
+                        .write_line(&format_value(&args[0]))
+                        .map(|_| ObjectWrapper::Null)
+                        .map_err(|e| format!("cannot write to stdout: {}", e).into())
+                },
+            ),
+        );
+
+        maps.insert(
+            "eputs".to_string(),
+            ObjectWrapper::BuiltinFn(
+                1,
+                |ev: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    ev.require(Capabilities::OUTPUT, "eputs")?;
+                    ev.output()
+                        .stderr
+                        .write_line(&format_value(&args[0]))
+                        .map(|_| ObjectWrapper::Null)
+                        .map_err(|e| format!("cannot write to stderr: {}", e).into())
+                },
+            ),
+        );
+
+        // input(prompt) prints the prompt and returns the next line of the
+        // script's stdin, read_line() only reads. Both return null at the end.
+        maps.insert(
+            "input".to_string(),
+            ObjectWrapper::BuiltinFn(
+                1,
+                |ev: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    ev.require(Capabilities::INPUT, "input")?;
+                    ev.require(Capabilities::OUTPUT, "input")?;
+                    ev.output()
+                        .stdout
+                        .write_str(&format_value(&args[0]))
+                        .map_err(|e| format!("cannot write to stdout: {}", e))?;
+                    read_input_line(ev)
+                },
+            ),
+        );
+
+        maps.insert(
+            "read_line".to_string(),
+            ObjectWrapper::BuiltinFn(
+                0,
+                |ev: &mut Evaluator, _: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    ev.require(Capabilities::INPUT, "read_line")?;
+                    read_input_line(ev)
+                },
+            ),
+        );
+
+        maps.insert(
+            "read_file".to_string(),
+            ObjectWrapper::BuiltinFn(
+                1,
+                |ev: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    ev.require(Capabilities::FILESYSTEM, "read_file")?;
+                    match &args[0] {
+                        ObjectWrapper::String(path) => std::fs::read_to_string(&**path)
+                            .map(ObjectWrapper::from)
+                            .map_err(|e| format!("cannot read {}: {}", path, e).into()),
+                        _ => Err(format!(
+                            "Argument to 'read_file' must be STRING, got {:?}",
+                            args[0].type_str()
+                        )
+                        .into()),
+                    }
+                },
+            ),
+        );
+
+        maps.insert(
+            "write_file".to_string(),
+            ObjectWrapper::BuiltinFn(
+                2,
+                |ev: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    ev.require(Capabilities::FILESYSTEM, "write_file")?;
+                    match (&args[0], &args[1]) {
+                        (ObjectWrapper::String(path), ObjectWrapper::String(content)) => {
+                            std::fs::write(&**path, content.as_bytes())
+                                .map(|_| ObjectWrapper::Null)
+                                .map_err(|e| format!("cannot write {}: {}", path, e).into())
+                        }
+                        _ => Err(format!(
+                            "Arguments to 'write_file' must be STRING, got {:?} and {:?}",
+                            args[0].type_str(),
+                            args[1].type_str()
+                        )
+                        .into()),
+                    }
+                },
+            ),
+        );
+
+        // milliseconds since the unix epoch
+        maps.insert(
+            "time".to_string(),
+            ObjectWrapper::BuiltinFn(
+                0,
+                |ev: &mut Evaluator, _: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    ev.require(Capabilities::CLOCK, "time")?;
+                    let now = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_err(|e| format!("cannot read the clock: {}", e))?;
+                    Ok(ObjectWrapper::Integer(now.as_millis() as i64))
+                },
+            ),
+        );
+
+        // benchmark(function, iterations): calls `function` without arguments
+        // `iterations` times, see `Stats::to_object` for what it returns
+        maps.insert(
+            "benchmark".to_string(),
+            ObjectWrapper::BuiltinFn(
+                2,
+                |ev: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    ev.require(Capabilities::CLOCK, "benchmark")?;
+                    match &args[1] {
+                        ObjectWrapper::Integer(n) if *n > 0 => {
+                            Ok(benchmark::measure(ev, &args[0], *n as usize)?.to_object())
+                        }
+                        _ => Err(format!(
+                            "Argument to `benchmark` must be a positive INTEGER, got {}",
+                            ObjectPrinter::new().print(&args[1])
+                        )
+                        .into()),
+                    }
+                },
+            ),
+        );
+
+        // a float in [0, 1)
+        maps.insert(
+            "random".to_string(),
+            ObjectWrapper::BuiltinFn(
+                0,
+                |ev: &mut Evaluator, _: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    ev.require(Capabilities::RANDOM, "random")?;
+                    Ok(ObjectWrapper::Float(
+                        (next_random() >> 11) as f64 / (1u64 << 53) as f64,
+                    ))
+                },
+            ),
+        );
+
+        // the value of a process environment variable, null if it is not set
+        maps.insert(
+            "env".to_string(),
+            ObjectWrapper::BuiltinFn(
+                1,
+                |ev: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    ev.require(Capabilities::ENVIRONMENT, "env")?;
+                    match &args[0] {
+                        ObjectWrapper::String(name) => match ev.vars().get(name) {
+                            Ok(Some(value)) => Ok(ObjectWrapper::from(value)),
+                            Ok(None) => Ok(ObjectWrapper::Null),
+                            Err(e) => Err(format!("cannot read {}: {}", name, e).into()),
+                        },
+                        _ => Err(format!(
+                            "Argument to 'env' must be STRING, got {:?}",
+                            args[0].type_str()
+                        )
+                        .into()),
+                    }
+                },
+            ),
+        );
+
+        maps.insert(
+            "set_env".to_string(),
+            ObjectWrapper::BuiltinFn(
+                2,
+                |ev: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    ev.require(Capabilities::ENVIRONMENT, "set_env")?;
+                    match (&args[0], &args[1]) {
+                        (ObjectWrapper::String(name), ObjectWrapper::String(value))
+                            if !name.is_empty() && !name.contains(&['=', '\0'][..]) =>
+                        {
+                            ev.vars().set(name, value);
+                            Ok(ObjectWrapper::Null)
+                        }
+                        (ObjectWrapper::String(name), ObjectWrapper::String(_)) => {
+                            Err(format!("invalid environment variable name: {:?}", name).into())
+                        }
+                        _ => Err(format!(
+                            "Arguments to 'set_env' must be STRING, got {:?} and {:?}",
+                            args[0].type_str(),
+                            args[1].type_str()
+                        )
+                        .into()),
+                    }
+                },
+            ),
+        );
+
+        // the arguments given to the script, an array of strings
+        maps.insert(
+            "args".to_string(),
+            ObjectWrapper::BuiltinFn(
+                0,
+                |ev: &mut Evaluator, _: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    let args = ev.args().iter().cloned().map(ObjectWrapper::from);
+                    Ok(ObjectWrapper::Array(Arc::new(args.collect())))
+                },
+            ),
+        );
+
+        // exit(code): stops the script, the host is told the code, see
+        // `Evaluator::exit_code`
+        maps.insert(
+            "exit".to_string(),
+            ObjectWrapper::BuiltinFn(
+                1,
+                |ev: &mut Evaluator, args: Vec<ObjectWrapper>| -> Result<ObjectWrapper> {
+                    ev.require(Capabilities::PROCESS, "exit")?;
+                    match &args[0] {
+                        ObjectWrapper::Integer(code) => match i32::try_from(*code) {
+                            Ok(code) => ev.exit(code),
+                            Err(_) => Err(format!("exit code out of range: {}", code).into()),
+                        },
+                        _ => Err(format!(
+                            "Argument to 'exit' must be INTEGER, got {:?}",
+                            args[0].type_str()
+                        )
+                        .into()),
+                    }
+                },
+            ),
+        );
+        let mut names = maps.keys().cloned().collect::<Vec<String>>();
+        names.sort();
+        drop(maps);
+        ret.names = names;
         ret
     };
 }
 
+fn expect_iterator<'a>(name: &str, obj: &'a ObjectWrapper) -> Result<&'a Iter> {
+    match obj {
+        ObjectWrapper::Iterator(iterator) => Ok(iterator),
+        _ => Err(format!(
+            "Argument to `{}` must be ITERATOR, got {}",
+            name,
+            obj.type_str()
+        )
+        .into()),
+    }
+}
+
+fn read_input_line(ev: &mut Evaluator) -> Result<ObjectWrapper> {
+    match ev.input().read_line() {
+        Ok(Some(line)) => Ok(ObjectWrapper::from(line)),
+        Ok(None) => Ok(ObjectWrapper::Null),
+        Err(e) => Err(format!("cannot read from stdin: {}", e).into()),
+    }
+}
+
+static RANDOM_STATE: AtomicU64 = AtomicU64::new(0);
+
+// xorshift64*, seeded from the std hasher keys on first use
+fn next_random() -> u64 {
+    if RANDOM_STATE.load(Ordering::Relaxed) == 0 {
+        let mut hasher = RandomState::new().build_hasher();
+        // only mixed in, a clock before the epoch is as good as any
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        hasher.write_u64(now.as_nanos() as u64);
+        // another thread may have seeded it in the meantime
+        let _ = RANDOM_STATE.compare_exchange(
+            0,
+            hasher.finish() | 1,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        );
+    }
+    let step = |mut x: u64| {
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        x
+    };
+    // threads calling it at once each get their own number
+    let previous = RANDOM_STATE
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| Some(step(x)))
+        .unwrap();
+    step(previous).wrapping_mul(0x2545_f491_4f6c_dd1d)
+}
+
 pub struct Builtins {
     builtins: Arc<Mutex<HashMap<String, ObjectWrapper>>>,
+    // sorted, compiled code refers to builtins by their index in here
+    names: Vec<String>,
 }
 
 impl Builtins {
@@ -161,6 +694,14 @@ impl Builtins {
     //         .insert(ident.to_string(), obj)
     // }
 
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn get_by_index(&self, index: usize) -> Option<ObjectWrapper> {
+        self.get(self.names.get(index)?)
+    }
+
     pub fn get(&self, ident: &str) -> Option<ObjectWrapper> {
         // 最后的结果如果不clone会导致无法返回，因此get_mut已经失去意义
         self.builtins.clone().lock().unwrap().get(ident).cloned()
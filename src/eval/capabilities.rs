@@ -0,0 +1,73 @@
+use std::fmt::{Display, Formatter};
+use std::ops::{BitOr, Sub};
+
+// Set of host capabilities a script may use. Builtins that need a disabled
+// capability still exist, but fail with a permission denied error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities(u8);
+
+impl Capabilities {
+    pub const NONE: Capabilities = Capabilities(0);
+    pub const FILESYSTEM: Capabilities = Capabilities(1);
+    pub const NETWORK: Capabilities = Capabilities(1 << 1);
+    pub const PROCESS: Capabilities = Capabilities(1 << 2);
+    pub const CLOCK: Capabilities = Capabilities(1 << 3);
+    pub const RANDOM: Capabilities = Capabilities(1 << 4);
+    pub const ENVIRONMENT: Capabilities = Capabilities(1 << 5);
+    // the script's stdout and stderr, see `Output`
+    pub const OUTPUT: Capabilities = Capabilities(1 << 6);
+    // the script's stdin, see `Input`
+    pub const INPUT: Capabilities = Capabilities(1 << 7);
+    pub const ALL: Capabilities = Capabilities(0b1111_1111);
+
+    pub fn contains(self, other: Capabilities) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Capabilities::ALL
+    }
+}
+
+impl BitOr for Capabilities {
+    type Output = Capabilities;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Capabilities(self.0 | rhs.0)
+    }
+}
+
+impl Sub for Capabilities {
+    type Output = Capabilities;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Capabilities(self.0 & !rhs.0)
+    }
+}
+
+impl Display for Capabilities {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let names = [
+            (Capabilities::FILESYSTEM, "filesystem"),
+            (Capabilities::NETWORK, "network"),
+            (Capabilities::PROCESS, "process"),
+            (Capabilities::CLOCK, "clock"),
+            (Capabilities::RANDOM, "random"),
+            (Capabilities::ENVIRONMENT, "environment"),
+            (Capabilities::OUTPUT, "output"),
+            (Capabilities::INPUT, "input"),
+        ];
+        let enabled = names
+            .iter()
+            .filter(|(cap, _)| self.contains(*cap))
+            .map(|(_, name)| *name)
+            .collect::<Vec<&str>>();
+        if enabled.is_empty() {
+            f.write_str("none")
+        } else {
+            f.write_str(&enabled.join(", "))
+        }
+    }
+}
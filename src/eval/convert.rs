@@ -0,0 +1,280 @@
+use crate::eval::host::HostHandle;
+use crate::eval::iterator::Iter;
+use crate::eval::{HashKey, ObjectWrapper};
+use crate::parser::ParseError;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+// Rust values into objects, and back with `TryFrom` so embedders need not
+// match on `ObjectWrapper`. An error object converts to an error with its
+// message.
+
+impl From<bool> for ObjectWrapper {
+    fn from(v: bool) -> Self {
+        ObjectWrapper::Boolean(v)
+    }
+}
+
+impl From<i64> for ObjectWrapper {
+    fn from(v: i64) -> Self {
+        ObjectWrapper::Integer(v)
+    }
+}
+
+impl From<f64> for ObjectWrapper {
+    fn from(v: f64) -> Self {
+        ObjectWrapper::Float(v)
+    }
+}
+
+impl From<String> for ObjectWrapper {
+    fn from(v: String) -> Self {
+        ObjectWrapper::String(v.into())
+    }
+}
+
+impl From<&str> for ObjectWrapper {
+    fn from(v: &str) -> Self {
+        ObjectWrapper::String(v.into())
+    }
+}
+
+impl<T: Into<ObjectWrapper>> From<Vec<T>> for ObjectWrapper {
+    fn from(v: Vec<T>) -> Self {
+        ObjectWrapper::Array(Arc::new(v.into_iter().map(Into::into).collect()))
+    }
+}
+
+impl<T: Into<ObjectWrapper>> From<HashMap<String, T>> for ObjectWrapper {
+    fn from(v: HashMap<String, T>) -> Self {
+        let hash = v
+            .into_iter()
+            .map(|(key, value)| (HashKey::String(key.into()), value.into()))
+            .collect();
+        ObjectWrapper::Hash(hash)
+    }
+}
+
+fn mismatch(expect: &str, obj: ObjectWrapper) -> ParseError {
+    match obj {
+        ObjectWrapper::ErrorObject(message) => message.into(),
+        _ => format!("expect {}, got {}", expect, obj.type_str()).into(),
+    }
+}
+
+impl TryFrom<ObjectWrapper> for bool {
+    type Error = ParseError;
+
+    fn try_from(obj: ObjectWrapper) -> Result<Self, Self::Error> {
+        match obj {
+            ObjectWrapper::Boolean(v) => Ok(v),
+            _ => Err(mismatch("bool", obj)),
+        }
+    }
+}
+
+impl TryFrom<ObjectWrapper> for i64 {
+    type Error = ParseError;
+
+    fn try_from(obj: ObjectWrapper) -> Result<Self, Self::Error> {
+        match obj {
+            ObjectWrapper::Integer(v) => Ok(v),
+            _ => Err(mismatch("int", obj)),
+        }
+    }
+}
+
+// integers are floats too, as in arithmetic
+impl TryFrom<ObjectWrapper> for f64 {
+    type Error = ParseError;
+
+    fn try_from(obj: ObjectWrapper) -> Result<Self, Self::Error> {
+        match obj {
+            ObjectWrapper::Float(v) => Ok(v),
+            ObjectWrapper::Integer(v) => Ok(v as f64),
+            _ => Err(mismatch("float", obj)),
+        }
+    }
+}
+
+impl TryFrom<ObjectWrapper> for String {
+    type Error = ParseError;
+
+    fn try_from(obj: ObjectWrapper) -> Result<Self, Self::Error> {
+        match obj {
+            ObjectWrapper::String(v) => Ok(v.to_string()),
+            _ => Err(mismatch("string", obj)),
+        }
+    }
+}
+
+impl From<HostHandle> for ObjectWrapper {
+    fn from(v: HostHandle) -> Self {
+        ObjectWrapper::Handle(v)
+    }
+}
+
+impl TryFrom<ObjectWrapper> for HostHandle {
+    type Error = ParseError;
+
+    fn try_from(obj: ObjectWrapper) -> Result<Self, Self::Error> {
+        match obj {
+            ObjectWrapper::Handle(v) => Ok(v),
+            _ => Err(mismatch("handle", obj)),
+        }
+    }
+}
+
+impl From<Iter> for ObjectWrapper {
+    fn from(v: Iter) -> Self {
+        ObjectWrapper::Iterator(v)
+    }
+}
+
+impl TryFrom<ObjectWrapper> for Iter {
+    type Error = ParseError;
+
+    fn try_from(obj: ObjectWrapper) -> Result<Self, Self::Error> {
+        match obj {
+            ObjectWrapper::Iterator(v) => Ok(v),
+            _ => Err(mismatch("iterator", obj)),
+        }
+    }
+}
+
+impl<T: TryFrom<ObjectWrapper, Error = ParseError>> TryFrom<ObjectWrapper> for Vec<T> {
+    type Error = ParseError;
+
+    fn try_from(obj: ObjectWrapper) -> Result<Self, Self::Error> {
+        match obj {
+            ObjectWrapper::Array(elements) => Arc::unwrap_or_clone(elements)
+                .into_iter()
+                .map(T::try_from)
+                .collect(),
+            _ => Err(mismatch("array", obj)),
+        }
+    }
+}
+
+// only hashes whose keys are all strings convert
+impl<T: TryFrom<ObjectWrapper, Error = ParseError>> TryFrom<ObjectWrapper> for HashMap<String, T> {
+    type Error = ParseError;
+
+    fn try_from(obj: ObjectWrapper) -> Result<Self, Self::Error> {
+        let hash = match obj {
+            ObjectWrapper::Hash(hash) => hash,
+            _ => return Err(mismatch("hash", obj)),
+        };
+        hash.into_iter()
+            .map(|(key, value)| match key {
+                HashKey::String(key) => Ok((key.to_string(), T::try_from(value)?)),
+                _ => Err(mismatch("string key", key.into())),
+            })
+            .collect()
+    }
+}
+
+// What a host function returns, see `Interpreter::register_typed_fn`. An
+// `Err` is a runtime error of the script.
+pub trait IntoObject {
+    fn into_object(self) -> Result<ObjectWrapper, ParseError>;
+}
+
+macro_rules! into_object {
+    ($($t:ty),*) => {
+        $(
+            impl IntoObject for $t {
+                fn into_object(self) -> Result<ObjectWrapper, ParseError> {
+                    Ok(self.into())
+                }
+            }
+        )*
+    };
+}
+
+into_object!(
+    bool,
+    i64,
+    f64,
+    String,
+    &str,
+    HostHandle,
+    Iter,
+    ObjectWrapper
+);
+
+impl IntoObject for () {
+    fn into_object(self) -> Result<ObjectWrapper, ParseError> {
+        Ok(ObjectWrapper::Null)
+    }
+}
+
+impl<T: Into<ObjectWrapper>> IntoObject for Vec<T> {
+    fn into_object(self) -> Result<ObjectWrapper, ParseError> {
+        Ok(self.into())
+    }
+}
+
+impl<T: Into<ObjectWrapper>> IntoObject for HashMap<String, T> {
+    fn into_object(self) -> Result<ObjectWrapper, ParseError> {
+        Ok(self.into())
+    }
+}
+
+impl<T: IntoObject> IntoObject for Result<T, ParseError> {
+    fn into_object(self) -> Result<ObjectWrapper, ParseError> {
+        self?.into_object()
+    }
+}
+
+// The arguments of a host function as a tuple, each converted with
+// `TryFrom`. The error names the function and the argument, counted from 1.
+pub trait FromArgs: Sized {
+    const ARITY: usize;
+
+    fn from_args(name: &str, args: Vec<ObjectWrapper>) -> Result<Self, ParseError>;
+}
+
+macro_rules! from_args {
+    ($arity:expr; $($t:ident),*) => {
+        impl<$($t),*> FromArgs for ($($t,)*)
+        where
+            $($t: TryFrom<ObjectWrapper, Error = ParseError>,)*
+        {
+            const ARITY: usize = $arity;
+
+            #[allow(unused_variables, unused_mut)]
+            fn from_args(name: &str, args: Vec<ObjectWrapper>) -> Result<Self, ParseError> {
+                if args.len() != $arity {
+                    return Err(format!(
+                        "Wrong number of arguments, expect {} got {}",
+                        $arity,
+                        args.len()
+                    )
+                    .into());
+                }
+                let mut args = args.into_iter().enumerate();
+                Ok(($(
+                    {
+                        let (i, arg) = args.next().unwrap();
+                        $t::try_from(arg).map_err(|e| {
+                            ParseError::from(format!(
+                                "argument {} of '{}': {}",
+                                i + 1,
+                                name,
+                                e.message()
+                            ))
+                        })?
+                    },
+                )*))
+            }
+        }
+    };
+}
+
+from_args!(0;);
+from_args!(1; A);
+from_args!(2; A, B);
+from_args!(3; A, B, C);
+from_args!(4; A, B, C, D);
@@ -0,0 +1,110 @@
+use crate::eval::environment::Environment;
+use crate::eval::observer::EvalObserver;
+use crate::lexer::span::Span;
+use crate::parser::program::{Program, Statement};
+use crate::parser::visit::{self, Visitor};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+// Counts how often the lines of a program ran. A line is executable when a
+// statement starts on it, nested ones included, and counts the statements
+// started on it that were evaluated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    hits: BTreeMap<usize, u64>,
+}
+
+struct Lines<'a>(&'a mut BTreeMap<usize, u64>);
+
+impl Visitor for Lines<'_> {
+    fn visit_statement(&mut self, statement: &Statement) {
+        self.0.insert(statement.span().start.line, 0);
+        visit::walk_statement(self, statement);
+    }
+}
+
+impl Coverage {
+    pub fn new(program: &Program) -> Self {
+        let mut hits = BTreeMap::new();
+        visit::walk_program(&mut Lines(&mut hits), program);
+        Coverage { hits }
+    }
+
+    // the executable lines with their counts
+    pub fn lines(&self) -> &BTreeMap<usize, u64> {
+        &self.hits
+    }
+
+    pub fn covered(&self) -> usize {
+        self.hits.values().filter(|count| **count > 0).count()
+    }
+
+    pub fn total(&self) -> usize {
+        self.hits.len()
+    }
+
+    // the lines that never ran, consecutive ones as ranges: `4, 7-9`
+    pub fn uncovered(&self) -> String {
+        let mut ranges: Vec<(usize, usize)> = vec![];
+        for (line, _) in self.hits.iter().filter(|(_, count)| **count == 0) {
+            match ranges.last_mut() {
+                Some((_, end)) if *end + 1 == *line => *end = *line,
+                _ => ranges.push((*line, *line)),
+            }
+        }
+        ranges
+            .iter()
+            .map(|(start, end)| {
+                if start == end {
+                    start.to_string()
+                } else {
+                    format!("{}-{}", start, end)
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    // one line like `math.mk: 7/9 lines (77.8%), not run: 4, 7`
+    pub fn summary(&self, path: &str) -> String {
+        let percent = match self.total() {
+            0 => 100.0,
+            total => self.covered() as f64 * 100.0 / total as f64,
+        };
+        let mut ret = format!(
+            "{}: {}/{} lines ({:.1}%)",
+            path,
+            self.covered(),
+            self.total(),
+            percent
+        );
+        if self.covered() < self.total() {
+            write!(ret, ", not run: {}", self.uncovered()).unwrap();
+        }
+        ret
+    }
+
+    // the record of the file in the lcov tracefile format
+    pub fn to_lcov(&self, path: &str) -> String {
+        let mut ret = format!("TN:\nSF:{}\n", path);
+        for (line, count) in &self.hits {
+            writeln!(ret, "DA:{},{}", line, count).unwrap();
+        }
+        write!(
+            ret,
+            "LF:{}\nLH:{}\nend_of_record\n",
+            self.total(),
+            self.covered()
+        )
+        .unwrap();
+        ret
+    }
+}
+
+impl EvalObserver for Coverage {
+    fn before_statement(&mut self, _statement: &Statement, span: Span, _env: &Environment) {
+        if let Some(count) = self.hits.get_mut(&span.start.line) {
+            *count += 1;
+        }
+    }
+}
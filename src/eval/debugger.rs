@@ -0,0 +1,265 @@
+use crate::eval::environment::Environment;
+use crate::eval::evaluator::CancelHandle;
+use crate::eval::input::Input;
+use crate::eval::observer::EvalObserver;
+use crate::eval::output::Sink;
+use crate::eval::printer::ObjectPrinter;
+use crate::eval::profiler::function_name;
+use crate::eval::ObjectWrapper;
+use crate::lexer::span::Span;
+use crate::parser::program::{Expression, Statement};
+use crate::parser::Result;
+use std::collections::BTreeSet;
+
+pub const PROMPT: &str = "(debug) ";
+
+// lines shown by `list` before and after the current one
+const LIST_CONTEXT: usize = 2;
+
+const HELP: &str = "\
+step, s           run to the next statement, into calls
+next, n           run to the next statement of this function
+continue, c       run to the next breakpoint
+break, b LINE     stop before the statements on LINE, without LINE list them
+delete, d LINE    remove the breakpoint on LINE
+print, p NAME     print a variable
+vars, v           print the variables visible here
+backtrace, bt     print the calls being evaluated, innermost first
+list, l           print the source around the current statement
+quit, q           stop the script
+help, h           print this";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Step,
+    // stops once no more than this many calls are active
+    Next(usize),
+    Continue,
+}
+
+// A command line debugger observing an evaluation. It stops before the
+// first statement and then wherever the commands read from `input` say,
+// what it prints goes to `output`. Quitting cancels the evaluation through
+// the handle given to `set_cancel_handle`.
+pub struct Debugger {
+    lines: Vec<String>,
+    breakpoints: BTreeSet<usize>,
+    mode: Mode,
+    // the calls being evaluated with their spans, innermost last
+    calls: Vec<(String, Span)>,
+    // the line and call depth stopped at, `continue` does not stop again
+    // before it is left
+    stopped_at: Option<(usize, usize)>,
+    last_command: String,
+    input: Input,
+    output: Sink,
+    cancel: Option<CancelHandle>,
+    quit: bool,
+}
+
+impl Debugger {
+    pub fn new(source: &str, input: Input, output: Sink) -> Self {
+        Debugger {
+            lines: source.lines().map(str::to_string).collect(),
+            breakpoints: BTreeSet::new(),
+            mode: Mode::Step,
+            calls: vec![],
+            stopped_at: None,
+            last_command: String::new(),
+            input,
+            output,
+            cancel: None,
+            quit: false,
+        }
+    }
+
+    pub fn set_cancel_handle(&mut self, cancel: CancelHandle) {
+        self.cancel = Some(cancel);
+    }
+
+    pub fn add_breakpoint(&mut self, line: usize) {
+        self.breakpoints.insert(line);
+    }
+
+    // whether the evaluation ended with `quit`
+    pub fn quit(&self) -> bool {
+        self.quit
+    }
+
+    fn print(&self, text: &str) {
+        // the debugger keeps going if its output is gone
+        let _ = self.output.write_line(text);
+    }
+
+    fn should_stop(&self, line: usize) -> bool {
+        match self.mode {
+            _ if self.quit => false,
+            Mode::Step => true,
+            Mode::Next(depth) => self.calls.len() <= depth,
+            Mode::Continue => self.breakpoints.contains(&line) && self.stopped_at.is_none(),
+        }
+    }
+
+    fn source_line(&self, line: usize) -> &str {
+        self.lines
+            .get(line.wrapping_sub(1))
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+
+    fn stop(&mut self, span: Span, env: &Environment) {
+        let line = span.start.line;
+        self.stopped_at = Some((line, self.calls.len()));
+        self.print(&format!("stopped at {}", span.start));
+        self.print(&format!("{:>4} | {}", line, self.source_line(line)));
+        loop {
+            let _ = self.output.write_str(PROMPT);
+            let command = match self.input.read_line() {
+                Ok(Some(command)) => command,
+                // nobody left to ask
+                _ => "quit".to_string(),
+            };
+            let command = match command.trim() {
+                "" => self.last_command.clone(),
+                command => command.to_string(),
+            };
+            self.last_command = command.clone();
+            if self.run_command(&command, span, env) {
+                return;
+            }
+        }
+    }
+
+    // true when the evaluation goes on
+    fn run_command(&mut self, command: &str, span: Span, env: &Environment) -> bool {
+        let mut words = command.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let arg = words.next();
+        match (name, arg) {
+            ("step" | "s", None) => self.mode = Mode::Step,
+            ("next" | "n", None) => self.mode = Mode::Next(self.calls.len()),
+            ("continue" | "c", None) => self.mode = Mode::Continue,
+            ("quit" | "q", None) => {
+                self.quit = true;
+                if let Some(cancel) = &self.cancel {
+                    cancel.cancel();
+                }
+            }
+            ("break" | "b", None) => {
+                if self.breakpoints.is_empty() {
+                    self.print("no breakpoints");
+                }
+                for line in &self.breakpoints {
+                    self.print(&format!("breakpoint at line {}", line));
+                }
+                return false;
+            }
+            ("break" | "b", Some(line)) => {
+                match line.parse::<usize>() {
+                    Ok(line) if line > 0 => {
+                        self.breakpoints.insert(line);
+                        self.print(&format!("breakpoint at line {}", line));
+                    }
+                    _ => self.print(&format!("not a line number: {}", line)),
+                }
+                return false;
+            }
+            ("delete" | "d", Some(line)) => {
+                match line.parse::<usize>() {
+                    Ok(line) if self.breakpoints.remove(&line) => {
+                        self.print(&format!("deleted the breakpoint at line {}", line))
+                    }
+                    _ => self.print(&format!("no breakpoint at line {}", line)),
+                }
+                return false;
+            }
+            ("print" | "p", Some(name)) => {
+                match env.get(name) {
+                    Some(obj) => {
+                        self.print(&format!("{} = {}", name, ObjectPrinter::new().print(&obj)))
+                    }
+                    None => self.print(&format!("undefined variable: {}", name)),
+                }
+                return false;
+            }
+            ("vars" | "v", None) => {
+                for (name, obj) in env.bindings() {
+                    self.print(&format!("{} = {}", name, ObjectPrinter::new().print(&obj)));
+                }
+                return false;
+            }
+            ("backtrace" | "bt", None) => {
+                self.print_backtrace(span);
+                return false;
+            }
+            ("list" | "l", None) => {
+                let line = span.start.line;
+                let first = line.saturating_sub(LIST_CONTEXT).max(1);
+                let last = (line + LIST_CONTEXT).min(self.lines.len());
+                for n in first..=last {
+                    let marker = if n == line { ">" } else { " " };
+                    self.print(&format!("{}{:>3} | {}", marker, n, self.source_line(n)));
+                }
+                return false;
+            }
+            ("help" | "h", None) => {
+                self.print(HELP);
+                return false;
+            }
+            _ => {
+                self.print(&format!("unknown command: {}, try `help`", command));
+                return false;
+            }
+        }
+        true
+    }
+
+    // each call is at the position of the call it makes, the innermost one
+    // at the current statement
+    fn print_backtrace(&self, span: Span) {
+        let names =
+            std::iter::once("<main>").chain(self.calls.iter().map(|(name, _)| name.as_str()));
+        let positions = self
+            .calls
+            .iter()
+            .map(|(_, span)| *span)
+            .chain(std::iter::once(span));
+        let frames = names.zip(positions).collect::<Vec<(&str, Span)>>();
+        for (i, (name, span)) in frames.iter().rev().enumerate() {
+            self.print(&format!("#{} {} at {}", i, name, span.start));
+        }
+    }
+}
+
+impl EvalObserver for Debugger {
+    fn before_statement(&mut self, _statement: &Statement, span: Span, env: &Environment) {
+        if self.stopped_at != Some((span.start.line, self.calls.len())) {
+            self.stopped_at = None;
+        }
+        if self.should_stop(span.start.line) {
+            self.stop(span, env);
+        }
+    }
+
+    fn before_call(
+        &mut self,
+        call: &Expression,
+        _func: &ObjectWrapper,
+        _args: &[ObjectWrapper],
+        span: Span,
+        _env: &Environment,
+    ) {
+        self.calls.push((function_name(call), span));
+    }
+
+    fn after_call(
+        &mut self,
+        _call: &Expression,
+        _func: &ObjectWrapper,
+        _span: Span,
+        _env: &Environment,
+        _result: &Result<ObjectWrapper>,
+    ) {
+        self.calls.pop();
+    }
+}
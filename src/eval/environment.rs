@@ -1,25 +1,228 @@
-use crate::eval::ObjectWrapper;
-use std::collections::HashMap;
+use crate::eval::{snapshot, HashMap, ObjectWrapper};
+use crate::lexer::lexer::Lexer;
+use crate::lexer::symbol::Symbol;
+use crate::parser::program::Statement;
+use crate::parser::{Parser, Result};
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, RwLock};
 
-#[derive(Default, Clone, Debug, PartialEq)]
+// Cloning an environment shares its bindings, a closure keeps the
+// environment it was defined in alive and sees later `let`s in it, which is
+// what makes recursive functions work.
+#[derive(Default, Clone)]
 pub struct Environment {
-    identifiers: HashMap<String, ObjectWrapper>,
+    identifiers: Arc<RwLock<Scope>>,
+    outer: Option<Box<Environment>>,
+}
+
+// The locals of a call that the resolver gave a slot are kept by slot, the
+// other bindings by name. A name is bound one way or the other in a scope,
+// the whole body of a function is resolved, and lookups by name see both.
+#[derive(Default)]
+struct Scope {
+    names: HashMap<Symbol, ObjectWrapper>,
+    slots: Vec<Option<(Symbol, ObjectWrapper)>>,
+}
+
+impl Scope {
+    fn get(&self, name: &Symbol) -> Option<&ObjectWrapper> {
+        if let Some(obj) = self.names.get(name) {
+            return Some(obj);
+        }
+        self.slots
+            .iter()
+            .flatten()
+            .find(|(slot, _)| slot == name)
+            .map(|(_, obj)| obj)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&Symbol, &ObjectWrapper)> {
+        let slots = self.slots.iter().flatten().map(|(name, obj)| (name, obj));
+        self.names.iter().chain(slots)
+    }
 }
 
 impl Environment {
+    // a new scope whose lookups fall back to `outer`
+    pub fn new_enclosed(outer: Environment) -> Self {
+        Environment {
+            identifiers: Default::default(),
+            outer: Some(Box::new(outer)),
+        }
+    }
+
     pub fn contains(&self, name: &str) -> bool {
-        self.identifiers.contains_key(name)
+        Symbol::lookup(name).is_some_and(|name| self.contains_symbol(&name))
     }
 
-    pub fn get(&self, name: &str) -> Option<&ObjectWrapper> {
-        self.identifiers.get(name)
+    pub fn contains_symbol(&self, name: &Symbol) -> bool {
+        self.identifiers.read().unwrap().get(name).is_some()
+            || self
+                .outer
+                .as_ref()
+                .is_some_and(|outer| outer.contains_symbol(name))
     }
 
-    pub fn get_mut(&mut self, name: &str) -> Option<&mut ObjectWrapper> {
-        self.identifiers.get_mut(name)
+    pub fn get(&self, name: &str) -> Option<ObjectWrapper> {
+        Symbol::lookup(name).and_then(|name| self.get_symbol(&name))
     }
 
+    pub fn get_symbol(&self, name: &Symbol) -> Option<ObjectWrapper> {
+        match self.identifiers.read().unwrap().get(name) {
+            Some(obj) => Some(obj.clone()),
+            None => self.outer.as_ref().and_then(|outer| outer.get_symbol(name)),
+        }
+    }
+
+    // the local `name` at `index` in the scope `depth` calls out, looked up
+    // by name from there while its `let` did not run
+    pub fn get_slot(&self, depth: u32, index: u32, name: &Symbol) -> Option<ObjectWrapper> {
+        let mut env = self;
+        for _ in 0..depth {
+            match &env.outer {
+                Some(outer) => env = outer,
+                None => return self.get_symbol(name),
+            }
+        }
+        let slot = match env.identifiers.read().unwrap().slots.get(index as usize) {
+            Some(Some((slot, obj))) if slot == name => Some(obj.clone()),
+            _ => None,
+        };
+        slot.or_else(|| env.get_symbol(name))
+    }
+
+    pub fn set_slot(&mut self, index: u32, name: Symbol, obj: ObjectWrapper) {
+        let slots = &mut self.identifiers.write().unwrap().slots;
+        let index = index as usize;
+        if slots.len() <= index {
+            slots.resize(index + 1, None);
+        }
+        slots[index] = Some((name, obj));
+    }
+
+    // always binds in the innermost scope
     pub fn set(&mut self, name: &str, obj: ObjectWrapper) -> Option<ObjectWrapper> {
-        self.identifiers.insert(name.to_string(), obj)
+        self.set_symbol(Symbol::intern(name), obj)
+    }
+
+    pub fn set_symbol(&mut self, name: Symbol, obj: ObjectWrapper) -> Option<ObjectWrapper> {
+        let mut scope = self.identifiers.write().unwrap();
+        match scope
+            .slots
+            .iter_mut()
+            .flatten()
+            .find(|(slot, _)| *slot == name)
+        {
+            Some((_, slot)) => Some(std::mem::replace(slot, obj)),
+            None => scope.names.insert(name, obj),
+        }
+    }
+
+    // unbinds `name` in the innermost scope, returns what it was bound to
+    pub fn remove(&mut self, name: &str) -> Option<ObjectWrapper> {
+        let name = Symbol::lookup(name)?;
+        let mut scope = self.identifiers.write().unwrap();
+        if let Some(obj) = scope.names.remove(&name) {
+            return Some(obj);
+        }
+        scope
+            .slots
+            .iter_mut()
+            .find(|slot| matches!(slot, Some((slot, _)) if *slot == name))?
+            .take()
+            .map(|(_, obj)| obj)
+    }
+
+    // the bindings of the innermost scope, to be put back with `reset`
+    pub fn local_bindings(&self) -> HashMap<Symbol, ObjectWrapper> {
+        let scope = self.identifiers.read().unwrap();
+        scope
+            .iter()
+            .map(|(name, obj)| (name.clone(), obj.clone()))
+            .collect()
+    }
+
+    // replaces the bindings of the innermost scope, closures defined in it
+    // see the change
+    pub fn reset(&mut self, bindings: HashMap<Symbol, ObjectWrapper>) {
+        *self.identifiers.write().unwrap() = Scope {
+            names: bindings,
+            slots: vec![],
+        };
+    }
+
+    // every visible binding sorted by name, inner scopes shadow outer ones
+    pub fn bindings(&self) -> Vec<(String, ObjectWrapper)> {
+        let mut bindings = self
+            .outer
+            .as_ref()
+            .map(|outer| outer.bindings())
+            .unwrap_or_default();
+        for (name, obj) in self.identifiers.read().unwrap().iter() {
+            match bindings.binary_search_by(|(n, _)| n.as_str().cmp(name)) {
+                Ok(pos) => bindings[pos].1 = obj.clone(),
+                Err(pos) => bindings.insert(pos, (name.to_string(), obj.clone())),
+            }
+        }
+        bindings
+    }
+
+    // serializes every binding whose value can be written as a literal,
+    // other bindings (functions, builtins, null) are left out. Also returns
+    // the names of the bindings left out that hold data, like a string no
+    // literal can be written for.
+    pub fn snapshot(&self) -> (String, Vec<String>) {
+        let identifiers = self.identifiers.read().unwrap();
+        let mut bindings = identifiers.iter().collect::<Vec<_>>();
+        bindings.sort_by_key(|(name, _)| name.as_str());
+        let mut source = String::new();
+        let mut skipped = vec![];
+        for (name, obj) in bindings {
+            match snapshot::to_source(obj) {
+                Some(value) => source.push_str(&format!("let {} = {};\n", name, value)),
+                None if snapshot::is_data(obj) => skipped.push(name.to_string()),
+                None => {}
+            }
+        }
+        (source, skipped)
+    }
+
+    // restores bindings written by `snapshot`, existing bindings with the
+    // same names are overwritten. Nothing is changed if the snapshot is invalid.
+    pub fn restore(&mut self, snapshot: &str) -> Result<()> {
+        let program = Parser::new(Lexer::new(snapshot)).parse_program()?;
+        let mut bindings = vec![];
+        for st in &program.statements {
+            match st {
+                Statement::LetStatement(ident, expr, _) => {
+                    bindings.push((ident.0.clone(), snapshot::from_source(expr)?));
+                }
+                _ => return Err(format!("invalid statement in snapshot: {}", st).into()),
+            }
+        }
+        for (name, value) in bindings {
+            self.set_symbol(name, value);
+        }
+        Ok(())
+    }
+}
+
+// environments reference the functions stored in them and the other way
+// around, so only the names are printed and equality is identity
+impl Debug for Environment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let identifiers = self.identifiers.read().unwrap();
+        let mut names = identifiers.iter().map(|(name, _)| name).collect::<Vec<_>>();
+        names.sort_by_key(|name| name.as_str());
+        f.debug_struct("Environment")
+            .field("identifiers", &names)
+            .field("outer", &self.outer)
+            .finish()
+    }
+}
+
+impl PartialEq for Environment {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.identifiers, &other.identifiers) && self.outer == other.outer
     }
 }
@@ -1,40 +1,577 @@
+use crate::compiler::Warning;
 use crate::eval::builtins::Builtins;
+use crate::eval::capabilities::Capabilities;
 use crate::eval::environment::Environment;
-use crate::eval::ObjectWrapper;
+use crate::eval::heap::Heap;
+use crate::eval::host::{AsyncCallSender, HostFunction};
+use crate::eval::input::Input;
+use crate::eval::observer::EvalObserver;
+use crate::eval::output::Output;
+use crate::eval::profiler::function_name;
+use crate::eval::vars::EnvVars;
+use crate::eval::{HashMap, ObjectWrapper};
+use crate::lexer::span::Span;
 use crate::lexer::token::Token;
-use crate::parser::program::{Expression, Ident, Statement};
-use crate::parser::Result;
+use crate::parser::program::{Expression, Ident, Slot, Statement};
+use crate::parser::{CallFrame, Result};
+use crate::vm::Callbacks;
+use std::sync::atomic::{AtomicI64, AtomicU64, AtomicU8, AtomicUsize, Ordering};
 use std::sync::Arc;
 
+// The default of `EvalOptions::max_depth`. Every call and the expressions
+// around it take a level, a function whose body is an `if` returning a call
+// takes two, so about 5000 calls of it nest whatever the size of the
+// thread's stack, it grows on the heap. It is not higher by default because
+// arrays and hashes nest as deep as the calls building them, and dropping
+// or printing them still recurses on the stack.
+#[cfg(not(target_arch = "wasm32"))]
+pub const MAX_DEPTH: usize = 10_000;
+// the stack of wasm cannot grow, this fits in the 1 MiB browsers give it
+#[cfg(target_arch = "wasm32")]
+pub const MAX_DEPTH: usize = 1024;
+
+// When less than `STACK_RED_ZONE` is left, evaluation goes on in a new
+// segment of `STACK_SEGMENT` bytes. A call takes about 11 KB in debug
+// builds, the rest of the red zone is for builtins walking nested values.
+#[cfg(not(target_arch = "wasm32"))]
+const STACK_RED_ZONE: usize = 1024 * 1024;
+#[cfg(not(target_arch = "wasm32"))]
+const STACK_SEGMENT: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct EvalOptions {
+    // every evaluated statement and expression costs one step, evaluation
+    // fails once more than `max_steps` steps were taken
+    pub max_steps: Option<u64>,
+    // approximate number of bytes the strings, arrays and hashes created
+    // during the evaluation may take at once. Strings and arrays no value
+    // holds anymore are subtracted, hashes, error messages and the caches of
+    // `memoize` count until the evaluation ends.
+    pub max_memory: Option<usize>,
+    // expressions evaluated inside each other, function calls included,
+    // before it fails with "stack overflow". Their stack grows on the heap
+    // but in the browser, hosts can raise it as far as their memory goes if
+    // the values scripts build are not nested as deep.
+    pub max_depth: usize,
+    // host capabilities builtins are allowed to use, all by default
+    pub capabilities: Capabilities,
+    // Operators on values of different types are reported as warnings, see
+    // `Evaluator::warnings`, once for each place in the source. `==` and
+    // `!=` then tell them apart instead of failing, the others still fail
+    // or convert one of them.
+    pub type_warnings: bool,
+}
+
+impl Default for EvalOptions {
+    fn default() -> Self {
+        EvalOptions {
+            max_steps: None,
+            max_memory: None,
+            max_depth: MAX_DEPTH,
+            capabilities: Capabilities::default(),
+            type_warnings: false,
+        }
+    }
+}
+
+// Shared flag used to stop a running evaluation from another thread, the
+// evaluator checks it on every step. A cancel sent between evaluations stops
+// the next one, it is only cleared once an evaluation stopped on it.
+#[derive(Debug, Clone, Default)]
+pub struct CancelHandle(Arc<AtomicU8>);
+
+const RUNNING: u8 = 0;
+const CANCELLED: u8 = 1;
+// an evaluation stopped on the cancel and has not returned yet
+const STOPPED: u8 = 2;
+
+impl CancelHandle {
+    pub fn cancel(&self) {
+        let _ = self
+            .0
+            .compare_exchange(RUNNING, CANCELLED, Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed) != RUNNING
+    }
+
+    pub fn reset(&self) {
+        self.0.store(RUNNING, Ordering::Relaxed);
+    }
+
+    // whether the evaluation is to stop, the cancel is then cleared by
+    // `stopped` once it returned
+    pub(crate) fn stops(&self) -> bool {
+        if self.is_cancelled() {
+            self.0.store(STOPPED, Ordering::Relaxed);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn stopped(&self) {
+        let _ = self
+            .0
+            .compare_exchange(STOPPED, RUNNING, Ordering::Relaxed, Ordering::Relaxed);
+    }
+}
+
+// The code `exit` was called with, shared with the workers. Scripts cannot
+// end the process of the host, `exit` fails the evaluation like an error
+// `rescue` does not stop, the host then ends it if it wants to.
+#[derive(Debug, Clone)]
+struct ExitCode(Arc<AtomicI64>);
+
+// not a code `exit` takes, they are i32
+const NO_EXIT: i64 = i64::MAX;
+
+impl Default for ExitCode {
+    fn default() -> Self {
+        ExitCode(Arc::new(AtomicI64::new(NO_EXIT)))
+    }
+}
+
+impl ExitCode {
+    fn set(&self, code: i32) {
+        self.0.store(code as i64, Ordering::Relaxed);
+    }
+
+    fn get(&self) -> Option<i32> {
+        match self.0.load(Ordering::Relaxed) {
+            NO_EXIT => None,
+            code => Some(code as i32),
+        }
+    }
+}
+
+// Tells apart the evaluations functions are defined by. The spans of an
+// error inside a function are in the source it was defined in, which is not
+// the one being evaluated for a function of an earlier input of the REPL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceId(u64);
+
+impl SourceId {
+    fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        SourceId(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+// The steps and bytes taken by the evaluators of a `Worker`. They are
+// shared, evaluators running at the same time stop once together they took
+// what the limits of the worker leave them.
+#[derive(Debug, Default)]
+struct Budget {
+    steps: AtomicU64,
+    allocated: AtomicUsize,
+}
+
+pub(crate) struct Worker {
+    env: Environment,
+    budget: Arc<Budget>,
+    source: SourceId,
+    options: EvalOptions,
+    cancel: CancelHandle,
+    exit: ExitCode,
+    async_calls: Option<AsyncCallSender>,
+    output: Output,
+    input: Input,
+    vars: EnvVars,
+    args: Vec<String>,
+    callbacks: Option<Arc<Callbacks>>,
+}
+
+impl Worker {
+    // `function` applied to `args`, with the steps it took and the values
+    // it created
+    pub(crate) fn apply(
+        &self,
+        function: &ObjectWrapper,
+        args: Vec<ObjectWrapper>,
+    ) -> (Result<ObjectWrapper>, u64, Heap) {
+        let mut ev = self.evaluator();
+        let ret = ev.apply_function(function, args);
+        (ret, ev.steps, ev.heap)
+    }
+
+    pub(crate) fn evaluator(&self) -> Evaluator<'static> {
+        let mut ev = Evaluator::with_env(&[], self.env.clone());
+        ev.source = self.source;
+        ev.running = self.source;
+        ev.options = self.options.clone();
+        ev.budget = Some(self.budget.clone());
+        ev.cancel = self.cancel.clone();
+        ev.exit = self.exit.clone();
+        ev.async_calls = self.async_calls.clone();
+        ev.output = self.output.clone();
+        ev.input = self.input.clone();
+        ev.vars = self.vars.clone();
+        ev.args = self.args.clone();
+        ev.callbacks = self.callbacks.clone();
+        ev
+    }
+}
+
 pub struct Evaluator<'a> {
-    statements: &'a Vec<Statement>,
+    statements: &'a [Statement],
+    // what the functions defined by the statements are tagged with
+    source: SourceId,
+    // the source of the function being evaluated
+    running: SourceId,
     env: Environment,
+    options: EvalOptions,
+    steps: u64,
+    heap: Heap,
+    // shared with the other evaluators of a worker, the limits are checked
+    // against it instead of `steps` and `heap`
+    budget: Option<Arc<Budget>>,
+    objects: usize,
+    // expressions being evaluated, calls included
+    depth: usize,
+    cancel: CancelHandle,
+    exit: ExitCode,
+    observer: Option<&'a mut dyn EvalObserver>,
+    async_calls: Option<AsyncCallSender>,
+    output: Output,
+    input: Input,
+    vars: EnvVars,
+    args: Vec<String>,
+    error_span: Option<Span>,
+    error_trace: Vec<CallFrame>,
+    warnings: Vec<Warning>,
+    // how the vm calling builtins with this evaluator runs its closures
+    callbacks: Option<Arc<Callbacks>>,
 }
 
 impl<'a> Evaluator<'a> {
-    pub fn new(statements: &'a Vec<Statement>) -> Self {
+    pub fn new(statements: &'a [Statement]) -> Self {
+        Self::with_env(statements, Environment::default())
+    }
+
+    pub fn with_env(statements: &'a [Statement], env: Environment) -> Self {
+        let source = SourceId::next();
         Evaluator {
             statements,
-            env: Environment::default(),
+            source,
+            running: source,
+            env,
+            options: EvalOptions::default(),
+            steps: 0,
+            heap: Heap::default(),
+            budget: None,
+            objects: 0,
+            depth: 0,
+            cancel: CancelHandle::default(),
+            exit: ExitCode::default(),
+            observer: None,
+            async_calls: None,
+            output: Output::default(),
+            input: Input::default(),
+            vars: EnvVars::default(),
+            args: vec![],
+            error_span: None,
+            error_trace: vec![],
+            warnings: vec![],
+            callbacks: None,
         }
     }
 
-    pub fn with_env(statements: &'a Vec<Statement>, env: Environment) -> Self {
-        Evaluator { statements, env }
+    pub fn set_options(&mut self, options: EvalOptions) {
+        self.options = options;
+    }
+
+    pub fn set_cancel_handle(&mut self, cancel: CancelHandle) {
+        self.cancel = cancel;
+    }
+
+    pub fn set_observer(&mut self, observer: &'a mut dyn EvalObserver) {
+        self.observer = Some(observer);
+    }
+
+    pub fn set_output(&mut self, output: Output) {
+        self.output = output;
+    }
+
+    // where builtins print to
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+
+    pub fn set_input(&mut self, input: Input) {
+        self.input = input;
+    }
+
+    // where builtins read from
+    pub fn input(&self) -> &Input {
+        &self.input
+    }
+
+    pub fn set_vars(&mut self, vars: EnvVars) {
+        self.vars = vars;
+    }
+
+    // what `env` reads and `set_env` writes
+    pub fn vars(&self) -> &EnvVars {
+        &self.vars
+    }
+
+    pub fn set_args(&mut self, args: Vec<String>) {
+        self.args = args;
+    }
+
+    // the script's command line arguments, what `args` returns
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    // lets builtins call the closures of the vm, see `Vm`
+    pub(crate) fn set_callbacks(&mut self, callbacks: Option<Arc<Callbacks>>) {
+        self.callbacks = callbacks;
+    }
+
+    // lets async host functions be called, see `Interpreter::eval_async`
+    pub(crate) fn set_async_calls(&mut self, async_calls: AsyncCallSender) {
+        self.async_calls = Some(async_calls);
     }
 
     pub fn get_env(self) -> Environment {
-        return self.env;
+        self.env
+    }
+
+    // number of steps taken so far
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    // approximate bytes taken by the values created so far and not
+    // released, see `EvalOptions::max_memory`, only counted with a limit
+    pub fn allocated(&self) -> usize {
+        self.heap.size()
+    }
+
+    // the values created so far, for the evaluator running the workers
+    pub(crate) fn take_heap(&mut self) -> Heap {
+        std::mem::take(&mut self.heap)
+    }
+
+    // objects created so far
+    pub fn objects(&self) -> usize {
+        self.objects
+    }
+
+    // accounts a newly created object against the memory limit, the vm
+    // counts the values it creates here too
+    pub(crate) fn track(&mut self, obj: ObjectWrapper) -> Result<ObjectWrapper> {
+        self.objects += 1;
+        if self.options.max_memory.is_none() {
+            return Ok(obj);
+        }
+        let before = self.heap.size();
+        self.heap.add(&obj);
+        self.check_memory(before)?;
+        Ok(obj)
+    }
+
+    // accounts `size` bytes kept by a builtin against the memory limit, like
+    // the entries of the cache of `memoize`
+    pub(crate) fn allocate(&mut self, size: usize) -> Result<()> {
+        if self.options.max_memory.is_none() {
+            return Ok(());
+        }
+        let before = self.heap.size();
+        self.heap.keep(size);
+        self.check_memory(before)
+    }
+
+    // fails once the values not released take more than the limit, the heap
+    // having taken `before` bytes
+    fn check_memory(&mut self, before: usize) -> Result<()> {
+        let max = match self.options.max_memory {
+            Some(max) => max,
+            None => return Ok(()),
+        };
+        if self.resize(before) <= max {
+            return Ok(());
+        }
+        let before = self.heap.size();
+        self.heap.sweep();
+        if self.resize(before) <= max {
+            Ok(())
+        } else {
+            Err(format!("out of memory: script allocated more than {} bytes", max).into())
+        }
+    }
+
+    // the bytes counted against the limit once the heap changed from
+    // `before` bytes, those of the other evaluators of a worker included
+    fn resize(&self, before: usize) -> usize {
+        let after = self.heap.size();
+        match &self.budget {
+            Some(budget) if after >= before => {
+                budget
+                    .allocated
+                    .fetch_add(after - before, Ordering::Relaxed)
+                    + (after - before)
+            }
+            Some(budget) => {
+                budget
+                    .allocated
+                    .fetch_sub(before - after, Ordering::Relaxed)
+                    - (before - after)
+            }
+            None => after,
+        }
+    }
+
+    // Ends the evaluation, see `exit_code`. The error goes through `rescue`.
+    pub(crate) fn exit(&mut self, code: i32) -> Result<ObjectWrapper> {
+        self.exit.set(code);
+        Err(format!("script exited with code {}", code).into())
+    }
+
+    // what `exit` was called with, if the evaluation failed because of it
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit.get()
+    }
+
+    // builtins call this before touching the host
+    pub fn require(&self, capability: Capabilities, builtin: &str) -> Result<()> {
+        if self.options.capabilities.contains(capability) {
+            Ok(())
+        } else {
+            Err(format!(
+                "permission denied: '{}' needs the {} capability",
+                builtin, capability
+            )
+            .into())
+        }
+    }
+
+    // What evaluators on other threads need to call functions of this one,
+    // for `pmap`. They share the environment, what is left of the limits,
+    // the cancel handle and where builtins print and read, not the observer.
+    // The workers of a worker share its limits.
+    pub(crate) fn worker(&self) -> Worker {
+        let max_depth = self.options.max_depth.saturating_sub(self.depth);
+        if let Some(budget) = &self.budget {
+            let options = EvalOptions {
+                max_depth,
+                ..self.options.clone()
+            };
+            return self.worker_with(options, budget.clone());
+        }
+        let options = EvalOptions {
+            max_steps: self
+                .options
+                .max_steps
+                .map(|max| max.saturating_sub(self.steps)),
+            max_memory: self
+                .options
+                .max_memory
+                .map(|max| max.saturating_sub(self.heap.size())),
+            max_depth,
+            capabilities: self.options.capabilities,
+            type_warnings: self.options.type_warnings,
+        };
+        self.worker_with(options, Arc::default())
+    }
+
+    fn worker_with(&self, options: EvalOptions, budget: Arc<Budget>) -> Worker {
+        Worker {
+            env: self.env.clone(),
+            budget,
+            source: self.source,
+            options,
+            cancel: self.cancel.clone(),
+            exit: self.exit.clone(),
+            async_calls: self.async_calls.clone(),
+            output: self.output.clone(),
+            input: self.input.clone(),
+            vars: self.vars.clone(),
+            args: self.args.clone(),
+            callbacks: self.callbacks.clone(),
+        }
+    }
+
+    // counts what workers did against the limits of this evaluator, those
+    // of a worker were already counted against them
+    pub(crate) fn absorb(&mut self, steps: u64, heap: Heap) -> Result<()> {
+        let twice = self.heap.absorb(heap);
+        self.steps += steps;
+        if let Some(budget) = &self.budget {
+            budget.allocated.fetch_sub(twice, Ordering::Relaxed);
+            return Ok(());
+        }
+        self.check_memory(self.heap.size())?;
+        match self.options.max_steps {
+            Some(max) if self.steps > max => Err("script exceeded execution limit".into()),
+            _ => Ok(()),
+        }
+    }
+
+    // the vm takes a step here for every instruction
+    pub(crate) fn step(&mut self) -> Result<()> {
+        self.steps += 1;
+        if self.cancel.stops() {
+            return Err("script cancelled".into());
+        }
+        let steps = match &self.budget {
+            Some(budget) => budget.steps.fetch_add(1, Ordering::Relaxed) + 1,
+            None => self.steps,
+        };
+        match self.options.max_steps {
+            Some(max) if steps > max => Err("script exceeded execution limit".into()),
+            _ => Ok(()),
+        }
     }
 
     pub fn eval(&mut self) -> Result<ObjectWrapper> {
-        match self.eval_statements(&self.statements) {
+        match self.try_eval() {
             Err(e) => Ok(ObjectWrapper::ErrorObject(e.to_string())),
             Ok(ret) => Ok(ret),
         }
     }
 
-    fn eval_statements(&mut self, statements: &Vec<Statement>) -> Result<ObjectWrapper> {
+    // Like `eval`, a runtime error is returned as `Err` with its span and
+    // trace instead of as an error value, an error value the statements
+    // evaluate to is still `Ok`.
+    pub fn try_eval(&mut self) -> Result<ObjectWrapper> {
+        self.eval_statements(self.statements).inspect_err(|e| {
+            self.error_span = e.span();
+            self.error_trace = e.trace().to_vec();
+        })
+    }
+
+    // Where the error `eval` returned happened, in the evaluated statements.
+    // An error inside a function defined by other statements, e.g. an
+    // earlier input of the REPL, is reported at the call.
+    pub fn error_span(&self) -> Option<Span> {
+        self.error_span
+    }
+
+    // the calls that error was raised in, innermost first, each at the call
+    // in the function around it and the outermost one in the evaluated
+    // statements
+    pub fn error_trace(&self) -> &[CallFrame] {
+        &self.error_trace
+    }
+
+    // what `EvalOptions::type_warnings` found, in the evaluated statements
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    // Warnings inside functions of other sources are left out, their spans
+    // are not in the evaluated statements.
+    fn warn(&mut self, span: Span, message: String) {
+        if self.running == self.source && self.warnings.iter().all(|w| w.span != span) {
+            self.warnings.push(Warning { span, message });
+        }
+    }
+
+    fn eval_statements(&mut self, statements: &[Statement]) -> Result<ObjectWrapper> {
         let mut ret = ObjectWrapper::Null;
         for st in statements {
             ret = self.eval_statement(st)?;
@@ -46,7 +583,7 @@ impl<'a> Evaluator<'a> {
         Ok(ret)
     }
 
-    fn eval_block_statements(&mut self, statements: &Vec<Statement>) -> Result<ObjectWrapper> {
+    fn eval_block_statements(&mut self, statements: &[Statement]) -> Result<ObjectWrapper> {
         let mut ret = ObjectWrapper::Null;
         for st in statements {
             ret = self.eval_statement(st)?;
@@ -58,12 +595,31 @@ impl<'a> Evaluator<'a> {
     }
 
     fn eval_statement(&mut self, statement: &Statement) -> Result<ObjectWrapper> {
+        self.step()?;
+        if self.observer.is_none() {
+            return self.do_eval_statement(statement);
+        }
+        self.eval_statement_observed(statement)
+    }
+
+    fn eval_statement_observed(&mut self, statement: &Statement) -> Result<ObjectWrapper> {
+        if let Some(observer) = self.observer.as_deref_mut() {
+            observer.before_statement(statement, statement.span(), &self.env);
+        }
+        let ret = self.do_eval_statement(statement);
+        if let Some(observer) = self.observer.as_deref_mut() {
+            observer.after_statement(statement, statement.span(), &self.env, &ret);
+        }
+        ret
+    }
+
+    fn do_eval_statement(&mut self, statement: &Statement) -> Result<ObjectWrapper> {
         match statement {
-            Statement::ReturnStatement(expr) => self.eval_return_statement(expr),
-            Statement::ExpressionStatement(expr) => self.eval_expression(expr),
-            Statement::LetStatement(ident, expression) => {
+            Statement::ReturnStatement(expr, _) => self.eval_return_statement(expr),
+            Statement::ExpressionStatement(expr, _) => self.eval_expression(expr),
+            Statement::LetStatement(ident, expression, _) => {
                 let value = self.eval_expression(expression)?;
-                self.env.set(&ident.0, value);
+                bind(&mut self.env, ident, value);
                 Ok(ObjectWrapper::Null)
             } // _ => Ok(ObjectWrapper::Null),
         }
@@ -75,49 +631,101 @@ impl<'a> Evaluator<'a> {
     }
 
     fn eval_expression(&mut self, expression: &Expression) -> Result<ObjectWrapper> {
-        match expression {
-            Expression::Identifier(ident) => self.eval_identifier(&ident.0),
-            Expression::IntLiteral(v) => Ok(ObjectWrapper::Integer(v.clone())),
-            Expression::BoolLiteral(v) => Ok(ObjectWrapper::Boolean(v.clone())),
-            Expression::StringLiteral(v) => Ok(ObjectWrapper::String(v.clone())),
-            Expression::InfixExpression(left, operator, right) => {
-                self.eval_infix_expression(left, operator, right)
+        if self.depth >= self.options.max_depth {
+            return Err("stack overflow".into());
+        }
+        self.depth += 1;
+        #[cfg(not(target_arch = "wasm32"))]
+        let ret = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || {
+            self.eval_nested_expression(expression)
+        });
+        #[cfg(target_arch = "wasm32")]
+        let ret = self.eval_nested_expression(expression);
+        self.depth -= 1;
+        ret
+    }
+
+    fn eval_nested_expression(&mut self, expression: &Expression) -> Result<ObjectWrapper> {
+        self.step()?;
+        let ret = match expression {
+            Expression::Identifier(ident) => self.eval_identifier(ident),
+            Expression::IntLiteral(v) => Ok(ObjectWrapper::Integer(*v)),
+            Expression::FloatLiteral(v) => Ok(ObjectWrapper::Float(*v)),
+            Expression::BoolLiteral(v) => Ok(ObjectWrapper::Boolean(*v)),
+            Expression::StringLiteral(v) => self.track(ObjectWrapper::String(v.clone())),
+            Expression::InfixExpression(left, operator, right, span) => {
+                self.eval_infix_expression(left, operator, right, *span)
             }
-            Expression::PrefixExpression(operator, right) => {
+            Expression::PrefixExpression(operator, right, _) => {
                 self.eval_prefix_expression(operator, right)
             }
-            Expression::IfExpression(condition, consequence, alternative) => {
+            Expression::IfExpression(condition, consequence, alternative, _) => {
                 self.eval_if_expression(condition, consequence, alternative)
             }
-            Expression::FunctionExpression(params, body) => Ok(ObjectWrapper::FunctionObject(
-                Arc::new(params.clone()),
-                Arc::new(body.clone()),
-                self.env.clone(),
-            )),
-            Expression::CallExpression(func, params) => self.eval_call_expression(func, params),
-            Expression::ArrayLiteral(array) => {
-                let elements = array
-                    .iter()
-                    .map(|expr| self.eval_expression(expr))
-                    .collect::<Result<Vec<ObjectWrapper>>>()?;
-                Ok(ObjectWrapper::Array(elements))
+            Expression::FunctionExpression(params, body, _) => {
+                self.eval_function_literal(params, body)
             }
-            Expression::IndexExpression(array, index) => {
-                let array = self.eval_expression(array)?;
-                let index = self.eval_expression(index)?;
-                array.index(&index)
-            },
-            _ => Ok(ObjectWrapper::Null),
+            Expression::CallExpression(..) => self.eval_call_expression(expression),
+            Expression::ArrayLiteral(array) => self.eval_array_literal(array),
+            Expression::HashLiteral(pairs) => self.eval_hash_literal(pairs),
+            Expression::IndexExpression(array, index, _) => {
+                self.eval_index_expression(array, index)
+            }
+        };
+        match (ret, expression.span()) {
+            (Err(e), Some(span)) => Err(e.or_span(span)),
+            (ret, _) => ret,
+        }
+    }
+
+    fn eval_function_literal(&self, params: &[Ident], body: &[Statement]) -> Result<ObjectWrapper> {
+        Ok(ObjectWrapper::FunctionObject(
+            Arc::new(params.to_vec()),
+            Arc::new(body.to_vec()),
+            self.env.clone(),
+            self.source,
+        ))
+    }
+
+    fn eval_index_expression(
+        &mut self,
+        array: &Expression,
+        index: &Expression,
+    ) -> Result<ObjectWrapper> {
+        let array = self.eval_expression(array)?;
+        let index = self.eval_expression(index)?;
+        array.index(&index)
+    }
+
+    fn eval_array_literal(&mut self, array: &[Expression]) -> Result<ObjectWrapper> {
+        let elements = array
+            .iter()
+            .map(|expr| self.eval_expression(expr))
+            .collect::<Result<Vec<ObjectWrapper>>>()?;
+        self.track(ObjectWrapper::Array(elements.into()))
+    }
+
+    fn eval_hash_literal(&mut self, pairs: &[(Expression, Expression)]) -> Result<ObjectWrapper> {
+        let mut hash = HashMap::with_capacity_and_hasher(pairs.len(), Default::default());
+        for (key, value) in pairs {
+            let key = self.eval_expression(key)?.hash_key()?;
+            let value = self.eval_expression(value)?;
+            hash.insert(key, value);
         }
+        self.track(ObjectWrapper::Hash(hash))
     }
 
-    fn eval_identifier(&mut self, ident: &str) -> Result<ObjectWrapper> {
-        if self.env.contains(ident) {
-            Ok(self.env.get(ident).unwrap().clone())
-        } else if Builtins::instance_ref().contains(ident) {
-            Ok(Builtins::instance_ref().get(ident).unwrap())
+    fn eval_identifier(&mut self, ident: &Ident) -> Result<ObjectWrapper> {
+        let obj = match ident.2 {
+            Slot::Local { depth, index } => self.env.get_slot(depth, index, &ident.0),
+            Slot::Name => self.env.get_symbol(&ident.0),
+        };
+        if let Some(obj) = obj {
+            Ok(obj)
+        } else if let Some(builtin) = Builtins::instance_ref().get(&ident.0) {
+            Ok(builtin)
         } else {
-            Err(format!("identifier not found: {}", ident).into())
+            Err(format!("identifier not found: {}", ident.0).into())
         }
     }
 
@@ -126,19 +734,51 @@ impl<'a> Evaluator<'a> {
         left: &Expression,
         operator: &Token,
         right: &Expression,
+        span: Span,
     ) -> Result<ObjectWrapper> {
         let left = self.eval_expression(left)?;
         let right = self.eval_expression(right)?;
+        let mixed = self.options.type_warnings && left.type_str() != right.type_str();
+        if mixed && matches!(operator, Token::Eq | Token::NotEq) {
+            let message = format!(
+                "{} {} {} compares different types, they are never equal",
+                left.type_str(),
+                operator,
+                right.type_str()
+            );
+            self.warn(span, message);
+            return Ok(ObjectWrapper::Boolean(*operator == Token::NotEq));
+        }
 
-        match operator {
+        let obj = match operator {
             Token::Plus => left.add(&right),
+            Token::Minus => left.sub(&right),
+            Token::Slash => left.divide(&right),
             Token::Eq => left.eq(&right),
             Token::GT => left.great_than(&right),
             Token::LT => left.less_than(&right),
             Token::NotEq => left.not_eq(&right),
             Token::Asterisk => left.multi(&right),
             _ => Ok(ObjectWrapper::Null),
+        }?;
+        if mixed {
+            // the one not of the type of the result was converted
+            let converted = if obj.type_str() == left.type_str() {
+                &right
+            } else {
+                &left
+            };
+            let message = format!(
+                "{} {} {} converts the {} to {}",
+                left.type_str(),
+                operator,
+                right.type_str(),
+                converted.type_str(),
+                obj.type_str()
+            );
+            self.warn(span, message);
         }
+        self.track(obj)
     }
 
     fn eval_prefix_expression(
@@ -152,11 +792,7 @@ impl<'a> Evaluator<'a> {
                 ObjectWrapper::Integer(v) => Ok(ObjectWrapper::Boolean(v == 0)),
                 _ => Ok(ObjectWrapper::Boolean(false)),
             },
-            Token::Minus => match self.eval_expression(expr)? {
-                ObjectWrapper::Integer(v) => Ok(ObjectWrapper::Integer(-v)),
-                ObjectWrapper::Float(f) => Ok(ObjectWrapper::Float(-f)),
-                _ => Err(format!("cannot eval {} after '-'.", operator.to_string()).into()),
-            },
+            Token::Minus => self.eval_expression(expr)?.neg(),
             _ => Ok(ObjectWrapper::Null),
         }
     }
@@ -164,8 +800,8 @@ impl<'a> Evaluator<'a> {
     fn eval_if_expression(
         &mut self,
         condition: &Expression,
-        consequence: &Vec<Statement>,
-        alternative: &Vec<Statement>,
+        consequence: &[Statement],
+        alternative: &[Statement],
     ) -> Result<ObjectWrapper> {
         let cond = self.eval_expression(condition)?;
         if let ObjectWrapper::Boolean(v) = cond {
@@ -175,56 +811,116 @@ impl<'a> Evaluator<'a> {
                 self.eval_block_statements(alternative)
             }
         } else {
-            return Err("Invalid 'if' condition.".into());
+            Err("Invalid 'if' condition.".into())
         }
     }
 
-    fn eval_call_expression(
-        &mut self,
-        func: &Expression,
-        params: &Vec<Expression>,
-    ) -> Result<ObjectWrapper> {
+    fn eval_call_expression(&mut self, call: &Expression) -> Result<ObjectWrapper> {
+        let (func, params, span) = match call {
+            Expression::CallExpression(func, params, span) => (func, params, *span),
+            _ => return Err(format!("not a call expression: {}", call).into()),
+        };
         let real_params = params
             .iter()
             .map(|expr| self.eval_expression(expr))
             .collect::<Result<Vec<ObjectWrapper>>>()?;
+        let func = self.eval_expression(func)?;
+        let ret = if self.observer.is_none() {
+            self.apply_function(&func, real_params)
+        } else {
+            self.apply_function_observed(call, span, &func, real_params)
+        };
+        let frame = || CallFrame {
+            function: function_name(call),
+            span,
+        };
         match func {
-            Expression::Identifier(ident) => {
-                let obj = self.eval_identifier(&ident.0)?;
-                match obj {
-                    ObjectWrapper::FunctionObject(params_ident, body, env_func) => {
-                        let params_ident = params_ident.clone();
-                        let body = body.clone();
-                        let env = env_func.clone();
-                        self.do_eval_function_call(&params_ident, &real_params, &body, env)
-                    }
-                    ObjectWrapper::BuiltinFn(nums, func) => {
-                        if real_params.len() != nums {
-                            return Err(format!(
-                                "Wrong number of arguments, expect {} got {}",
-                                nums,
-                                real_params.len()
-                            )
-                            .into());
-                        }
-                        func(real_params)
-                    }
-                    _ => Err(format!("function not found: {}", &ident.0).into()),
-                }
+            ObjectWrapper::FunctionObject(.., source) if source == self.source => {
+                ret.map_err(|e| e.or_span(span).in_call(frame()))
             }
-            Expression::FunctionExpression(params_ident, body) => {
-                self.do_eval_function_call(params_ident, &real_params, body, self.env.clone())
+            ObjectWrapper::FunctionObject(..) => {
+                ret.map_err(|e| e.with_span(span).without_trace().in_call(frame()))
             }
-            _ => Err("invalid call expression.".into()),
+            // the function may come from another source
+            _ => ret.map_err(|e| e.with_span(span).without_trace()),
         }
     }
 
+    // kept apart so the frames of unobserved calls stay small
+    fn apply_function_observed(
+        &mut self,
+        call: &Expression,
+        span: Span,
+        func: &ObjectWrapper,
+        params: Vec<ObjectWrapper>,
+    ) -> Result<ObjectWrapper> {
+        if let Some(observer) = self.observer.as_deref_mut() {
+            observer.before_call(call, func, &params, span, &self.env);
+        }
+        let ret = self.apply_function(func, params);
+        if let Some(observer) = self.observer.as_deref_mut() {
+            observer.after_call(call, func, span, &self.env, &ret);
+        }
+        ret
+    }
+
+    pub fn apply_function(
+        &mut self,
+        func: &ObjectWrapper,
+        params: Vec<ObjectWrapper>,
+    ) -> Result<ObjectWrapper> {
+        match func {
+            ObjectWrapper::FunctionObject(params_ident, body, env_func, source) => {
+                let outer = std::mem::replace(&mut self.running, *source);
+                let ret = self.do_eval_function_call(params_ident, &params, body, env_func.clone());
+                self.running = outer;
+                ret
+            }
+            ObjectWrapper::BuiltinFn(nums, func) => {
+                if params.len() != *nums {
+                    return Err(format!(
+                        "Wrong number of arguments, expect {} got {}",
+                        nums,
+                        params.len()
+                    )
+                    .into());
+                }
+                let obj = func(self, params)?;
+                self.track(obj)
+            }
+            ObjectWrapper::HostFn(func) => self.call_host_function(func, params),
+            ObjectWrapper::Memoized(memo) => memo.call(self, params),
+            ObjectWrapper::Closure(closure) => match self.callbacks.clone() {
+                Some(callbacks) => callbacks.call(self, closure.clone(), params),
+                None => Err(format!("not a function: {}", func.type_str()).into()),
+            },
+            _ => Err(format!("not a function: {}", func.type_str()).into()),
+        }
+    }
+
+    fn call_host_function(
+        &mut self,
+        func: &HostFunction,
+        params: Vec<ObjectWrapper>,
+    ) -> Result<ObjectWrapper> {
+        if params.len() != func.arity() {
+            return Err(format!(
+                "Wrong number of arguments, expect {} got {}",
+                func.arity(),
+                params.len()
+            )
+            .into());
+        }
+        let obj = func.call(params, self.async_calls.as_ref(), &self.cancel)?;
+        self.track(obj)
+    }
+
     fn do_eval_function_call(
         &mut self,
-        params_ident: &Vec<Ident>,
-        params: &Vec<ObjectWrapper>,
-        body: &Vec<Statement>,
-        mut env: Environment,
+        params_ident: &[Ident],
+        params: &[ObjectWrapper],
+        body: &[Statement],
+        env: Environment,
     ) -> Result<ObjectWrapper> {
         if params.len() != params_ident.len() {
             return Err(format!(
@@ -234,13 +930,28 @@ impl<'a> Evaluator<'a> {
             )
             .into());
         }
+        let mut env = Environment::new_enclosed(env);
         params
             .iter()
             .zip(params_ident.iter())
             .for_each(|(obj, param_ident)| {
-                env.set(&param_ident.0, obj.clone());
+                bind(&mut env, param_ident, obj.clone());
             });
-        let mut evaluator = Evaluator::with_env(body, env);
-        evaluator.eval()
+        // errors raised in the body propagate to the caller instead of being
+        // turned into a value, `rescue` is the way to stop them
+        let outer = std::mem::replace(&mut self.env, env);
+        let ret = self.eval_statements(body);
+        self.env = outer;
+        ret
+    }
+}
+
+// binds in the slot the resolver gave `ident`, by name if it gave none
+fn bind(env: &mut Environment, ident: &Ident, obj: ObjectWrapper) {
+    match ident.2 {
+        Slot::Local { index, .. } => env.set_slot(index, ident.0.clone(), obj),
+        Slot::Name => {
+            env.set_symbol(ident.0.clone(), obj);
+        }
     }
 }
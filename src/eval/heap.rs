@@ -0,0 +1,120 @@
+use crate::eval::{HashMap, ObjectWrapper};
+use std::collections::hash_map::Entry;
+use std::sync::{Arc, Weak};
+
+// The bytes taken by the values an evaluator created, see
+// `EvalOptions::max_memory`. Strings and arrays are shared through an `Arc`,
+// a weak reference to it tells once no value holds them anymore and their
+// bytes are no longer counted. Hashes, error messages and the entries of
+// `memoize` caches are counted until the evaluation ends.
+pub(crate) struct Heap {
+    // the strings and arrays by address, with the bytes they were counted for
+    shared: HashMap<usize, (Shared, usize)>,
+    // the bytes of `shared`, released values included until swept, and of
+    // the values that are not shared
+    size: usize,
+    // the length `shared` is swept at when it grows, like the interner
+    sweep_at: usize,
+}
+
+enum Shared {
+    String(Weak<str>),
+    Array(Weak<Vec<ObjectWrapper>>),
+}
+
+impl Shared {
+    fn released(&self) -> bool {
+        match self {
+            Shared::String(v) => v.strong_count() == 0,
+            Shared::Array(v) => v.strong_count() == 0,
+        }
+    }
+}
+
+const SWEEP_AT: usize = 1024;
+
+impl Default for Heap {
+    fn default() -> Self {
+        Heap {
+            shared: HashMap::default(),
+            size: 0,
+            sweep_at: SWEEP_AT,
+        }
+    }
+}
+
+impl Heap {
+    pub(crate) fn size(&self) -> usize {
+        self.size
+    }
+
+    // counts `obj`, a string or an array counted before for what it grew by
+    // since, like the array `push` appends to in place
+    pub(crate) fn add(&mut self, obj: &ObjectWrapper) {
+        let (address, shared) = match obj {
+            ObjectWrapper::String(v) => (
+                Arc::as_ptr(v) as *const u8 as usize,
+                Shared::String(Arc::downgrade(v)),
+            ),
+            ObjectWrapper::Array(v) => (Arc::as_ptr(v) as usize, Shared::Array(Arc::downgrade(v))),
+            obj => return self.keep(obj.shallow_size()),
+        };
+        let size = obj.shallow_size();
+        // the weak reference keeps the address from being reused
+        if let Some((_, counted)) = self.shared.get_mut(&address) {
+            self.size = self.size - *counted + size;
+            *counted = size;
+            return;
+        }
+        if self.shared.len() >= self.sweep_at {
+            self.sweep();
+        }
+        self.shared.insert(address, (shared, size));
+        self.size += size;
+    }
+
+    // counts `size` bytes until the evaluation ends
+    pub(crate) fn keep(&mut self, size: usize) {
+        self.size += size;
+    }
+
+    // stops counting the strings and arrays no value holds anymore
+    pub(crate) fn sweep(&mut self) {
+        let mut released = 0;
+        self.shared.retain(|_, (shared, size)| {
+            let keep = !shared.released();
+            if !keep {
+                released += *size;
+            }
+            keep
+        });
+        self.size -= released;
+        self.sweep_at = (self.shared.len() * 2).max(SWEEP_AT);
+    }
+
+    // counts the values a worker created, returns the bytes of those both
+    // counted, which are counted once
+    pub(crate) fn absorb(&mut self, other: Heap) -> usize {
+        let mut twice = 0;
+        let mut unshared = other.size;
+        for (address, (shared, size)) in other.shared {
+            unshared -= size;
+            match self.shared.entry(address) {
+                Entry::Occupied(mut entry) => {
+                    let counted = &mut entry.get_mut().1;
+                    twice += size.min(*counted);
+                    if size > *counted {
+                        self.size += size - *counted;
+                        *counted = size;
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert((shared, size));
+                    self.size += size;
+                }
+            }
+        }
+        self.size += unshared;
+        twice
+    }
+}
@@ -0,0 +1,226 @@
+use crate::eval::convert::{FromArgs, IntoObject};
+use crate::eval::evaluator::CancelHandle;
+use crate::eval::ObjectWrapper;
+use crate::parser::Result;
+use std::any::Any;
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+// how often a script waiting for an async host function checks if it was
+// cancelled
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+pub type HostFuture = Pin<Box<dyn Future<Output = Result<ObjectWrapper>> + Send>>;
+
+type SyncHostFn = dyn Fn(Vec<ObjectWrapper>) -> Result<ObjectWrapper> + Send + Sync;
+type AsyncHostFn = dyn Fn(Vec<ObjectWrapper>) -> HostFuture + Send + Sync;
+
+enum Callable {
+    Sync(Box<SyncHostFn>),
+    Async(Box<AsyncHostFn>),
+}
+
+// A builtin implemented by the embedding application, see
+// `Interpreter::register_fn` and `Interpreter::register_async_fn`.
+#[derive(Clone)]
+pub struct HostFunction {
+    name: Arc<str>,
+    arity: usize,
+    callable: Arc<Callable>,
+}
+
+// an async host function called by a script running on the evaluator thread,
+// awaited by `Interpreter::eval_async`
+pub(crate) struct AsyncCall {
+    future: HostFuture,
+    reply: mpsc::Sender<Result<ObjectWrapper>>,
+}
+
+pub(crate) type AsyncCallSender = async_std::channel::Sender<AsyncCall>;
+
+impl AsyncCall {
+    pub(crate) async fn run(self) {
+        let ret = self.future.await;
+        // the script may have been cancelled in the meantime
+        let _ = self.reply.send(ret);
+    }
+}
+
+impl HostFunction {
+    pub fn new<F>(name: &str, arity: usize, f: F) -> Self
+    where
+        F: Fn(Vec<ObjectWrapper>) -> Result<ObjectWrapper> + Send + Sync + 'static,
+    {
+        HostFunction {
+            name: name.into(),
+            arity,
+            callable: Arc::new(Callable::Sync(Box::new(f))),
+        }
+    }
+
+    pub fn new_async<F, Fut>(name: &str, arity: usize, f: F) -> Self
+    where
+        F: Fn(Vec<ObjectWrapper>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<ObjectWrapper>> + Send + 'static,
+    {
+        HostFunction {
+            name: name.into(),
+            arity,
+            callable: Arc::new(Callable::Async(Box::new(move |args| Box::pin(f(args))))),
+        }
+    }
+
+    // a host function from a Rust function taking and returning values that
+    // convert, its arity is the number of parameters
+    pub fn new_typed<F, Args>(name: &str, f: F) -> Self
+    where
+        F: TypedFn<Args>,
+        Args: FromArgs,
+    {
+        let owned = name.to_string();
+        Self::new(name, Args::ARITY, move |args| {
+            f.call(Args::from_args(&owned, args)?)
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn is_async(&self) -> bool {
+        matches!(self.callable.as_ref(), Callable::Async(_))
+    }
+
+    // async functions are handed to `async_calls` and waited for, without
+    // them only synchronous functions can be called
+    pub(crate) fn call(
+        &self,
+        args: Vec<ObjectWrapper>,
+        async_calls: Option<&AsyncCallSender>,
+        cancel: &CancelHandle,
+    ) -> Result<ObjectWrapper> {
+        match (self.callable.as_ref(), async_calls) {
+            (Callable::Sync(f), _) => f(args),
+            (Callable::Async(_), None) => Err(format!(
+                "'{}' is an async host function, evaluate with eval_async",
+                self.name
+            )
+            .into()),
+            (Callable::Async(f), Some(sender)) => {
+                let (reply, receiver) = mpsc::channel();
+                let call = AsyncCall {
+                    future: f(args),
+                    reply,
+                };
+                if sender.try_send(call).is_err() {
+                    return Err(format!("cannot call '{}', the host stopped", self.name).into());
+                }
+                loop {
+                    match receiver.recv_timeout(CANCEL_POLL_INTERVAL) {
+                        Ok(ret) => return ret,
+                        Err(mpsc::RecvTimeoutError::Timeout) if !cancel.stops() => {}
+                        Err(mpsc::RecvTimeoutError::Timeout) => {
+                            return Err("script cancelled".into())
+                        }
+                        Err(mpsc::RecvTimeoutError::Disconnected) => {
+                            return Err(
+                                format!("cannot call '{}', the host stopped", self.name).into()
+                            )
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// A Rust function `HostFunction::new_typed` can call with the converted
+// arguments `Args`, implemented for closures of up to four parameters.
+pub trait TypedFn<Args>: Send + Sync + 'static {
+    fn call(&self, args: Args) -> Result<ObjectWrapper>;
+}
+
+macro_rules! typed_fn {
+    ($($t:ident),*) => {
+        impl<F, R, $($t),*> TypedFn<($($t,)*)> for F
+        where
+            F: Fn($($t),*) -> R + Send + Sync + 'static,
+            R: IntoObject,
+        {
+            #[allow(non_snake_case)]
+            fn call(&self, ($($t,)*): ($($t,)*)) -> Result<ObjectWrapper> {
+                self($($t),*).into_object()
+            }
+        }
+    };
+}
+
+typed_fn!();
+typed_fn!(A);
+typed_fn!(A, B);
+typed_fn!(A, B, C);
+typed_fn!(A, B, C, D);
+
+impl Debug for HostFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HostFunction({})", self.name)
+    }
+}
+
+// two host functions are equal if they are the same registration
+impl PartialEq for HostFunction {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.callable, &other.callable)
+    }
+}
+
+// A Rust value scripts can store and pass around but not look into, e.g. a
+// connection host functions are given back, see `Interpreter::wrap`.
+#[derive(Clone)]
+pub struct HostHandle {
+    type_name: &'static str,
+    value: Arc<dyn Any + Send + Sync>,
+}
+
+impl HostHandle {
+    pub fn new<T: Any + Send + Sync>(value: T) -> Self {
+        HostHandle {
+            type_name: std::any::type_name::<T>(),
+            value: Arc::new(value),
+        }
+    }
+
+    // the name of the wrapped type, for messages
+    pub fn type_name(&self) -> &str {
+        self.type_name
+    }
+
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.value.downcast_ref()
+    }
+
+    // the wrapped value if it is a `T`, shared with the handle
+    pub fn downcast<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
+        self.value.clone().downcast().ok()
+    }
+}
+
+impl Debug for HostHandle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HostHandle({})", self.type_name)
+    }
+}
+
+// two handles are equal if they wrap the same value
+impl PartialEq for HostHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.value, &other.value)
+    }
+}
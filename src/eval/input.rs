@@ -0,0 +1,55 @@
+use std::fmt::{Debug, Formatter};
+use std::io::BufRead;
+use std::sync::{Arc, Mutex};
+
+enum Source {
+    // not locked between reads, the REPL reads from the same stdin
+    Stdin,
+    Reader(Box<dyn BufRead + Send>),
+}
+
+// Where `input` and `read_line` read from, the process' stdin by default.
+// Clones read from the same source.
+#[derive(Clone)]
+pub struct Input(Arc<Mutex<Source>>);
+
+impl Input {
+    pub fn new<R: BufRead + Send + 'static>(reader: R) -> Self {
+        Input(Arc::new(Mutex::new(Source::Reader(Box::new(reader)))))
+    }
+
+    pub fn stdin() -> Self {
+        Input(Arc::new(Mutex::new(Source::Stdin)))
+    }
+
+    // the next line without its line ending, None at the end of the input
+    pub fn read_line(&self) -> std::io::Result<Option<String>> {
+        let mut line = String::new();
+        let n = match &mut *self.0.lock().unwrap() {
+            Source::Stdin => std::io::stdin().read_line(&mut line)?,
+            Source::Reader(reader) => reader.read_line(&mut line)?,
+        };
+        if n == 0 {
+            return Ok(None);
+        }
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Some(line))
+    }
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Self::stdin()
+    }
+}
+
+impl Debug for Input {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Input")
+    }
+}
@@ -0,0 +1,77 @@
+use crate::eval::evaluator::Evaluator;
+use crate::eval::ObjectWrapper;
+use crate::parser::Result;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+
+// A sequence the host produces one item at a time, e.g. the rows of a
+// database cursor, so scripts can go through it without all of it being in
+// memory. Scripts read it with `next`, `take` and `map`.
+pub trait HostIterator: Send {
+    // the next item, None once it is exhausted
+    fn next(&mut self) -> Result<Option<ObjectWrapper>>;
+}
+
+// any Rust iterator of values converting to objects
+impl<I, T> HostIterator for I
+where
+    I: Iterator<Item = T> + Send,
+    T: Into<ObjectWrapper>,
+{
+    fn next(&mut self) -> Result<Option<ObjectWrapper>> {
+        Ok(Iterator::next(self).map(Into::into))
+    }
+}
+
+// An iterator object. Copies of it share the position, an item read through
+// one is not seen by the others.
+#[derive(Clone)]
+pub struct Iter(Arc<Mutex<Source>>);
+
+enum Source {
+    Host(Box<dyn HostIterator>),
+    // the items of the iterator passed to the function
+    Map(Iter, ObjectWrapper),
+}
+
+impl Iter {
+    pub fn new<I: HostIterator + 'static>(iterator: I) -> Self {
+        Iter(Arc::new(Mutex::new(Source::Host(Box::new(iterator)))))
+    }
+
+    // a lazy iterator of what `function` returns for the items of this one
+    pub fn map(&self, function: ObjectWrapper) -> Self {
+        Iter(Arc::new(Mutex::new(Source::Map(self.clone(), function))))
+    }
+
+    pub fn next(&self, ev: &mut Evaluator) -> Result<Option<ObjectWrapper>> {
+        let mut source = self.0.lock().unwrap();
+        let (inner, function) = match &mut *source {
+            Source::Host(iterator) => return iterator.next(),
+            // unlocked while the function runs, it may read this iterator
+            Source::Map(inner, function) => (inner.clone(), function.clone()),
+        };
+        drop(source);
+        let item = match inner.next(ev)? {
+            Some(item) => item,
+            None => return Ok(None),
+        };
+        match ev.apply_function(&function, vec![item])? {
+            ObjectWrapper::ErrorObject(message) => Err(message.into()),
+            obj => Ok(Some(obj)),
+        }
+    }
+}
+
+impl Debug for Iter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Iter")
+    }
+}
+
+// two iterators are equal if they are the same
+impl PartialEq for Iter {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
@@ -0,0 +1,76 @@
+use crate::eval::convert::IntoObject;
+use crate::eval::{HashKey, ObjectWrapper};
+use crate::parser::ParseError;
+use serde_json::{Map, Number, Value};
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+// Objects to and from json. Numbers that are an `i64` become integers and
+// the others floats, integers become json integers and floats json floats,
+// so both ways are lossless but for integers beyond `i64` and for the float
+// they turn into. Only hashes with string keys, and no functions or errors,
+// convert to json.
+
+impl From<Value> for ObjectWrapper {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => ObjectWrapper::Null,
+            Value::Bool(v) => ObjectWrapper::Boolean(v),
+            Value::Number(v) => match v.as_i64() {
+                Some(v) => ObjectWrapper::Integer(v),
+                // u64 beyond i64, the others are all f64
+                None => ObjectWrapper::Float(v.as_f64().unwrap_or(f64::NAN)),
+            },
+            Value::String(v) => ObjectWrapper::String(v.into()),
+            Value::Array(v) => {
+                ObjectWrapper::Array(Arc::new(v.into_iter().map(Into::into).collect()))
+            }
+            Value::Object(v) => ObjectWrapper::Hash(
+                v.into_iter()
+                    .map(|(key, value)| (HashKey::String(key.into()), value.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl TryFrom<ObjectWrapper> for Value {
+    type Error = ParseError;
+
+    fn try_from(obj: ObjectWrapper) -> Result<Self, Self::Error> {
+        match obj {
+            ObjectWrapper::Null => Ok(Value::Null),
+            ObjectWrapper::Boolean(v) => Ok(Value::Bool(v)),
+            ObjectWrapper::Integer(v) => Ok(Value::Number(v.into())),
+            ObjectWrapper::Float(v) => Number::from_f64(v)
+                .map(Value::Number)
+                .ok_or_else(|| format!("{} has no json number", v).into()),
+            ObjectWrapper::String(v) => Ok(Value::String(v.to_string())),
+            ObjectWrapper::Array(v) => Arc::unwrap_or_clone(v)
+                .into_iter()
+                .map(Value::try_from)
+                .collect::<Result<_, _>>()
+                .map(Value::Array),
+            ObjectWrapper::Hash(v) => v
+                .into_iter()
+                .map(|(key, value)| match key {
+                    HashKey::String(key) => Ok((key.to_string(), Value::try_from(value)?)),
+                    _ => Err(format!(
+                        "json keys are strings, got {}",
+                        ObjectWrapper::from(key).type_str()
+                    )
+                    .into()),
+                })
+                .collect::<Result<Map<String, Value>, _>>()
+                .map(Value::Object),
+            ObjectWrapper::ErrorObject(message) => Err(message.into()),
+            _ => Err(format!("cannot convert {} to json", obj.type_str()).into()),
+        }
+    }
+}
+
+impl IntoObject for Value {
+    fn into_object(self) -> Result<ObjectWrapper, ParseError> {
+        Ok(self.into())
+    }
+}
@@ -0,0 +1,66 @@
+use crate::eval::evaluator::Evaluator;
+use crate::eval::{HashKey, HashMap, ObjectWrapper};
+use crate::parser::Result;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+
+// A function remembering what it returned for the arguments it was called
+// with, made by `memoize`. Copies of it share the cache. Calls with an
+// argument that cannot be a hash key are not cached, neither are errors.
+// Every entry counts against the memory limit of the evaluator adding it.
+#[derive(Clone)]
+pub struct Memo(Arc<Cache>);
+
+struct Cache {
+    function: ObjectWrapper,
+    results: Mutex<HashMap<Vec<HashKey>, ObjectWrapper>>,
+}
+
+impl Memo {
+    pub fn new(function: ObjectWrapper) -> Self {
+        Memo(Arc::new(Cache {
+            function,
+            results: Mutex::new(HashMap::default()),
+        }))
+    }
+
+    pub fn function(&self) -> &ObjectWrapper {
+        &self.0.function
+    }
+
+    pub fn call(&self, ev: &mut Evaluator, args: Vec<ObjectWrapper>) -> Result<ObjectWrapper> {
+        let key: Vec<HashKey> = match args.iter().map(ObjectWrapper::hash_key).collect() {
+            Ok(key) => key,
+            Err(_) => return ev.apply_function(&self.0.function, args),
+        };
+        if let Some(obj) = self.0.results.lock().unwrap().get(&key) {
+            return Ok(obj.clone());
+        }
+        // unlocked while the function runs, it calls itself through the cache
+        let obj = ev.apply_function(&self.0.function, args)?;
+        if !matches!(obj, ObjectWrapper::ErrorObject(_)) {
+            ev.allocate(entry_size(&key))?;
+            self.0.results.lock().unwrap().insert(key, obj.clone());
+        }
+        Ok(obj)
+    }
+}
+
+// the bytes an entry keeps besides its value and the strings of its key,
+// which share what the arguments were made with
+fn entry_size(key: &[HashKey]) -> usize {
+    std::mem::size_of::<(Vec<HashKey>, ObjectWrapper)>() + std::mem::size_of_val(key)
+}
+
+impl Debug for Memo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Memo({:?})", self.0.function)
+    }
+}
+
+// two memoized functions are equal if they share the cache
+impl PartialEq for Memo {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
@@ -1,33 +1,104 @@
 use std::fmt::{Display, Formatter};
 use std::sync::Arc;
 
+use crate::compiler::{Closure, CompiledFunction};
 use crate::eval::environment::Environment;
+use crate::eval::evaluator::{Evaluator, SourceId};
+use crate::eval::host::{HostFunction, HostHandle};
+use crate::eval::iterator::Iter;
+use crate::eval::memo::Memo;
 use crate::parser::Result;
 
 use super::parser::program::{Ident, Statement};
 
+pub mod benchmark;
 pub mod builtins;
+pub mod capabilities;
+pub mod convert;
+pub mod coverage;
+pub mod debugger;
 pub mod environment;
 pub mod evaluator;
+mod heap;
+pub mod host;
+pub mod input;
+pub mod iterator;
+#[cfg(feature = "serde_json")]
+pub mod json;
+pub mod memo;
+pub mod observer;
+pub mod output;
+pub mod parallel;
+pub mod printer;
+pub mod profiler;
+pub mod snapshot;
+pub mod vars;
 
 #[cfg(test)]
 mod test;
 
+// The maps of hashes, environments and builtins. With the `fxhash` feature
+// they hash with FxHash, which is much faster on their small keys than
+// SipHash but does not resist keys made to collide. Their order is not part
+// of what scripts see, hashes are printed and serialized sorted by key.
+#[cfg(feature = "fxhash")]
+pub type HashMap<K, V> = std::collections::HashMap<K, V, rustc_hash::FxBuildHasher>;
+#[cfg(not(feature = "fxhash"))]
+pub type HashMap<K, V> = std::collections::HashMap<K, V>;
+
+// builtins are compared by their function pointer
+#[allow(unpredictable_function_pointer_comparisons)]
 #[derive(Debug, PartialEq, Clone)]
 pub enum ObjectWrapper {
     Null,
     Integer(i64),
     Float(f64),
     Boolean(bool),
-    String(String),
-    Array(Vec<ObjectWrapper>),
+    // shared, copying a string is a reference count increment
+    String(Arc<str>),
+    // shared too, builtins returning a changed array copy it only when
+    // another value still holds it
+    Array(Arc<Vec<ObjectWrapper>>),
+    Hash(HashMap<HashKey, ObjectWrapper>),
     ReturnValue(Box<ObjectWrapper>),
     ErrorObject(String),
-    FunctionObject(Arc<Vec<Ident>>, Arc<Vec<Statement>>, Environment),
+    FunctionObject(Arc<Vec<Ident>>, Arc<Vec<Statement>>, Environment, SourceId),
     BuiltinFn(usize, BuiltinFunction),
+    HostFn(HostFunction),
+    Handle(HostHandle),
+    Iterator(Iter),
+    Memoized(Memo),
+    CompiledFn(Arc<CompiledFunction>),
+    Closure(Arc<Closure>),
 }
 
-pub type BuiltinFunction = fn(Vec<ObjectWrapper>) -> Result<ObjectWrapper>;
+// objects share data only through `Arc`, so values, functions and their
+// environments included, can be moved to and shared between threads
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<ObjectWrapper>();
+    assert_send_sync::<Environment>();
+};
+
+// the object types that can be used as hash keys
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone)]
+pub enum HashKey {
+    Integer(i64),
+    Boolean(bool),
+    String(Arc<str>),
+}
+
+impl From<HashKey> for ObjectWrapper {
+    fn from(key: HashKey) -> Self {
+        match key {
+            HashKey::Integer(v) => ObjectWrapper::Integer(v),
+            HashKey::Boolean(v) => ObjectWrapper::Boolean(v),
+            HashKey::String(v) => ObjectWrapper::String(v),
+        }
+    }
+}
+
+pub type BuiltinFunction = fn(&mut Evaluator, Vec<ObjectWrapper>) -> Result<ObjectWrapper>;
 
 impl Display for ObjectWrapper {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -37,16 +108,28 @@ impl Display for ObjectWrapper {
             ObjectWrapper::Float(flt) => write!(f, "Float: {}", flt),
             ObjectWrapper::ReturnValue(v) => write!(f, "Retrun Object: {:?}", v),
             ObjectWrapper::ErrorObject(err) => write!(f, "Error: {}", err),
-            ObjectWrapper::FunctionObject(idents, body, _) => {
+            ObjectWrapper::FunctionObject(idents, body, ..) => {
                 write!(f, "FunctionObject: ident: {:?}, body: {:?}", idents, body)
             }
             ObjectWrapper::String(v) => write!(f, "String: {}", v),
             ObjectWrapper::Array(array) => write!(f, "Array: {:?}", array),
+            ObjectWrapper::Hash(hash) => {
+                let mut pairs = hash.iter().collect::<Vec<_>>();
+                pairs.sort_by(|a, b| a.0.cmp(b.0));
+                f.write_str("Hash: ")?;
+                f.debug_map().entries(pairs).finish()
+            }
             _ => f.write_str("unimplemented display objectWrapper"),
         }
     }
 }
 
+// integer arithmetic fails rather than wrapping around
+fn checked(v: Option<i64>) -> Result<ObjectWrapper> {
+    v.map(ObjectWrapper::Integer)
+        .ok_or_else(|| "integer overflow".into())
+}
+
 fn ensure_compare_with_same_type(one: &ObjectWrapper, two: &ObjectWrapper) -> Result<()> {
     if !one.type_str().eq(two.type_str()) {
         return Err(format!(
@@ -69,31 +152,81 @@ impl ObjectWrapper {
             ObjectWrapper::String(_) => "string",
             ObjectWrapper::ReturnValue(_) => "return_value",
             ObjectWrapper::ErrorObject(_) => "error",
-            ObjectWrapper::FunctionObject(_, _, _) => "function",
+            ObjectWrapper::FunctionObject(..) => "function",
             ObjectWrapper::BuiltinFn(_, _) => "builtin-fn",
+            ObjectWrapper::HostFn(_) => "builtin-fn",
+            ObjectWrapper::Handle(_) => "handle",
+            ObjectWrapper::Iterator(_) => "iterator",
+            ObjectWrapper::Memoized(_) => "function",
+            ObjectWrapper::CompiledFn(_) | ObjectWrapper::Closure(_) => "function",
             ObjectWrapper::Array(_) => "array",
+            ObjectWrapper::Hash(_) => "hash",
             // _ => "untyped",
         }
     }
 
+    pub fn neg(&self) -> Result<Self> {
+        match self {
+            ObjectWrapper::Integer(v) => checked(v.checked_neg()),
+            ObjectWrapper::Float(v) => Ok(ObjectWrapper::Float(-v)),
+            _ => Err("cannot eval - after '-'.".into()),
+        }
+    }
+
     pub fn add(&self, other: &Self) -> Result<Self> {
         match self {
             ObjectWrapper::Integer(one) => match other {
-                ObjectWrapper::Integer(two) => Ok(ObjectWrapper::Integer(one + two)),
+                ObjectWrapper::Integer(two) => checked(one.checked_add(*two)),
                 ObjectWrapper::Float(two) => Ok(ObjectWrapper::Float((*one as f64) + two)),
                 _ => Err(format!("int cannot '+' with type {}.", other.type_str()).into()),
             },
+            ObjectWrapper::Float(one) => match other {
+                ObjectWrapper::Integer(two) => Ok(ObjectWrapper::Float(one + (*two as f64))),
+                ObjectWrapper::Float(two) => Ok(ObjectWrapper::Float(one + two)),
+                _ => Err(format!("float cannot '+' with type {}.", other.type_str()).into()),
+            },
             ObjectWrapper::String(one) => match other {
-                ObjectWrapper::String(two) => Ok(ObjectWrapper::String(format!("{}{}", one, two))),
-                ObjectWrapper::Integer(two) => Ok(ObjectWrapper::String(format!("{}{}", one, two))),
-                ObjectWrapper::Float(two) => Ok(ObjectWrapper::String(format!("{}{}", one, two))),
-                ObjectWrapper::Boolean(two) => Ok(ObjectWrapper::String(format!("{}{}", one, two))),
+                ObjectWrapper::String(two) => {
+                    Ok(ObjectWrapper::String(format!("{}{}", one, two).into()))
+                }
+                ObjectWrapper::Integer(two) => {
+                    Ok(ObjectWrapper::String(format!("{}{}", one, two).into()))
+                }
+                ObjectWrapper::Float(two) => {
+                    Ok(ObjectWrapper::String(format!("{}{}", one, two).into()))
+                }
+                ObjectWrapper::Boolean(two) => {
+                    Ok(ObjectWrapper::String(format!("{}{}", one, two).into()))
+                }
                 _ => Err(format!("string cannot '+' with type {}.", other.type_str()).into()),
             },
             _ => Err(format!("type {} dose not support '+' operation.", self.type_str()).into()),
         }
     }
 
+    pub fn sub(&self, other: &Self) -> Result<Self> {
+        match (self, other) {
+            (ObjectWrapper::Integer(one), ObjectWrapper::Integer(two)) => {
+                checked(one.checked_sub(*two))
+            }
+            (ObjectWrapper::Integer(one), ObjectWrapper::Float(two)) => {
+                Ok(ObjectWrapper::Float((*one as f64) - two))
+            }
+            (ObjectWrapper::Float(one), ObjectWrapper::Float(two)) => {
+                Ok(ObjectWrapper::Float(one - two))
+            }
+            (ObjectWrapper::Float(one), ObjectWrapper::Integer(two)) => {
+                Ok(ObjectWrapper::Float(one - (*two as f64)))
+            }
+            _ => Err(format!(
+                "'-' is not support between {} and {}",
+                self.type_str(),
+                other.type_str()
+            )
+            .into()),
+        }
+    }
+
     pub fn eq(&self, other: &Self) -> Result<Self> {
         ensure_compare_with_same_type(self, other)?;
 
@@ -107,7 +240,19 @@ impl ObjectWrapper {
             (ObjectWrapper::Boolean(one), ObjectWrapper::Boolean(two)) => {
                 Ok(ObjectWrapper::Boolean(one == two))
             }
+            (ObjectWrapper::String(one), ObjectWrapper::String(two)) => {
+                Ok(ObjectWrapper::Boolean(one == two))
+            }
             (ObjectWrapper::Null, ObjectWrapper::Null) => Ok(ObjectWrapper::Boolean(true)),
+            (ObjectWrapper::Handle(one), ObjectWrapper::Handle(two)) => {
+                Ok(ObjectWrapper::Boolean(one == two))
+            }
+            (ObjectWrapper::Iterator(one), ObjectWrapper::Iterator(two)) => {
+                Ok(ObjectWrapper::Boolean(one == two))
+            }
+            (ObjectWrapper::Memoized(one), ObjectWrapper::Memoized(two)) => {
+                Ok(ObjectWrapper::Boolean(one == two))
+            }
             _ => Ok(ObjectWrapper::Boolean(false)),
         }
     }
@@ -125,7 +270,19 @@ impl ObjectWrapper {
             (ObjectWrapper::Boolean(one), ObjectWrapper::Boolean(two)) => {
                 Ok(ObjectWrapper::Boolean(one != two))
             }
+            (ObjectWrapper::String(one), ObjectWrapper::String(two)) => {
+                Ok(ObjectWrapper::Boolean(one != two))
+            }
             (ObjectWrapper::Null, ObjectWrapper::Null) => Ok(ObjectWrapper::Boolean(false)),
+            (ObjectWrapper::Handle(one), ObjectWrapper::Handle(two)) => {
+                Ok(ObjectWrapper::Boolean(one != two))
+            }
+            (ObjectWrapper::Iterator(one), ObjectWrapper::Iterator(two)) => {
+                Ok(ObjectWrapper::Boolean(one != two))
+            }
+            (ObjectWrapper::Memoized(one), ObjectWrapper::Memoized(two)) => {
+                Ok(ObjectWrapper::Boolean(one != two))
+            }
             _ => Ok(ObjectWrapper::Boolean(true)),
         }
     }
@@ -135,7 +292,7 @@ impl ObjectWrapper {
 
         match (self, other) {
             (ObjectWrapper::Integer(one), ObjectWrapper::Integer(two)) => {
-                Ok(ObjectWrapper::Integer(one * two))
+                checked(one.checked_mul(*two))
             }
             (ObjectWrapper::Float(one), ObjectWrapper::Float(two)) => {
                 Ok(ObjectWrapper::Float(one * two))
@@ -153,8 +310,11 @@ impl ObjectWrapper {
         ensure_compare_with_same_type(self, other)?;
 
         match (self, other) {
+            (ObjectWrapper::Integer(_), ObjectWrapper::Integer(0)) => {
+                Err("division by zero".into())
+            }
             (ObjectWrapper::Integer(one), ObjectWrapper::Integer(two)) => {
-                Ok(ObjectWrapper::Integer(one / two))
+                checked(one.checked_div(*two))
             }
             (ObjectWrapper::Float(one), ObjectWrapper::Float(two)) => {
                 Ok(ObjectWrapper::Float(one / two))
@@ -192,7 +352,7 @@ impl ObjectWrapper {
 
         match (self, other) {
             (ObjectWrapper::Integer(one), ObjectWrapper::Integer(two)) => {
-                Ok(ObjectWrapper::Boolean(one > two))
+                Ok(ObjectWrapper::Boolean(one < two))
             }
             (ObjectWrapper::Float(one), ObjectWrapper::Float(two)) => {
                 Ok(ObjectWrapper::Boolean(one < two))
@@ -206,8 +366,36 @@ impl ObjectWrapper {
         }
     }
 
+    pub fn hash_key(&self) -> Result<HashKey> {
+        match self {
+            ObjectWrapper::Integer(v) => Ok(HashKey::Integer(*v)),
+            ObjectWrapper::Boolean(v) => Ok(HashKey::Boolean(*v)),
+            ObjectWrapper::String(v) => Ok(HashKey::String(v.clone())),
+            _ => Err(format!("unusable as hash key: {}", self.type_str()).into()),
+        }
+    }
+
+    // approximate number of heap bytes the object itself owns, the values
+    // in an array or a hash were counted when they were created
+    pub fn shallow_size(&self) -> usize {
+        match self {
+            ObjectWrapper::String(v) => v.len(),
+            ObjectWrapper::ErrorObject(v) => v.len(),
+            ObjectWrapper::Array(array) => array.len() * std::mem::size_of::<ObjectWrapper>(),
+            ObjectWrapper::Hash(hash) => {
+                hash.len() * std::mem::size_of::<(HashKey, ObjectWrapper)>()
+            }
+            ObjectWrapper::ReturnValue(v) => v.shallow_size(),
+            _ => 0,
+        }
+    }
+
     pub fn index(&self, other: &Self) -> Result<Self> {
         match (self, other) {
+            (ObjectWrapper::Hash(hash), key) => Ok(hash
+                .get(&key.hash_key()?)
+                .cloned()
+                .unwrap_or(ObjectWrapper::Null)),
             (ObjectWrapper::Array(array), ObjectWrapper::Integer(index)) => {
                 let index = *index as usize;
                 if index >= array.len() {
@@ -0,0 +1,41 @@
+use crate::eval::environment::Environment;
+use crate::eval::ObjectWrapper;
+use crate::lexer::span::Span;
+use crate::parser::program::{Expression, Statement};
+use crate::parser::Result;
+
+// Hooks called by the evaluator around every statement and function call,
+// all of them do nothing by default. `Environment::bindings` gives the
+// variables visible at that point.
+pub trait EvalObserver {
+    fn before_statement(&mut self, _statement: &Statement, _span: Span, _env: &Environment) {}
+
+    fn after_statement(
+        &mut self,
+        _statement: &Statement,
+        _span: Span,
+        _env: &Environment,
+        _result: &Result<ObjectWrapper>,
+    ) {
+    }
+
+    fn before_call(
+        &mut self,
+        _call: &Expression,
+        _func: &ObjectWrapper,
+        _args: &[ObjectWrapper],
+        _span: Span,
+        _env: &Environment,
+    ) {
+    }
+
+    fn after_call(
+        &mut self,
+        _call: &Expression,
+        _func: &ObjectWrapper,
+        _span: Span,
+        _env: &Environment,
+        _result: &Result<ObjectWrapper>,
+    ) {
+    }
+}
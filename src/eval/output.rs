@@ -0,0 +1,97 @@
+use crate::eval::printer::ObjectPrinter;
+use crate::eval::ObjectWrapper;
+use std::fmt::{Debug, Formatter};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+// A shared writer scripts print to. Clones write to the same destination.
+#[derive(Clone)]
+pub struct Sink(Arc<Mutex<Box<dyn Write + Send>>>);
+
+impl Sink {
+    pub fn new<W: Write + Send + 'static>(writer: W) -> Self {
+        Sink(Arc::new(Mutex::new(Box::new(writer))))
+    }
+
+    pub fn stdout() -> Self {
+        Self::new(std::io::stdout())
+    }
+
+    pub fn stderr() -> Self {
+        Self::new(std::io::stderr())
+    }
+
+    pub fn write_line(&self, line: &str) -> std::io::Result<()> {
+        let mut writer = self.0.lock().unwrap();
+        writeln!(writer, "{}", line)?;
+        writer.flush()
+    }
+
+    pub fn write_str(&self, text: &str) -> std::io::Result<()> {
+        let mut writer = self.0.lock().unwrap();
+        writer.write_all(text.as_bytes())?;
+        writer.flush()
+    }
+}
+
+impl Debug for Sink {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Sink")
+    }
+}
+
+// where `puts` and `eputs` write to, the process' stdout and stderr by default
+#[derive(Debug, Clone)]
+pub struct Output {
+    pub stdout: Sink,
+    pub stderr: Sink,
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Output {
+            stdout: Sink::stdout(),
+            stderr: Sink::stderr(),
+        }
+    }
+}
+
+// Collects everything written to it, for capturing script output:
+// `interpreter.set_stdout(buffer.clone())` and later `buffer.contents()`.
+#[derive(Debug, Clone, Default)]
+pub struct OutputBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl OutputBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
+impl Write for OutputBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// how `puts` shows a value: strings as they are, everything else as the
+// REPL does
+pub fn format_value(obj: &ObjectWrapper) -> String {
+    match obj {
+        ObjectWrapper::String(v) => v.to_string(),
+        ObjectWrapper::ReturnValue(v) => format_value(v),
+        _ => ObjectPrinter::new().print(obj),
+    }
+}
@@ -0,0 +1,38 @@
+use crate::eval::evaluator::Evaluator;
+use crate::eval::ObjectWrapper;
+use crate::parser::Result;
+
+// `function` called on every item, on all cores with the `parallel` feature
+// and in order without it, by evaluators sharing the environment of `ev`.
+// What they take counts against the limits of `ev`, all of them together
+// on every step and allocation. A function returning an error fails the map
+// like one raised in it.
+pub fn map(
+    ev: &mut Evaluator,
+    items: Vec<ObjectWrapper>,
+    function: &ObjectWrapper,
+) -> Result<Vec<ObjectWrapper>> {
+    let worker = ev.worker();
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    let results = {
+        use rayon::prelude::*;
+        items
+            .into_par_iter()
+            .map(|item| worker.apply(function, vec![item]))
+            .collect::<Vec<_>>()
+    };
+    #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+    let results = items
+        .into_iter()
+        .map(|item| worker.apply(function, vec![item]));
+
+    let mut ret = vec![];
+    for (obj, steps, heap) in results {
+        ev.absorb(steps, heap)?;
+        match obj? {
+            ObjectWrapper::ErrorObject(message) => return Err(message.into()),
+            obj => ret.push(obj),
+        }
+    }
+    Ok(ret)
+}
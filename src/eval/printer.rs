@@ -0,0 +1,189 @@
+use crate::eval::{snapshot, HashKey, ObjectWrapper};
+use std::fmt::Write;
+
+// Shows values the way they are written in Monkey, strings quoted. A
+// collection that does not fit in `max_width` columns is broken over several
+// lines, one element per line, and only its first `max_items` elements are
+// shown. Hash keys are sorted so the output does not depend on hashing.
+//
+//   {
+//     "a": [1, 2, 3],
+//     "b": [
+//       "x",
+//       … 998 more items
+//     ]
+//   }
+#[derive(Debug, Clone)]
+pub struct ObjectPrinter {
+    pub indent: usize,
+    pub max_width: usize,
+    pub max_items: usize,
+}
+
+impl Default for ObjectPrinter {
+    fn default() -> Self {
+        ObjectPrinter {
+            indent: 2,
+            max_width: 80,
+            max_items: 100,
+        }
+    }
+}
+
+impl ObjectPrinter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn print(&self, obj: &ObjectWrapper) -> String {
+        let mut ret = String::new();
+        self.write(&mut ret, obj, 0);
+        ret
+    }
+
+    fn write(&self, out: &mut String, obj: &ObjectWrapper, depth: usize) {
+        let (open, close, items, rest) = match self.items(obj) {
+            Some(items) => items,
+            None => return self.write_compact(out, obj),
+        };
+        let mut compact = String::new();
+        self.write_compact(&mut compact, obj);
+        let column = out.rfind('\n').map_or(out.len(), |i| out.len() - i - 1);
+        if items.is_empty() || column + compact.chars().count() <= self.max_width {
+            out.push_str(&compact);
+            return;
+        }
+
+        let padding = " ".repeat(self.indent * (depth + 1));
+        out.push_str(open);
+        for (key, value) in items {
+            out.push('\n');
+            out.push_str(&padding);
+            if let Some(key) = key {
+                self.write_compact(out, &key.clone().into());
+                out.push_str(": ");
+            }
+            self.write(out, value, depth + 1);
+            out.push(',');
+        }
+        if let Some(rest) = rest {
+            write!(out, "\n{}{}", padding, rest).unwrap();
+        } else {
+            out.pop();
+        }
+        write!(out, "\n{}{}", " ".repeat(self.indent * depth), close).unwrap();
+    }
+
+    fn write_compact(&self, out: &mut String, obj: &ObjectWrapper) {
+        let (open, close, items, rest) = match self.items(obj) {
+            Some(items) => items,
+            None => return out.push_str(&self.print_scalar(obj)),
+        };
+        out.push_str(open);
+        for (i, (key, value)) in items.iter().enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            if let Some(key) = key {
+                self.write_compact(out, &(*key).clone().into());
+                out.push_str(": ");
+            }
+            self.write_compact(out, value);
+        }
+        if let Some(rest) = rest {
+            if !items.is_empty() {
+                out.push_str(", ");
+            }
+            out.push_str(&rest);
+        }
+        out.push_str(close);
+    }
+
+    fn items<'a>(&self, obj: &'a ObjectWrapper) -> Option<Items<'a>> {
+        match obj {
+            ObjectWrapper::Array(array) => {
+                let items = array.iter().take(self.max_items).map(|v| (None, v));
+                let rest = array.len().saturating_sub(self.max_items);
+                let rest = Some(format!("… {} more items", rest)).filter(|_| rest > 0);
+                Some(("[", "]", items.collect(), rest))
+            }
+            ObjectWrapper::Hash(hash) => {
+                let mut pairs = hash.iter().collect::<Vec<_>>();
+                pairs.sort_by(|a, b| a.0.cmp(b.0));
+                let rest = pairs.len().saturating_sub(self.max_items);
+                let rest = Some(format!("… {} more entries", rest)).filter(|_| rest > 0);
+                let items = pairs
+                    .into_iter()
+                    .take(self.max_items)
+                    .map(|(key, value)| (Some(key), value));
+                Some(("{", "}", items.collect(), rest))
+            }
+            ObjectWrapper::ReturnValue(v) => self.items(v),
+            _ => None,
+        }
+    }
+
+    fn print_scalar(&self, obj: &ObjectWrapper) -> String {
+        match obj {
+            ObjectWrapper::Null => "null".to_string(),
+            ObjectWrapper::Integer(v) => v.to_string(),
+            ObjectWrapper::String(v) => quote(v),
+            ObjectWrapper::ErrorObject(v) => format!("error({})", quote(v)),
+            ObjectWrapper::ReturnValue(v) => self.print(v),
+            ObjectWrapper::FunctionObject(..)
+            | ObjectWrapper::CompiledFn(_)
+            | ObjectWrapper::Closure(_)
+            | ObjectWrapper::Memoized(_) => "<function>".to_string(),
+            ObjectWrapper::BuiltinFn(..) | ObjectWrapper::HostFn(_) => "<builtin>".to_string(),
+            ObjectWrapper::Handle(handle) => format!("<handle {}>", handle.type_name()),
+            ObjectWrapper::Iterator(_) => "<iterator>".to_string(),
+            _ => snapshot::to_source(obj).unwrap_or_else(|| obj.to_string()),
+        }
+    }
+}
+
+// The type of a value and what else is quick to tell about it, for the
+// REPL's `:type`: `array, 3 items`, `hash, 1 key`, `function, 2 parameters`.
+pub fn summary(obj: &ObjectWrapper) -> String {
+    let (count, what) = match obj {
+        ObjectWrapper::String(v) => (v.chars().count(), "character"),
+        ObjectWrapper::Array(array) => (array.len(), "item"),
+        ObjectWrapper::Hash(hash) => (hash.len(), "key"),
+        ObjectWrapper::FunctionObject(params, ..) => (params.len(), "parameter"),
+        ObjectWrapper::BuiltinFn(arity, _) => (*arity, "parameter"),
+        ObjectWrapper::HostFn(f) => (f.arity(), "parameter"),
+        ObjectWrapper::CompiledFn(f) => (f.num_parameters, "parameter"),
+        ObjectWrapper::Closure(c) => (c.func.num_parameters, "parameter"),
+        ObjectWrapper::Memoized(memo) => return summary(memo.function()),
+        ObjectWrapper::ReturnValue(v) => return summary(v),
+        _ => return obj.type_str().to_string(),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{}, {} {}{}", obj.type_str(), count, what, plural)
+}
+
+// the brackets of a collection, the elements shown and what is said about the
+// others
+type Items<'a> = (
+    &'static str,
+    &'static str,
+    Vec<(Option<&'a HashKey>, &'a ObjectWrapper)>,
+    Option<String>,
+);
+
+fn quote(v: &str) -> String {
+    let mut ret = String::with_capacity(v.len() + 2);
+    ret.push('"');
+    for c in v.chars() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            '\t' => ret.push_str("\\t"),
+            '\r' => ret.push_str("\\r"),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
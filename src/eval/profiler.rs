@@ -0,0 +1,212 @@
+use crate::eval::environment::Environment;
+use crate::eval::observer::EvalObserver;
+use crate::eval::ObjectWrapper;
+use crate::lexer::span::Span;
+use crate::parser::program::{Expression, Statement};
+use crate::parser::Result;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+// statements longer than this are cut in the report
+const LABEL_WIDTH: usize = 40;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileEntry {
+    pub name: String,
+    pub count: u64,
+    pub total: Duration,
+}
+
+// hot spots sorted by cumulative time, slowest first
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileReport {
+    pub functions: Vec<ProfileEntry>,
+    pub statements: Vec<ProfileEntry>,
+}
+
+#[derive(Default)]
+struct Counter {
+    count: u64,
+    total: Duration,
+    // recursive calls are only timed once, by their outermost frame
+    depth: usize,
+    started: Option<Instant>,
+}
+
+impl Counter {
+    fn enter(&mut self) {
+        self.count += 1;
+        if self.depth == 0 {
+            self.started = Some(Instant::now());
+        }
+        self.depth += 1;
+    }
+
+    fn leave(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        if self.depth == 0 {
+            if let Some(started) = self.started.take() {
+                self.total += started.elapsed();
+            }
+        }
+    }
+}
+
+// Counts evaluations and cumulative time per called function (named after
+// the callee) and per statement (named after its position and source). The
+// counters survive between evaluations, so they add up over a REPL session.
+#[derive(Default)]
+pub struct Profiler {
+    functions: HashMap<String, Counter>,
+    statements: HashMap<String, Counter>,
+    // keys of the calls and statements being evaluated, innermost last
+    active_functions: Vec<String>,
+    active_statements: Vec<String>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn report(&self) -> ProfileReport {
+        let mut functions = self
+            .functions
+            .iter()
+            .map(|(name, counter)| entry(name.clone(), counter))
+            .collect::<Vec<ProfileEntry>>();
+        let mut statements = self
+            .statements
+            .iter()
+            .map(|(name, counter)| entry(name.clone(), counter))
+            .collect::<Vec<ProfileEntry>>();
+        sort_entries(&mut functions);
+        sort_entries(&mut statements);
+        ProfileReport {
+            functions,
+            statements,
+        }
+    }
+}
+
+fn entry(name: String, counter: &Counter) -> ProfileEntry {
+    ProfileEntry {
+        name,
+        count: counter.count,
+        total: counter.total,
+    }
+}
+
+fn sort_entries(entries: &mut [ProfileEntry]) {
+    entries.sort_by(|a, b| {
+        b.total
+            .cmp(&a.total)
+            .then(b.count.cmp(&a.count))
+            .then(a.name.cmp(&b.name))
+    });
+}
+
+fn statement_label(statement: &Statement, span: Span) -> String {
+    let mut text = statement.to_string().replace('\n', " ");
+    if text.chars().count() > LABEL_WIDTH {
+        text = text.chars().take(LABEL_WIDTH - 3).collect::<String>() + "...";
+    }
+    format!("{} {}", span.start, text)
+}
+
+pub(crate) fn function_name(call: &Expression) -> String {
+    match call {
+        Expression::CallExpression(func, _, _) => match func.as_ref() {
+            Expression::Identifier(ident) => ident.0.to_string(),
+            _ => "<anonymous>".to_string(),
+        },
+        _ => call.to_string(),
+    }
+}
+
+impl EvalObserver for Profiler {
+    fn before_statement(&mut self, statement: &Statement, span: Span, _env: &Environment) {
+        let label = statement_label(statement, span);
+        self.statements.entry(label.clone()).or_default().enter();
+        self.active_statements.push(label);
+    }
+
+    fn after_statement(
+        &mut self,
+        _statement: &Statement,
+        _span: Span,
+        _env: &Environment,
+        _result: &Result<ObjectWrapper>,
+    ) {
+        if let Some(label) = self.active_statements.pop() {
+            if let Some(counter) = self.statements.get_mut(&label) {
+                counter.leave();
+            }
+        }
+    }
+
+    fn before_call(
+        &mut self,
+        call: &Expression,
+        _func: &ObjectWrapper,
+        _args: &[ObjectWrapper],
+        _span: Span,
+        _env: &Environment,
+    ) {
+        let name = function_name(call);
+        self.functions.entry(name.clone()).or_default().enter();
+        self.active_functions.push(name);
+    }
+
+    fn after_call(
+        &mut self,
+        _call: &Expression,
+        _func: &ObjectWrapper,
+        _span: Span,
+        _env: &Environment,
+        _result: &Result<ObjectWrapper>,
+    ) {
+        if let Some(name) = self.active_functions.pop() {
+            if let Some(counter) = self.functions.get_mut(&name) {
+                counter.leave();
+            }
+        }
+    }
+}
+
+fn write_table(f: &mut Formatter<'_>, title: &str, entries: &[ProfileEntry]) -> std::fmt::Result {
+    let width = entries
+        .iter()
+        .map(|e| e.name.chars().count())
+        .chain(std::iter::once(title.len()))
+        .max()
+        .unwrap_or_default();
+    writeln!(
+        f,
+        "{:<width$} {:>10} {:>12}",
+        title,
+        "count",
+        "total ms",
+        width = width
+    )?;
+    for e in entries {
+        writeln!(
+            f,
+            "{:<width$} {:>10} {:>12.3}",
+            e.name,
+            e.count,
+            e.total.as_secs_f64() * 1000.0,
+            width = width
+        )?;
+    }
+    Ok(())
+}
+
+impl Display for ProfileReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_table(f, "function", &self.functions)?;
+        writeln!(f)?;
+        write_table(f, "statement", &self.statements)
+    }
+}
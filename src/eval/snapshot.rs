@@ -0,0 +1,121 @@
+use crate::eval::{HashKey, HashMap, ObjectWrapper};
+use crate::lexer::lexer::Lexer;
+use crate::lexer::token::Token;
+use crate::parser::program::Expression;
+use crate::parser::Result;
+
+// Snapshots are plain Monkey source, one `let` per binding. Restoring only
+// accepts literal values so loading a snapshot never runs any code.
+
+// returns None for values that cannot be written back as a literal
+// (functions, builtins, null, ...)
+pub fn to_source(obj: &ObjectWrapper) -> Option<String> {
+    match obj {
+        // its magnitude does not fit a literal, it is written as arithmetic
+        // `from_source` accepts
+        ObjectWrapper::Integer(i64::MIN) => Some(format!("({} - 1)", i64::MIN + 1)),
+        ObjectWrapper::Integer(v) => Some(v.to_string()),
+        ObjectWrapper::Float(v) if v.is_finite() => {
+            let s = v.to_string();
+            if s.contains('.') {
+                Some(s)
+            } else {
+                Some(format!("{}.0", s))
+            }
+        }
+        ObjectWrapper::Boolean(v) => Some(v.to_string()),
+        ObjectWrapper::String(v) => string_literal(v),
+        ObjectWrapper::ErrorObject(v) => string_literal(v).map(|s| format!("error({})", s)),
+        ObjectWrapper::Array(array) => {
+            let elements = array
+                .iter()
+                .map(to_source)
+                .collect::<Option<Vec<String>>>()?;
+            Some(format!("[{}]", elements.join(", ")))
+        }
+        ObjectWrapper::Hash(hash) => {
+            let mut pairs = hash.iter().collect::<Vec<(&HashKey, &ObjectWrapper)>>();
+            pairs.sort_by(|a, b| a.0.cmp(b.0));
+            let pairs = pairs
+                .into_iter()
+                .map(|(key, value)| {
+                    Some(format!(
+                        "{}: {}",
+                        to_source(&key.clone().into())?,
+                        to_source(value)?
+                    ))
+                })
+                .collect::<Option<Vec<String>>>()?;
+            Some(format!("{{{}}}", pairs.join(", ")))
+        }
+        _ => None,
+    }
+}
+
+// whether `obj` is a value rather than code, a value `to_source` returns
+// None for is lost when saved
+pub fn is_data(obj: &ObjectWrapper) -> bool {
+    matches!(
+        obj,
+        ObjectWrapper::Integer(_)
+            | ObjectWrapper::Float(_)
+            | ObjectWrapper::Boolean(_)
+            | ObjectWrapper::String(_)
+            | ObjectWrapper::ErrorObject(_)
+            | ObjectWrapper::Array(_)
+            | ObjectWrapper::Hash(_)
+    )
+}
+
+// the lexer keeps string contents verbatim, so a string can only be written
+// back if lexing the quoted text yields exactly the same string
+fn string_literal(v: &str) -> Option<String> {
+    let quoted = format!("\"{}\"", v);
+    let mut lx = Lexer::new(quoted.as_str());
+    match (lx.next_source_token().0, lx.next_source_token().0) {
+        (Token::String(s), Token::EOF) if s == v => Some(quoted),
+        _ => None,
+    }
+}
+
+pub fn from_source(expr: &Expression) -> Result<ObjectWrapper> {
+    match expr {
+        Expression::IntLiteral(v) => Ok(ObjectWrapper::Integer(*v)),
+        Expression::FloatLiteral(v) => Ok(ObjectWrapper::Float(*v)),
+        Expression::BoolLiteral(v) => Ok(ObjectWrapper::Boolean(*v)),
+        Expression::StringLiteral(v) => Ok(ObjectWrapper::String(v.clone())),
+        Expression::PrefixExpression(Token::Minus, right, _) => match from_source(right)? {
+            v @ (ObjectWrapper::Integer(_) | ObjectWrapper::Float(_)) => v.neg(),
+            _ => Err(format!("invalid value in snapshot: {}", expr).into()),
+        },
+        Expression::InfixExpression(left, Token::Minus, right, _)
+            if matches!(right.as_ref(), Expression::IntLiteral(1))
+                && from_source(left)? == ObjectWrapper::Integer(i64::MIN + 1) =>
+        {
+            Ok(ObjectWrapper::Integer(i64::MIN))
+        }
+        Expression::ArrayLiteral(array) => Ok(ObjectWrapper::Array(
+            array
+                .iter()
+                .map(from_source)
+                .collect::<Result<Vec<_>>>()?
+                .into(),
+        )),
+        Expression::HashLiteral(pairs) => {
+            let mut hash = HashMap::with_capacity_and_hasher(pairs.len(), Default::default());
+            for (key, value) in pairs {
+                hash.insert(from_source(key)?.hash_key()?, from_source(value)?);
+            }
+            Ok(ObjectWrapper::Hash(hash))
+        }
+        Expression::CallExpression(func, args, _) => match (func.as_ref(), args.as_slice()) {
+            (Expression::Identifier(ident), [Expression::StringLiteral(msg)])
+                if ident.0 == "error" =>
+            {
+                Ok(ObjectWrapper::ErrorObject(msg.to_string()))
+            }
+            _ => Err(format!("invalid value in snapshot: {}", expr).into()),
+        },
+        _ => Err(format!("invalid value in snapshot: {}", expr).into()),
+    }
+}
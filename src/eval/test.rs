@@ -1,14 +1,26 @@
-use crate::eval::evaluator::Evaluator;
+use crate::eval::capabilities::Capabilities;
+use crate::eval::coverage::Coverage;
+use crate::eval::environment::Environment;
+use crate::eval::evaluator::{EvalOptions, Evaluator};
+use crate::eval::observer::EvalObserver;
+use crate::eval::output::{Output, OutputBuffer, Sink};
+use crate::eval::printer::{self, ObjectPrinter};
+use crate::eval::snapshot;
 use crate::eval::ObjectWrapper;
 use crate::lexer::lexer::Lexer;
+use crate::lexer::span::Span;
+use crate::parser::program::{Expression, Statement};
+use crate::parser::resolve::resolve;
 use crate::parser::Parser;
 use crate::parser::Result;
+use std::sync::Arc;
 
 #[cfg(test)]
 fn test_eval(input: &str) -> Result<ObjectWrapper> {
     let l = Lexer::new(input);
     let mut p = Parser::new(l);
-    let program = p.parse_program()?;
+    let mut program = p.parse_program()?;
+    resolve(&mut program);
     let mut evaluator = Evaluator::new(&program.statements);
     evaluator.eval()
 }
@@ -21,7 +33,7 @@ fn test_integer_object() {
         if let ObjectWrapper::Integer(v) = obj {
             assert_eq!(v, expect);
         } else {
-            assert!(false, "{:?} is not an integer object.", obj);
+            panic!("{:?} is not an integer object.", obj);
         }
     }
 }
@@ -55,7 +67,7 @@ fn test_error_handle() {
         if let ObjectWrapper::ErrorObject(v) = obj {
             assert_eq!(&v, expect);
         } else {
-            assert!(false, "{:?} is not an error object.", obj);
+            panic!("{:?} is not an error object.", obj);
         }
     }
 }
@@ -84,7 +96,7 @@ fn test_function_call() {
         if let ObjectWrapper::Integer(v) = obj {
             assert_eq!(v, expect);
         } else {
-            assert!(false, "expect integer: {}, got {:?}", expect, obj);
+            panic!("expect integer: {}, got {:?}", expect, obj);
         }
     }
 }
@@ -101,6 +113,365 @@ fn test_builtin_functions() {
             "len(10, 10);",
             ObjectWrapper::ErrorObject("Wrong number of arguments, expect 1 got 2".to_string()),
         ),
+        ("assert(1 < 2, \"ordered\"); 1", ObjectWrapper::Integer(1)),
+        (
+            "assert(2 < 1, \"ordered\"); 1",
+            ObjectWrapper::ErrorObject("assertion failed: ordered".to_string()),
+        ),
+        (
+            "assert(1, \"ordered\")",
+            ObjectWrapper::ErrorObject(
+                "Argument to 'assert' must be BOOLEAN, got \"int\"".to_string(),
+            ),
+        ),
+        ("assert_eq([1, 2], [1, 2])", ObjectWrapper::Null),
+        (
+            "assert_eq(\"a\", \"b\")",
+            ObjectWrapper::ErrorObject("assertion failed: expect \"b\", got \"a\"".to_string()),
+        ),
+    ];
+
+    for (input, expect) in cases {
+        let obj = test_eval(input).unwrap();
+        assert_eq!(obj, expect);
+    }
+}
+
+#[test]
+fn test_error_values() {
+    let cases = [
+        ("is_error(error(\"boom\"))", ObjectWrapper::Boolean(true)),
+        ("is_error(1)", ObjectWrapper::Boolean(false)),
+        (
+            "let e = error(\"boom\"); e",
+            ObjectWrapper::ErrorObject("boom".to_string()),
+        ),
+        (
+            "error(1)",
+            ObjectWrapper::ErrorObject(
+                "Argument to 'error' must be STRING, got \"int\"".to_string(),
+            ),
+        ),
+        (
+            "let f = fn() { foobar }; f(); 5",
+            ObjectWrapper::ErrorObject("identifier not found: foobar".to_string()),
+        ),
+    ];
+
+    for (input, expect) in cases {
+        let obj = test_eval(input).unwrap();
+        assert_eq!(obj, expect);
+    }
+}
+
+#[test]
+fn test_rescue() {
+    let cases = [
+        ("rescue(fn() { 1 }, fn(err) { 5 })", ObjectWrapper::Integer(1)),
+        ("rescue(fn() { foobar }, fn(err) { 5 })", ObjectWrapper::Integer(5)),
+        (
+            "rescue(fn() { foobar }, fn(err) { err })",
+            ObjectWrapper::ErrorObject("identifier not found: foobar".to_string()),
+        ),
+        (
+            "rescue(fn() { error(\"boom\") }, fn(err) { err })",
+            ObjectWrapper::ErrorObject("boom".to_string()),
+        ),
+        (
+            "let risky = fn(x) { x + len(1) }; rescue(fn() { risky(1) }, fn(err) { is_error(err) })",
+            ObjectWrapper::Boolean(true),
+        ),
+        (
+            "rescue(fn() { error(\"boom\") }, fn(err) { \"caught: \" + message(err) })",
+            ObjectWrapper::String("caught: boom".into()),
+        ),
+        (
+            "rescue(fn() { 1 / 0 }, fn(err) { len(message(err)) })",
+            ObjectWrapper::Integer(16),
+        ),
+        (
+            "message(\"boom\")",
+            ObjectWrapper::ErrorObject("Argument to 'message' must be ERROR, got \"string\"".to_string()),
+        ),
+        (
+            "rescue(fn() { foobar }, fn(err) { barfoo })",
+            ObjectWrapper::ErrorObject("identifier not found: barfoo".to_string()),
+        ),
+    ];
+
+    for (input, expect) in cases {
+        let obj = test_eval(input).unwrap();
+        assert_eq!(obj, expect);
+    }
+}
+
+#[test]
+fn test_pmap() {
+    let ints = |v: &[i64]| {
+        ObjectWrapper::Array(Arc::new(
+            v.iter().copied().map(ObjectWrapper::Integer).collect(),
+        ))
+    };
+    let cases = [
+        ("pmap([1, 2, 3], fn(x) { x * x })", ints(&[1, 4, 9])),
+        ("let k = 10; pmap([1, 2], fn(x) { x + k })", ints(&[11, 12])),
+        (
+            "let sq = fn(x) { x * x }; pmap(pmap([1, 2], sq), sq)",
+            ints(&[1, 16]),
+        ),
+        ("pmap([[1], [1, 2]], len)", ints(&[1, 2])),
+        ("pmap([], len)", ints(&[])),
+        (
+            "pmap([1, 2], fn(x) { error(\"boom\") })",
+            ObjectWrapper::ErrorObject("boom".to_string()),
+        ),
+        (
+            "rescue(fn() { pmap([1], fn(x) { foobar }) }, fn(err) { err })",
+            ObjectWrapper::ErrorObject("identifier not found: foobar".to_string()),
+        ),
+        (
+            "pmap(1, len)",
+            ObjectWrapper::ErrorObject("Argument to 'pmap' must be ARRAY, got \"int\"".to_string()),
+        ),
+    ];
+    for (input, expect) in cases {
+        assert_eq!(test_eval(input).unwrap(), expect, "{}", input);
+    }
+
+    // the steps taken by every call count
+    let input = "let f = fn(n) { if (n < 1) { 0 } else { 1 + f(n - 1) } }; pmap([40, 40, 40], f)";
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+    let mut evaluator = Evaluator::new(&program.statements);
+    evaluator.set_options(EvalOptions {
+        max_steps: Some(1000),
+        ..Default::default()
+    });
+    assert_eq!(
+        evaluator.eval().unwrap(),
+        ObjectWrapper::ErrorObject("script exceeded execution limit".to_string())
+    );
+
+    // the calls share what is left, they stop once together they took it,
+    // at most three of them get to print
+    let cases = [
+        (
+            "let f = fn(n) { if (n < 1) { 0 } else { 1 + f(n - 1) } }; pmap([1, 2, 3, 4, 5, 6, 7, 8], fn(n) { let r = f(40); puts(n); r })",
+            EvalOptions {
+                max_steps: Some(1000),
+                ..Default::default()
+            },
+        ),
+        (
+            "pmap([1, 2, 3, 4, 5, 6, 7, 8], fn(n) { let a = [n, n, n, n]; puts(n); a })",
+            EvalOptions {
+                max_memory: Some(512),
+                ..Default::default()
+            },
+        ),
+    ];
+    for (input, options) in cases {
+        let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+        let mut evaluator = Evaluator::new(&program.statements);
+        evaluator.set_options(options);
+        let stdout = OutputBuffer::new();
+        evaluator.set_output(Output {
+            stdout: Sink::new(stdout.clone()),
+            stderr: Sink::stderr(),
+        });
+        assert!(matches!(
+            evaluator.eval().unwrap(),
+            ObjectWrapper::ErrorObject(_)
+        ));
+        assert!(stdout.contents().lines().count() <= 3, "{}", input);
+    }
+}
+
+#[test]
+fn test_memoize() {
+    let cases = [
+        (
+            "let fib = memoize(fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }); fib(90)",
+            ObjectWrapper::Integer(2880067194370816120),
+        ),
+        ("memoize(len)([1, 2])", ObjectWrapper::Integer(2)),
+        // unhashable arguments are passed through uncached
+        ("memoize(len)([[1], [2]])", ObjectWrapper::Integer(2)),
+        ("let f = memoize(len); f == f", ObjectWrapper::Boolean(true)),
+        (
+            "memoize(fn(x) { error(\"boom\") })(1)",
+            ObjectWrapper::ErrorObject("boom".to_string()),
+        ),
+        (
+            "memoize(1)",
+            ObjectWrapper::ErrorObject("Argument to `memoize` must be FUNCTION, got int".to_string()),
+        ),
+    ];
+    for (input, expect) in cases {
+        assert_eq!(test_eval(input).unwrap(), expect, "{}", input);
+    }
+
+    // the body runs once for each distinct list of arguments
+    let input = "let f = memoize(fn(x, y) { puts(x); x + y }); [f(1, 2), f(1, 2), f(2, 1)]";
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+    let mut evaluator = Evaluator::new(&program.statements);
+    let stdout = OutputBuffer::new();
+    evaluator.set_output(Output {
+        stdout: Sink::new(stdout.clone()),
+        stderr: Sink::stderr(),
+    });
+    evaluator.eval().unwrap();
+    assert_eq!(stdout.contents(), "1\n2\n");
+}
+
+#[test]
+fn test_memoize_memory() {
+    // the entries of the cache count against the memory limit
+    let input = "let f = memoize(fn(x) { x }); let g = fn(n) { if (n > 0) { f(n); g(n - 1) } else { n } }; g(200)";
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+    let mut evaluator = Evaluator::new(&program.statements);
+    evaluator.set_options(EvalOptions {
+        max_memory: Some(4096),
+        ..Default::default()
+    });
+    assert!(matches!(
+        evaluator.eval().unwrap(),
+        ObjectWrapper::ErrorObject(e) if e.starts_with("out of memory")
+    ));
+}
+
+#[test]
+fn test_type_warnings() {
+    let input = "let f = fn(x) { x == \"1\" };\n[f(1), f(2), 1 + 2.5, \"n\" + 1, 1 != true, 1 + 1]";
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+    let mut evaluator = Evaluator::new(&program.statements);
+    evaluator.set_options(EvalOptions {
+        type_warnings: true,
+        ..Default::default()
+    });
+    assert_eq!(
+        snapshot::to_source(&evaluator.eval().unwrap()).unwrap(),
+        "[false, false, 3.5, \"n1\", true, 2]"
+    );
+    let warnings = evaluator
+        .warnings()
+        .iter()
+        .map(|w| (w.span.start.line, w.span.start.column, w.message.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        warnings,
+        [
+            (
+                1,
+                17,
+                "int == string compares different types, they are never equal"
+            ),
+            (2, 14, "int + float converts the int to float"),
+            (2, 23, "string + int converts the int to string"),
+            (
+                2,
+                32,
+                "int != bool compares different types, they are never equal"
+            ),
+        ]
+    );
+
+    // off, they fail as without warnings
+    let input = "1 == \"1\"";
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+    let mut evaluator = Evaluator::new(&program.statements);
+    assert!(matches!(
+        evaluator.eval().unwrap(),
+        ObjectWrapper::ErrorObject(_)
+    ));
+    assert!(evaluator.warnings().is_empty());
+}
+
+#[test]
+fn test_environment_snapshot() {
+    let input = r#"
+    let a = 5;
+    let b = -2.5;
+    let c = "hello";
+    let d = [1, [true, "x"], 3.0];
+    let e = error("boom");
+    let h = {"b": [1], "a": {true: "yes"}, 3: 4};
+    let m = [-9223372036854775807 - 1];
+    let f = fn(x) { x };
+    let g = len;
+    "#;
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+    let mut evaluator = Evaluator::with_env(&program.statements, Environment::default());
+    evaluator.eval().unwrap();
+    let env = evaluator.get_env();
+
+    let (snapshot, skipped) = env.snapshot();
+    assert!(skipped.is_empty());
+    assert_eq!(
+        snapshot,
+        "let a = 5;\nlet b = -2.5;\nlet c = \"hello\";\nlet d = [1, [true, \"x\"], 3.0];\nlet e = error(\"boom\");\nlet h = {3: 4, \"a\": {true: \"yes\"}, \"b\": [1]};\nlet m = [(-9223372036854775807 - 1)];\n"
+    );
+
+    let mut restored = Environment::default();
+    restored.restore(&snapshot).unwrap();
+    for name in ["a", "b", "c", "d", "e", "h", "m"] {
+        assert_eq!(restored.get(name), env.get(name));
+    }
+    assert!(!restored.contains("f"));
+    assert!(!restored.contains("g"));
+}
+
+#[test]
+fn test_environment_snapshot_skipped() {
+    // the lexer has no escapes, a string with a quote has no literal
+    let mut env = Environment::default();
+    env.set("a", ObjectWrapper::Integer(1));
+    env.set("q", ObjectWrapper::String("say \"hi\"".into()));
+    env.set(
+        "r",
+        ObjectWrapper::Array(vec![ObjectWrapper::Float(f64::NAN)].into()),
+    );
+    env.set("n", ObjectWrapper::Null);
+    let (snapshot, skipped) = env.snapshot();
+    assert_eq!(snapshot, "let a = 1;\n");
+    assert_eq!(skipped, ["q", "r"]);
+}
+
+#[test]
+fn test_environment_restore_rejects_code() {
+    let cases = [
+        "let a = len(\"x\");",
+        "let a = 1 + 2;",
+        "puts(1);",
+        "let a = fn() { 1 };",
+    ];
+
+    for input in cases {
+        let mut env = Environment::default();
+        env.set("a", ObjectWrapper::Integer(1));
+        assert!(env.restore(input).is_err(), "{} should be rejected", input);
+        assert_eq!(env.get("a"), Some(ObjectWrapper::Integer(1)));
+    }
+}
+
+#[test]
+fn test_arithmetic() {
+    let cases = [
+        ("5 - 3", ObjectWrapper::Integer(2)),
+        ("10 / 4", ObjectWrapper::Integer(2)),
+        ("1.5 + 1", ObjectWrapper::Float(2.5)),
+        ("1 - 0.5", ObjectWrapper::Float(0.5)),
+        ("1 < 2", ObjectWrapper::Boolean(true)),
+        ("2 < 1", ObjectWrapper::Boolean(false)),
+        ("\"ab\" == \"a\" + \"b\"", ObjectWrapper::Boolean(true)),
+        ("\"a\" != \"a\"", ObjectWrapper::Boolean(false)),
+        (
+            "1 / 0",
+            ObjectWrapper::ErrorObject("division by zero".to_string()),
+        ),
+        (
+            "9223372036854775807 + 1",
+            ObjectWrapper::ErrorObject("integer overflow".to_string()),
+        ),
     ];
 
     for (input, expect) in cases {
@@ -108,3 +479,684 @@ fn test_builtin_functions() {
         assert_eq!(obj, expect);
     }
 }
+
+#[test]
+fn test_closures_share_environment() {
+    let cases = [
+        (
+            "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(15)",
+            610,
+        ),
+        ("let f = fn() { a }; let a = 3; f()", 3),
+        ("let x = 1; let f = fn(x) { x }; f(2) + x", 3),
+    ];
+
+    for (input, expect) in cases {
+        let obj = test_eval(input).unwrap();
+        assert_eq!(obj, ObjectWrapper::Integer(expect));
+    }
+}
+
+#[test]
+fn test_eval_step_limit() {
+    let cases = [
+        ("1 + 2", ObjectWrapper::Integer(3)),
+        (
+            "let f = fn() { f() }; f()",
+            ObjectWrapper::ErrorObject("script exceeded execution limit".to_string()),
+        ),
+        (
+            "let f = fn(n) { if (n < 1) { 0 } else { 1 + f(n - 1) } }; f(1000)",
+            ObjectWrapper::ErrorObject("script exceeded execution limit".to_string()),
+        ),
+    ];
+
+    for (input, expect) in cases {
+        let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+        let mut evaluator = Evaluator::new(&program.statements);
+        evaluator.set_options(EvalOptions {
+            max_steps: Some(1000),
+            ..Default::default()
+        });
+        assert_eq!(evaluator.eval().unwrap(), expect);
+        assert!(evaluator.steps() <= 1001);
+    }
+
+    let program = Parser::new(Lexer::new("1 + 2")).parse_program().unwrap();
+    let mut evaluator = Evaluator::new(&program.statements);
+    evaluator.eval().unwrap();
+    assert_eq!(evaluator.steps(), 4);
+}
+
+#[cfg(test)]
+fn test_eval_with_capabilities(input: &str, capabilities: Capabilities) -> ObjectWrapper {
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+    let mut evaluator = Evaluator::new(&program.statements);
+    evaluator.set_options(EvalOptions {
+        capabilities,
+        ..Default::default()
+    });
+    evaluator.eval().unwrap()
+}
+
+#[test]
+fn test_sandbox_capabilities() {
+    let denied = [
+        (
+            "read_file(\"Cargo.toml\")",
+            "permission denied: 'read_file' needs the filesystem capability",
+        ),
+        (
+            "write_file(\"x\", \"y\")",
+            "permission denied: 'write_file' needs the filesystem capability",
+        ),
+        (
+            "time()",
+            "permission denied: 'time' needs the clock capability",
+        ),
+        (
+            "benchmark(fn() { 1 }, 1)",
+            "permission denied: 'benchmark' needs the clock capability",
+        ),
+        (
+            "random()",
+            "permission denied: 'random' needs the random capability",
+        ),
+        (
+            "exit(1)",
+            "permission denied: 'exit' needs the process capability",
+        ),
+        (
+            "env(\"HOME\")",
+            "permission denied: 'env' needs the environment capability",
+        ),
+        (
+            "puts(1)",
+            "permission denied: 'puts' needs the output capability",
+        ),
+        (
+            "eputs(1)",
+            "permission denied: 'eputs' needs the output capability",
+        ),
+        (
+            "input(\"> \")",
+            "permission denied: 'input' needs the input capability",
+        ),
+        (
+            "read_line()",
+            "permission denied: 'read_line' needs the input capability",
+        ),
+        (
+            "set_env(\"HOME\", \"/\")",
+            "permission denied: 'set_env' needs the environment capability",
+        ),
+    ];
+    for (input, expect) in denied {
+        assert_eq!(
+            test_eval_with_capabilities(input, Capabilities::NONE),
+            ObjectWrapper::ErrorObject(expect.to_string())
+        );
+    }
+
+    let path = std::env::temp_dir().join(format!("monkey_sandbox_{}.txt", std::process::id()));
+    let input = format!(
+        "write_file(\"{0}\", \"hello\"); read_file(\"{0}\")",
+        path.display()
+    );
+    assert_eq!(
+        test_eval_with_capabilities(&input, Capabilities::FILESYSTEM),
+        ObjectWrapper::String("hello".into())
+    );
+    std::fs::remove_file(path).unwrap();
+
+    let allowed = [
+        ("time() > 0", Capabilities::CLOCK),
+        (
+            "let r = random(); if (r < 1.0) { r > 0.0 } else { false }",
+            Capabilities::RANDOM,
+        ),
+        (
+            "is_error(rescue(fn() { time() }, fn(e) { e }))",
+            Capabilities::ALL - Capabilities::CLOCK,
+        ),
+    ];
+    for (input, capabilities) in allowed {
+        assert_eq!(
+            test_eval_with_capabilities(input, capabilities),
+            ObjectWrapper::Boolean(true),
+            "{}",
+            input
+        );
+    }
+    assert_eq!(
+        (Capabilities::FILESYSTEM | Capabilities::CLOCK).to_string(),
+        "filesystem, clock"
+    );
+}
+
+#[test]
+fn test_hash_and_push() {
+    let cases = [
+        ("{\"a\": 1, 2: true}[\"a\"]", ObjectWrapper::Integer(1)),
+        ("{\"a\": 1, 2: true}[2]", ObjectWrapper::Boolean(true)),
+        ("{\"a\": 1}[\"b\"]", ObjectWrapper::Null),
+        ("let k = \"x\"; {k: 5}[\"x\"]", ObjectWrapper::Integer(5)),
+        ("len({\"a\": 1, \"b\": 2})", ObjectWrapper::Integer(2)),
+        (
+            "{1.5: 1}",
+            ObjectWrapper::ErrorObject("unusable as hash key: float".to_string()),
+        ),
+        (
+            "{\"a\": 1}[[1]]",
+            ObjectWrapper::ErrorObject("unusable as hash key: array".to_string()),
+        ),
+        (
+            "let a = [1]; let b = push(a, 2); len(a) + len(b)",
+            ObjectWrapper::Integer(3),
+        ),
+        (
+            "push(1, 2)",
+            ObjectWrapper::ErrorObject("Argument to 'push' must be ARRAY, got \"int\"".to_string()),
+        ),
+    ];
+
+    for (input, expect) in cases {
+        let obj = test_eval(input).unwrap();
+        assert_eq!(obj, expect, "{}", input);
+    }
+}
+
+#[test]
+fn test_shared_arrays() {
+    // bound again and passed around, the items are not copied
+    let obj = test_eval("let a = [1, [2]]; let f = fn(x) { x }; let b = a; [a, f(b)]").unwrap();
+    match obj {
+        ObjectWrapper::Array(pair) => match (&pair[0], &pair[1]) {
+            (ObjectWrapper::Array(a), ObjectWrapper::Array(b)) => assert!(Arc::ptr_eq(a, b)),
+            _ => panic!("expect arrays, got {:?}", pair),
+        },
+        obj => panic!("expect an array, got {:?}", obj),
+    }
+}
+
+#[test]
+fn test_eval_memory_limit() {
+    let fill = "let fill = fn(arr, n) { if (n < 1) { arr } else { fill(push(arr, \"xxxxxxxx\"), n - 1) } };";
+    let cases = [
+        (format!("{} len(fill([], 10))", fill), ObjectWrapper::Integer(10)),
+        // the calls not returned yet each hold the array they pushed to
+        (format!("{} len(fill([], 40))", fill), ObjectWrapper::Integer(40)),
+        (
+            format!("{} len(fill([], 100))", fill),
+            ObjectWrapper::ErrorObject(
+                "out of memory: script allocated more than 65536 bytes".to_string(),
+            ),
+        ),
+        // 100 times 2 KB of strings no value holds once `kb` returned
+        (
+            "let kb = fn(s, n) { if (n < 1) { len(s) } else { kb(s + s, n - 1) } }; let f = fn(n) { if (n < 1) { 0 } else { kb(\"xxxxxxxxxxxxxxxx\", 6); f(n - 1) } }; f(100)".to_string(),
+            ObjectWrapper::Integer(0),
+        ),
+        (
+            "let s = \"xxxxxxxxxxxxxxxx\"; let double = fn(s, n) { if (n < 1) { s } else { double(s + s, n - 1) } }; double(s, 20)".to_string(),
+            ObjectWrapper::ErrorObject(
+                "out of memory: script allocated more than 65536 bytes".to_string(),
+            ),
+        ),
+    ];
+
+    for (input, expect) in cases {
+        let program = Parser::new(Lexer::new(input.as_str()))
+            .parse_program()
+            .unwrap();
+        let mut evaluator = Evaluator::new(&program.statements);
+        evaluator.set_options(EvalOptions {
+            max_memory: Some(64 * 1024),
+            ..Default::default()
+        });
+        assert_eq!(evaluator.eval().unwrap(), expect);
+    }
+}
+
+#[test]
+fn test_env_builtins() {
+    let name = format!("MONKEY_TEST_ENV_{}", std::process::id());
+    let cases = [
+        (format!("env(\"{}\")", name), ObjectWrapper::Null),
+        (
+            format!("set_env(\"{0}\", \"on\"); env(\"{0}\")", name),
+            ObjectWrapper::String("on".into()),
+        ),
+        (
+            "set_env(\"A=B\", \"x\")".to_string(),
+            ObjectWrapper::ErrorObject("invalid environment variable name: \"A=B\"".to_string()),
+        ),
+        (
+            "env(1)".to_string(),
+            ObjectWrapper::ErrorObject("Argument to 'env' must be STRING, got \"int\"".to_string()),
+        ),
+    ];
+    for (input, expect) in cases {
+        assert_eq!(
+            test_eval_with_capabilities(&input, Capabilities::ENVIRONMENT),
+            expect
+        );
+    }
+    // the process environment is left alone
+    assert!(std::env::var(&name).is_err());
+    std::env::set_var(&name, "process");
+    assert_eq!(
+        test_eval_with_capabilities(
+            &format!(
+                "[env(\"{0}\"), set_env(\"{0}\", \"script\"), env(\"{0}\")]",
+                name
+            ),
+            Capabilities::ENVIRONMENT
+        ),
+        ObjectWrapper::Array(
+            vec![
+                ObjectWrapper::String("process".into()),
+                ObjectWrapper::Null,
+                ObjectWrapper::String("script".into()),
+            ]
+            .into()
+        )
+    );
+    std::env::remove_var(&name);
+}
+
+#[test]
+fn test_benchmark_builtin() {
+    let cases = [
+        (
+            "let calls = []; let b = benchmark(fn() { let calls = push(calls, 1) }, 3); \
+             [b[\"iterations\"], !(b[\"min\"] > b[\"mean\"]), !(b[\"mean\"] > b[\"max\"])]",
+            ObjectWrapper::Array(
+                vec![
+                    ObjectWrapper::Integer(3),
+                    ObjectWrapper::Boolean(true),
+                    ObjectWrapper::Boolean(true),
+                ]
+                .into(),
+            ),
+        ),
+        (
+            "benchmark(fn() { 1 }, 0)",
+            ObjectWrapper::ErrorObject(
+                "Argument to `benchmark` must be a positive INTEGER, got 0".to_string(),
+            ),
+        ),
+        (
+            "benchmark(fn() { 1 / 0 }, 2)",
+            ObjectWrapper::ErrorObject("division by zero".to_string()),
+        ),
+    ];
+    for (input, expect) in cases {
+        assert_eq!(
+            test_eval_with_capabilities(input, Capabilities::CLOCK),
+            expect,
+            "{}",
+            input
+        );
+    }
+}
+
+#[cfg(test)]
+#[derive(Default)]
+struct TraceObserver {
+    events: Vec<String>,
+}
+
+#[cfg(test)]
+impl EvalObserver for TraceObserver {
+    fn before_statement(&mut self, statement: &Statement, span: Span, env: &Environment) {
+        let names = env
+            .bindings()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<String>>();
+        self.events.push(format!(
+            "{} {} [{}]",
+            span.start.line,
+            statement,
+            names.join(", ")
+        ));
+    }
+
+    fn before_call(
+        &mut self,
+        _call: &Expression,
+        _func: &ObjectWrapper,
+        args: &[ObjectWrapper],
+        span: Span,
+        _env: &Environment,
+    ) {
+        self.events.push(format!(
+            "call {}:{} {:?}",
+            span.start.line, span.start.column, args
+        ));
+    }
+
+    fn after_call(
+        &mut self,
+        _call: &Expression,
+        _func: &ObjectWrapper,
+        _span: Span,
+        _env: &Environment,
+        result: &Result<ObjectWrapper>,
+    ) {
+        self.events.push(format!("return {:?}", result));
+    }
+}
+
+#[test]
+fn test_eval_observer() {
+    let input = "let double = fn(x) { x * 2 };\nlet y = double(3);\ny";
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+
+    let mut observer = TraceObserver::default();
+    let mut evaluator = Evaluator::new(&program.statements);
+    evaluator.set_observer(&mut observer);
+    assert_eq!(evaluator.eval().unwrap(), ObjectWrapper::Integer(6));
+
+    assert_eq!(
+        observer.events,
+        vec![
+            "1 let double = fn(x) { (x * 2) }; []",
+            "2 let y = double(3); [double]",
+            "call 2:9 [Integer(3)]",
+            "1 (x * 2) [double, x]",
+            "return Ok(Integer(6))",
+            "3 y [double, y]",
+        ]
+    );
+}
+
+#[test]
+fn test_coverage() {
+    let input = "let sign = fn(x) {
+    if (x < 0) {
+        -1
+    } else {
+        1
+    }
+};
+sign(2);
+sign(3)";
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+    let mut coverage = Coverage::new(&program);
+    let mut evaluator = Evaluator::new(&program.statements);
+    evaluator.set_observer(&mut coverage);
+    evaluator.eval().unwrap();
+
+    let lines = coverage.lines().iter().map(|(line, count)| (*line, *count));
+    assert_eq!(
+        lines.collect::<Vec<_>>(),
+        vec![(1, 1), (2, 2), (3, 0), (5, 2), (8, 1), (9, 1)]
+    );
+    assert_eq!(
+        coverage.summary("sign.mk"),
+        "sign.mk: 5/6 lines (83.3%), not run: 3"
+    );
+    assert_eq!(
+        coverage.to_lcov("sign.mk"),
+        "TN:\nSF:sign.mk\nDA:1,1\nDA:2,2\nDA:3,0\nDA:5,2\nDA:8,1\nDA:9,1\nLF:6\nLH:5\nend_of_record\n"
+    );
+}
+
+#[test]
+fn test_object_printer() {
+    let printer = ObjectPrinter {
+        indent: 2,
+        max_width: 40,
+        max_items: 3,
+    };
+    let cases = [
+        ("1", "1"),
+        ("1.5", "1.5"),
+        ("\"a \\\"b\"", "\"a \\\\\\\"b\""),
+        ("puts", "<builtin>"),
+        ("error(\"oops\")", "error(\"oops\")"),
+        ("[]", "[]"),
+        ("[1, [2, 3]]", "[1, [2, 3]]"),
+        ("[1, 2, 3, 4, 5]", "[1, 2, 3, … 2 more items]"),
+        ("{2: \"b\", 1: \"a\"}", "{1: \"a\", 2: \"b\"}"),
+        (
+            "[\"a longer first string\", \"second string\"]",
+            "[\n  \"a longer first string\",\n  \"second string\"\n]",
+        ),
+        (
+            "{\"key\": [\"a nested value string here\", 2], \"z\": 1}",
+            "{\n  \"key\": [\n    \"a nested value string here\",\n    2\n  ],\n  \"z\": 1\n}",
+        ),
+        (
+            "[\"aaaaaaaa\", \"bbbbbbbb\", \"c\", \"d\"]",
+            "[\n  \"aaaaaaaa\",\n  \"bbbbbbbb\",\n  \"c\",\n  … 1 more items\n]",
+        ),
+        (
+            "{1: 1, 2: 2, 3: 3, 4: 4, 5: 5}",
+            "{1: 1, 2: 2, 3: 3, … 2 more entries}",
+        ),
+    ];
+    for (input, expect) in cases {
+        let obj = test_eval(input).unwrap();
+        assert_eq!(printer.print(&obj), expect, "{}", input);
+    }
+}
+
+#[test]
+fn test_summary() {
+    let cases = [
+        ("1", "int"),
+        ("\"héllo\"", "string, 5 characters"),
+        ("[1, [2, 3]]", "array, 2 items"),
+        ("{\"a\": 1}", "hash, 1 key"),
+        ("fn(a, b) { a }", "function, 2 parameters"),
+        ("len", "builtin-fn, 1 parameter"),
+        ("if (false) { 1 }", "NULL"),
+    ];
+    for (input, expect) in cases {
+        let obj = test_eval(input).unwrap();
+        assert_eq!(printer::summary(&obj), expect, "{}", input);
+    }
+}
+
+#[test]
+fn test_conversions() {
+    use std::collections::HashMap;
+    use std::convert::TryFrom;
+
+    let scores = HashMap::from([("a".to_string(), vec![1i64, 2]), ("b".to_string(), vec![])]);
+    let obj = ObjectWrapper::from(scores.clone());
+    assert_eq!(HashMap::<String, Vec<i64>>::try_from(obj).unwrap(), scores);
+
+    let obj = test_eval("[1.5, 2, 3 * 2]").unwrap();
+    assert_eq!(Vec::<f64>::try_from(obj).unwrap(), vec![1.5, 2.0, 6.0]);
+    assert_eq!(ObjectWrapper::from("x"), ObjectWrapper::String("x".into()));
+    assert_eq!(
+        ObjectWrapper::from(vec![true]),
+        ObjectWrapper::Array(vec![ObjectWrapper::Boolean(true)].into())
+    );
+
+    let errors = [
+        ("1.5", "expect int, got float"),
+        ("[1, \"a\"]", "expect int, got string"),
+        ("{1: 2}", "expect string key, got int"),
+        ("1 / 0", "division by zero"),
+    ];
+    for (input, expect) in errors {
+        let obj = test_eval(input).unwrap();
+        let e = match obj {
+            ObjectWrapper::Hash(_) => HashMap::<String, i64>::try_from(obj).unwrap_err(),
+            ObjectWrapper::Array(_) => Vec::<i64>::try_from(obj).unwrap_err(),
+            _ => i64::try_from(obj).unwrap_err(),
+        };
+        assert_eq!(e.message(), expect, "{}", input);
+    }
+    assert_eq!(
+        String::try_from(ObjectWrapper::Null).unwrap_err().message(),
+        "expect string, got NULL"
+    );
+    assert!(bool::try_from(test_eval("1 < 2").unwrap()).unwrap());
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_json_conversions() {
+    use serde_json::{json, Value};
+    use std::convert::TryFrom;
+
+    let value = json!({
+        "name": "monkey",
+        "version": 1,
+        "ratio": 0.5,
+        "whole": 2.0,
+        "tags": ["a", null, true],
+        "nested": {"empty": {}}
+    });
+    let obj = ObjectWrapper::from(value.clone());
+    let mut interpreter = crate::Interpreter::new();
+    interpreter.env_mut().set("config", obj.clone());
+    assert_eq!(
+        interpreter
+            .eval("config[\"version\"] + config[\"ratio\"] + len(config[\"tags\"])")
+            .unwrap(),
+        ObjectWrapper::Float(4.5)
+    );
+    assert_eq!(Value::try_from(obj).unwrap(), value);
+
+    let big = json!(u64::MAX);
+    assert_eq!(
+        ObjectWrapper::from(big),
+        ObjectWrapper::Float(u64::MAX as f64)
+    );
+
+    let errors = [
+        ("{1: 2}", "json keys are strings, got int"),
+        ("[fn(x) { x }]", "cannot convert function to json"),
+        ("1 / 0", "division by zero"),
+    ];
+    for (input, expect) in errors {
+        let e = Value::try_from(test_eval(input).unwrap()).unwrap_err();
+        assert_eq!(e.message(), expect, "{}", input);
+    }
+    assert_eq!(
+        Value::try_from(ObjectWrapper::Float(f64::INFINITY))
+            .unwrap_err()
+            .message(),
+        "inf has no json number"
+    );
+}
+
+#[test]
+fn test_deep_recursion() {
+    let count = "let count = fn(n) { if (n == 0) { 0 } else { 1 + count(n - 1) } };";
+    let nest = "let nest = fn(n) { if (n == 0) { [] } else { [nest(n - 1)] } };";
+    let cases = [
+        (
+            format!("{} count(3000)", count),
+            ObjectWrapper::Integer(3000),
+        ),
+        (
+            format!("{} count(-1)", count),
+            ObjectWrapper::ErrorObject("stack overflow".to_string()),
+        ),
+        (
+            format!("{} len(nest(3000))", nest),
+            ObjectWrapper::Integer(1),
+        ),
+    ];
+    for (input, expect) in cases {
+        // the stack grows on the heap, the thread's own is far too small
+        let ret = std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(move || test_eval(&input).unwrap())
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(ret, expect);
+    }
+
+    // hosts set how deep calls go
+    let cases = [
+        (100, "count(25)", ObjectWrapper::Integer(25)),
+        (
+            100,
+            "count(40)",
+            ObjectWrapper::ErrorObject("stack overflow".to_string()),
+        ),
+        (100_000, "count(20000)", ObjectWrapper::Integer(20000)),
+    ];
+    for (max_depth, call, expect) in cases {
+        let input = format!("{} {}", count, call);
+        let ret = std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(move || {
+                let program = Parser::new(Lexer::new(&input)).parse_program().unwrap();
+                let mut evaluator = Evaluator::new(&program.statements);
+                evaluator.set_options(EvalOptions {
+                    max_depth,
+                    ..Default::default()
+                });
+                evaluator.eval().unwrap()
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(ret, expect);
+    }
+}
+
+#[test]
+fn test_resolved_locals() {
+    let cases = [
+        // shadows a global, the global is unchanged
+        (
+            "let x = 1; let f = fn() { let x = 2; x }; [f(), x]",
+            "[2, 1]",
+        ),
+        // a closure reads the local of the call it was made in
+        ("let add = fn(a) { fn(b) { a + b } }; add(2)(3)", "5"),
+        // a local read before its `let` is the global of the same name
+        (
+            "let x = 1; let f = fn() { let y = x; let x = 2; [y, x] }; f()",
+            "[1, 2]",
+        ),
+        // a closure sees a local bound after it was made
+        (
+            "let x = 1; let f = fn() { let g = fn() { x }; let x = 2; g() }; f()",
+            "2",
+        ),
+        // a `let` in a branch binds a local of the call
+        ("let f = fn(c) { if (c) { let x = 1 } x }; f(true)", "1"),
+        // rebinding a parameter
+        ("let f = fn(a) { let a = a + 1; a }; f(1)", "2"),
+        (
+            "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(10)",
+            "55",
+        ),
+    ];
+    for (input, expect) in cases {
+        let mut program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+        let unresolved = Evaluator::new(&program.statements).eval().unwrap();
+        resolve(&mut program);
+        let resolved = Evaluator::new(&program.statements).eval().unwrap();
+        assert_eq!(snapshot::to_source(&resolved).unwrap(), expect, "{}", input);
+        assert_eq!(
+            snapshot::to_source(&unresolved).unwrap(),
+            expect,
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_hash_display() {
+    // sorted by key whatever the hasher
+    let obj = test_eval(r#"{"b": 2, "a": 1, 3: true}"#).unwrap();
+    assert_eq!(
+        obj.to_string(),
+        r#"Hash: {Integer(3): Boolean(true), String("a"): Integer(1), String("b"): Integer(2)}"#
+    );
+}
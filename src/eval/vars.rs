@@ -0,0 +1,35 @@
+use std::collections::HashMap;
+use std::env::VarError;
+use std::sync::{Arc, Mutex};
+
+// The environment variables `set_env` set, `env` finds them before the
+// process' ones. The process environment is left alone, changing it races
+// with other threads reading it. Clones share the variables.
+#[derive(Debug, Clone, Default)]
+pub struct EnvVars(Arc<Mutex<HashMap<String, String>>>);
+
+impl EnvVars {
+    // the value set here, or else the process', None if neither has it
+    pub fn get(&self, name: &str) -> Result<Option<String>, VarError> {
+        if let Some(value) = self.0.lock().unwrap().get(name) {
+            return Ok(Some(value.clone()));
+        }
+        match std::env::var(name) {
+            Ok(value) => Ok(Some(value)),
+            Err(VarError::NotPresent) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    // a copy not sharing the variables, `set` on it leaves these alone
+    pub fn copy(&self) -> EnvVars {
+        EnvVars(Arc::new(Mutex::new(self.0.lock().unwrap().clone())))
+    }
+
+    pub fn set(&self, name: &str, value: &str) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), value.to_string());
+    }
+}
@@ -0,0 +1,235 @@
+use crate::fmt::{literal, parenthesize_operand, precedence};
+use crate::lexer::lexer::Lexer;
+use crate::parser::program::{Expression, Ident, Precedence, Program, Statement};
+use crate::parser::visit::{self, Visitor};
+use crate::parser::{Parser, Result};
+use crate::symbols::SymbolIndex;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Default)]
+pub struct MinifyOptions {
+    // gives parameters and the bindings local to functions the shortest
+    // names not used otherwise
+    pub rename_locals: bool,
+}
+
+// the minified program in `source`, or the error it does not parse with
+pub fn minify_source(source: &str, options: &MinifyOptions) -> Result<String> {
+    let program = Parser::new(Lexer::new(source)).parse_program()?;
+    Ok(minify(&program, options))
+}
+
+// Prints a program on one line without comments, with spaces only between
+// words and `;` only between statements. It evaluates as `program` does.
+pub fn minify(program: &Program, options: &MinifyOptions) -> String {
+    let names = if options.rename_locals {
+        local_names(program)
+    } else {
+        HashMap::new()
+    };
+    Minifier { names }.block(&program.statements)
+}
+
+const KEYWORDS: &[&str] = &["fn", "let", "if", "else", "return", "true", "false"];
+
+// `a` to `z`, then `aa`, `ab`...
+fn short_name(mut n: usize) -> String {
+    let mut ret = vec![];
+    loop {
+        ret.push(b'a' + (n % 26) as u8);
+        if n < 26 {
+            break;
+        }
+        n = n / 26 - 1;
+    }
+    ret.reverse();
+    String::from_utf8(ret).unwrap()
+}
+
+// The new name of each local binding by the offset of its definition and
+// references. Every local gets its own name so none can hide another, and
+// none is a name the program uses for anything else.
+fn local_names(program: &Program) -> HashMap<usize, String> {
+    let index = SymbolIndex::new(program);
+    let locals = index
+        .symbols()
+        .iter()
+        .filter(|symbol| symbol.scope.is_some())
+        .collect::<Vec<_>>();
+    let renamed = locals
+        .iter()
+        .flat_map(|symbol| std::iter::once(&symbol.span).chain(&symbol.references))
+        .map(|span| span.start.offset)
+        .collect::<HashSet<usize>>();
+    let mut reserved = KEYWORDS
+        .iter()
+        .map(|k| k.to_string())
+        .collect::<HashSet<_>>();
+    let mut idents = Idents(vec![]);
+    visit::walk_program(&mut idents, program);
+    for Ident(name, span, _) in idents.0 {
+        if !renamed.contains(&span.start.offset) {
+            reserved.insert(name.to_string());
+        }
+    }
+    let mut ret = HashMap::new();
+    let mut n = 0;
+    for symbol in locals {
+        let name = loop {
+            let name = short_name(n);
+            n += 1;
+            if !reserved.contains(&name) {
+                break name;
+            }
+        };
+        for span in std::iter::once(&symbol.span).chain(&symbol.references) {
+            ret.insert(span.start.offset, name.clone());
+        }
+    }
+    ret
+}
+
+// every name in a program, bound or referred to
+struct Idents(Vec<Ident>);
+
+impl Visitor for Idents {
+    fn visit_statement(&mut self, statement: &Statement) {
+        if let Statement::LetStatement(ident, _, _) = statement {
+            self.0.push(ident.clone());
+        }
+        visit::walk_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Identifier(ident) => self.0.push(ident.clone()),
+            Expression::FunctionExpression(params, _, _) => self.0.extend(params.iter().cloned()),
+            _ => {}
+        }
+        visit::walk_expression(self, expression);
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// `left` then `right`, with a space if they would read as one word
+fn join(left: &str, right: &str) -> String {
+    match (left.chars().last(), right.chars().next()) {
+        (Some(l), Some(r)) if is_word(l) && is_word(r) => format!("{} {}", left, right),
+        _ => format!("{}{}", left, right),
+    }
+}
+
+struct Minifier {
+    names: HashMap<usize, String>,
+}
+
+impl Minifier {
+    fn name<'a>(&'a self, ident: &'a Ident) -> &'a str {
+        self.names
+            .get(&ident.1.start.offset)
+            .map_or(&ident.0, |name| name)
+    }
+
+    fn block(&self, statements: &[Statement]) -> String {
+        statements
+            .iter()
+            .map(|statement| self.statement(statement))
+            .collect::<Vec<String>>()
+            .join(";")
+    }
+
+    fn statement(&self, statement: &Statement) -> String {
+        match statement {
+            Statement::LetStatement(ident, value, _) => {
+                format!("let {}={}", self.name(ident), self.expression(value))
+            }
+            Statement::ReturnStatement(value, _) => join("return", &self.expression(value)),
+            Statement::ExpressionStatement(value, _) => self.expression(value),
+        }
+    }
+
+    // `expression`, in parentheses unless it binds at least as tight as `min`
+    fn operand(&self, expression: &Expression, min: Precedence) -> String {
+        if precedence(expression) < min {
+            format!("({})", self.expression(expression))
+        } else {
+            self.expression(expression)
+        }
+    }
+
+    fn list<'a>(&self, expressions: impl Iterator<Item = &'a Expression>) -> String {
+        expressions
+            .map(|expression| self.expression(expression))
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    fn expression(&self, expression: &Expression) -> String {
+        match expression {
+            Expression::Identifier(ident) => self.name(ident).to_string(),
+            Expression::IntLiteral(_)
+            | Expression::FloatLiteral(_)
+            | Expression::BoolLiteral(_)
+            | Expression::StringLiteral(_) => literal(expression),
+            Expression::PrefixExpression(operator, right, _) => {
+                let right = if parenthesize_operand(operator, right) {
+                    format!("({})", self.expression(right))
+                } else {
+                    self.expression(right)
+                };
+                join(&operator.to_string(), &right)
+            }
+            Expression::InfixExpression(left, operator, right, _) => {
+                let p = Precedence::from_token(operator);
+                let right = if precedence(right) <= p {
+                    format!("({})", self.expression(right))
+                } else {
+                    self.expression(right)
+                };
+                format!("{}{}{}", self.operand(left, p), operator, right)
+            }
+            Expression::CallExpression(function, args, _) => format!(
+                "{}({})",
+                self.operand(function, Precedence::Call),
+                self.list(args.iter())
+            ),
+            Expression::IndexExpression(left, index, _) => format!(
+                "{}[{}]",
+                self.operand(left, Precedence::Call),
+                self.expression(index)
+            ),
+            Expression::ArrayLiteral(elements) => format!("[{}]", self.list(elements.iter())),
+            Expression::HashLiteral(pairs) => {
+                let pairs = pairs
+                    .iter()
+                    .map(|(key, value)| {
+                        format!("{}:{}", self.expression(key), self.expression(value))
+                    })
+                    .collect::<Vec<String>>();
+                format!("{{{}}}", pairs.join(","))
+            }
+            Expression::IfExpression(condition, consequence, alternative, _) => {
+                let mut ret = format!(
+                    "if({}){{{}}}",
+                    self.expression(condition),
+                    self.block(consequence)
+                );
+                if !alternative.is_empty() {
+                    ret.push_str(&format!("else{{{}}}", self.block(alternative)));
+                }
+                ret
+            }
+            Expression::FunctionExpression(params, body, _) => {
+                let params = params
+                    .iter()
+                    .map(|param| self.name(param))
+                    .collect::<Vec<&str>>()
+                    .join(",");
+                format!("fn({}){{{}}}", params, self.block(body))
+            }
+        }
+    }
+}
@@ -0,0 +1,415 @@
+use crate::lexer::lexer::{Comment, Lexer};
+use crate::lexer::span::Position;
+use crate::lexer::token::Token;
+use crate::parser::program::{Expression, Precedence, Program, Statement};
+use crate::parser::{Parser, Result};
+use std::cell::Cell;
+
+pub mod minify;
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrailingComma {
+    Never,
+    // after the last element of a list broken over several lines
+    Multiline,
+}
+
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    // spaces per level
+    pub indent: usize,
+    // calls, arrays and hashes longer than this are broken over several
+    // lines, one element per line
+    pub max_width: usize,
+    pub trailing_comma: TrailingComma,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            indent: 4,
+            max_width: 100,
+            trailing_comma: TrailingComma::Multiline,
+        }
+    }
+}
+
+// the formatted program in `source`, or the error it does not parse with
+pub fn format_source(source: &str, options: &FormatOptions) -> Result<String> {
+    let program = Parser::new(Lexer::new(source)).parse_program()?;
+    Ok(format(&program, options))
+}
+
+// Prints a program in the canonical style `monkey fmt` writes: one statement
+// per line ending with `;`, blocks indented with the brace on the line they
+// open on, one space around infix operators and after commas, and
+// parentheses only where precedence needs them. The last expression of a
+// block, the value it evaluates to, and `if` expressions take no `;`.
+// Comments stay before the statement they were above, or after the one
+// they followed on its line.
+pub fn format_program(program: &Program) -> String {
+    format(program, &FormatOptions::default())
+}
+
+fn format(program: &Program, options: &FormatOptions) -> String {
+    let next = Cell::new(0);
+    Formatter {
+        options,
+        comments: &program.comments,
+        next: &next,
+    }
+    .program(program)
+}
+
+// how tightly the expression binds, `if` and function literals are
+// parenthesized wherever an operand is expected
+fn precedence(expression: &Expression) -> Precedence {
+    match expression {
+        Expression::InfixExpression(_, operator, _, _) => Precedence::from_token(operator),
+        Expression::PrefixExpression(..) => Precedence::Prefix,
+        Expression::CallExpression(..) => Precedence::Call,
+        Expression::IfExpression(..) | Expression::FunctionExpression(..) => Precedence::Lowest,
+        _ => Precedence::Index,
+    }
+}
+
+// whether the operand of a prefix operator takes parentheses. `-` binds
+// tighter than the others, it leaves calls out of its operand, `-f(x)[0]`
+// indexes what calling `-f` returns, and takes no prefix operator.
+fn parenthesize_operand(operator: &Token, operand: &Expression) -> bool {
+    fn calls(expression: &Expression) -> bool {
+        match expression {
+            Expression::CallExpression(..) => true,
+            Expression::IndexExpression(left, _, _) => calls(left),
+            _ => false,
+        }
+    }
+    match operator {
+        Token::Minus => precedence(operand) <= Precedence::Prefix || calls(operand),
+        _ => precedence(operand) < Precedence::Prefix,
+    }
+}
+
+// whether `expression` is written starting with `-`, `(` or `[`, which
+// would make an infix operator, a call or an index of what is before it
+fn continues(expression: &Expression) -> bool {
+    fn operand(expression: &Expression, min: Precedence) -> bool {
+        precedence(expression) < min || continues(expression)
+    }
+    match expression {
+        Expression::PrefixExpression(Token::Minus, ..) | Expression::ArrayLiteral(_) => true,
+        Expression::InfixExpression(left, operator, _, _) => {
+            operand(left, Precedence::from_token(operator))
+        }
+        Expression::CallExpression(left, _, _) | Expression::IndexExpression(left, _, _) => {
+            operand(left, Precedence::Call)
+        }
+        _ => false,
+    }
+}
+
+// the source of a literal that is not a list
+fn literal(expression: &Expression) -> String {
+    match expression {
+        Expression::IntLiteral(v) => v.to_string(),
+        Expression::FloatLiteral(v) => {
+            let s = v.to_string();
+            if s.contains('.') {
+                s
+            } else {
+                format!("{}.0", s)
+            }
+        }
+        Expression::BoolLiteral(v) => v.to_string(),
+        // the lexer keeps the contents verbatim
+        Expression::StringLiteral(v) => format!("\"{}\"", v),
+        _ => unreachable!("not a literal: {}", expression),
+    }
+}
+
+// the column `text` ends at when it starts at `column`
+fn end_column(column: usize, text: &str) -> usize {
+    match text.rfind('\n') {
+        Some(i) => text[i + 1..].chars().count(),
+        None => column + text.chars().count(),
+    }
+}
+
+// an element of a list
+enum Item<'a> {
+    Expression(&'a Expression),
+    Pair(&'a Expression, &'a Expression),
+}
+
+// Expressions are formatted knowing the column they start at, so lists can
+// be broken where they do not fit.
+struct Formatter<'a> {
+    options: &'a FormatOptions,
+    comments: &'a [Comment],
+    // the comments before this index are written
+    next: &'a Cell<usize>,
+}
+
+impl Formatter<'_> {
+    fn program(&self, program: &Program) -> String {
+        let mut ret = String::new();
+        for (i, statement) in program.statements.iter().enumerate() {
+            let next = program.statements.get(i + 1);
+            self.statement(&mut ret, statement, 0, false, next);
+        }
+        self.comments_before(&mut ret, usize::MAX, 0);
+        ret
+    }
+
+    fn indent(&self, depth: usize) -> String {
+        " ".repeat(self.options.indent * depth)
+    }
+
+    // the comments not written yet that start before `offset`, each on its
+    // own line
+    fn comments_before(&self, out: &mut String, offset: usize, depth: usize) {
+        while let Some(comment) = self.comments.get(self.next.get()) {
+            if comment.span.start.offset >= offset {
+                return;
+            }
+            out.push_str(&self.indent(depth));
+            out.push_str(&comment.text);
+            out.push('\n');
+            self.next.set(self.next.get() + 1);
+        }
+    }
+
+    // the comment right after the code that ends at `end`, on its line
+    fn trailing_comment(&self, out: &mut String, end: Position) {
+        if let Some(comment) = self.comments.get(self.next.get()) {
+            if comment.after == Some(end) {
+                out.push(' ');
+                out.push_str(&comment.text);
+                self.next.set(self.next.get() + 1);
+            }
+        }
+    }
+
+    // the last statement of a block, its value, takes no `;`
+    fn statement(
+        &self,
+        out: &mut String,
+        statement: &Statement,
+        depth: usize,
+        last: bool,
+        next: Option<&Statement>,
+    ) {
+        self.comments_before(out, statement.span().start.offset, depth);
+        let indent = self.indent(depth);
+        out.push_str(&indent);
+        match statement {
+            Statement::LetStatement(ident, value, _) => {
+                let prefix = format!("let {} = ", ident.0);
+                let column = indent.len() + prefix.chars().count();
+                out.push_str(&prefix);
+                out.push_str(&self.expression(value, depth, column));
+                out.push(';');
+            }
+            Statement::ReturnStatement(value, _) => {
+                let column = indent.len() + "return ".len();
+                out.push_str("return ");
+                out.push_str(&self.expression(value, depth, column));
+                out.push(';');
+            }
+            Statement::ExpressionStatement(value, _) => {
+                out.push_str(&self.expression(value, depth, indent.len()));
+                let semicolon = match (value, next) {
+                    _ if last => false,
+                    // what would apply to the `if` otherwise
+                    (
+                        Expression::IfExpression(..),
+                        Some(Statement::ExpressionStatement(next, _)),
+                    ) => continues(next),
+                    (Expression::IfExpression(..), _) => false,
+                    _ => true,
+                };
+                if semicolon {
+                    out.push(';');
+                }
+            }
+        }
+        self.trailing_comment(out, statement.span().end);
+        out.push('\n');
+    }
+
+    // the comments before `end` are written in the block
+    fn block(&self, statements: &[Statement], depth: usize, end: usize) -> String {
+        let mut ret = "{\n".to_string();
+        for (i, statement) in statements.iter().enumerate() {
+            let last = i + 1 == statements.len();
+            self.statement(&mut ret, statement, depth + 1, last, statements.get(i + 1));
+        }
+        self.comments_before(&mut ret, end, depth + 1);
+        if statements.is_empty() && ret.len() == 2 {
+            return "{}".to_string();
+        }
+        ret.push_str(&self.indent(depth));
+        ret.push('}');
+        ret
+    }
+
+    // `expression`, in parentheses unless it binds at least as tight as `min`
+    fn operand(
+        &self,
+        expression: &Expression,
+        min: Precedence,
+        depth: usize,
+        column: usize,
+    ) -> String {
+        if precedence(expression) < min {
+            format!("({})", self.expression(expression, depth, column + 1))
+        } else {
+            self.expression(expression, depth, column)
+        }
+    }
+
+    fn item(&self, item: &Item, depth: usize, column: usize) -> String {
+        match item {
+            Item::Expression(expression) => self.expression(expression, depth, column),
+            Item::Pair(key, value) => {
+                let key = self.expression(key, depth, column);
+                let value = self.expression(value, depth, end_column(column, &key) + 2);
+                format!("{}: {}", key, value)
+            }
+        }
+    }
+
+    // On one line if all of it fits, otherwise one element per line. Outer
+    // lists are broken before the ones in them.
+    fn list(&self, open: &str, close: &str, items: &[Item], depth: usize, column: usize) -> String {
+        let unbroken = Formatter {
+            options: &FormatOptions {
+                max_width: usize::MAX,
+                ..self.options.clone()
+            },
+            comments: self.comments,
+            next: self.next,
+        };
+        // the comments in the list are written again if it is broken
+        let next = self.next.get();
+        let mut ret = open.to_string();
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                ret.push_str(", ");
+            }
+            ret.push_str(&unbroken.item(item, depth, end_column(column, &ret)));
+        }
+        ret.push_str(close);
+        let fits = ret.lines().enumerate().all(|(i, line)| {
+            let start = if i == 0 { column } else { 0 };
+            start + line.chars().count() <= self.options.max_width
+        });
+        if fits || items.is_empty() {
+            return ret;
+        }
+        self.next.set(next);
+
+        let indent = self.indent(depth + 1);
+        let mut ret = format!("{}\n", open);
+        for (i, item) in items.iter().enumerate() {
+            ret.push_str(&indent);
+            ret.push_str(&self.item(item, depth + 1, indent.len()));
+            if i + 1 < items.len() || self.options.trailing_comma == TrailingComma::Multiline {
+                ret.push(',');
+            }
+            ret.push('\n');
+        }
+        ret.push_str(&self.indent(depth));
+        ret.push_str(close);
+        ret
+    }
+
+    fn expression(&self, expression: &Expression, depth: usize, column: usize) -> String {
+        match expression {
+            Expression::Identifier(ident) => ident.0.to_string(),
+            Expression::IntLiteral(_)
+            | Expression::FloatLiteral(_)
+            | Expression::BoolLiteral(_)
+            | Expression::StringLiteral(_) => literal(expression),
+            Expression::PrefixExpression(operator, right, _) => {
+                let parenthesize = parenthesize_operand(operator, right);
+                let operator = operator.to_string();
+                let column = column + operator.chars().count();
+                let right = if parenthesize {
+                    format!("({})", self.expression(right, depth, column + 1))
+                } else {
+                    self.expression(right, depth, column)
+                };
+                format!("{}{}", operator, right)
+            }
+            Expression::InfixExpression(left, operator, right, _) => {
+                let p = Precedence::from_token(operator);
+                // operators are left associative, `a - (b - c)` keeps its
+                // parentheses
+                let parenthesize = precedence(right) <= p;
+                let left = self.operand(left, p, depth, column);
+                let operator = operator.to_string();
+                let column = end_column(column, &left) + operator.chars().count() + 2;
+                let right = if parenthesize {
+                    format!("({})", self.expression(right, depth, column + 1))
+                } else {
+                    self.expression(right, depth, column)
+                };
+                format!("{} {} {}", left, operator, right)
+            }
+            Expression::CallExpression(function, args, _) => {
+                let function = self.operand(function, Precedence::Call, depth, column);
+                let args = args.iter().map(Item::Expression).collect::<Vec<_>>();
+                let column = end_column(column, &function);
+                format!("{}{}", function, self.list("(", ")", &args, depth, column))
+            }
+            Expression::IndexExpression(left, index, _) => {
+                // calls and indexes chain without parentheses
+                let left = self.operand(left, Precedence::Call, depth, column);
+                let index = self.expression(index, depth, end_column(column, &left) + 1);
+                format!("{}[{}]", left, index)
+            }
+            Expression::ArrayLiteral(elements) => {
+                let elements = elements.iter().map(Item::Expression).collect::<Vec<_>>();
+                self.list("[", "]", &elements, depth, column)
+            }
+            Expression::HashLiteral(pairs) => {
+                let pairs = pairs
+                    .iter()
+                    .map(|(key, value)| Item::Pair(key, value))
+                    .collect::<Vec<_>>();
+                self.list("{", "}", &pairs, depth, column)
+            }
+            Expression::IfExpression(condition, consequence, alternative, span) => {
+                // comments at the end of the consequence go in the
+                // alternative when there is one
+                let end = if alternative.is_empty() {
+                    span.end.offset
+                } else {
+                    0
+                };
+                let mut ret = format!(
+                    "if ({}) {}",
+                    self.expression(condition, depth, column + "if (".len()),
+                    self.block(consequence, depth, end)
+                );
+                if !alternative.is_empty() {
+                    ret.push_str(" else ");
+                    ret.push_str(&self.block(alternative, depth, span.end.offset));
+                }
+                ret
+            }
+            Expression::FunctionExpression(params, body, span) => {
+                let params = params
+                    .iter()
+                    .map(|ident| ident.0.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(", ");
+                let body = self.block(body, depth, span.end.offset);
+                format!("fn({}) {}", params, body)
+            }
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use crate::fmt::minify::{minify_source, MinifyOptions};
+use crate::fmt::{format_program, format_source, FormatOptions, TrailingComma};
+use crate::interpreter::Interpreter;
+use crate::lexer::lexer::Lexer;
+use crate::parser::Parser;
+
+#[test]
+fn test_format_program() {
+    let cases = [
+        ("let   a=1+2*3", "let a = 1 + 2 * 3;\n"),
+        (
+            "(1 + 2) * 3; 1 - (2 - 3); (1 - 2) - 3",
+            "(1 + 2) * 3;\n1 - (2 - 3);\n1 - 2 - 3;\n",
+        ),
+        ("-(a + b); !-x; -a * b", "-(a + b);\n!-x;\n-a * b;\n"),
+        (
+            "f(1,2)[0]; [1,2][1]; {\"a\":1.0, 2:true}",
+            "f(1, 2)[0];\n[1, 2][1];\n{\"a\": 1.0, 2: true};\n",
+        ),
+        (
+            "let add = fn(a, b) { return a + b; }; add(1, 2)",
+            "let add = fn(a, b) {\n    return a + b;\n};\nadd(1, 2);\n",
+        ),
+        (
+            "if (x > 1) { let y = x; y } else { if (x) { 1 } }",
+            "if (x > 1) {\n    let y = x;\n    y\n} else {\n    if (x) {\n        1\n    }\n}\n",
+        ),
+        (
+            "map([1], fn(x) { x * 2; })",
+            "map([1], fn(x) {\n    x * 2\n});\n",
+        ),
+        (
+            "fn() {}(); (fn(x) { x })(1)",
+            "(fn() {})();\n(fn(x) {\n    x\n})(1);\n",
+        ),
+        ("puts(\"a \\\"b\\\"\")", "puts(\"a \\\"b\\\"\");\n"),
+        // `-` leaves calls and prefixes out of its operand
+        (
+            "-(f(x)); -(a(1)[0]); -(-x)",
+            "-(f(x));\n-(a(1)[0]);\n-(-x);\n",
+        ),
+        // an `if` followed by what would apply to it keeps its `;`
+        (
+            "if (a) { 1 }; -b; if (a) { 1 }; [2]; if (a) { 1 } c",
+            "if (a) {\n    1\n};\n-b;\nif (a) {\n    1\n};\n[2];\nif (a) {\n    1\n}\nc;\n",
+        ),
+    ];
+    for (input, expect) in cases {
+        let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+        let formatted = format_program(&program);
+        assert_eq!(formatted, expect, "{}", input);
+
+        // formatting keeps the meaning and is stable
+        let reparsed = Parser::new(Lexer::new(formatted.as_str()))
+            .parse_program()
+            .unwrap();
+        assert_eq!(reparsed.to_string(), program.to_string(), "{}", input);
+        assert_eq!(format_program(&reparsed), formatted, "{}", input);
+    }
+}
+
+#[test]
+fn test_format_options() {
+    let narrow = FormatOptions {
+        indent: 2,
+        max_width: 20,
+        trailing_comma: TrailingComma::Multiline,
+    };
+    let cases = [
+        ("if (a) { b }", "if (a) {\n  b\n}\n"),
+        ("f(1, 2)", "f(1, 2);\n"),
+        (
+            "let xs = [100, 200, 300];",
+            "let xs = [\n  100,\n  200,\n  300,\n];\n",
+        ),
+        (
+            "let h = {\"key\": [1, 2], \"other\": 3}",
+            "let h = {\n  \"key\": [1, 2],\n  \"other\": 3,\n};\n",
+        ),
+        // the function literal's lines fit, the call stays on one line
+        ("map(xs, fn(x) { x })", "map(xs, fn(x) {\n  x\n});\n"),
+        (
+            "fn() { callback(argument1) }",
+            "fn() {\n  callback(\n    argument1,\n  )\n};\n",
+        ),
+    ];
+    for (input, expect) in cases {
+        let formatted = format_source(input, &narrow).unwrap();
+        assert_eq!(formatted, expect, "{}", input);
+        assert_eq!(format_source(&formatted, &narrow).unwrap(), formatted);
+    }
+
+    let never = FormatOptions {
+        trailing_comma: TrailingComma::Never,
+        ..narrow
+    };
+    assert_eq!(
+        format_source("[1000, 2000, 3000, 4000]", &never).unwrap(),
+        "[\n  1000,\n  2000,\n  3000,\n  4000\n];\n"
+    );
+    assert!(format_source("let = 1", &never).is_err());
+}
+
+#[test]
+fn test_format_comments() {
+    let cases = [
+        (
+            "// a\nlet a = 1; // one\n// end",
+            "// a\nlet a = 1; // one\n// end\n",
+        ),
+        (
+            "let f = fn() {\n// first\nx // x\n// last\n}",
+            "let f = fn() {\n    // first\n    x // x\n    // last\n};\n",
+        ),
+        ("fn() { // todo\n}", "fn() {\n    // todo\n};\n"),
+        (
+            "if (a) { 1 } else { // no\n2 }",
+            "if (a) {\n    1\n} else {\n    // no\n    2\n}\n",
+        ),
+        // comments in an expression go before the next statement
+        ("let a = [1, // x\n2];\nb", "let a = [1, 2];\n// x\nb;\n"),
+    ];
+    for (input, expect) in cases {
+        let formatted = format_source(input, &FormatOptions::default()).unwrap();
+        assert_eq!(formatted, expect, "{}", input);
+    }
+}
+
+#[test]
+fn test_minify() {
+    let cases = [
+        ("let a = 1 + 2 * 3; // seven\n", false, "let a=1+2*3"),
+        (
+            "(1 + 2) * 3; 1 - (2 - 3); -(-x); !true",
+            false,
+            "(1+2)*3;1-(2-3);-(-x);!true",
+        ),
+        (
+            "let f = fn(x) {\n    return x;\n};\nif (f(1) > 0) { [1.0, \"a b\"] } else { {\"k\": 2} }",
+            false,
+            "let f=fn(x){return x};if(f(1)>0){[1.0,\"a b\"]}else{{\"k\":2}}",
+        ),
+        // locals get names not used for anything else
+        (
+            "let a = 1; let f = fn(x, y) { let sum = x + y; sum + a + len([]) };",
+            true,
+            "let a=1;let f=fn(b,c){let d=b+c;d+a+len([])}",
+        ),
+        (
+            "let f = fn(x) { fn(x) { x } }; let x = 1;",
+            true,
+            "let f=fn(a){fn(b){b}};let x=1",
+        ),
+    ];
+    for (input, rename_locals, expected) in cases {
+        let options = MinifyOptions { rename_locals };
+        assert_eq!(
+            minify_source(input, &options).unwrap(),
+            expected,
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_minify_evaluates_the_same() {
+    let input = "let fib = fn(n) { if (n < 2) { return n; } let m = fib(n - 1); m + fib(n - 2) };
+let apply = fn(f, v) { f(v) - -1 };
+[apply(fib, 10), {\"k\": !true}, -(1 + 2) * 3, 10 - (4 - 1), \"s\"]";
+    let options = MinifyOptions {
+        rename_locals: true,
+    };
+    let minified = minify_source(input, &options).unwrap();
+    let eval = |source: &str| Interpreter::new().eval(source).unwrap().to_string();
+    assert_eq!(eval(&minified), eval(input));
+}
@@ -0,0 +1,195 @@
+use crate::eval::builtins::Builtins;
+use crate::lexer::span::Span;
+use crate::parser::program::{Expression, Program, Statement};
+use crate::parser::visit::{self, Visitor};
+use crate::symbols::{SymbolIndex, SymbolKind};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeKind {
+    // the code outside of named functions
+    Main,
+    Function,
+    Builtin,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Node {
+    pub name: String,
+    pub kind: NodeKind,
+    // of the name of a function, None for the others
+    pub span: Option<Span>,
+}
+
+// a call of `callee` from `caller`, indexes of nodes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Call {
+    pub caller: usize,
+    pub callee: usize,
+    pub span: Span,
+}
+
+// a call of something that is not a named function: a parameter, a
+// variable, an unbound name or what an expression evaluates to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DynamicCall {
+    pub caller: usize,
+    // the called expression as source
+    pub callee: String,
+    pub span: Span,
+}
+
+// Which named functions call which. The calls made by a function literal
+// that is not bound by a `let` are made by the function it is in. The
+// first node is the program itself.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CallGraph {
+    pub nodes: Vec<Node>,
+    pub calls: Vec<Call>,
+    pub dynamic_calls: Vec<DynamicCall>,
+}
+
+impl CallGraph {
+    pub fn new(program: &Program) -> Self {
+        let index = SymbolIndex::new(program);
+        let mut builder = Builder {
+            graph: CallGraph {
+                nodes: vec![Node {
+                    name: "<main>".to_string(),
+                    kind: NodeKind::Main,
+                    span: None,
+                }],
+                calls: vec![],
+                dynamic_calls: vec![],
+            },
+            functions: HashMap::new(),
+            builtins: HashMap::new(),
+            callers: vec![0],
+            index: &index,
+        };
+        let functions = index
+            .symbols()
+            .iter()
+            .filter(|symbol| symbol.kind == SymbolKind::Function);
+        for symbol in functions {
+            builder
+                .functions
+                .insert(symbol.span, builder.graph.nodes.len());
+            builder.graph.nodes.push(Node {
+                name: symbol.name.clone(),
+                kind: NodeKind::Function,
+                span: Some(symbol.span),
+            });
+        }
+        visit::walk_program(&mut builder, program);
+        builder.graph
+    }
+
+    // Graphviz source, a dashed diamond for each dynamic call
+    pub fn to_dot(&self) -> String {
+        let mut ret = String::from("digraph calls {\n");
+        for (i, node) in self.nodes.iter().enumerate() {
+            let attributes = match node.kind {
+                NodeKind::Main => ", shape=box",
+                NodeKind::Function => "",
+                NodeKind::Builtin => ", style=dashed",
+            };
+            writeln!(ret, "  n{} [label={:?}{}];", i, node.name, attributes).unwrap();
+        }
+        // a function calling another twice has one edge
+        let mut edges = vec![];
+        for call in &self.calls {
+            if !edges.contains(&(call.caller, call.callee)) {
+                edges.push((call.caller, call.callee));
+            }
+        }
+        for (caller, callee) in edges {
+            writeln!(ret, "  n{} -> n{};", caller, callee).unwrap();
+        }
+        for (i, call) in self.dynamic_calls.iter().enumerate() {
+            writeln!(
+                ret,
+                "  d{} [label={:?}, shape=diamond, style=dashed];",
+                i, call.callee
+            )
+            .unwrap();
+            writeln!(ret, "  n{} -> d{} [style=dashed];", call.caller, i).unwrap();
+        }
+        ret.push_str("}\n");
+        ret
+    }
+}
+
+struct Builder<'a> {
+    graph: CallGraph,
+    // the node of each function by the span of its name
+    functions: HashMap<Span, usize>,
+    builtins: HashMap<String, usize>,
+    // the named functions being walked, innermost last
+    callers: Vec<usize>,
+    index: &'a SymbolIndex,
+}
+
+impl Builder<'_> {
+    fn call(&mut self, function: &Expression, span: Span) {
+        let caller = *self.callers.last().unwrap();
+        let callee = match function {
+            Expression::Identifier(ident) => match self.index.symbol_at(ident.1.start) {
+                Some(symbol) => self.functions.get(&symbol.span).copied(),
+                None if Builtins::instance_ref().contains(&ident.0) => Some(self.builtin(&ident.0)),
+                None => None,
+            },
+            _ => None,
+        };
+        match callee {
+            Some(callee) => self.graph.calls.push(Call {
+                caller,
+                callee,
+                span,
+            }),
+            None => self.graph.dynamic_calls.push(DynamicCall {
+                caller,
+                callee: function.to_string(),
+                span,
+            }),
+        }
+    }
+
+    fn builtin(&mut self, name: &str) -> usize {
+        let nodes = &mut self.graph.nodes;
+        *self.builtins.entry(name.to_string()).or_insert_with(|| {
+            nodes.push(Node {
+                name: name.to_string(),
+                kind: NodeKind::Builtin,
+                span: None,
+            });
+            nodes.len() - 1
+        })
+    }
+}
+
+impl Visitor for Builder<'_> {
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::LetStatement(ident, value @ Expression::FunctionExpression(..), _) => {
+                self.callers.push(self.functions[&ident.1]);
+                self.visit_expression(value);
+                self.callers.pop();
+            }
+            _ => visit::walk_statement(self, statement),
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        if let Expression::CallExpression(function, _, span) = expression {
+            self.call(function, *span);
+        }
+        visit::walk_expression(self, expression);
+    }
+}
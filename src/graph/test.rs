@@ -0,0 +1,60 @@
+use crate::graph::CallGraph;
+use crate::lexer::lexer::Lexer;
+use crate::parser::Parser;
+
+#[test]
+fn test_call_graph() {
+    let input = "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } };
+let apply = fn(f, x) { let twice = fn(y) { f(f(y)) }; twice(x) };
+let main = fn() { push([1], fn(x) { fib(x) }) };
+puts(apply(fib, 10));";
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+    let graph = CallGraph::new(&program);
+    let name = |i: usize| graph.nodes[i].name.as_str();
+    let calls = graph
+        .calls
+        .iter()
+        .map(|call| (name(call.caller), name(call.callee), call.span.start.line))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        calls,
+        vec![
+            ("fib", "fib", 1),
+            ("fib", "fib", 1),
+            ("apply", "twice", 2),
+            ("main", "push", 3),
+            ("main", "fib", 3),
+            ("<main>", "puts", 4),
+            ("<main>", "apply", 4),
+        ]
+    );
+    let dynamic = graph
+        .dynamic_calls
+        .iter()
+        .map(|call| (name(call.caller), call.callee.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(dynamic, vec![("twice", "f"), ("twice", "f")]);
+    assert_eq!(
+        graph.to_dot(),
+        "digraph calls {
+  n0 [label=\"<main>\", shape=box];
+  n1 [label=\"fib\"];
+  n2 [label=\"apply\"];
+  n3 [label=\"twice\"];
+  n4 [label=\"main\"];
+  n5 [label=\"push\", style=dashed];
+  n6 [label=\"puts\", style=dashed];
+  n1 -> n1;
+  n2 -> n3;
+  n4 -> n5;
+  n4 -> n1;
+  n0 -> n6;
+  n0 -> n2;
+  d0 [label=\"f\", shape=diamond, style=dashed];
+  n3 -> d0 [style=dashed];
+  d1 [label=\"f\", shape=diamond, style=dashed];
+  n3 -> d1 [style=dashed];
+}
+"
+    );
+}
@@ -0,0 +1,241 @@
+use crate::eval::builtins::Builtins;
+use crate::lexer::lexer::tokenize_source;
+use crate::lexer::span::Span;
+use crate::lexer::token::{SourceToken, Token};
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod test;
+
+// ANSI escapes used for colored output
+pub const KEYWORD: &str = "\x1b[35m";
+pub const STRING: &str = "\x1b[32m";
+pub const NUMBER: &str = "\x1b[36m";
+pub const BRACKET: &str = "\x1b[1;33m";
+pub const RESET: &str = "\x1b[0m";
+
+// Colors `line` for a terminal as the lexer reads it: keywords, strings and
+// numbers, and the bracket at or right before `cursor` together with the one
+// matching it.
+pub fn highlight(line: &str, cursor: usize) -> String {
+    let tokens = lex(line)
+        .into_iter()
+        .map(|(token, span)| (token, span.start.offset, span.end.offset))
+        .collect::<Vec<_>>();
+    let mut colors: Vec<Option<&str>> = classify(line)
+        .into_iter()
+        .map(|(_, kind)| match kind {
+            Some(TokenKind::Keyword) => Some(KEYWORD),
+            Some(TokenKind::String) => Some(STRING),
+            Some(TokenKind::Number) => Some(NUMBER),
+            _ => None,
+        })
+        .collect();
+    let at_cursor = tokens
+        .iter()
+        .position(|(token, start, _)| is_bracket(token) && *start == cursor)
+        .or_else(|| {
+            tokens
+                .iter()
+                .position(|(token, _, end)| is_bracket(token) && *end == cursor)
+        });
+    if let Some(i) = at_cursor {
+        if let Some(j) = matching_bracket(&tokens, i) {
+            colors[i] = Some(BRACKET);
+            colors[j] = Some(BRACKET);
+        }
+    }
+
+    let mut ret = String::with_capacity(line.len());
+    let mut offset = 0;
+    for ((_, start, end), color) in tokens.iter().zip(colors) {
+        if let Some(color) = color {
+            ret.push_str(&line[offset..*start]);
+            ret.push_str(color);
+            ret.push_str(&line[*start..*end]);
+            ret.push_str(RESET);
+            offset = *end;
+        }
+    }
+    ret.push_str(&line[offset..]);
+    ret
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    // `true` and `false` included
+    Keyword,
+    Function,
+    Parameter,
+    Variable,
+    Builtin,
+    String,
+    Number,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SemanticToken {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+// The tokens of `source` worth coloring, in order. Names are classified by
+// what binds them: a function parameter, a `let` of a function literal, a
+// builtin or else a variable. Names bound nowhere are functions when they
+// are called. Works on incomplete code, as typed in the REPL.
+pub fn semantic_tokens(source: &str) -> Vec<SemanticToken> {
+    classify(source)
+        .into_iter()
+        .filter_map(|(span, kind)| Some(SemanticToken { kind: kind?, span }))
+        .collect()
+}
+
+// the tokens before the EOF, names are not interned
+fn lex(source: &str) -> Vec<(SourceToken<'_>, Span)> {
+    let mut ret = tokenize_source(source);
+    ret.pop();
+    ret
+}
+
+// the names seen inside a function body, and the brace depth it ends at
+struct Scope<'src> {
+    names: HashMap<&'src str, TokenKind>,
+    depth: usize,
+}
+
+// every token of `source` with its kind, if it has one
+fn classify(source: &str) -> Vec<(Span, Option<TokenKind>)> {
+    let tokens = lex(source);
+    let is =
+        |i: usize, expected: SourceToken| tokens.get(i).map(|(token, _)| token) == Some(&expected);
+    // `name` in `let name = fn...`
+    let binding = |i: usize| {
+        if is(i + 1, Token::Assign) && is(i + 2, Token::Function) {
+            TokenKind::Function
+        } else {
+            TokenKind::Variable
+        }
+    };
+    // every `let` of the source, so names used before they are bound, in
+    // the body of a function, are found
+    let mut globals = HashMap::new();
+    for (i, (token, _)) in tokens.iter().enumerate() {
+        if let (Token::Let, Some((Token::Ident(name), _))) = (token, tokens.get(i + 1)) {
+            globals.entry(*name).or_insert_with(|| binding(i + 1));
+        }
+    }
+    let mut scopes: Vec<Scope> = vec![];
+    let mut depth = 0;
+    // the parameters of the function whose body comes next, and whether
+    // they are being read
+    let mut params: Option<HashMap<&str, TokenKind>> = None;
+    let mut in_params = false;
+    let mut ret = vec![];
+    for (i, (token, span)) in tokens.iter().enumerate() {
+        let after_let = i > 0 && is(i - 1, Token::Let);
+        let kind = match token {
+            Token::Let
+            | Token::Function
+            | Token::If
+            | Token::Else
+            | Token::Return
+            | Token::Bool(_) => Some(TokenKind::Keyword),
+            Token::String(_) => Some(TokenKind::String),
+            // an unterminated string
+            Token::Illegal if source[span.start.offset..].starts_with('"') => {
+                Some(TokenKind::String)
+            }
+            Token::Int(_) | Token::Float(_) => Some(TokenKind::Number),
+            Token::Ident(name) if in_params => {
+                if let Some(params) = params.as_mut() {
+                    params.insert(name, TokenKind::Parameter);
+                }
+                Some(TokenKind::Parameter)
+            }
+            Token::Ident(name) if after_let => {
+                let kind = binding(i);
+                if let Some(scope) = scopes.last_mut() {
+                    scope.names.insert(name, kind);
+                }
+                Some(kind)
+            }
+            Token::Ident(name) => {
+                let local = scopes.iter().rev().find_map(|scope| scope.names.get(name));
+                Some(match local.or_else(|| globals.get(name)) {
+                    Some(kind) => *kind,
+                    None if Builtins::instance_ref().contains(name) => TokenKind::Builtin,
+                    None if is(i + 1, Token::LParen) => TokenKind::Function,
+                    None => TokenKind::Variable,
+                })
+            }
+            Token::LParen if i > 0 && is(i - 1, Token::Function) => {
+                params = Some(HashMap::new());
+                in_params = true;
+                None
+            }
+            Token::RParen => {
+                in_params = false;
+                None
+            }
+            Token::LBrace => {
+                depth += 1;
+                if let Some(names) = params.take() {
+                    scopes.push(Scope { names, depth });
+                }
+                None
+            }
+            Token::RBrace => {
+                if scopes.last().map(|scope| scope.depth) == Some(depth) {
+                    scopes.pop();
+                }
+                depth = depth.saturating_sub(1);
+                None
+            }
+            _ => None,
+        };
+        ret.push((*span, kind));
+    }
+    ret
+}
+
+fn is_bracket(token: &SourceToken) -> bool {
+    matches!(
+        token,
+        Token::LParen
+            | Token::RParen
+            | Token::LBracket
+            | Token::RBracket
+            | Token::LBrace
+            | Token::RBrace
+    )
+}
+
+// index of the token closing or opening the bracket at `i`
+fn matching_bracket(tokens: &[(SourceToken, usize, usize)], i: usize) -> Option<usize> {
+    let (open, close, forward) = match tokens[i].0 {
+        Token::LParen => (Token::LParen, Token::RParen, true),
+        Token::LBracket => (Token::LBracket, Token::RBracket, true),
+        Token::LBrace => (Token::LBrace, Token::RBrace, true),
+        Token::RParen => (Token::LParen, Token::RParen, false),
+        Token::RBracket => (Token::LBracket, Token::RBracket, false),
+        Token::RBrace => (Token::LBrace, Token::RBrace, false),
+        _ => return None,
+    };
+    let mut depth = 0;
+    let indexes: Box<dyn Iterator<Item = usize>> = if forward {
+        Box::new(i..tokens.len())
+    } else {
+        Box::new((0..=i).rev())
+    };
+    for j in indexes {
+        if tokens[j].0 == open {
+            depth += if forward { 1 } else { -1 };
+        } else if tokens[j].0 == close {
+            depth += if forward { -1 } else { 1 };
+        }
+        if depth == 0 {
+            return Some(j);
+        }
+    }
+    None
+}
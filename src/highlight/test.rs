@@ -0,0 +1,145 @@
+use crate::highlight::{
+    highlight, semantic_tokens, TokenKind, BRACKET, KEYWORD, NUMBER, RESET, STRING,
+};
+
+#[cfg(test)]
+fn color(color: &str, text: &str) -> String {
+    format!("{}{}{}", color, text, RESET)
+}
+
+#[test]
+fn test_highlight() {
+    let cases = [
+        ("", 0, "".to_string()),
+        ("x + y", 0, "x + y".to_string()),
+        (
+            "let a = 1.5;",
+            12,
+            format!("{} a = {};", color(KEYWORD, "let"), color(NUMBER, "1.5")),
+        ),
+        (
+            "if (true) { \"hi\" }",
+            0,
+            format!(
+                "{} ({}) {{ {} }}",
+                color(KEYWORD, "if"),
+                color(KEYWORD, "true"),
+                color(STRING, "\"hi\"")
+            ),
+        ),
+        ("puts(\"é", 7, format!("puts({}", color(STRING, "\"é"))),
+        // the bracket under the cursor and the one it matches
+        (
+            "f(g(x))",
+            1,
+            format!("f{}g(x){}", color(BRACKET, "("), color(BRACKET, ")")),
+        ),
+        (
+            "f(g(x))",
+            5,
+            format!("f(g{}x{})", color(BRACKET, "("), color(BRACKET, ")")),
+        ),
+        // else the one right before the cursor
+        (
+            "(x) + 1",
+            3,
+            format!(
+                "{}x{} + {}",
+                color(BRACKET, "("),
+                color(BRACKET, ")"),
+                color(NUMBER, "1")
+            ),
+        ),
+        ("f(g(x", 1, "f(g(x".to_string()),
+        (
+            "[1]",
+            3,
+            format!(
+                "{}{}{}",
+                color(BRACKET, "["),
+                color(NUMBER, "1"),
+                color(BRACKET, "]")
+            ),
+        ),
+    ];
+    for (input, cursor, expect) in cases {
+        assert_eq!(highlight(input, cursor), expect, "{}", input);
+    }
+}
+
+#[test]
+fn test_semantic_tokens() {
+    use TokenKind::*;
+    let cases = vec![
+        ("", vec![]),
+        (
+            "let add = fn(a, b) { a + b + c };",
+            vec![
+                ("let", Keyword),
+                ("add", Function),
+                ("fn", Keyword),
+                ("a", Parameter),
+                ("b", Parameter),
+                ("a", Parameter),
+                ("b", Parameter),
+                ("c", Variable),
+            ],
+        ),
+        // parameters end with their function, globals can be used before
+        // they are bound
+        (
+            "let f = fn(x) { g(x) }; x; let g = fn(y) { y };",
+            vec![
+                ("let", Keyword),
+                ("f", Function),
+                ("fn", Keyword),
+                ("x", Parameter),
+                ("g", Function),
+                ("x", Parameter),
+                ("x", Variable),
+                ("let", Keyword),
+                ("g", Function),
+                ("fn", Keyword),
+                ("y", Parameter),
+                ("y", Parameter),
+            ],
+        ),
+        (
+            "fn(len) { let n = len; puts(n, 1.5, \"s\") }",
+            vec![
+                ("fn", Keyword),
+                ("len", Parameter),
+                ("let", Keyword),
+                ("n", Variable),
+                ("len", Parameter),
+                ("puts", Builtin),
+                ("n", Variable),
+                ("1.5", Number),
+                ("\"s\"", String),
+            ],
+        ),
+        (
+            "if (true) { run(PI) } else { \"open",
+            vec![
+                ("if", Keyword),
+                ("true", Keyword),
+                ("run", Function),
+                ("PI", Builtin),
+                ("else", Keyword),
+                ("\"open", String),
+            ],
+        ),
+    ];
+    for (input, expected) in cases {
+        let tokens = semantic_tokens(input)
+            .into_iter()
+            .map(|token| {
+                (
+                    &input[token.span.start.offset..token.span.end.offset],
+                    token.kind,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, expected, "{}", input);
+    }
+}
@@ -0,0 +1,491 @@
+use crate::compiler::Warning;
+use crate::eval::capabilities::Capabilities;
+use crate::eval::convert::FromArgs;
+use crate::eval::environment::Environment;
+use crate::eval::evaluator::{CancelHandle, EvalOptions, Evaluator};
+use crate::eval::host::{HostFunction, HostHandle, TypedFn};
+use crate::eval::input::Input;
+use crate::eval::iterator::{HostIterator, Iter};
+use crate::eval::observer::EvalObserver;
+use crate::eval::output::{Output, Sink};
+use crate::eval::profiler::{ProfileReport, Profiler};
+use crate::eval::vars::EnvVars;
+use crate::eval::ObjectWrapper;
+use crate::lexer::lexer::Lexer;
+use crate::lexer::span::Span;
+use crate::parser::fold::fold;
+use crate::parser::program::Program;
+use crate::parser::resolve::resolve;
+use crate::parser::{CallFrame, ParseError, Parser, Result};
+use std::any::Any;
+use std::convert::TryFrom;
+use std::future::Future;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+pub mod pool;
+mod sandbox;
+mod strict;
+#[cfg(test)]
+mod test;
+
+// the same as the main thread, scripts recurse on the stack
+const EVAL_THREAD_STACK_SIZE: usize = 8 * 1024 * 1024;
+
+// Keeps the global environment between `eval` calls, so bindings made by one
+// input are visible to the next, like in the REPL.
+#[derive(Default)]
+pub struct Interpreter {
+    env: Environment,
+    options: EvalOptions,
+    cancel: CancelHandle,
+    // shared with the thread of `eval_async`, which may outlive its future
+    profiler: Arc<Mutex<Option<Profiler>>>,
+    output: Output,
+    input: Input,
+    vars: EnvVars,
+    args: Vec<String>,
+    error_span: Option<Span>,
+    error_trace: Vec<CallFrame>,
+    warnings: Vec<Warning>,
+    exit_code: Option<i32>,
+    steps: u64,
+    fold_constants: bool,
+    strict: bool,
+}
+
+// an interpreter can be moved into another thread or held across `.await`s,
+// a program shared between threads
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Interpreter>();
+    assert_send_sync::<ParseError>();
+    assert_send_sync::<Program>();
+};
+
+// The thread of `eval_async`. If the future is dropped before the script
+// is done, the script is cancelled and the thread left to stop at its next
+// step, the executor is not blocked waiting for it.
+struct Running<T> {
+    worker: Option<std::thread::JoinHandle<T>>,
+    state: Arc<Mutex<RunState>>,
+    cancel: CancelHandle,
+}
+
+#[derive(Default)]
+struct RunState {
+    done: bool,
+    abandoned: bool,
+}
+
+impl RunState {
+    // called by the thread once the script returned, it clears the cancel
+    // of an abandoned script, which may have returned before stopping on it
+    fn finish(state: &Mutex<RunState>, cancel: &CancelHandle) {
+        let mut state = state.lock().unwrap();
+        state.done = true;
+        if state.abandoned {
+            cancel.reset();
+        }
+    }
+}
+
+impl<T> Drop for Running<T> {
+    fn drop(&mut self) {
+        if self.worker.take().is_some() {
+            let mut state = self.state.lock().unwrap();
+            if !state.done {
+                state.abandoned = true;
+                self.cancel.cancel();
+            }
+        }
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_options(options: EvalOptions) -> Self {
+        Interpreter {
+            options,
+            ..Default::default()
+        }
+    }
+
+    // the handle stays valid for the lifetime of the interpreter, cancelling
+    // stops the evaluation in progress with a "script cancelled" error, or
+    // the next one if none is
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.cancel.clone()
+    }
+
+    // starts counting calls and statements of the following evaluations
+    pub fn enable_profiling(&mut self) {
+        self.profiler
+            .lock()
+            .unwrap()
+            .get_or_insert_with(Profiler::new);
+    }
+
+    // folds the operators on literals of the following inputs before they
+    // run, see `parser::fold`
+    pub fn enable_constant_folding(&mut self) {
+        self.fold_constants = true;
+    }
+
+    // rejects the following inputs using a name nothing defines before they
+    // run, instead of failing when the name is reached, see `strict::check`
+    pub fn enable_strict_mode(&mut self) {
+        self.strict = true;
+    }
+
+    // the error `eval` rejects `program` with before it runs, which strict
+    // mode is the only cause of, for hosts telling it apart from a runtime
+    // error
+    pub fn check(&self, program: &Program) -> Result<()> {
+        if self.strict {
+            strict::check(program, &self.env)
+        } else {
+            Ok(())
+        }
+    }
+
+    // what was profiled since `enable_profiling`, None when it is off
+    pub fn profile_report(&self) -> Option<ProfileReport> {
+        self.profiler
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|profiler| profiler.report())
+    }
+
+    // what `puts` prints goes to `writer`, see `OutputBuffer` to capture it
+    pub fn set_stdout<W: Write + Send + 'static>(&mut self, writer: W) {
+        self.output.stdout = Sink::new(writer);
+    }
+
+    pub fn set_stderr<W: Write + Send + 'static>(&mut self, writer: W) {
+        self.output.stderr = Sink::new(writer);
+    }
+
+    // what `input` and `read_line` read, e.g. a `Cursor` with canned input
+    pub fn set_stdin<R: BufRead + Send + 'static>(&mut self, reader: R) {
+        self.input = Input::new(reader);
+    }
+
+    // the environment variables scripts see, `set_env` only changes them
+    // for this interpreter
+    pub fn env_vars(&self) -> &EnvVars {
+        &self.vars
+    }
+
+    // what `args` returns, the arguments given to the script
+    pub fn set_args(&mut self, args: Vec<String>) {
+        self.args = args;
+    }
+
+    pub fn env(&self) -> &Environment {
+        &self.env
+    }
+
+    pub fn env_mut(&mut self) -> &mut Environment {
+        &mut self.env
+    }
+
+    // binds `name` in the global environment, e.g. to data the script reads
+    pub fn set_global<T: Into<ObjectWrapper>>(&mut self, name: &str, value: T) {
+        self.env.set(name, value.into());
+    }
+
+    // unbinds the global `name`, returns what it was bound to
+    pub(crate) fn remove_global(&mut self, name: &str) -> Option<ObjectWrapper> {
+        self.env.remove(name)
+    }
+
+    // the global `name` converted, see `eval::convert`
+    pub fn get_global<T>(&self, name: &str) -> Result<T>
+    where
+        T: TryFrom<ObjectWrapper, Error = ParseError>,
+    {
+        match self.env.get(name) {
+            Some(obj) => T::try_from(obj),
+            None => Err(format!("identifier not found: {}", name).into()),
+        }
+    }
+
+    // where in the input of the last evaluation its runtime error happened,
+    // see `Evaluator::error_span`
+    pub fn error_span(&self) -> Option<Span> {
+        self.error_span
+    }
+
+    // the calls that error was raised in, see `Evaluator::error_trace`
+    pub fn error_trace(&self) -> &[CallFrame] {
+        &self.error_trace
+    }
+
+    // the warnings of the last evaluation, see `EvalOptions::type_warnings`
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    // what the last evaluation called `exit` with, see `Evaluator::exit_code`
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    // the steps the last evaluation took, see `EvalOptions::max_steps`
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    // Parse and runtime errors are returned as `Err`, with their span and
+    // the calls they were raised in, see `ParseError::trace`. An error value
+    // the script evaluates to, e.g. `error("...")`, is `Ok`.
+    pub fn eval(&mut self, input: &str) -> Result<ObjectWrapper> {
+        self.eval_with(input, None)
+    }
+
+    // `eval` of the script at `path`, a file that cannot be read is an `Err`
+    pub fn eval_file<P: AsRef<Path>>(&mut self, path: P) -> Result<ObjectWrapper> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| ParseError::from(format!("cannot read {}: {}", path.display(), e)))?;
+        self.eval(&source)
+    }
+
+    // like `eval`, with `observer` called around every statement and call
+    // instead of the profiler, see `Debugger`
+    pub fn eval_observed(
+        &mut self,
+        input: &str,
+        observer: &mut dyn EvalObserver,
+    ) -> Result<ObjectWrapper> {
+        self.eval_with(input, Some(observer))
+    }
+
+    fn parse(&self, input: &str) -> Result<Program> {
+        let mut program = Parser::new(Lexer::new(input)).parse_program()?;
+        if self.fold_constants {
+            fold(&mut program);
+        }
+        self.check(&program)?;
+        resolve(&mut program);
+        Ok(program)
+    }
+
+    fn eval_with(
+        &mut self,
+        input: &str,
+        observer: Option<&mut dyn EvalObserver>,
+    ) -> Result<ObjectWrapper> {
+        self.error_span = None;
+        self.error_trace.clear();
+        self.warnings.clear();
+        self.exit_code = None;
+        self.steps = 0;
+        let program = self.parse(input)?;
+        self.eval_parsed(&program, observer)
+    }
+
+    // Like `eval` for a program parsed once, e.g. kept in an `Arc` and run by
+    // an interpreter per request. Functions it defines are copied into this
+    // interpreter's globals, the program is not changed. `resolve` it first
+    // for its locals to be looked up by slot, `fold` it for its constants to
+    // be folded.
+    pub fn eval_program(&mut self, program: &Program) -> Result<ObjectWrapper> {
+        self.error_span = None;
+        self.error_trace.clear();
+        self.warnings.clear();
+        self.exit_code = None;
+        self.steps = 0;
+        self.eval_parsed(program, None)
+    }
+
+    // Evaluates a single expression written by an end user, e.g. a filter in
+    // a config file. It cannot define functions or use builtins doing IO,
+    // which fails like a parse error, and fails once it takes more than a
+    // few thousand steps. It sees the globals and host functions.
+    pub fn eval_expr_sandboxed(&mut self, input: &str) -> Result<ObjectWrapper> {
+        self.error_span = None;
+        self.error_trace.clear();
+        self.warnings.clear();
+        self.exit_code = None;
+        self.steps = 0;
+        let program = self.parse(input)?;
+        sandbox::check(&program, &self.env)?;
+        let options = EvalOptions {
+            max_steps: Some(sandbox::MAX_STEPS),
+            max_memory: Some(sandbox::MAX_MEMORY),
+            capabilities: Capabilities::NONE,
+            type_warnings: self.options.type_warnings,
+            ..Default::default()
+        };
+        let options = std::mem::replace(&mut self.options, options);
+        let ret = self.eval_parsed(&program, None);
+        self.options = options;
+        ret
+    }
+
+    fn eval_parsed(
+        &mut self,
+        program: &Program,
+        observer: Option<&mut dyn EvalObserver>,
+    ) -> Result<ObjectWrapper> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("eval", statements = program.statements.len()).entered();
+        let mut profiler = self.profiler.lock().unwrap();
+        let mut evaluator = Evaluator::with_env(&program.statements, self.env.clone());
+        evaluator.set_options(self.options.clone());
+        evaluator.set_cancel_handle(self.cancel.clone());
+        evaluator.set_output(self.output.clone());
+        evaluator.set_input(self.input.clone());
+        evaluator.set_vars(self.vars.clone());
+        evaluator.set_args(self.args.clone());
+        match observer {
+            Some(observer) => evaluator.set_observer(observer),
+            None => {
+                if let Some(profiler) = profiler.as_mut() {
+                    evaluator.set_observer(profiler);
+                }
+            }
+        }
+        let ret = evaluator.try_eval();
+        self.cancel.stopped();
+        self.error_span = evaluator.error_span();
+        self.error_trace = evaluator.error_trace().to_vec();
+        self.warnings = evaluator.warnings().to_vec();
+        self.exit_code = evaluator.exit_code();
+        self.steps = evaluator.steps();
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            steps = evaluator.steps(),
+            objects = evaluator.objects(),
+            allocated = evaluator.allocated(),
+            "evaluated"
+        );
+        ret
+    }
+
+    // Like `eval`, but async host functions can be called. The script runs
+    // on its own thread and blocks there while the calling task awaits the
+    // host futures, so it works on any executor.
+    pub async fn eval_async(&mut self, input: &str) -> Result<ObjectWrapper> {
+        self.error_span = None;
+        self.error_trace.clear();
+        self.warnings.clear();
+        self.exit_code = None;
+        self.steps = 0;
+        let program = self.parse(input)?;
+
+        let (sender, receiver) = async_std::channel::unbounded();
+        let env = self.env.clone();
+        let options = self.options.clone();
+        let cancel = self.cancel.clone();
+        let output = self.output.clone();
+        let input = self.input.clone();
+        let vars = self.vars.clone();
+        let args = self.args.clone();
+        let profiler = self.profiler.clone();
+        let state = Arc::new(Mutex::new(RunState::default()));
+        let run_state = state.clone();
+        let worker = std::thread::Builder::new()
+            .name("monkey-eval".to_string())
+            .stack_size(EVAL_THREAD_STACK_SIZE)
+            .spawn(move || {
+                // taken for the run, an abandoned script does not hold up
+                // the next evaluation
+                let mut taken = profiler.lock().unwrap().take();
+                let mut evaluator = Evaluator::with_env(&program.statements, env);
+                evaluator.set_options(options);
+                evaluator.set_cancel_handle(cancel.clone());
+                evaluator.set_output(output);
+                evaluator.set_input(input);
+                evaluator.set_vars(vars);
+                evaluator.set_args(args);
+                evaluator.set_async_calls(sender);
+                if let Some(profiler) = taken.as_mut() {
+                    evaluator.set_observer(profiler);
+                }
+                let ret = evaluator.try_eval();
+                let error = (evaluator.error_span(), evaluator.error_trace().to_vec());
+                let warnings = evaluator.warnings().to_vec();
+                let done = (evaluator.exit_code(), evaluator.steps());
+                drop(evaluator);
+                if let Some(taken) = taken {
+                    profiler.lock().unwrap().get_or_insert(taken);
+                }
+                RunState::finish(&run_state, &cancel);
+                (ret, error, warnings, done)
+            })
+            .map_err(|e| ParseError::from(format!("cannot start evaluation: {}", e)))?;
+        let mut worker = Running {
+            worker: Some(worker),
+            state,
+            cancel: self.cancel.clone(),
+        };
+
+        // the channel closes once the evaluator is done
+        while let Ok(call) = receiver.recv().await {
+            call.run().await;
+        }
+        let joined = worker.worker.take().unwrap().join();
+        self.cancel.stopped();
+        match joined {
+            Ok((ret, (error_span, error_trace), warnings, (exit_code, steps))) => {
+                self.error_span = error_span;
+                self.error_trace = error_trace;
+                self.warnings = warnings;
+                self.exit_code = exit_code;
+                self.steps = steps;
+                ret
+            }
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+
+    // Wraps a Rust value in a handle scripts can only pass around, host
+    // functions get it back with `HostHandle::downcast`.
+    pub fn wrap<T: Any + Send + Sync>(value: T) -> ObjectWrapper {
+        ObjectWrapper::Handle(HostHandle::new(value))
+    }
+
+    // an iterator scripts read with `next`, `take` and `map`, its items are
+    // produced when they do
+    pub fn iterator<I: HostIterator + 'static>(iterator: I) -> ObjectWrapper {
+        ObjectWrapper::Iterator(Iter::new(iterator))
+    }
+
+    pub fn register_fn<F>(&mut self, name: &str, arity: usize, f: F)
+    where
+        F: Fn(Vec<ObjectWrapper>) -> Result<ObjectWrapper> + Send + Sync + 'static,
+    {
+        let func = HostFunction::new(name, arity, f);
+        self.env.set(name, ObjectWrapper::HostFn(func));
+    }
+
+    // Registers a Rust function like `|a: i64, b: i64| a + b`, its arguments
+    // and result are converted, see `eval::convert`. Arguments that do not
+    // convert are runtime errors.
+    pub fn register_typed_fn<F, Args>(&mut self, name: &str, f: F)
+    where
+        F: TypedFn<Args>,
+        Args: FromArgs,
+    {
+        let func = HostFunction::new_typed(name, f);
+        self.env.set(name, ObjectWrapper::HostFn(func));
+    }
+
+    // the function can only be called by scripts run with `eval_async`
+    pub fn register_async_fn<F, Fut>(&mut self, name: &str, arity: usize, f: F)
+    where
+        F: Fn(Vec<ObjectWrapper>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<ObjectWrapper>> + Send + 'static,
+    {
+        let func = HostFunction::new_async(name, arity, f);
+        self.env.set(name, ObjectWrapper::HostFn(func));
+    }
+}
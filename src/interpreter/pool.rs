@@ -0,0 +1,155 @@
+use crate::eval::evaluator::EvalOptions;
+use crate::eval::input::Input;
+use crate::eval::output::Output;
+use crate::eval::vars::EnvVars;
+use crate::eval::{HashMap, ObjectWrapper};
+use crate::interpreter::Interpreter;
+use crate::lexer::lexer::Lexer;
+use crate::lexer::symbol::Symbol;
+use crate::parser::program::Program;
+use crate::parser::resolve::resolve;
+use crate::parser::{Parser, Result};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+// Interpreters with a prelude already evaluated, for servers running a
+// script per request. The prelude is parsed once and evaluated by every
+// interpreter the pool makes. `checkin` puts the globals back to what the
+// prelude left, so what a request binds is not seen by the next one.
+pub struct Pool {
+    size: usize,
+    prelude: Arc<Program>,
+    make: Box<dyn Fn() -> Interpreter + Send + Sync>,
+    idle: Mutex<Vec<Pooled>>,
+}
+
+// an interpreter checked out of a pool, give it back with `Pool::checkin`
+pub struct Pooled {
+    interpreter: Interpreter,
+    // the globals after the prelude
+    globals: HashMap<Symbol, ObjectWrapper>,
+    setup: Setup,
+}
+
+// what `make` set on an interpreter, put back by `checkin`
+struct Setup {
+    options: EvalOptions,
+    fold_constants: bool,
+    strict: bool,
+    output: Output,
+    input: Input,
+    vars: EnvVars,
+    args: Vec<String>,
+    profiling: bool,
+}
+
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Pool>();
+};
+
+impl Pool {
+    // `size` interpreters made by `make`, e.g. `Interpreter::new`, with
+    // `prelude` evaluated. It fails if the prelude does.
+    pub fn new<F>(size: usize, prelude: &str, make: F) -> Result<Pool>
+    where
+        F: Fn() -> Interpreter + Send + Sync + 'static,
+    {
+        let mut prelude = Parser::new(Lexer::new(prelude)).parse_program()?;
+        resolve(&mut prelude);
+        let pool = Pool {
+            size,
+            prelude: Arc::new(prelude),
+            make: Box::new(make),
+            idle: Mutex::new(Vec::with_capacity(size)),
+        };
+        let idle = (0..size)
+            .map(|_| pool.warm())
+            .collect::<Result<Vec<Pooled>>>()?;
+        *pool.idle.lock().unwrap() = idle;
+        Ok(pool)
+    }
+
+    fn warm(&self) -> Result<Pooled> {
+        let mut interpreter = (self.make)();
+        if let ObjectWrapper::ErrorObject(e) = interpreter.eval_program(&self.prelude)? {
+            // the prelude returned an error value
+            return Err(e.into());
+        }
+        let globals = interpreter.env.local_bindings();
+        let setup = Setup {
+            options: interpreter.options.clone(),
+            fold_constants: interpreter.fold_constants,
+            strict: interpreter.strict,
+            output: interpreter.output.clone(),
+            input: interpreter.input.clone(),
+            vars: interpreter.vars.copy(),
+            args: interpreter.args.clone(),
+            profiling: interpreter.profile_report().is_some(),
+        };
+        Ok(Pooled {
+            interpreter,
+            globals,
+            setup,
+        })
+    }
+
+    // an idle interpreter, or a new one when all are checked out
+    pub fn checkout(&self) -> Result<Pooled> {
+        match self.idle.lock().unwrap().pop() {
+            Some(pooled) => Ok(pooled),
+            None => self.warm(),
+        }
+    }
+
+    // Resets the globals and what was set on the interpreter since the
+    // checkout, its options, constant folding, strict mode, output, input,
+    // environment variables and arguments go back to what `make` set. Its
+    // profile and what the last evaluation left are cleared. It is kept if
+    // fewer than `size` are idle.
+    pub fn checkin(&self, mut pooled: Pooled) {
+        let interpreter = &mut pooled.interpreter;
+        let setup = &pooled.setup;
+        interpreter.env.reset(pooled.globals.clone());
+        interpreter.cancel = Default::default();
+        interpreter.profiler = Default::default();
+        if setup.profiling {
+            interpreter.enable_profiling();
+        }
+        interpreter.options = setup.options.clone();
+        interpreter.fold_constants = setup.fold_constants;
+        interpreter.strict = setup.strict;
+        interpreter.output = setup.output.clone();
+        interpreter.input = setup.input.clone();
+        interpreter.vars = setup.vars.copy();
+        interpreter.args = setup.args.clone();
+        interpreter.error_span = None;
+        interpreter.error_trace.clear();
+        interpreter.warnings.clear();
+        interpreter.exit_code = None;
+        interpreter.steps = 0;
+        let mut idle = self.idle.lock().unwrap();
+        if idle.len() < self.size {
+            idle.push(pooled);
+        }
+    }
+
+    // how many interpreters are waiting to be checked out
+    pub fn idle(&self) -> usize {
+        self.idle.lock().unwrap().len()
+    }
+}
+
+impl Deref for Pooled {
+    type Target = Interpreter;
+
+    fn deref(&self) -> &Interpreter {
+        &self.interpreter
+    }
+}
+
+impl DerefMut for Pooled {
+    fn deref_mut(&mut self) -> &mut Interpreter {
+        &mut self.interpreter
+    }
+}
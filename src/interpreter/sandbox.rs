@@ -0,0 +1,73 @@
+use crate::eval::builtins::Builtins;
+use crate::eval::environment::Environment;
+use crate::lexer::span::Span;
+use crate::parser::program::{Expression, Program, Statement};
+use crate::parser::visit::{self, Visitor};
+use crate::parser::{ParseError, Result};
+
+// how many steps and bytes a sandboxed expression may take, see
+// `EvalOptions`
+pub(super) const MAX_STEPS: u64 = 10_000;
+pub(super) const MAX_MEMORY: usize = 1 << 20;
+
+// the builtins that only compute, new ones are forbidden until added
+const PURE_BUILTINS: &[&str] = &[
+    "PI", "error", "first", "is_error", "last", "len", "push", "rest",
+];
+
+// The error of the first thing `program` may not do in the sandbox: be
+// more than one expression, define a function or use a builtin doing IO.
+// Globals of `env` hide builtins, host functions can be called.
+pub(super) fn check(program: &Program, env: &Environment) -> Result<()> {
+    match &program.statements[..] {
+        [Statement::ExpressionStatement(..)] => {}
+        [] => return Err("expect an expression".into()),
+        [statement, ..] => {
+            return Err(ParseError::new(
+                "expect a single expression",
+                statement.span(),
+            ))
+        }
+    }
+    let mut checker = Checker { env, error: None };
+    visit::walk_program(&mut checker, program);
+    match checker.error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+struct Checker<'a> {
+    env: &'a Environment,
+    error: Option<ParseError>,
+}
+
+impl Checker<'_> {
+    fn fail(&mut self, message: String, span: Span) {
+        self.error
+            .get_or_insert_with(|| ParseError::new(message, span));
+    }
+}
+
+impl Visitor for Checker<'_> {
+    fn visit_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::FunctionExpression(_, _, span) => self.fail(
+                "functions are not allowed in the sandbox".to_string(),
+                *span,
+            ),
+            Expression::Identifier(ident)
+                if Builtins::instance_ref().contains(&ident.0)
+                    && !PURE_BUILTINS.contains(&ident.0.as_str())
+                    && !self.env.contains(&ident.0) =>
+            {
+                self.fail(
+                    format!("`{}` is not allowed in the sandbox", ident.0),
+                    ident.1,
+                )
+            }
+            _ => {}
+        }
+        visit::walk_expression(self, expression);
+    }
+}
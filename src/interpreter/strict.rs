@@ -0,0 +1,69 @@
+use crate::eval::builtins::Builtins;
+use crate::eval::environment::Environment;
+use crate::lexer::symbol::Symbol;
+use crate::parser::program::{Expression, Program};
+use crate::parser::resolve::Declarations;
+use crate::parser::visit::{self, Visitor};
+use crate::parser::{ParseError, Result};
+
+// The error of the first name `program` uses that nothing defines: no `let`
+// or parameter of a function around it, no `let` at the top level, wherever
+// it is, no global of `env` and no builtin. A name bound after its use is
+// fine, a function may be called once it is.
+pub(super) fn check(program: &Program, env: &Environment) -> Result<()> {
+    let mut globals = vec![];
+    Declarations(&mut globals).visit_block(&program.statements);
+    let mut checker = Checker {
+        env,
+        scopes: vec![globals],
+        error: None,
+    };
+    visit::walk_program(&mut checker, program);
+    match checker.error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+struct Checker<'a> {
+    env: &'a Environment,
+    // the names bound at the top level and in the functions around,
+    // innermost last
+    scopes: Vec<Vec<Symbol>>,
+    error: Option<ParseError>,
+}
+
+impl Checker<'_> {
+    fn defines(&self, name: &Symbol) -> bool {
+        self.scopes.iter().any(|scope| scope.contains(name))
+            || self.env.contains_symbol(name)
+            || Builtins::instance_ref().contains(name)
+    }
+}
+
+impl Visitor for Checker<'_> {
+    fn visit_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Identifier(ident) if self.error.is_none() && !self.defines(&ident.0) => {
+                self.error = Some(ParseError::new(
+                    format!("identifier not found: {}", ident.0),
+                    ident.1,
+                ))
+            }
+            Expression::FunctionExpression(params, body, _) => {
+                let mut scope = vec![];
+                let mut declarations = Declarations(&mut scope);
+                params
+                    .iter()
+                    .for_each(|param| declarations.declare(param.0.clone()));
+                declarations.visit_block(body);
+                self.scopes.push(scope);
+                visit::walk_expression(self, expression);
+                self.scopes.pop();
+                return;
+            }
+            _ => {}
+        }
+        visit::walk_expression(self, expression);
+    }
+}
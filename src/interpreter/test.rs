@@ -0,0 +1,1073 @@
+use crate::eval::capabilities::Capabilities;
+use crate::eval::debugger::Debugger;
+use crate::eval::evaluator::EvalOptions;
+use crate::eval::input::Input;
+use crate::eval::output::{OutputBuffer, Sink};
+use crate::eval::printer::ObjectPrinter;
+use crate::eval::ObjectWrapper;
+use crate::interpreter::pool::Pool;
+use crate::interpreter::Interpreter;
+use std::io::Cursor;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn test_interpreter_keeps_state() {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval("let a = 5;").unwrap();
+    interpreter.eval("let add = fn(x) { x + a };").unwrap();
+    assert_eq!(
+        interpreter.eval("add(2)").unwrap(),
+        ObjectWrapper::Integer(7)
+    );
+    assert!(interpreter.eval("let = 1;").is_err());
+}
+
+#[test]
+fn test_eval_file() {
+    let path = std::env::temp_dir().join(format!("monkey_eval_file_{}.mk", std::process::id()));
+    std::fs::write(&path, "let double = fn(x) { x * 2 };\ndouble(2)").unwrap();
+    let mut interpreter = crate::Interpreter::new();
+    assert_eq!(
+        interpreter.eval_file(&path).unwrap(),
+        crate::Value::Integer(4)
+    );
+    // the file's bindings stay for the next inputs
+    assert_eq!(
+        interpreter.eval("double(5)").unwrap(),
+        crate::Value::Integer(10)
+    );
+    std::fs::remove_file(&path).unwrap();
+
+    let e = interpreter.eval_file(&path).unwrap_err();
+    assert!(e.message().starts_with("cannot read "), "{}", e);
+}
+
+#[test]
+fn test_globals() {
+    use std::collections::HashMap;
+
+    let mut interpreter = Interpreter::new();
+    let config = HashMap::from([("retries".to_string(), 3i64)]);
+    interpreter.set_global("config", config);
+    interpreter.set_global("seen", Vec::<String>::new());
+    interpreter
+        .eval("let seen = push(seen, \"a\"); let total = config[\"retries\"] * 2;")
+        .unwrap();
+    assert_eq!(interpreter.get_global::<i64>("total").unwrap(), 6);
+    assert_eq!(
+        interpreter.get_global::<Vec<String>>("seen").unwrap(),
+        vec!["a".to_string()]
+    );
+    assert_eq!(
+        interpreter
+            .get_global::<String>("total")
+            .unwrap_err()
+            .message(),
+        "expect string, got int"
+    );
+    assert_eq!(
+        interpreter
+            .get_global::<i64>("missing")
+            .unwrap_err()
+            .message(),
+        "identifier not found: missing"
+    );
+}
+
+#[test]
+fn test_eval_expr_sandboxed() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_global("price", 12);
+    interpreter.set_global("tags", vec!["new", "sale"]);
+    interpreter
+        .eval("let count = fn(n) { if (n < 1) { 1 } else { count(n - 1) + count(n - 1) } };")
+        .unwrap();
+    interpreter.register_typed_fn("discount", |p: i64| p / 2);
+    interpreter
+        .eval("let shout = fn() { puts(\"io\") };")
+        .unwrap();
+
+    let cases = [
+        (
+            "price > 10 == (len(tags) == 2)",
+            Ok(ObjectWrapper::Boolean(true)),
+        ),
+        ("discount(price) + count(3)", Ok(ObjectWrapper::Integer(14))),
+        ("count(20)", Err("script exceeded execution limit")),
+        // functions defined before cannot do IO either
+        (
+            "shout()",
+            Err("permission denied: 'puts' needs the output capability"),
+        ),
+    ];
+    for (input, expect) in cases {
+        assert_eq!(
+            interpreter
+                .eval_expr_sandboxed(input)
+                .map_err(|e| e.to_string()),
+            expect.map_err(String::from),
+            "{}",
+            input
+        );
+    }
+
+    let errors = [
+        ("", "expect an expression", None),
+        ("let a = 1; a", "expect a single expression", Some("1:1")),
+        ("1; 2", "expect a single expression", Some("1:1")),
+        (
+            "first([fn(x) { x }])",
+            "functions are not allowed in the sandbox",
+            Some("1:8"),
+        ),
+        (
+            "len(read_file(\"/etc/passwd\"))",
+            "`read_file` is not allowed in the sandbox",
+            Some("1:5"),
+        ),
+        (
+            "puts(price)",
+            "`puts` is not allowed in the sandbox",
+            Some("1:1"),
+        ),
+        ("price +", "no prefix parse function for EOF", Some("1:8")),
+    ];
+    for (input, message, position) in errors {
+        let e = interpreter.eval_expr_sandboxed(input).unwrap_err();
+        assert_eq!(
+            (e.message(), e.span().map(|span| span.start.to_string())),
+            (message, position.map(String::from)),
+            "{}",
+            input
+        );
+    }
+
+    // the limits are only the sandbox's
+    assert_eq!(
+        interpreter.eval("count(12)").unwrap(),
+        ObjectWrapper::Integer(4096)
+    );
+}
+
+#[test]
+fn test_cancel_running_script() {
+    let mut interpreter = Interpreter::new();
+    let handle = interpreter.cancel_handle();
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        handle.cancel();
+    });
+
+    // runs for far longer than the test is willing to wait
+    let e = interpreter
+        .eval("let f = fn(n) { if (n < 1) { 0 } else { f(n - 1) + f(n - 1) } }; f(60)")
+        .unwrap_err();
+    canceller.join().unwrap();
+    assert_eq!(e.message(), "script cancelled");
+
+    // the interpreter is still usable afterwards
+    assert_eq!(interpreter.eval("f(3)").unwrap(), ObjectWrapper::Integer(0));
+
+    // a cancel sent before the evaluation starts is not lost
+    interpreter.cancel_handle().cancel();
+    assert_eq!(
+        interpreter.eval("f(3)").unwrap_err().message(),
+        "script cancelled"
+    );
+    assert_eq!(interpreter.eval("f(3)").unwrap(), ObjectWrapper::Integer(0));
+}
+
+#[test]
+fn test_profile_report() {
+    let mut interpreter = Interpreter::new();
+    assert!(interpreter.profile_report().is_none());
+
+    interpreter.enable_profiling();
+    interpreter
+        .eval("let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } };")
+        .unwrap();
+    interpreter.eval("fib(10); len(\"abc\")").unwrap();
+
+    let report = interpreter.profile_report().unwrap();
+    let functions = report
+        .functions
+        .iter()
+        .map(|e| (e.name.as_str(), e.count))
+        .collect::<Vec<_>>();
+    assert_eq!(functions, vec![("fib", 177), ("len", 1)]);
+
+    let body = report
+        .statements
+        .iter()
+        .find(|e| e.name.starts_with("1:19 if"))
+        .unwrap();
+    assert_eq!(body.count, 177);
+    assert!(report.to_string().starts_with("function "));
+}
+
+#[cfg(test)]
+fn run_debugger(source: &str, commands: &str) -> (Result<ObjectWrapper, String>, String) {
+    let mut interpreter = Interpreter::new();
+    let output = OutputBuffer::new();
+    let mut debugger = Debugger::new(
+        source,
+        Input::new(Cursor::new(commands.to_string())),
+        Sink::new(output.clone()),
+    );
+    debugger.set_cancel_handle(interpreter.cancel_handle());
+    let obj = interpreter
+        .eval_observed(source, &mut debugger)
+        .map_err(|e| e.to_string());
+    (obj, output.contents().replace("(debug) ", ""))
+}
+
+#[test]
+fn test_debugger() {
+    let source = "let add = fn(a, b) {
+  let c = a + b;
+  c * 2
+};
+let x = add(1, 2);
+add(x, 3)";
+    let cases = [
+        (
+            "b 2\nc\nbt\nv\nn\np c\nc\nc\n",
+            Ok(ObjectWrapper::Integer(18)),
+            "stopped at 1:1
+   1 | let add = fn(a, b) {
+breakpoint at line 2
+stopped at 2:3
+   2 |   let c = a + b;
+#0 add at 2:3
+#1 <main> at 5:9
+a = 1
+add = <function>
+b = 2
+stopped at 3:3
+   3 |   c * 2
+c = 3
+stopped at 2:3
+   2 |   let c = a + b;
+",
+        ),
+        // `next` steps over calls, an empty line repeats the last command
+        (
+            "n\n\nl\nd 2\nc\n",
+            Ok(ObjectWrapper::Integer(18)),
+            "stopped at 1:1
+   1 | let add = fn(a, b) {
+stopped at 5:1
+   5 | let x = add(1, 2);
+stopped at 6:1
+   6 | add(x, 3)
+   4 | };
+   5 | let x = add(1, 2);
+>  6 | add(x, 3)
+no breakpoint at line 2
+",
+        ),
+        // `step` goes into calls
+        (
+            "s\ns\nfoo\nq\n",
+            Err("script cancelled".to_string()),
+            "stopped at 1:1
+   1 | let add = fn(a, b) {
+stopped at 5:1
+   5 | let x = add(1, 2);
+stopped at 2:3
+   2 |   let c = a + b;
+unknown command: foo, try `help`
+",
+        ),
+    ];
+    for (commands, expect, transcript) in cases {
+        let (obj, output) = run_debugger(source, commands);
+        assert_eq!(obj, expect, "{}", commands);
+        assert_eq!(output, transcript, "{}", commands);
+    }
+}
+
+#[test]
+fn test_shared_program() {
+    use crate::lexer::lexer::Lexer;
+    use crate::parser::Parser;
+    use std::sync::Arc;
+
+    let source = "let count = count + 1; let greet = fn() { name + \" #\" + count }; greet()";
+    let program = Arc::new(Parser::new(Lexer::new(source)).parse_program().unwrap());
+    let workers = ["a", "b"]
+        .iter()
+        .map(|name| {
+            let program = program.clone();
+            let name = name.to_string();
+            thread::spawn(move || {
+                let mut interpreter = Interpreter::new();
+                interpreter
+                    .eval(&format!("let name = \"{}\"; let count = 0;", name))
+                    .unwrap();
+                let mut ret = vec![];
+                for _ in 0..2 {
+                    ret.push(interpreter.eval_program(&program).unwrap());
+                }
+                ret
+            })
+        })
+        .collect::<Vec<_>>();
+    let results = workers
+        .into_iter()
+        .map(|worker| worker.join().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        results,
+        vec![
+            vec![ObjectWrapper::from("a #1"), ObjectWrapper::from("a #2")],
+            vec![ObjectWrapper::from("b #1"), ObjectWrapper::from("b #2")],
+        ]
+    );
+
+    let mut interpreter = Interpreter::new();
+    interpreter.eval("let count = 0;").unwrap();
+    let e = interpreter.eval_program(&program).unwrap_err();
+    assert_eq!(e.message(), "identifier not found: name");
+    assert!(e.span().is_some());
+    assert_eq!(interpreter.error_span(), e.span());
+}
+
+#[test]
+fn test_interpreter_across_threads() {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval("let n = 40;").unwrap();
+
+    let worker = thread::spawn(move || {
+        let obj = interpreter
+            .eval("let add = fn(x) { x + n }; add(2)")
+            .unwrap();
+        (interpreter, obj)
+    });
+    let (mut interpreter, obj) = worker.join().unwrap();
+    assert_eq!(obj, ObjectWrapper::Integer(42));
+
+    // a function value keeps its environment when used on another thread
+    let add = interpreter.env().get("add").unwrap();
+    let obj = thread::spawn(move || {
+        let mut other = Interpreter::new();
+        other.env_mut().set("plus_n", add);
+        other.eval("plus_n(1)").unwrap()
+    })
+    .join()
+    .unwrap();
+    assert_eq!(obj, ObjectWrapper::Integer(41));
+    assert_eq!(interpreter.eval("n").unwrap(), ObjectWrapper::Integer(40));
+}
+
+#[test]
+fn test_register_typed_fn() {
+    let mut interpreter = Interpreter::new();
+    interpreter.register_typed_fn("add", |a: i64, b: i64| a + b);
+    interpreter.register_typed_fn("words", |s: String| {
+        s.split_whitespace().map(String::from).collect::<Vec<_>>()
+    });
+    interpreter.register_typed_fn("mean", |xs: Vec<f64>| {
+        if xs.is_empty() {
+            return Err("mean of nothing".into());
+        }
+        Ok(xs.iter().sum::<f64>() / xs.len() as f64)
+    });
+    interpreter.register_typed_fn("nothing", || ());
+
+    let cases = [
+        ("add(2, 3)", Ok(ObjectWrapper::Integer(5))),
+        ("len(words(\"a b  c\"))", Ok(ObjectWrapper::Integer(3))),
+        (
+            "mean([1, 2.5, 3])",
+            Ok(ObjectWrapper::Float(2.1666666666666665)),
+        ),
+        ("nothing()", Ok(ObjectWrapper::Null)),
+        (
+            "add(1, \"2\")",
+            Err("argument 2 of 'add': expect int, got string"),
+        ),
+        ("add(1)", Err("Wrong number of arguments, expect 2 got 1")),
+        ("mean([])", Err("mean of nothing")),
+    ];
+    for (input, expect) in cases {
+        assert_eq!(
+            interpreter.eval(input).map_err(|e| e.to_string()),
+            expect.map_err(String::from),
+            "{}",
+            input
+        );
+    }
+}
+
+#[crate::builtin]
+fn repeat(s: String, n: i64) -> String {
+    s.repeat(n.max(0) as usize)
+}
+
+#[crate::builtin(name = "mean")]
+fn mean_of(xs: Vec<f64>) -> crate::parser::Result<f64> {
+    if xs.is_empty() {
+        return Err("mean of nothing".into());
+    }
+    Ok(xs.iter().sum::<f64>() / xs.len() as f64)
+}
+
+#[test]
+fn test_builtin_macro() {
+    let mut interpreter = Interpreter::new();
+    register_repeat(&mut interpreter);
+    register_mean_of(&mut interpreter);
+    assert_eq!(repeat("a".to_string(), 2), "aa");
+
+    let cases = [
+        ("repeat(\"ab\", 3)", Ok(ObjectWrapper::from("ababab"))),
+        ("mean([1, 2])", Ok(ObjectWrapper::Float(1.5))),
+        (
+            "repeat(1, 2)",
+            Err("argument 1 of 'repeat': expect string, got int"),
+        ),
+        ("mean([])", Err("mean of nothing")),
+        ("mean()", Err("Wrong number of arguments, expect 1 got 0")),
+    ];
+    for (input, expect) in cases {
+        assert_eq!(
+            interpreter.eval(input).map_err(|e| e.to_string()),
+            expect.map_err(String::from),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_host_handles() {
+    use crate::eval::host::HostHandle;
+    use std::sync::Mutex;
+
+    struct Counter(Mutex<i64>);
+
+    let mut interpreter = Interpreter::new();
+    interpreter.register_typed_fn("open", || Interpreter::wrap(Counter(Mutex::new(0))));
+    interpreter.register_typed_fn("incr", |handle: HostHandle| {
+        let counter = handle
+            .downcast::<Counter>()
+            .ok_or_else(|| format!("expect a counter, got {}", handle.type_name()))?;
+        let mut n = counter.0.lock().unwrap();
+        *n += 1;
+        Ok(*n)
+    });
+    interpreter
+        .env_mut()
+        .set("name", Interpreter::wrap("not a counter".to_string()));
+
+    let cases = [
+        (
+            "let c = open(); let d = open(); incr(c); incr(d); incr(c)",
+            Ok(ObjectWrapper::Integer(2)),
+        ),
+        (
+            "[c == c, c == d, c != d]",
+            Ok(ObjectWrapper::from(vec![true, false, true])),
+        ),
+        (
+            "incr(name)",
+            Err("expect a counter, got alloc::string::String"),
+        ),
+        (
+            "incr(1)",
+            Err("argument 1 of 'incr': expect handle, got int"),
+        ),
+    ];
+    for (input, expect) in cases {
+        assert_eq!(
+            interpreter.eval(input).map_err(|e| e.to_string()),
+            expect.map_err(String::from),
+            "{}",
+            input
+        );
+    }
+    let c = interpreter.eval("c").unwrap();
+    assert_eq!(
+        ObjectPrinter::new().print(&c),
+        format!("<handle {}>", std::any::type_name::<Counter>())
+    );
+}
+
+#[async_std::test]
+async fn test_eval_async() {
+    let mut interpreter = Interpreter::new();
+    interpreter.register_fn("double", 1, |args| match &args[0] {
+        ObjectWrapper::Integer(v) => Ok(ObjectWrapper::Integer(v * 2)),
+        other => Err(format!("cannot double {}", other.type_str()).into()),
+    });
+    interpreter.register_async_fn("fetch", 1, |args| async move {
+        async_std::task::sleep(Duration::from_millis(10)).await;
+        match &args[0] {
+            ObjectWrapper::String(key) => {
+                Ok(ObjectWrapper::String(format!("value of {}", key).into()))
+            }
+            _ => Err("fetch needs a string".into()),
+        }
+    });
+
+    let cases = [
+        ("double(21)", Ok(ObjectWrapper::Integer(42))),
+        (
+            "let get = fn(k) { fetch(k) }; get(\"a\") + \", \" + get(\"b\")",
+            Ok(ObjectWrapper::String("value of a, value of b".into())),
+        ),
+        ("fetch(1)", Err("fetch needs a string")),
+        (
+            "rescue(fn() { fetch() }, fn(e) { e })",
+            Ok(ObjectWrapper::ErrorObject(
+                "Wrong number of arguments, expect 1 got 0".to_string(),
+            )),
+        ),
+    ];
+    for (input, expect) in cases {
+        assert_eq!(
+            interpreter
+                .eval_async(input)
+                .await
+                .map_err(|e| e.to_string()),
+            expect.map_err(String::from)
+        );
+    }
+
+    // can be spawned on multi-threaded executors
+    fn assert_send<T: Send>(t: T) -> T {
+        t
+    }
+    assert_eq!(
+        assert_send(interpreter.eval_async("double(1)"))
+            .await
+            .unwrap(),
+        ObjectWrapper::Integer(2)
+    );
+
+    // the synchronous entry point cannot wait for the host
+    assert_eq!(
+        interpreter.eval("fetch(\"a\")").unwrap_err().message(),
+        "'fetch' is an async host function, evaluate with eval_async"
+    );
+    assert_eq!(
+        interpreter.eval("double(2)").unwrap(),
+        ObjectWrapper::Integer(4)
+    );
+}
+
+#[async_std::test]
+async fn test_eval_async_dropped() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let ticks = Arc::new(AtomicUsize::new(0));
+    let mut interpreter = Interpreter::new();
+    let counter = ticks.clone();
+    interpreter.register_typed_fn("tick", move || {
+        counter.fetch_add(1, Ordering::Relaxed);
+    });
+
+    // runs for far longer than the test is willing to wait
+    let input = "let f = fn(n) { tick(); if (n < 1) { 0 } else { f(n - 1) + f(n - 1) } }; f(60)";
+    let timeout = Duration::from_millis(50);
+    assert!(
+        async_std::future::timeout(timeout, interpreter.eval_async(input))
+            .await
+            .is_err()
+    );
+    // the thread is not waited for, it stops at its next step
+    thread::sleep(Duration::from_millis(50));
+    let ticked = ticks.load(Ordering::Relaxed);
+    assert!(ticked > 0);
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(ticks.load(Ordering::Relaxed), ticked);
+
+    // the next evaluation is not cancelled
+    assert_eq!(
+        interpreter.eval_async("f(2)").await.unwrap(),
+        ObjectWrapper::Integer(0)
+    );
+}
+
+#[test]
+fn test_eval_async_dropped_when_done() {
+    use std::future::Future;
+    use std::task::{Context, Poll, Waker};
+
+    let mut interpreter = Interpreter::new();
+    interpreter.enable_profiling();
+    {
+        let mut future = Box::pin(interpreter.eval_async("let f = fn() { 1 }; f()"));
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(matches!(future.as_mut().poll(&mut cx), Poll::Pending));
+        // the script is done, the future not polled again
+        thread::sleep(Duration::from_millis(200));
+    }
+    assert_eq!(
+        interpreter.eval("2 + 2").unwrap(),
+        ObjectWrapper::Integer(4)
+    );
+    assert!(interpreter.profile_report().is_some());
+}
+
+#[test]
+fn test_capture_output() {
+    let stdout = OutputBuffer::new();
+    let stderr = OutputBuffer::new();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(stdout.clone());
+    interpreter.set_stderr(stderr.clone());
+
+    let cases = [
+        ("puts(\"hello\")", "hello\n"),
+        ("puts(1 + 2)", "3\n"),
+        ("puts([1, \"a\", {true: 1.5}])", "[1, \"a\", {true: 1.5}]\n"),
+        ("puts(fn(x) { x })", "<function>\n"),
+        ("puts(puts)", "<builtin>\n"),
+    ];
+    for (input, expect) in cases {
+        stdout.clear();
+        assert_eq!(interpreter.eval(input).unwrap(), ObjectWrapper::Null);
+        assert_eq!(stdout.contents(), expect);
+    }
+
+    interpreter.eval("eputs(\"oops\")").unwrap();
+    assert_eq!(stderr.contents(), "oops\n");
+}
+
+#[test]
+fn test_canned_input() {
+    let stdout = OutputBuffer::new();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(stdout.clone());
+    interpreter.set_stdin(Cursor::new("Ada\r\n3\nlast"));
+
+    let cases = [
+        (
+            "let name = input(\"name? \"); \"hello \" + name",
+            ObjectWrapper::String("hello Ada".into()),
+        ),
+        ("read_line()", ObjectWrapper::String("3".into())),
+        ("read_line()", ObjectWrapper::String("last".into())),
+        ("read_line()", ObjectWrapper::Null),
+    ];
+    for (input, expect) in cases {
+        assert_eq!(interpreter.eval(input).unwrap(), expect);
+    }
+    assert_eq!(stdout.contents(), "name? ");
+}
+
+#[test]
+fn test_args() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        interpreter.eval("args()").unwrap(),
+        ObjectWrapper::Array(vec![].into())
+    );
+    interpreter.set_args(vec!["a".to_string(), "b c".to_string()]);
+    assert_eq!(
+        interpreter.eval("args()").unwrap(),
+        ObjectWrapper::Array(
+            vec![
+                ObjectWrapper::String("a".into()),
+                ObjectWrapper::String("b c".into()),
+            ]
+            .into()
+        )
+    );
+}
+
+#[test]
+fn test_exit_code() {
+    let mut interpreter = Interpreter::new();
+    // the process goes on, `rescue` does not stop it
+    assert_eq!(
+        interpreter
+            .eval("rescue(fn() { exit(3) }, fn(e) { 0 })")
+            .unwrap_err()
+            .message(),
+        "script exited with code 3"
+    );
+    assert_eq!(interpreter.exit_code(), Some(3));
+    assert_eq!(
+        interpreter
+            .eval("exit(1 + 4294967296)")
+            .unwrap_err()
+            .message(),
+        "exit code out of range: 4294967297"
+    );
+    assert_eq!(interpreter.exit_code(), None);
+    interpreter.eval("1").unwrap();
+    assert_eq!(interpreter.exit_code(), None);
+}
+
+#[test]
+fn test_steps() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .eval("let f = fn(n) { if (n < 1) { 0 } else { f(n - 1) } };")
+        .unwrap();
+    interpreter.eval("f(1)").unwrap();
+    let short = interpreter.steps();
+    interpreter.eval("f(10)").unwrap();
+    assert!(short > 0 && interpreter.steps() > short);
+    assert!(interpreter.eval("f(").is_err());
+    assert_eq!(interpreter.steps(), 0);
+}
+
+#[test]
+fn test_constant_folding() {
+    let input = "let f = fn(n) { if (n < 1) { 0 } else { f(n - (2 * 3 - 5)) } }; f(10)";
+    let mut interpreter = Interpreter::new();
+    assert_eq!(interpreter.eval(input).unwrap(), ObjectWrapper::Integer(0));
+    let steps = interpreter.steps();
+    let mut interpreter = Interpreter::new();
+    interpreter.enable_constant_folding();
+    assert_eq!(interpreter.eval(input).unwrap(), ObjectWrapper::Integer(0));
+    assert!(interpreter.steps() < steps);
+    // failing operators are left to fail where they are
+    assert_eq!(
+        interpreter.eval("1 / 0").unwrap_err().message(),
+        "division by zero"
+    );
+}
+
+#[test]
+fn test_strict_mode() {
+    let mut interpreter = Interpreter::new();
+    interpreter.enable_strict_mode();
+    interpreter.eval("let total = 0;").unwrap();
+    let programs = [
+        // earlier inputs and builtins, names bound after a function using
+        // them, the parameters and lets of functions around
+        "len([total])",
+        "let f = fn() { g() }; let g = fn() { 1 }; f()",
+        "let h = fn(x) { fn() { if (x) { let y = x; }; y } }; h(true)()",
+        "if (true) { let z = 2 }; z",
+    ];
+    for input in programs {
+        assert!(interpreter.eval(input).is_ok(), "{}", input);
+    }
+    let errors = [
+        (
+            "puts(1);
+puts(missing)",
+            "2:6",
+        ),
+        ("let k = fn(a) { a + b }", "1:21"),
+        // a local is not seen outside its function
+        ("let m = fn() { let local = 1; }; local", "1:34"),
+    ];
+    for (input, position) in errors {
+        let e = interpreter.eval(input).unwrap_err();
+        assert_eq!(
+            e.span().map(|span| span.start.to_string()).as_deref(),
+            Some(position),
+            "{}",
+            input
+        );
+    }
+    // nothing of a rejected input ran
+    assert_eq!(
+        interpreter
+            .eval("let n = 1; n + not_defined")
+            .unwrap_err()
+            .message(),
+        "identifier not found: not_defined"
+    );
+    assert!(interpreter.eval("n").is_err());
+}
+
+#[test]
+fn test_error_span() {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval("let f = fn(x) { x + y };").unwrap();
+    let cases = [
+        ("let a = 1;\nlet b = a + c;", Some((2, 13, 14))),
+        ("1 + -true", Some((1, 5, 10))),
+        ("[1, 2][\"a\"]", Some((1, 1, 12))),
+        // inside a function of an earlier input, at the call
+        ("let z = 1;\n  f(2)", Some((2, 3, 7))),
+        // inside one of the same input, where it happened, also called back
+        ("let g = fn(x) {\n  x + y\n};\ng(1)", Some((2, 7, 8))),
+        ("let g = fn(x) { x() };\ng(fn() { y })", Some((2, 10, 11))),
+        ("let g = fn(x) { x(1) };\ng(f)", Some((1, 17, 21))),
+        ("error(\"not raised\")", None),
+        ("1 + 1", None),
+    ];
+    for (input, expect) in cases {
+        let ret = interpreter.eval(input);
+        let span =
+            |span: crate::lexer::span::Span| (span.start.line, span.start.column, span.end.column);
+        let error_span = ret.as_ref().err().map(|e| e.span().map(span).unwrap());
+        assert_eq!(error_span, expect, "{} returned {:?}", input, ret);
+        assert_eq!(interpreter.error_span().map(span), expect, "{}", input);
+    }
+}
+
+#[test]
+fn test_error_trace() {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval("let f = fn(x) { x + y };").unwrap();
+    let cases = [
+        (
+            "let g = fn(x) { h(x) };\nlet h = fn(x) { x + y };\ng(1)",
+            vec![("h", (1, 17)), ("g", (3, 1))],
+        ),
+        ("fn(x) { y }(1)", vec![("<anonymous>", (1, 1))]),
+        // inside a function of an earlier input only the call is known
+        (
+            "let g = fn(x) { f(x) };\ng(1)",
+            vec![("f", (1, 17)), ("g", (2, 1))],
+        ),
+        ("let g = fn(x) { x };\n[g(1), len(1)]", vec![]),
+        // a rescued error is not traced
+        ("rescue(fn() { f(1) }, fn(e) { 1 }); len(1)", vec![]),
+    ];
+    for (input, expect) in cases {
+        let ret = interpreter.eval(input);
+        let e = ret.as_ref().err();
+        assert_eq!(
+            e.map_or(&[][..], |e| e.trace()),
+            interpreter.error_trace(),
+            "{}",
+            input
+        );
+        let trace = interpreter
+            .error_trace()
+            .iter()
+            .map(|frame| {
+                let start = frame.span.start;
+                (frame.function.as_str(), (start.line, start.column))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(trace, expect, "{} returned {:?}", input, ret);
+    }
+    interpreter.eval("1").unwrap();
+    assert!(interpreter.error_trace().is_empty());
+}
+
+// a failing script is an `Err`, an error value it returns is not
+#[test]
+fn test_eval_errors() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        interpreter.eval("error(\"on purpose\")").unwrap(),
+        ObjectWrapper::ErrorObject("on purpose".to_string())
+    );
+    let e = interpreter
+        .eval("let f = fn() { 1 / 0 };\nf()")
+        .unwrap_err();
+    assert_eq!(e.message(), "division by zero");
+    assert_eq!(e.span().unwrap().start.to_string(), "1:16");
+    assert_eq!(e.trace().len(), 1);
+    assert_eq!(e.trace()[0].span.start.to_string(), "2:1");
+}
+
+// inputs fuzzing found crashing fail with the error on their first line
+#[test]
+fn test_fuzz_regressions() {
+    let pattern = concat!(env!("CARGO_MANIFEST_DIR"), "/fuzz/regressions/*.mk");
+    let paths = glob::glob(pattern).unwrap().collect::<Vec<_>>();
+    assert!(!paths.is_empty());
+    for path in paths {
+        let path = path.unwrap();
+        let source = std::fs::read_to_string(&path).unwrap();
+        let expect = source.lines().next().unwrap()["// expect: ".len()..].to_string();
+        let message = thread::Builder::new()
+            .stack_size(super::EVAL_THREAD_STACK_SIZE)
+            .spawn(move || match Interpreter::new().eval(&source) {
+                Ok(ObjectWrapper::ErrorObject(message)) => message,
+                Ok(obj) => format!("no error, got {}", obj),
+                Err(e) => e.message().to_string(),
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(message, expect, "{}", path.display());
+    }
+}
+
+#[test]
+fn test_pool() {
+    let prelude = "let prefix = \"hi \"; let greet = fn(name) { prefix + name };";
+    let pool = Arc::new(Pool::new(2, prelude, Interpreter::new).unwrap());
+    assert_eq!(pool.idle(), 2);
+
+    let mut first = pool.checkout().unwrap();
+    let output = OutputBuffer::new();
+    first.set_stdout(output.clone());
+    first
+        .eval("let prefix = \"bye \"; let seen = true; puts(greet(\"a\"));")
+        .unwrap();
+    assert_eq!(output.contents(), "bye a\n");
+    pool.checkin(first);
+
+    // the next request sees the globals of the prelude only
+    let mut second = pool.checkout().unwrap();
+    assert_eq!(
+        second.eval("greet(\"b\")").unwrap(),
+        ObjectWrapper::String("hi b".into())
+    );
+    assert_eq!(
+        second.eval("seen").unwrap_err().message(),
+        "identifier not found: seen"
+    );
+    second.eval("puts(1)").unwrap();
+    assert_eq!(output.contents(), "bye a\n");
+    pool.checkin(second);
+
+    // more checkouts than interpreters, the extra one is not kept
+    let workers = (0..3)
+        .map(|i| {
+            let pool = pool.clone();
+            thread::spawn(move || {
+                let mut interpreter = pool.checkout().unwrap();
+                let ret = interpreter.eval(&format!("greet(\"{}\")", i)).unwrap();
+                (interpreter, ret)
+            })
+        })
+        .map(|worker| worker.join().unwrap())
+        .collect::<Vec<_>>();
+    for (i, (interpreter, ret)) in workers.into_iter().enumerate() {
+        assert_eq!(ret, ObjectWrapper::String(format!("hi {}", i).into()));
+        pool.checkin(interpreter);
+    }
+    assert_eq!(pool.idle(), 2);
+
+    let e = Pool::new(1, "let a = 1 / 0;", Interpreter::new)
+        .err()
+        .unwrap();
+    assert_eq!(e.message(), "division by zero");
+}
+
+#[test]
+fn test_pool_keeps_setup() {
+    let output = OutputBuffer::new();
+    let sink = output.clone();
+    let pool = Pool::new(1, "", move || {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_stdout(sink.clone());
+        interpreter.env_vars().set("MONKEY_POOL_MODE", "pooled");
+        interpreter
+    })
+    .unwrap();
+
+    let mut first = pool.checkout().unwrap();
+    first.set_stdout(OutputBuffer::new());
+    first
+        .eval("set_env(\"MONKEY_POOL_MODE\", \"changed\"); puts(1)")
+        .unwrap();
+    pool.checkin(first);
+    assert_eq!(output.contents(), "");
+
+    // what `make` set is back, what the request set is not
+    let mut second = pool.checkout().unwrap();
+    second.eval("puts(env(\"MONKEY_POOL_MODE\"))").unwrap();
+    assert_eq!(output.contents(), "pooled\n");
+    pool.checkin(second);
+}
+
+#[test]
+fn test_pool_resets_settings() {
+    let pool = Pool::new(1, "", || {
+        Interpreter::with_options(EvalOptions {
+            max_steps: Some(1000),
+            capabilities: Capabilities::NONE,
+            type_warnings: true,
+            ..Default::default()
+        })
+    })
+    .unwrap();
+
+    let mut first = pool.checkout().unwrap();
+    // a request relaxing the limits and capabilities
+    first.options = EvalOptions {
+        type_warnings: true,
+        ..Default::default()
+    };
+    first.enable_constant_folding();
+    first.enable_strict_mode();
+    first
+        .eval("let f = fn() { exit(3) }; 1 == \"a\"; f()")
+        .unwrap_err();
+    assert_eq!(first.exit_code(), Some(3));
+    assert!(!first.warnings().is_empty());
+    assert!(!first.error_trace().is_empty());
+    pool.checkin(first);
+
+    let second = pool.checkout().unwrap();
+    assert_eq!(second.options.max_steps, Some(1000));
+    assert_eq!(second.options.capabilities, Capabilities::NONE);
+    assert!(second.options.type_warnings);
+    assert!(!second.fold_constants);
+    assert!(!second.strict);
+    assert_eq!(second.exit_code(), None);
+    assert!(second.warnings().is_empty());
+    assert!(second.error_trace().is_empty());
+    assert_eq!(second.error_span(), None);
+    pool.checkin(second);
+}
+
+#[test]
+fn test_host_iterators() {
+    use crate::eval::iterator::HostIterator;
+    use crate::parser::Result;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // rows of a cursor, counting how many were read
+    struct Rows(Arc<AtomicUsize>);
+    impl HostIterator for Rows {
+        fn next(&mut self) -> Result<Option<ObjectWrapper>> {
+            match self.0.fetch_add(1, Ordering::SeqCst) {
+                n if n < 5 => Ok(Some(format!("row {}", n).into())),
+                5 => Err("connection lost".into()),
+                _ => Ok(None),
+            }
+        }
+    }
+
+    let read = Arc::new(AtomicUsize::new(0));
+    let mut interpreter = Interpreter::new();
+    interpreter.set_global("rows", Interpreter::iterator(Rows(read.clone())));
+    interpreter.set_global("numbers", Interpreter::iterator(1i64..));
+    let cases = [
+        ("next(rows)", "\"row 0\""),
+        (
+            "let upper = map(rows, fn(row) { row + \"!\" }); take(upper, 2)",
+            "[\"row 1!\", \"row 2!\"]",
+        ),
+        // shared with `upper`
+        ("next(rows)", "\"row 3\""),
+        ("take(map(numbers, fn(n) { n * n }), 4)", "[1, 4, 9, 16]"),
+        ("next(numbers)", "5"),
+        ("take(numbers, 0)", "[]"),
+        ("numbers == numbers", "true"),
+        ("[numbers]", "[<iterator>]"),
+        ("take(rows, 10)", "error: connection lost"),
+        (
+            "next(1)",
+            "error: Argument to `next` must be ITERATOR, got int",
+        ),
+        (
+            "take(numbers, -1)",
+            "error: Argument to `take` must be a non-negative INTEGER, got -1",
+        ),
+        (
+            "take(map(numbers, fn(n) { n / 0 }), 1)",
+            "error: division by zero",
+        ),
+    ];
+    for (input, expect) in cases {
+        let shown = match interpreter.eval(input) {
+            Ok(obj) => ObjectPrinter::new().print(&obj),
+            Err(e) => format!("error: {}", e),
+        };
+        assert_eq!(shown, expect, "{}", input);
+    }
+    // nothing was read ahead
+    assert_eq!(read.load(Ordering::SeqCst), 6);
+}
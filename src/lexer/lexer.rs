@@ -1,44 +1,132 @@
-use crate::lexer::token::Token;
+use crate::lexer::span::{Position, Span};
+use crate::lexer::token::{SourceToken, Token};
+use alloc::string::{String, ToString};
+use alloc::{vec, vec::Vec};
+use serde::Serialize;
 
-pub struct Lexer {
-    input: String,
+// A `//` comment, running to the end of its line. The parser skips them,
+// they are kept for the formatter and the documentation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Comment {
+    // the comment as written, slashes included
+    pub text: String,
+    pub span: Span,
+    // the end of the code before it on its line, None when it is alone on
+    // its line
+    pub after: Option<Position>,
+}
+
+impl Comment {
+    // the text of a `///` doc comment without the slashes and the space
+    // after them
+    pub fn doc(&self) -> Option<&str> {
+        let text = self.text.strip_prefix("///")?;
+        if text.starts_with('/') {
+            return None;
+        }
+        Some(text.strip_prefix(' ').unwrap_or(text))
+    }
+}
+
+// Reads tokens out of a source it borrows, the parser's or, with
+// `next_source_token`, ones still borrowing their text.
+pub struct Lexer<'src> {
+    input: &'src str,
     position: usize,
     read_position: usize,
     ch: u8,
+    // line and column of `ch`
+    line: usize,
+    column: usize,
+    comments: Vec<Comment>,
+    // where the last token ended
+    token_end: Option<Position>,
 }
 
-impl Lexer {
-    pub fn new<T: Into<String>>(input: T) -> Lexer {
+impl<'src> Lexer<'src> {
+    pub fn new(input: &'src str) -> Lexer<'src> {
         let mut ret = Lexer {
-            input: input.into(),
+            input,
             position: 0,
             read_position: 0,
             ch: 0,
+            line: 1,
+            column: 0,
+            comments: vec![],
+            token_end: None,
         };
         ret.read_char();
+        // `#!/usr/bin/env monkey_rust`, so scripts can be run directly
+        if ret.input.starts_with("#!") {
+            while ret.ch != b'\n' && ret.ch != 0 {
+                ret.read_char();
+            }
+        }
         ret
     }
 
     pub fn read_char(&mut self) {
+        if self.ch == 0 && self.position >= self.input.len() && self.column > 0 {
+            // already at the end of input
+            return;
+        }
+        if self.ch == b'\n' {
+            self.line += 1;
+            self.column = 0;
+        }
         if self.read_position >= self.input.len() {
             self.ch = 0;
         } else {
             self.ch = self.input.as_bytes()[self.read_position];
         }
-        self.position = self.read_position;
+        self.position = self.read_position.min(self.input.len());
         self.read_position += 1;
+        // utf-8 continuation bytes belong to the previous character
+        if self.ch & 0b1100_0000 != 0b1000_0000 {
+            self.column += 1;
+        }
     }
 
-    pub fn read_identifier(&mut self) -> String {
+    pub fn current_position(&self) -> Position {
+        Position {
+            offset: self.position,
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    pub fn read_identifier(&mut self) -> &'src str {
         let pos = self.position;
-        while (self.ch as char).is_ascii_alphabetic() {
+        while is_ident_char(self.ch) {
             self.read_char();
         }
-        String::from_utf8(self.input.as_bytes()[pos..self.position].to_vec()).unwrap()
+        &self.input[pos..self.position]
     }
 
     pub fn next_token(&mut self) -> Token {
+        self.next_token_with_span().0
+    }
+
+    pub fn next_token_with_span(&mut self) -> (Token, Span) {
+        let (token, span) = self.next_source_token();
+        (token.into(), span)
+    }
+
+    pub fn next_source_token(&mut self) -> (SourceToken<'src>, Span) {
         self.skip_whitespace();
+        let start = self.current_position();
+        let token = self.read_token();
+        let end = self.current_position();
+        self.token_end = Some(end);
+        (token, Span::new(start, end))
+    }
+
+    // the comments skipped so far, in the order they appear
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
+    fn read_token(&mut self) -> SourceToken<'src> {
         let ret = match self.ch {
             b'=' => {
                 if self.peek_char() == b'=' {
@@ -79,9 +167,10 @@ impl Lexer {
                 let ch = self.ch as char;
 
                 // read_identifier和read_number_token中都进行了read_char，所以直接returns
-                if ch.is_ascii_alphabetic() {
-                    return Token::from_str(self.read_identifier());
-                } else if ch.is_numeric() {
+                if ch.is_ascii_alphabetic() || ch == '_' {
+                    let ident = self.read_identifier();
+                    return Token::keyword(ident).unwrap_or(Token::Ident(ident));
+                } else if ch.is_ascii_digit() {
                     return self.read_number_token();
                 } else {
                     Token::Illegal
@@ -89,22 +178,41 @@ impl Lexer {
             }
         };
         self.read_char();
-        return ret;
+        ret
     }
 
     fn skip_whitespace(&mut self) {
-        while (self.ch as char).is_whitespace() {
+        loop {
+            while (self.ch as char).is_whitespace() {
+                self.read_char();
+            }
+            if self.ch != b'/' || self.peek_char() != b'/' {
+                return;
+            }
+            self.read_comment();
+        }
+    }
+
+    fn read_comment(&mut self) {
+        let start = self.current_position();
+        while self.ch != b'\n' && self.ch != 0 {
             self.read_char();
         }
+        let text = self.input[start.offset..self.position].trim_end();
+        self.comments.push(Comment {
+            text: text.to_string(),
+            span: Span::new(start, self.current_position()),
+            after: self.token_end.filter(|end| end.line == start.line),
+        });
     }
 
-    fn read_number_token(&mut self) -> Token {
+    fn read_number_token(&mut self) -> SourceToken<'src> {
         let pos = self.position;
         let mut is_float = false;
         loop {
             let ch = self.ch as char;
 
-            if ch.is_numeric() || (!is_float && ch == '.') {
+            if ch.is_ascii_digit() || (!is_float && ch == '.') {
                 self.read_char();
                 if ch == '.' {
                     is_float = true;
@@ -114,23 +222,26 @@ impl Lexer {
             }
         }
 
-        let sub_str =
-            String::from_utf8(self.input.as_bytes()[pos..self.position].to_vec()).unwrap();
+        let sub_str = &self.input[pos..self.position];
         if is_float {
             Token::from_float(sub_str.parse::<f64>().unwrap())
         } else {
-            Token::from_int(sub_str.parse::<i64>().unwrap())
+            // too large for an i64
+            sub_str
+                .parse::<i64>()
+                .map_or(Token::Illegal, Token::from_int)
         }
     }
 
     fn peek_char(&self) -> u8 {
-        if self.read_position >= self.input.as_bytes().len() {
+        if self.read_position >= self.input.len() {
             return 0;
         }
-        return self.input.as_bytes()[self.read_position];
+        self.input.as_bytes()[self.read_position]
     }
 
-    fn read_string(&mut self) -> Token {
+    // an unterminated string is illegal
+    fn read_string(&mut self) -> SourceToken<'src> {
         let pos = self.position + 1;
         loop {
             self.read_char();
@@ -138,10 +249,36 @@ impl Lexer {
                 self.read_char();
             } else if self.ch == b'"' {
                 break;
+            } else if self.ch == 0 {
+                return Token::Illegal;
             }
         }
-        return Token::String(
-            String::from_utf8(self.input.as_bytes()[pos..self.position].to_vec()).unwrap(),
-        );
+        Token::String(&self.input[pos..self.position])
     }
 }
+
+// every token of `input` with its span, up to and including the EOF
+pub fn tokenize(input: &str) -> Vec<(Token, Span)> {
+    tokenize_source(input)
+        .into_iter()
+        .map(|(token, span)| (token.into(), span))
+        .collect()
+}
+
+// like `tokenize`, names and strings borrowed from `input`
+pub fn tokenize_source(input: &str) -> Vec<(SourceToken<'_>, Span)> {
+    let mut l = Lexer::new(input);
+    let mut ret = vec![];
+    loop {
+        let (token, span) = l.next_source_token();
+        let eof = token == Token::EOF;
+        ret.push((token, span));
+        if eof {
+            return ret;
+        }
+    }
+}
+
+fn is_ident_char(ch: u8) -> bool {
+    ch.is_ascii_alphanumeric() || ch == b'_'
+}
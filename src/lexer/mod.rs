@@ -1,5 +1,7 @@
-pub mod token;
 pub mod lexer;
+pub mod span;
+pub mod symbol;
+pub mod token;
 
 #[cfg(test)]
-mod test;
\ No newline at end of file
+mod test;
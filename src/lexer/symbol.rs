@@ -0,0 +1,141 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
+use core::fmt::{Debug, Display, Formatter};
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+use serde::{Serialize, Serializer};
+
+// An identifier interned in a table shared by the whole process. The lexer
+// interns the names it reads, the parser and the evaluator then copy,
+// compare and hash them as pointers. A name is freed once no symbol of it
+// is left, the table only keeps a weak reference.
+#[derive(Clone)]
+pub struct Symbol(Arc<str>);
+
+struct Interner {
+    symbols: BTreeMap<Box<str>, Weak<str>>,
+    // the size of the table the names no longer used are removed at
+    sweep_at: usize,
+}
+
+// the smallest table names are removed from
+const SWEEP_AT: usize = 1024;
+
+impl Interner {
+    const fn new() -> Self {
+        Interner {
+            symbols: BTreeMap::new(),
+            sweep_at: SWEEP_AT,
+        }
+    }
+
+    fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(symbol) = self.lookup(name) {
+            return symbol;
+        }
+        if self.symbols.len() >= self.sweep_at {
+            self.symbols.retain(|_, symbol| symbol.strong_count() > 0);
+            self.sweep_at = (self.symbols.len() * 2).max(SWEEP_AT);
+        }
+        let symbol: Arc<str> = Arc::from(name);
+        self.symbols
+            .insert(Box::from(name), Arc::downgrade(&symbol));
+        Symbol(symbol)
+    }
+
+    fn lookup(&self, name: &str) -> Option<Symbol> {
+        self.symbols.get(name).and_then(Weak::upgrade).map(Symbol)
+    }
+}
+
+// the symbols are only looked at when interning, not when they are read
+#[cfg(feature = "std")]
+static INTERNER: std::sync::Mutex<Interner> = std::sync::Mutex::new(Interner::new());
+#[cfg(not(feature = "std"))]
+static INTERNER: spin::Mutex<Interner> = spin::Mutex::new(Interner::new());
+
+#[cfg(feature = "std")]
+fn interner() -> std::sync::MutexGuard<'static, Interner> {
+    // the table is left consistent by every operation on it
+    INTERNER.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(not(feature = "std"))]
+fn interner() -> spin::MutexGuard<'static, Interner> {
+    INTERNER.lock()
+}
+
+impl Symbol {
+    pub fn intern(name: &str) -> Symbol {
+        interner().intern(name)
+    }
+
+    // the symbol of `name` if one is in use, nothing can be bound to a name
+    // no symbol is left of
+    pub fn lookup(name: &str) -> Option<Symbol> {
+        interner().lookup(name)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Symbol) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).cast::<u8>().hash(state)
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Symbol::intern(name)
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl Debug for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
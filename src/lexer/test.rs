@@ -1,5 +1,9 @@
-use crate::lexer::lexer::Lexer;
+use crate::lexer::lexer::{tokenize, tokenize_source, Lexer};
+use crate::lexer::symbol::Symbol;
 use crate::lexer::token::Token;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use alloc::{format, vec};
 
 #[test]
 fn sign_test() {
@@ -139,11 +143,190 @@ fn simple_token_test() {
 fn test_string_token() {
     let cases = [
         ("\"hello\"", Token::String("hello".to_string())),
-        ("\"hello \\\"world\\\"\"", Token::String("hello \\\"world\\\"".to_string())),
+        (
+            "\"hello \\\"world\\\"\"",
+            Token::String("hello \\\"world\\\"".to_string()),
+        ),
     ];
 
     for (input, expect) in cases {
         let mut lx = Lexer::new(input);
         assert_eq!(lx.next_token(), expect)
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_ident_token() {
+    let mut lx = Lexer::new("is_error _tmp x1");
+    let expects = [
+        Token::from_str("is_error"),
+        Token::from_str("_tmp"),
+        Token::from_str("x1"),
+        Token::EOF,
+    ];
+
+    for tk in expects {
+        assert_eq!(tk, lx.next_token());
+    }
+}
+
+#[test]
+fn test_token_span() {
+    let mut lx = Lexer::new("let a\n  = \"é\";");
+    let expects = [
+        (Token::Let, (1, 1), (1, 4)),
+        (Token::from_str("a"), (1, 5), (1, 6)),
+        (Token::Assign, (2, 3), (2, 4)),
+        (Token::String("é".to_string()), (2, 5), (2, 8)),
+        (Token::Semicolon, (2, 8), (2, 9)),
+        (Token::EOF, (2, 9), (2, 9)),
+        (Token::EOF, (2, 9), (2, 9)),
+    ];
+
+    for (tk, start, end) in expects {
+        let (token, span) = lx.next_token_with_span();
+        assert_eq!(token, tk);
+        assert_eq!((span.start.line, span.start.column), start);
+        assert_eq!((span.end.line, span.end.column), end);
+    }
+}
+
+#[test]
+fn test_unterminated_string() {
+    let mut lx = Lexer::new("let s = \"abc");
+    let expects = [
+        Token::Let,
+        Token::from_str("s"),
+        Token::Assign,
+        Token::Illegal,
+        Token::EOF,
+    ];
+
+    for tk in expects {
+        assert_eq!(tk, lx.next_token());
+    }
+}
+
+#[test]
+fn test_tokenize() {
+    let tokens = tokenize("f(1)\n")
+        .into_iter()
+        .map(|(token, span)| (token, span.start.offset, span.end.offset))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tokens,
+        vec![
+            (Token::from_str("f"), 0, 1),
+            (Token::LParen, 1, 2),
+            (Token::Int(1), 2, 3),
+            (Token::RParen, 3, 4),
+            (Token::EOF, 5, 5),
+        ]
+    );
+}
+
+#[test]
+fn test_shebang() {
+    let tokens = tokenize("#!/usr/bin/env monkey_rust\nputs(1)")
+        .into_iter()
+        .map(|(token, span)| (token, span.start.line, span.start.column))
+        .take(2)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tokens,
+        vec![(Token::from_str("puts"), 2, 1), (Token::LParen, 2, 5)]
+    );
+    // only on the first line
+    let mut lx = Lexer::new("1\n#!");
+    assert_eq!(lx.next_token(), Token::Int(1));
+    assert_eq!(lx.next_token(), Token::Illegal);
+}
+
+#[test]
+fn test_comments() {
+    let input = "// header\nlet a = 1 / 2; // half\n/// doc\n////banner\n";
+    let mut lx = Lexer::new(input);
+    let expects = [
+        Token::Let,
+        Token::from_str("a"),
+        Token::Assign,
+        Token::Int(1),
+        Token::Slash,
+        Token::Int(2),
+        Token::Semicolon,
+        Token::EOF,
+    ];
+    for tk in expects {
+        assert_eq!(lx.next_token(), tk);
+    }
+    let comments = lx
+        .comments()
+        .iter()
+        .map(|c| {
+            (
+                c.text.as_str(),
+                c.span.start.line,
+                c.after.map(|p| p.column),
+                c.doc(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        comments,
+        vec![
+            ("// header", 1, None, None),
+            ("// half", 2, Some(15), None),
+            ("/// doc", 3, None, Some("doc")),
+            ("////banner", 4, None, None),
+        ]
+    );
+}
+
+#[test]
+fn test_number_limits() {
+    let mut lx = Lexer::new("9223372036854775807 9223372036854775808 \u{b2}");
+    assert_eq!(lx.next_token(), Token::Int(i64::MAX));
+    assert_eq!(lx.next_token(), Token::Illegal);
+    // the second byte of `²` is not a digit
+    assert_eq!(lx.next_token(), Token::Illegal);
+    assert_eq!(lx.next_token(), Token::Illegal);
+    assert_eq!(lx.next_token(), Token::EOF);
+}
+
+#[test]
+fn test_symbols() {
+    let mut lx = Lexer::new("let interned_name = interned_name;");
+    assert_eq!(lx.next_token(), Token::Let);
+    let first = lx.next_token();
+    lx.next_token();
+    assert_eq!(first, lx.next_token());
+    assert_eq!(first, Token::Ident(Symbol::intern("interned_name")));
+    assert_eq!(Symbol::intern("interned_name").as_str(), "interned_name");
+    assert_eq!(format!("{:?}", Symbol::intern("x")), "\"x\"");
+    assert_eq!(Symbol::lookup("never_interned_name"), None);
+    // freed once no symbol of it is left
+    let freed = Symbol::intern("freed_name");
+    assert_eq!(Symbol::lookup("freed_name"), Some(freed.clone()));
+    drop(freed);
+    assert_eq!(Symbol::lookup("freed_name"), None);
+}
+
+#[test]
+fn test_source_tokens() {
+    let source = r#"let never_interned_source = "a \"b\""; fn(x) { x * 2.5 }"#;
+    let tokens = tokenize_source(source);
+    let (name, span) = &tokens[1];
+    assert_eq!(*name, Token::Ident("never_interned_source"));
+    assert_eq!(
+        &source[span.start.offset..span.end.offset],
+        "never_interned_source"
+    );
+    assert_eq!(tokens[3].0, Token::String(r#"a \"b\""#));
+    // borrowed from the source, nothing was interned
+    if let (Token::String(text), _) = tokens[3] {
+        assert!(source.as_bytes().as_ptr_range().contains(&text.as_ptr()));
+    }
+    assert_eq!(Symbol::lookup("never_interned_source"), None);
+    let owned = tokens.into_iter().map(|(token, span)| (token.into(), span));
+    assert!(owned.eq(tokenize(source)));
+}
@@ -1,5 +1,12 @@
-#[derive(Debug, PartialEq, Clone)]
-pub enum Token {
+use crate::lexer::symbol::Symbol;
+use alloc::string::String;
+use core::fmt::{Display, Formatter};
+use serde::Serialize;
+
+// A token of the tree, names interned and string contents copied. Tools that
+// only read tokens use `SourceToken` instead.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub enum Token<Name = Symbol, Text = String> {
     Illegal,
     EOF,
 
@@ -32,42 +39,101 @@ pub enum Token {
     LBrace,   // {
     RBrace,   // }
 
-    Ident(String),
+    Ident(Name),
     Bool(bool), // true/false
     Int(i64),
     Float(f64),
-    String(String),
+    String(Text),
 }
 
-impl Eq for Token {}
+impl<Name: Eq, Text: Eq> Eq for Token<Name, Text> {}
 
-pub const EOF_TOKEN: Token = Token::EOF;
+// A token whose name or string contents are borrowed from the source, for
+// highlighting, linting or formatting large codebases without interning or
+// copying their text. `Lexer::next_source_token` reads them.
+pub type SourceToken<'src> = Token<&'src str, &'src str>;
 
-impl Token {
-    pub fn from_str<T: Into<String>>(input: T) -> Token {
-        let input = input.into();
-        match input.as_str() {
-            "fn" => Token::Function,
-            "let" => Token::Let,
-            "true" => Token::Bool(true),
-            "false" => Token::Bool(false),
-            "if" => Token::If,
-            "else" => Token::Else,
-            "return" => Token::Return,
-            _ => Token::Ident(input),
-        }
-    }
+pub const EOF_TOKEN: Token = Token::EOF;
 
-    pub fn from_int(input: i64) -> Token {
+impl<Name, Text> Token<Name, Text> {
+    pub fn from_int(input: i64) -> Self {
         Token::Int(input)
     }
 
-    pub fn from_float(input: f64) -> Token {
+    pub fn from_float(input: f64) -> Self {
         Token::Float(input)
     }
 
     pub fn is_eof(&self) -> bool {
-        *self == Token::EOF
+        matches!(self, Token::EOF)
+    }
+
+    // the keyword `input` is, `true` and `false` included
+    pub fn keyword(input: &str) -> Option<Self> {
+        match input {
+            "fn" => Some(Token::Function),
+            "let" => Some(Token::Let),
+            "true" => Some(Token::Bool(true)),
+            "false" => Some(Token::Bool(false)),
+            "if" => Some(Token::If),
+            "else" => Some(Token::Else),
+            "return" => Some(Token::Return),
+            _ => None,
+        }
+    }
+
+    // the same token with its name or text converted
+    pub fn map<N, T>(
+        self,
+        name: impl FnOnce(Name) -> N,
+        text: impl FnOnce(Text) -> T,
+    ) -> Token<N, T> {
+        match self {
+            Token::Illegal => Token::Illegal,
+            Token::EOF => Token::EOF,
+            Token::Let => Token::Let,
+            Token::Function => Token::Function,
+            Token::If => Token::If,
+            Token::Else => Token::Else,
+            Token::Return => Token::Return,
+            Token::Assign => Token::Assign,
+            Token::Plus => Token::Plus,
+            Token::Minus => Token::Minus,
+            Token::Bang => Token::Bang,
+            Token::Asterisk => Token::Asterisk,
+            Token::Slash => Token::Slash,
+            Token::LT => Token::LT,
+            Token::GT => Token::GT,
+            Token::Comma => Token::Comma,
+            Token::Semicolon => Token::Semicolon,
+            Token::Colon => Token::Colon,
+            Token::Eq => Token::Eq,
+            Token::NotEq => Token::NotEq,
+            Token::LParen => Token::LParen,
+            Token::RParen => Token::RParen,
+            Token::LBracket => Token::LBracket,
+            Token::RBracket => Token::RBracket,
+            Token::LBrace => Token::LBrace,
+            Token::RBrace => Token::RBrace,
+            Token::Ident(v) => Token::Ident(name(v)),
+            Token::Bool(v) => Token::Bool(v),
+            Token::Int(v) => Token::Int(v),
+            Token::Float(v) => Token::Float(v),
+            Token::String(v) => Token::String(text(v)),
+        }
+    }
+}
+
+impl From<SourceToken<'_>> for Token {
+    fn from(token: SourceToken<'_>) -> Self {
+        token.map(Symbol::intern, String::from)
+    }
+}
+
+impl Token {
+    pub fn from_str<T: AsRef<str>>(input: T) -> Token {
+        let input = input.as_ref();
+        Token::keyword(input).unwrap_or_else(|| Token::Ident(Symbol::intern(input)))
     }
 
     // pub fn is_operator(&self) -> bool {
@@ -76,22 +142,25 @@ impl Token {
     //         _ => false,
     //     }
     // }
+}
 
-    pub fn to_string(&self) -> String {
-        match self {
-            Token::Plus => "+".to_string(),
-            Token::Minus => "-".to_string(),
-            Token::Bang => "!".to_string(),
-            Token::Asterisk => "*".to_string(),
-            Token::Slash => "/".to_string(),
-            Token::LT => "<".to_string(),
-            Token::GT => ">".to_string(),
-            Token::Comma => ",".to_string(),
-            Token::Colon => ":".to_string(),
-            Token::Semicolon => ";".to_string(),
-            Token::Eq => "==".to_string(),
-            Token::NotEq => "!=".to_string(),
-            _ => "".to_string(),
-        }
+impl<Name, Text> Display for Token<Name, Text> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let s = match self {
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Bang => "!",
+            Token::Asterisk => "*",
+            Token::Slash => "/",
+            Token::LT => "<",
+            Token::GT => ">",
+            Token::Comma => ",",
+            Token::Colon => ":",
+            Token::Semicolon => ";",
+            Token::Eq => "==",
+            Token::NotEq => "!=",
+            _ => "",
+        };
+        f.write_str(s)
     }
 }
@@ -0,0 +1,66 @@
+#![allow(
+    clippy::enum_variant_names,
+    clippy::upper_case_acronyms,
+    clippy::module_inception,
+    clippy::should_implement_trait
+)]
+// the lexer and the parser build without std, see the `std` feature
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+// the test harness links std anyway, tests print with it
+#[cfg(all(test, not(feature = "std")))]
+#[macro_use]
+extern crate std;
+// the code `#[monkey::builtin]` generates refers to `::monkey`
+#[cfg(feature = "std")]
+extern crate self as monkey;
+
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "std")]
+pub mod compiler;
+#[cfg(feature = "std")]
+pub mod diagnostics;
+#[cfg(feature = "std")]
+pub mod doc;
+#[cfg(feature = "std")]
+pub mod eval;
+#[cfg(feature = "std")]
+pub mod fmt;
+#[cfg(feature = "std")]
+pub mod graph;
+#[cfg(feature = "std")]
+pub mod highlight;
+#[cfg(feature = "std")]
+pub mod interpreter;
+pub mod lexer;
+#[cfg(feature = "std")]
+pub mod lint;
+pub mod parser;
+#[cfg(feature = "plugins")]
+pub mod plugin;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "std")]
+pub mod refactor;
+#[cfg(feature = "std")]
+pub mod symbols;
+#[cfg(feature = "std")]
+pub mod template;
+#[cfg(feature = "std")]
+pub mod testing;
+#[cfg(feature = "std")]
+pub mod typecheck;
+#[cfg(feature = "std")]
+pub mod vm;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+// what scripts evaluate to, for applications embedding an `Interpreter`
+#[cfg(feature = "std")]
+pub use eval::ObjectWrapper as Value;
+#[cfg(feature = "std")]
+pub use interpreter::Interpreter;
+#[cfg(feature = "std")]
+pub use monkey_macros::builtin;
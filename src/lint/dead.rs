@@ -0,0 +1,51 @@
+use crate::lexer::span::Span;
+use crate::parser::program::{Ident, Program, Statement};
+use crate::parser::visit::{self, Visitor};
+use crate::symbols::{SymbolIndex, SymbolKind};
+
+// What a program computes for nothing, see `DeadCode::new`. Names starting
+// with `_` are never unused.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeadCode {
+    // `let` bindings never read, a function calling itself reads itself
+    pub unused_bindings: Vec<Ident>,
+    pub unused_parameters: Vec<Ident>,
+    // the statements after a `return`, from the first to the last of their
+    // block
+    pub unreachable: Vec<Span>,
+}
+
+impl DeadCode {
+    pub fn new(program: &Program) -> Self {
+        let mut ret = DeadCode::default();
+        let unused = SymbolIndex::new(program);
+        let unused = unused
+            .symbols()
+            .iter()
+            .filter(|symbol| symbol.references.is_empty() && !symbol.name.starts_with('_'));
+        for symbol in unused {
+            let ident = Ident::new(symbol.name.as_str().into(), symbol.span);
+            match symbol.kind {
+                SymbolKind::Parameter => ret.unused_parameters.push(ident),
+                SymbolKind::Function | SymbolKind::Variable => ret.unused_bindings.push(ident),
+            }
+        }
+        visit::walk_program(&mut Unreachable(&mut ret.unreachable), program);
+        ret
+    }
+}
+
+struct Unreachable<'a>(&'a mut Vec<Span>);
+
+impl Visitor for Unreachable<'_> {
+    fn visit_block(&mut self, statements: &[Statement]) {
+        let rest = statements
+            .iter()
+            .position(|statement| matches!(statement, Statement::ReturnStatement(..)))
+            .map_or(&[][..], |i| &statements[i + 1..]);
+        if let (Some(first), Some(last)) = (rest.first(), rest.last()) {
+            self.0.push(first.span().to(last.span()));
+        }
+        visit::walk_block(self, statements);
+    }
+}
@@ -0,0 +1,282 @@
+use crate::eval::builtins::Builtins;
+use crate::lexer::span::Span;
+use crate::lexer::token::Token;
+use crate::lint::dead::DeadCode;
+use crate::parser::program::{Expression, Ident, Program, Statement};
+use crate::parser::visit::{self, Visitor};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+pub mod dead;
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Warning => f.write_str("warning"),
+            Severity::Error => f.write_str("error"),
+        }
+    }
+}
+
+// something a rule found, `rule` is its id
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub span: Span,
+    pub message: String,
+}
+
+pub struct Rule {
+    pub id: &'static str,
+    pub description: &'static str,
+    check: fn(&Program, &mut Vec<(Span, String)>),
+}
+
+pub const RULES: &[Rule] = &[
+    Rule {
+        id: "unused-binding",
+        description: "a `let` binding that is never read, names starting with `_` are exempt",
+        check: check_unused,
+    },
+    Rule {
+        id: "unused-parameter",
+        description: "a function parameter that is never read, names starting with `_` are exempt",
+        check: check_unused_parameters,
+    },
+    Rule {
+        id: "shadowing",
+        description: "a binding hiding a builtin or another binding of the same name",
+        check: check_shadowing,
+    },
+    Rule {
+        id: "unreachable-code",
+        description: "statements after a `return`",
+        check: check_unreachable,
+    },
+    Rule {
+        id: "constant-condition",
+        description: "an `if` on a literal",
+        check: check_constant_condition,
+    },
+    Rule {
+        id: "mismatched-comparison",
+        description: "`==` or `!=` on literals of different types, an error at runtime",
+        check: check_mismatched_comparison,
+    },
+];
+
+// Which rules run and how severe what they find is, every rule reports
+// warnings by default.
+#[derive(Debug, Clone, Default)]
+pub struct LintOptions {
+    levels: HashMap<&'static str, Option<Severity>>,
+}
+
+impl LintOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // None turns the rule off, fails for an unknown rule
+    pub fn set_level(&mut self, id: &str, level: Option<Severity>) -> Result<(), String> {
+        let rule = RULES
+            .iter()
+            .find(|rule| rule.id == id)
+            .ok_or_else(|| format!("unknown lint rule {}", id))?;
+        self.levels.insert(rule.id, level);
+        Ok(())
+    }
+
+    pub fn level(&self, id: &str) -> Option<Severity> {
+        self.levels
+            .get(id)
+            .copied()
+            .unwrap_or(Some(Severity::Warning))
+    }
+}
+
+// what the enabled rules find in `program`, in source order
+pub fn lint(program: &Program, options: &LintOptions) -> Vec<Lint> {
+    let mut ret = vec![];
+    for rule in RULES {
+        let severity = match options.level(rule.id) {
+            Some(severity) => severity,
+            None => continue,
+        };
+        let mut found = vec![];
+        (rule.check)(program, &mut found);
+        ret.extend(found.into_iter().map(|(span, message)| Lint {
+            rule: rule.id,
+            severity,
+            span,
+            message,
+        }));
+    }
+    ret.sort_by_key(|lint| lint.span.start);
+    ret
+}
+
+fn check_unused(program: &Program, found: &mut Vec<(Span, String)>) {
+    for Ident(name, span, _) in DeadCode::new(program).unused_bindings {
+        found.push((span, format!("`{}` is never used", name)));
+    }
+}
+
+fn check_unused_parameters(program: &Program, found: &mut Vec<(Span, String)>) {
+    for Ident(name, span, _) in DeadCode::new(program).unused_parameters {
+        found.push((span, format!("parameter `{}` is never used", name)));
+    }
+}
+
+fn check_shadowing(program: &Program, found: &mut Vec<(Span, String)>) {
+    found.append(&mut Bindings::check(program).shadowed);
+}
+
+fn check_unreachable(program: &Program, found: &mut Vec<(Span, String)>) {
+    for span in DeadCode::new(program).unreachable {
+        found.push((span, "unreachable code after `return`".to_string()));
+    }
+}
+
+fn check_constant_condition(program: &Program, found: &mut Vec<(Span, String)>) {
+    each_expression(program, |expression| {
+        if let Expression::IfExpression(condition, _, _, span) = expression {
+            let message = match condition.as_ref() {
+                Expression::BoolLiteral(v) => format!("the condition is always {}", v),
+                condition => match literal_type(condition) {
+                    Some(kind) => format!("the condition is a constant {}", kind),
+                    None => return,
+                },
+            };
+            found.push((*span, message));
+        }
+    });
+}
+
+fn check_mismatched_comparison(program: &Program, found: &mut Vec<(Span, String)>) {
+    each_expression(program, |expression| {
+        if let Expression::InfixExpression(left, Token::Eq | Token::NotEq, right, span) = expression
+        {
+            match (literal_type(left), literal_type(right)) {
+                (Some(left), Some(right)) if left != right => found.push((
+                    *span,
+                    format!("comparing {} with {} fails at runtime", left, right),
+                )),
+                _ => {}
+            }
+        }
+    });
+}
+
+// the type of the value if `expression` is written out in the source
+fn literal_type(expression: &Expression) -> Option<&'static str> {
+    match expression {
+        Expression::IntLiteral(_) => Some("int"),
+        Expression::FloatLiteral(_) => Some("float"),
+        Expression::BoolLiteral(_) => Some("bool"),
+        Expression::StringLiteral(_) => Some("string"),
+        Expression::ArrayLiteral(_) => Some("array"),
+        Expression::HashLiteral(_) => Some("hash"),
+        Expression::FunctionExpression(..) => Some("function"),
+        _ => None,
+    }
+}
+
+fn each_expression<F: FnMut(&Expression)>(program: &Program, f: F) {
+    struct EachExpression<F>(F);
+
+    impl<F: FnMut(&Expression)> Visitor for EachExpression<F> {
+        fn visit_expression(&mut self, expression: &Expression) {
+            (self.0)(expression);
+            visit::walk_expression(self, expression);
+        }
+    }
+
+    visit::walk_program(&mut EachExpression(f), program);
+}
+
+struct Binding {
+    name: String,
+    span: Span,
+}
+
+// Tracks the names in scope. The program and function bodies have a scope
+// each, blocks of `if` do not.
+#[derive(Default)]
+struct Bindings {
+    scopes: Vec<Vec<Binding>>,
+    shadowed: Vec<(Span, String)>,
+}
+
+impl Bindings {
+    fn check(program: &Program) -> Self {
+        let mut ret = Bindings {
+            scopes: vec![vec![]],
+            ..Default::default()
+        };
+        visit::walk_program(&mut ret, program);
+        ret
+    }
+
+    fn declare(&mut self, ident: &Ident) {
+        let Ident(name, span, _) = ident;
+        let earlier = self
+            .scopes
+            .iter()
+            .flatten()
+            .rev()
+            .find(|b| *name == *b.name);
+        if let Some(earlier) = earlier {
+            let message = format!("`{}` shadows the binding at {}", name, earlier.span.start);
+            self.shadowed.push((*span, message));
+        } else if Builtins::instance_ref().contains(name) {
+            let message = format!("`{}` shadows the builtin of the same name", name);
+            self.shadowed.push((*span, message));
+        }
+        self.scopes.last_mut().unwrap().push(Binding {
+            name: name.to_string(),
+            span: *span,
+        });
+    }
+}
+
+impl Visitor for Bindings {
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            // functions can call themselves
+            Statement::LetStatement(ident, value @ Expression::FunctionExpression(..), _) => {
+                self.declare(ident);
+                self.visit_expression(value);
+            }
+            // `let x = x + 1` reads the earlier `x`
+            Statement::LetStatement(ident, value, _) => {
+                self.visit_expression(value);
+                self.declare(ident);
+            }
+            _ => visit::walk_statement(self, statement),
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::FunctionExpression(params, body, _) => {
+                self.scopes.push(vec![]);
+                for param in params {
+                    self.declare(param);
+                }
+                self.visit_block(body);
+                self.scopes.pop();
+            }
+            _ => visit::walk_expression(self, expression),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::lexer::lexer::Lexer;
+use crate::lint::dead::DeadCode;
+use crate::lint::{lint, LintOptions, Severity};
+use crate::parser::program::Ident;
+use crate::parser::Parser;
+
+#[cfg(test)]
+fn test_lint(input: &str, options: &LintOptions) -> Vec<(&'static str, Severity, String)> {
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+    lint(&program, options)
+        .into_iter()
+        .map(|lint| {
+            let location = format!("{}-{} {}", lint.span.start, lint.span.end, lint.message);
+            (lint.rule, lint.severity, location)
+        })
+        .collect()
+}
+
+#[test]
+fn test_rules() {
+    let options = LintOptions::new();
+    let cases = [
+        ("let a = 1; puts(a);", vec![]),
+        (
+            "let a = 1; let _b = 2;",
+            vec![("unused-binding", "1:5-1:6 `a` is never used")],
+        ),
+        // recursive functions are not unused, parameters can be
+        (
+            "let f = fn(n, m, _o) { f(n) }; f(1);",
+            vec![("unused-parameter", "1:15-1:16 parameter `m` is never used")],
+        ),
+        // functions read the globals defined after them
+        ("let f = fn() { g() }; let g = fn() { 1 }; f();", vec![]),
+        (
+            "let x = 1; let x = x + 1; puts(x);",
+            vec![("shadowing", "1:16-1:17 `x` shadows the binding at 1:5")],
+        ),
+        (
+            "let len = fn(x) { x }; len(1);",
+            vec![(
+                "shadowing",
+                "1:5-1:8 `len` shadows the builtin of the same name",
+            )],
+        ),
+        (
+            "let f = fn() { return 1; puts(2); 3 }; f();",
+            vec![(
+                "unreachable-code",
+                "1:26-1:36 unreachable code after `return`",
+            )],
+        ),
+        (
+            "if (true) { 1 }; if (1) { 2 }",
+            vec![
+                (
+                    "constant-condition",
+                    "1:1-1:16 the condition is always true",
+                ),
+                (
+                    "constant-condition",
+                    "1:18-1:30 the condition is a constant int",
+                ),
+            ],
+        ),
+        (
+            "1 == \"1\"; [1] != [2]",
+            vec![(
+                "mismatched-comparison",
+                "1:1-1:9 comparing int with string fails at runtime",
+            )],
+        ),
+    ];
+    for (input, expect) in cases {
+        let expect = expect
+            .into_iter()
+            .map(|(rule, message)| (rule, Severity::Warning, message.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(test_lint(input, &options), expect, "{}", input);
+    }
+}
+
+#[test]
+fn test_lint_options() {
+    let mut options = LintOptions::new();
+    options.set_level("unused-binding", None).unwrap();
+    options
+        .set_level("constant-condition", Some(Severity::Error))
+        .unwrap();
+    assert!(options.set_level("no-such-rule", None).is_err());
+    assert_eq!(
+        test_lint("let a = 1; if (false) { 1 }", &options),
+        vec![(
+            "constant-condition",
+            Severity::Error,
+            "1:12-1:28 the condition is always false".to_string()
+        )]
+    );
+}
+
+#[test]
+fn test_dead_code() {
+    let input = "let a = 1;
+let f = fn(x, y) {
+  if (x) { return 1; 2 }
+  return 0;
+  puts(a);
+};";
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+    let dead = DeadCode::new(&program);
+    let names = |idents: &[Ident]| {
+        idents
+            .iter()
+            .map(|Ident(name, span, _)| format!("{} {}", name, span.start))
+            .collect::<Vec<String>>()
+    };
+    assert_eq!(names(&dead.unused_bindings), vec!["f 2:5"]);
+    assert_eq!(names(&dead.unused_parameters), vec!["y 2:15"]);
+    let unreachable = dead
+        .unreachable
+        .iter()
+        .map(|span| &input[span.start.offset..span.end.offset])
+        .collect::<Vec<&str>>();
+    assert_eq!(unreachable, vec!["puts(a);", "2"]);
+}
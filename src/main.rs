@@ -1,51 +1,1516 @@
-use std::io::Write;
+use std::borrow::Cow;
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
 use async_ctrlc::CtrlC;
-use async_std::prelude::*;
+use clap::error::ErrorKind;
+use clap::{ArgGroup, CommandFactory, Parser as _, Subcommand, ValueEnum};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::{CmdKind, Highlighter};
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Editor, Helper};
 
-use crate::lexer::lexer::Lexer;
-use crate::parser::Parser;
-use crate::eval::environment::Environment;
-use crate::eval::evaluator::Evaluator;
-
-mod eval;
-mod lexer;
-mod parser;
+use monkey::compiler::{Bytecode, Compiler};
+use monkey::diagnostics;
+use monkey::doc::{self, DocFormat};
+use monkey::eval::coverage::Coverage;
+use monkey::eval::debugger::Debugger;
+use monkey::eval::evaluator::EvalOptions;
+use monkey::eval::input::Input;
+use monkey::eval::output::Sink;
+use monkey::eval::printer::{self, ObjectPrinter};
+use monkey::eval::ObjectWrapper;
+use monkey::fmt::minify::{self, MinifyOptions};
+use monkey::fmt::{self, FormatOptions};
+use monkey::graph::CallGraph;
+use monkey::highlight;
+use monkey::lexer::lexer::{self, Lexer};
+use monkey::lint::{self, LintOptions, Severity};
+use monkey::parser::program::{Program, Statement};
+use monkey::parser::{self, dump, Parser};
+use monkey::testing;
+use monkey::typecheck;
+use monkey::vm::{Vm, VmOptions};
+use monkey::Interpreter;
 
 const PROMPT: &str = ">>";
+// shown while the input so far is incomplete
+const CONTINUATION_PROMPT: &str = "..";
+// how often `run --watch` looks at the script
+const WATCH_INTERVAL: Duration = Duration::from_millis(200);
+// ends the input of `:paste`, as does Ctrl+D
+const PASTE_END: &str = ":end";
+// kept in the home directory
+const HISTORY_FILE: &str = ".monkey_history";
+// the path that reads the script from stdin
+const STDIN: &str = "-";
+
+// process exit codes, success is 0
+const EXIT_RUNTIME: i32 = 1;
+const EXIT_PARSE: i32 = 2;
+const EXIT_USAGE: i32 = 3;
+
+#[derive(clap::Parser)]
+#[command(
+    name = "monkey_rust",
+    version,
+    about = "The Monkey programming language, runs the REPL without a script",
+    after_help = "Exit status: 0 on success, 1 on a runtime error, 2 on a script that does not \
+                  parse, compile or pass --strict, 3 on a usage error, like a script that cannot \
+                  be read.",
+    args_conflicts_with_subcommands = true
+)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(long, help = "Print how often functions and statements ran")]
+    profile: bool,
+    #[arg(long, value_enum, help = "Print or write bytecode instead of running")]
+    emit: Option<Emit>,
+    #[arg(long, help = "Run on the vm, printing every instruction to stderr")]
+    trace: bool,
+    #[arg(help = "Script to run, `-` for stdin, the same as `run <script>`")]
+    script: Option<String>,
+    #[arg(last = true, help = "Arguments for the script, see `args()`")]
+    args: Vec<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Run a script, or a .monkeyc file on the vm")]
+    Run {
+        #[arg(help = "Monkey source or a .monkeyc file, `-` for stdin")]
+        script: String,
+        #[arg(
+            long,
+            conflicts_with_all = ["coverage", "lcov"],
+            help = "Print how often functions and statements ran"
+        )]
+        profile: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["coverage", "lcov"],
+            help = "Run on the vm, printing every instruction to stderr"
+        )]
+        trace: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["coverage", "lcov"],
+            help = "Run again whenever the script changes"
+        )]
+        watch: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["trace", "coverage", "lcov"],
+            help = "Warn about operators on values of different types, `==` and `!=` do not \
+                    fail on them"
+        )]
+        warn_types: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["trace", "coverage", "lcov"],
+            help = "Refuse to run a script using a name that is never defined"
+        )]
+        strict: bool,
+        #[command(flatten)]
+        coverage: CoverageArgs,
+        #[arg(last = true, help = "Arguments for the script, see `args()`")]
+        args: Vec<String>,
+    },
+    #[command(about = "Run a script under the debugger, `help` at its prompt lists the commands")]
+    Debug {
+        #[arg(help = "Monkey source to debug")]
+        script: String,
+        #[arg(
+            short,
+            long = "break",
+            value_name = "LINE",
+            help = "Stop before the statements on this line, can be repeated"
+        )]
+        breakpoints: Vec<usize>,
+        #[arg(last = true, help = "Arguments for the script, see `args()`")]
+        args: Vec<String>,
+    },
+    #[command(about = "Compile a script to a .monkeyc file")]
+    Build {
+        #[arg(help = "Monkey source to compile, `-` for stdin")]
+        script: String,
+        #[arg(
+            short,
+            long,
+            help = "Where to write, the script with a .monkeyc extension by default"
+        )]
+        output: Option<String>,
+    },
+    #[command(about = "Run a .monkeyc file on the vm")]
+    Exec {
+        #[arg(help = "A file written by `build`, `-` for stdin")]
+        file: String,
+        #[arg(long, help = "Print every instruction to stderr")]
+        trace: bool,
+        #[arg(last = true, help = "Arguments for the program, see `args()`")]
+        args: Vec<String>,
+    },
+    #[command(about = "Print the syntax tree of a script or an expression")]
+    #[command(group(ArgGroup::new("input").required(true).args(["script", "expression"])))]
+    Ast {
+        #[arg(help = "Monkey source to parse, `-` for stdin")]
+        script: Option<String>,
+        #[arg(
+            short = 'e',
+            long = "expr",
+            allow_hyphen_values = true,
+            help = "Parse this code instead of a file"
+        )]
+        expression: Option<String>,
+        #[arg(
+            long,
+            value_enum,
+            default_value = "text",
+            help = "How to print the tree"
+        )]
+        format: AstFormat,
+    },
+    #[command(about = "Print which functions of a script call which")]
+    Graph {
+        #[arg(help = "Monkey source, `-` for stdin")]
+        script: String,
+        #[arg(
+            long,
+            value_enum,
+            default_value = "dot",
+            help = "How to print the graph"
+        )]
+        format: GraphFormat,
+    },
+    #[command(about = "Print the tokens of a script or an expression with their spans")]
+    #[command(group(ArgGroup::new("input").required(true).args(["script", "expression"])))]
+    Tokens {
+        #[arg(help = "Monkey source to lex, `-` for stdin")]
+        script: Option<String>,
+        #[arg(
+            short = 'e',
+            long = "expr",
+            allow_hyphen_values = true,
+            help = "Lex this code instead of a file"
+        )]
+        expression: Option<String>,
+    },
+    #[command(about = "Rewrite scripts in the canonical style")]
+    Fmt {
+        #[arg(
+            required = true,
+            help = "Scripts or glob patterns like 'src/**/*.mk', `-` for stdin"
+        )]
+        files: Vec<String>,
+        #[arg(
+            long,
+            help = "Only list the files that are not formatted, failing if any"
+        )]
+        check: bool,
+        #[arg(
+            long,
+            conflicts_with = "check",
+            help = "Print the scripts as small as they go, without comments, instead"
+        )]
+        minify: bool,
+        #[arg(
+            long,
+            requires = "minify",
+            help = "Also give parameters and local bindings the shortest free names"
+        )]
+        rename_locals: bool,
+    },
+    #[command(about = "Report suspicious code, see --allow for the rules")]
+    Lint {
+        #[arg(
+            required = true,
+            help = "Scripts or glob patterns like 'src/**/*.mk', `-` for stdin"
+        )]
+        files: Vec<String>,
+        #[arg(
+            long,
+            value_name = "RULE",
+            help = "Turn a rule off: unused-binding, unused-parameter, shadowing, \
+                    unreachable-code, constant-condition or mismatched-comparison"
+        )]
+        allow: Vec<String>,
+        #[arg(long, value_name = "RULE", help = "Report a rule as an error, failing")]
+        deny: Vec<String>,
+    },
+    #[command(about = "Run the test_* functions of *_test.mk files")]
+    Test {
+        #[arg(
+            default_value = ".",
+            help = "Test files, glob patterns or directories searched for *_test.mk files"
+        )]
+        files: Vec<String>,
+        #[command(flatten)]
+        coverage: CoverageArgs,
+    },
+    #[command(about = "Print the functions of scripts with their /// doc comments")]
+    Doc {
+        #[arg(
+            required = true,
+            help = "Scripts or glob patterns like 'src/**/*.mk', `-` for stdin"
+        )]
+        files: Vec<String>,
+        #[arg(long, value_enum, default_value = "markdown", help = "What to write")]
+        format: PageFormat,
+        #[arg(
+            short = 'o',
+            long = "output",
+            value_name = "FILE",
+            help = "Write to FILE instead of stdout"
+        )]
+        output: Option<String>,
+    },
+    #[command(about = "Time the bench_* functions of *_bench.mk files")]
+    Bench {
+        #[arg(
+            default_value = ".",
+            help = "Benchmark files, glob patterns or directories searched for *_bench.mk files"
+        )]
+        files: Vec<String>,
+        #[arg(
+            short = 'n',
+            long,
+            default_value = "100",
+            value_parser = clap::value_parser!(u64).range(1..),
+            help = "How many times each function is called"
+        )]
+        iterations: u64,
+    },
+    #[command(about = "Parse scripts without running them, reporting every error")]
+    Check {
+        #[arg(
+            required = true,
+            help = "Scripts or glob patterns like 'src/**/*.mk', `-` for stdin"
+        )]
+        files: Vec<String>,
+        #[arg(
+            long,
+            help = "Also warn about operators, calls and indexes on values of types they fail on. \
+                    Parameters are only typed in calls by name of functions bound by `let`"
+        )]
+        types: bool,
+    },
+}
+
+#[derive(clap::Args)]
+struct CoverageArgs {
+    #[arg(long, help = "Print which lines ran, per file")]
+    coverage: bool,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Write which lines ran to FILE in the lcov format"
+    )]
+    lcov: Option<String>,
+}
+
+impl CoverageArgs {
+    fn enabled(&self) -> bool {
+        self.coverage || self.lcov.is_some()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum AstFormat {
+    // one node per line, see `dump::tree`
+    Text,
+    // the serialized `Program`
+    Json,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum GraphFormat {
+    // Graphviz source, see `CallGraph::to_dot`
+    Dot,
+    // the serialized `CallGraph`
+    Json,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum PageFormat {
+    Markdown,
+    // a standalone page
+    Html,
+}
+
+// what to produce instead of running the code
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Emit {
+    // the disassembled bytecode, on stdout
+    Asm,
+    // a .monkeyc file next to the script, see `Bytecode::to_bytes`
+    Monkeyc,
+}
+
+fn print_profile(interpreter: &Interpreter) {
+    if let Some(report) = interpreter.profile_report() {
+        eprint!("{}", report);
+    }
+}
+
+// how `path` is named in messages
+fn source_name(path: &str) -> &str {
+    if path == STDIN {
+        "<stdin>"
+    } else {
+        path
+    }
+}
+
+// `-` reads until the end of stdin
+fn read_bytes(path: &str) -> std::io::Result<Vec<u8>> {
+    if path != STDIN {
+        return std::fs::read(path);
+    }
+    let mut ret = vec![];
+    std::io::stdin().lock().read_to_end(&mut ret)?;
+    Ok(ret)
+}
+
+// clap exits with 2 on bad arguments, that is a parse error here
+fn usage_error(kind: ErrorKind, message: &str) -> ! {
+    let _ = Args::command().error(kind, message).print();
+    std::process::exit(EXIT_USAGE);
+}
+
+// a script that does not read is a usage error
+fn read_script(path: &str) -> String {
+    let ret = read_bytes(path).and_then(|bytes| {
+        String::from_utf8(bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    });
+    match ret {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("cannot read {}: {}", source_name(path), e);
+            std::process::exit(EXIT_USAGE);
+        }
+    }
+}
+
+// the program in `source`, the process fails if it does not parse
+fn parse_or_exit(source: &str, path: Option<&str>) -> Program {
+    match Parser::new(Lexer::new(source)).parse_program() {
+        Ok(program) => program,
+        Err(e) => {
+            eprint!("{}", e.render(source, path));
+            std::process::exit(EXIT_PARSE);
+        }
+    }
+}
+
+// warnings go to stderr with the source line they point at, `path` names
+// the source if it came from a file
+fn compile(
+    compiler: &mut Compiler,
+    program: &Program,
+    source: &str,
+    path: Option<&str>,
+) -> monkey::parser::Result<Bytecode> {
+    compiler.compile(program)?;
+    for warning in compiler.warnings() {
+        let message = &warning.message;
+        eprint!(
+            "{}",
+            diagnostics::render("warning", message, source, warning.span, path)
+        );
+    }
+    Ok(compiler.bytecode())
+}
+
+// the bytecode of a script, the process fails like for a parse error if it
+// does not compile
+fn compile_or_exit(program: &Program, source: &str, name: &str) -> Bytecode {
+    match compile(&mut Compiler::new(), program, source, Some(name)) {
+        Ok(bytecode) => bytecode,
+        Err(e) => {
+            eprint!("{}", e.render(source, Some(name)));
+            std::process::exit(EXIT_PARSE);
+        }
+    }
+}
+
+// a REPL input, its errors are shown without stopping the REPL
+fn compile_input(compiler: &mut Compiler, input: &str) -> monkey::parser::Result<Bytecode> {
+    let program = Parser::new(Lexer::new(input)).parse_program()?;
+    compile(compiler, &program, input, None)
+}
+
+// `output` is only used for Emit::Monkeyc
+fn emit_script(path: &str, emit: Emit, output: Option<&str>) {
+    if emit == Emit::Monkeyc && output.is_none() && path == STDIN {
+        usage_error(
+            ErrorKind::MissingRequiredArgument,
+            "a script read from stdin needs an output, see `build - -o <file>`",
+        );
+    }
+    let source = read_script(path);
+    let name = source_name(path);
+    let program = parse_or_exit(&source, Some(name));
+    let bytecode = compile_or_exit(&program, &source, name);
+    let ret = match emit {
+        Emit::Asm => {
+            print!("{}", bytecode.disassemble());
+            Ok(())
+        }
+        Emit::Monkeyc => {
+            let out = match output {
+                Some(output) => PathBuf::from(output),
+                None => Path::new(path).with_extension("monkeyc"),
+            };
+            bytecode.to_bytes().and_then(|bytes| {
+                std::fs::write(&out, bytes)
+                    .map_err(|e| format!("cannot write {}: {}", out.display(), e).into())
+            })
+        }
+    };
+    if let Err(e) = ret {
+        eprint!("{}", e.render(&source, Some(name)));
+        std::process::exit(EXIT_RUNTIME);
+    }
+}
+
+fn vm_options(trace: bool) -> VmOptions {
+    VmOptions {
+        trace,
+        ..Default::default()
+    }
+}
+
+// Runs a file written by `build`, or a script with --trace, on the vm. With
+// `exec` the file is always read as bytecode, whatever its extension.
+fn run_bytecode(path: &str, trace: bool, exec: bool, args: Vec<String>) {
+    let name = source_name(path);
+    // empty for .monkeyc files, their errors point at the line and column only
+    let mut source = String::new();
+    let bytecode = if exec || path.ends_with(".monkeyc") {
+        let bytes = read_bytes(path).unwrap_or_else(|e| {
+            eprintln!("cannot read {}: {}", name, e);
+            std::process::exit(EXIT_USAGE);
+        });
+        Bytecode::from_bytes(&bytes)
+    } else {
+        source = read_script(path);
+        let program = parse_or_exit(&source, Some(name));
+        Ok(compile_or_exit(&program, &source, name))
+    };
+    let ret = bytecode.and_then(|bytecode| {
+        let mut vm = Vm::new(bytecode);
+        vm.set_options(vm_options(trace));
+        vm.set_args(args);
+        let ret = vm.run();
+        if let Some(code) = vm.exit_code() {
+            std::process::exit(code);
+        }
+        ret?;
+        Ok(vm.last_popped_stack_elem().clone())
+    });
+    match ret {
+        Ok(ObjectWrapper::Null) => {}
+        Ok(obj) => println!("{}", ObjectPrinter::new().print(&obj)),
+        Err(e) => {
+            eprint!("{}", e.render(&source, Some(name)));
+            std::process::exit(EXIT_RUNTIME);
+        }
+    }
+}
+
+// runs a script file, the process fails if the script does
+fn run_script(mut interpreter: Interpreter, path: &str) {
+    let name = source_name(path);
+    let source = read_script(path);
+    // parsed and checked here too, so only runtime errors are left to `eval`
+    let program = parse_or_exit(&source, Some(name));
+    if let Err(e) = interpreter.check(&program) {
+        eprint!("{}", e.render(&source, Some(name)));
+        std::process::exit(EXIT_PARSE);
+    }
+    let ret = interpreter.eval(&source);
+    for warning in interpreter.warnings() {
+        let message = &warning.message;
+        eprint!(
+            "{}",
+            diagnostics::render("warning", message, &source, warning.span, Some(name))
+        );
+    }
+    print_profile(&interpreter);
+    report_result(&interpreter, ret, &source, name);
+}
+
+// prints what a script evaluated to, exits if it failed
+fn report_result(
+    interpreter: &Interpreter,
+    ret: parser::Result<ObjectWrapper>,
+    source: &str,
+    name: &str,
+) {
+    if let Some(code) = interpreter.exit_code() {
+        std::process::exit(code);
+    }
+    match ret {
+        // the script returned an error value, it failed as well
+        Ok(ObjectWrapper::ErrorObject(e)) => {
+            eprint!("{}", diagnostics::render_message("error", &e, Some(name)));
+            std::process::exit(EXIT_RUNTIME);
+        }
+        Ok(ObjectWrapper::Null) => {}
+        Ok(obj) => println!("{}", ObjectPrinter::new().print(&obj)),
+        Err(e) => {
+            eprint!("{}", e.render(source, Some(name)));
+            eprint!("{}", diagnostics::render_trace(e.trace(), Some(name)));
+            std::process::exit(EXIT_RUNTIME);
+        }
+    }
+}
+
+// `run --coverage`, the lines that ran are reported before the result
+fn run_covered(path: &str, options: &CoverageArgs, args: Vec<String>) {
+    if path.ends_with(".monkeyc") {
+        usage_error(
+            ErrorKind::ArgumentConflict,
+            "coverage needs the source of the script",
+        );
+    }
+    let name = source_name(path);
+    let source = read_script(path);
+    let program = parse_or_exit(&source, Some(name));
+    let mut coverage = Coverage::new(&program);
+    let mut interpreter = Interpreter::new();
+    interpreter.set_args(args);
+    let ret = interpreter.eval_observed(&source, &mut coverage);
+    report_coverage(&[(name.to_string(), coverage)], options);
+    report_result(&interpreter, ret, &source, name);
+}
+
+// the summary goes to stderr, the lcov report to its file
+fn report_coverage(files: &[(String, Coverage)], options: &CoverageArgs) {
+    if options.coverage {
+        for (name, coverage) in files {
+            eprintln!("{}", coverage.summary(name));
+        }
+    }
+    if let Some(lcov) = &options.lcov {
+        let report = files
+            .iter()
+            .map(|(name, coverage)| coverage.to_lcov(name))
+            .collect::<String>();
+        if let Err(e) = std::fs::write(lcov, report) {
+            eprintln!("cannot write {}: {}", lcov, e);
+            std::process::exit(EXIT_RUNTIME);
+        }
+    }
+}
+
+// `debug`, the debugger reads its commands from stdin, like the script
+fn debug_file(path: &str, breakpoints: &[usize], args: Vec<String>) {
+    if path == STDIN {
+        usage_error(
+            ErrorKind::InvalidValue,
+            "cannot debug stdin, the debugger reads its commands from it",
+        );
+    }
+    let name = source_name(path);
+    let source = read_script(path);
+    parse_or_exit(&source, Some(name));
+    let mut interpreter = Interpreter::new();
+    interpreter.set_args(args);
+    let mut debugger = Debugger::new(&source, Input::stdin(), Sink::stdout());
+    debugger.set_cancel_handle(interpreter.cancel_handle());
+    for line in breakpoints {
+        debugger.add_breakpoint(*line);
+    }
+    let ret = interpreter.eval_observed(&source, &mut debugger);
+    if !debugger.quit() {
+        report_result(&interpreter, ret, &source, name);
+    }
+}
+
+// Patterns are expanded here as well, for shells that do not or when they
+// are quoted. A pattern matching nothing is an error, `-` is kept for stdin.
+fn expand_globs(patterns: &[String]) -> Result<Vec<PathBuf>, String> {
+    let mut ret = vec![];
+    for pattern in patterns {
+        if pattern == STDIN {
+            ret.push(PathBuf::from(STDIN));
+            continue;
+        }
+        let paths = glob::glob(pattern)
+            .map_err(|e| format!("invalid pattern {}: {}", pattern, e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        if paths.is_empty() {
+            return Err(format!("no files match {}", pattern));
+        }
+        ret.extend(paths);
+    }
+    Ok(ret)
+}
+
+// `check`, the process fails if any file does not parse
+fn check_files(patterns: &[String], types: bool) {
+    let paths = expand_globs(patterns).unwrap_or_else(|e| usage_error(ErrorKind::InvalidValue, &e));
+    let mut errors = 0;
+    let mut failed = 0;
+    for path in &paths {
+        let path = path.display().to_string();
+        let name = source_name(&path);
+        let source = read_script(&path);
+        let (program, found) = Parser::new(Lexer::new(source.as_str())).parse_program_with_errors();
+        for e in &found {
+            eprint!("{}", e.render(&source, Some(name)));
+        }
+        if !found.is_empty() {
+            errors += found.len();
+            failed += 1;
+        } else if types {
+            for warning in typecheck::check(&program) {
+                let message = &warning.message;
+                eprint!(
+                    "{}",
+                    diagnostics::render("warning", message, &source, warning.span, Some(name))
+                );
+            }
+        }
+    }
+    if errors > 0 {
+        eprintln!(
+            "{} error(s) in {} of {} file(s)",
+            errors,
+            failed,
+            paths.len()
+        );
+        std::process::exit(EXIT_PARSE);
+    }
+}
+
+// `doc`, fails if a file does not parse
+fn document_files(patterns: &[String], format: PageFormat, output: Option<&str>) {
+    let paths = expand_globs(patterns).unwrap_or_else(|e| usage_error(ErrorKind::InvalidValue, &e));
+    let mut files = vec![];
+    for path in &paths {
+        let path = path.display().to_string();
+        let name = source_name(&path);
+        let source = read_script(&path);
+        match Parser::new(Lexer::new(source.as_str())).parse_program() {
+            Ok(program) => files.push((name.to_string(), doc::extract(&program))),
+            Err(e) => {
+                eprint!("{}", e.render(&source, Some(name)));
+                std::process::exit(EXIT_PARSE);
+            }
+        }
+    }
+    let format = match format {
+        PageFormat::Markdown => DocFormat::Markdown,
+        PageFormat::Html => DocFormat::Html,
+    };
+    let page = doc::render(&files, format);
+    match output {
+        Some(output) => {
+            if let Err(e) = std::fs::write(output, page) {
+                eprintln!("cannot write {}: {}", output, e);
+                std::process::exit(EXIT_RUNTIME);
+            }
+        }
+        None => print!("{}", page),
+    }
+}
+
+// `lint`, fails if a file does not parse or a denied rule is broken
+fn lint_files(patterns: &[String], allow: &[String], deny: &[String]) {
+    let mut options = LintOptions::new();
+    let levels = allow.iter().map(|id| (id, None));
+    for (id, level) in levels.chain(deny.iter().map(|id| (id, Some(Severity::Error)))) {
+        if let Err(e) = options.set_level(id, level) {
+            usage_error(ErrorKind::InvalidValue, &e);
+        }
+    }
+    let paths = expand_globs(patterns).unwrap_or_else(|e| usage_error(ErrorKind::InvalidValue, &e));
+    let mut unparsed = false;
+    let (mut warnings, mut errors) = (0, 0);
+    for path in &paths {
+        let path = path.display().to_string();
+        let name = source_name(&path);
+        let source = read_script(&path);
+        let program = match Parser::new(Lexer::new(source.as_str())).parse_program() {
+            Ok(program) => program,
+            Err(e) => {
+                eprint!("{}", e.render(&source, Some(name)));
+                unparsed = true;
+                continue;
+            }
+        };
+        for found in lint::lint(&program, &options) {
+            let level = format!("{}[{}]", found.severity, found.rule);
+            let message = &found.message;
+            eprint!(
+                "{}",
+                diagnostics::render(&level, message, &source, found.span, Some(name))
+            );
+            match found.severity {
+                Severity::Warning => warnings += 1,
+                Severity::Error => errors += 1,
+            }
+        }
+    }
+    if warnings + errors > 0 {
+        eprintln!("{} warning(s), {} error(s)", warnings, errors);
+    }
+    if unparsed {
+        std::process::exit(EXIT_PARSE);
+    }
+    if errors > 0 {
+        std::process::exit(EXIT_RUNTIME);
+    }
+}
+
+// the files matching `patterns`, directories are searched for the files
+// ending with `suffix`
+fn expand_test_globs(patterns: &[String], suffix: &str) -> Vec<PathBuf> {
+    let patterns = patterns
+        .iter()
+        .map(|pattern| {
+            if Path::new(pattern).is_dir() {
+                format!("{}/**/*{}", pattern, suffix)
+            } else {
+                pattern.clone()
+            }
+        })
+        .collect::<Vec<String>>();
+    expand_globs(&patterns).unwrap_or_else(|e| usage_error(ErrorKind::InvalidValue, &e))
+}
+
+// `bench`, fails like `test`
+fn bench_files(patterns: &[String], iterations: usize) {
+    let paths = expand_test_globs(patterns, testing::BENCH_FILE_SUFFIX);
+    let mut unparsed = false;
+    let mut failures = String::new();
+    let (mut measured, mut failed) = (0, 0);
+    for path in &paths {
+        let path = path.display().to_string();
+        let name = source_name(&path);
+        let source = read_script(&path);
+        let results = match testing::run_benches(&source, iterations) {
+            Ok(results) => results,
+            Err(e) => {
+                eprint!("{}", e.render(&source, Some(name)));
+                unparsed = true;
+                continue;
+            }
+        };
+        println!("running {} benchmark(s) in {}", results.len(), name);
+        for result in results {
+            let failure = match &result.stats {
+                Ok(stats) => {
+                    println!("bench {} ... {}", result.case.name, stats);
+                    measured += 1;
+                    continue;
+                }
+                Err(failure) => failure,
+            };
+            println!("bench {} ... FAILED", result.case.name);
+            failed += 1;
+            failures.push_str(&format!(
+                "\n---- {} at {}:{} ----\n",
+                result.case.name, name, result.case.span.start
+            ));
+            failures.push_str(&result.output);
+            failures.push_str(&render_failure(failure, &source, name));
+        }
+    }
+    if failed > 0 {
+        print!("\nfailures:\n{}", failures);
+    }
+    let status = if failed > 0 { "FAILED" } else { "ok" };
+    println!(
+        "\nbench result: {}. {} measured; {} failed",
+        status, measured, failed
+    );
+    if unparsed {
+        std::process::exit(EXIT_PARSE);
+    }
+    if failed > 0 {
+        std::process::exit(EXIT_RUNTIME);
+    }
+}
+
+fn render_failure(failure: &testing::Failure, source: &str, name: &str) -> String {
+    match failure.span {
+        Some(span) => diagnostics::render("error", &failure.message, source, span, Some(name)),
+        None => diagnostics::render_message("error", &failure.message, Some(name)),
+    }
+}
+
+// `test`, the process fails if a test does and with EXIT_PARSE if a file
+// does not parse
+fn test_files(patterns: &[String], options: &CoverageArgs) {
+    let paths = expand_test_globs(patterns, testing::TEST_FILE_SUFFIX);
+    let mut unparsed = false;
+    let (mut passed, mut failed) = (0, 0);
+    let mut failures = String::new();
+    let mut covered = vec![];
+    for path in &paths {
+        let path = path.display().to_string();
+        let name = source_name(&path);
+        let source = read_script(&path);
+        let program = match Parser::new(Lexer::new(source.as_str())).parse_program() {
+            Ok(program) => program,
+            Err(e) => {
+                eprint!("{}", e.render(&source, Some(name)));
+                unparsed = true;
+                continue;
+            }
+        };
+        let mut coverage = Coverage::new(&program);
+        // parsed above, it cannot fail
+        let results = testing::run_tests(&source, Some(&mut coverage)).unwrap();
+        if options.enabled() {
+            covered.push((name.to_string(), coverage));
+        }
+        println!("running {} test(s) in {}", results.len(), name);
+        for result in results {
+            let failure = match &result.failure {
+                None => {
+                    println!("test {} ... ok", result.case.name);
+                    passed += 1;
+                    continue;
+                }
+                Some(failure) => failure,
+            };
+            println!("test {} ... FAILED", result.case.name);
+            failed += 1;
+            failures.push_str(&format!(
+                "\n---- {} at {}:{} ----\n",
+                result.case.name, name, result.case.span.start
+            ));
+            failures.push_str(&result.output);
+            failures.push_str(&render_failure(failure, &source, name));
+        }
+    }
+    if failed > 0 {
+        print!("\nfailures:\n{}", failures);
+    }
+    let status = if failed > 0 { "FAILED" } else { "ok" };
+    println!(
+        "\ntest result: {}. {} passed; {} failed",
+        status, passed, failed
+    );
+    report_coverage(&covered, options);
+    if unparsed {
+        std::process::exit(EXIT_PARSE);
+    }
+    if failed > 0 {
+        std::process::exit(EXIT_RUNTIME);
+    }
+}
+
+// `fmt`, files that do not parse are left alone and fail the process with
+// EXIT_PARSE. Code read from stdin is written to stdout.
+fn format_files(patterns: &[String], check: bool) {
+    let paths = expand_globs(patterns).unwrap_or_else(|e| usage_error(ErrorKind::InvalidValue, &e));
+    let mut failed = false;
+    let mut unparsed = false;
+    for path in &paths {
+        let path = path.display().to_string();
+        let source = read_script(&path);
+        let mut formatted = match fmt::format_source(&source, &FormatOptions::default()) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                eprint!("{}", e.render(&source, Some(source_name(&path))));
+                unparsed = true;
+                continue;
+            }
+        };
+        // the lexer skips the shebang line, it is kept as it was
+        if let Some(shebang) = source.lines().next().filter(|line| line.starts_with("#!")) {
+            formatted = format!("{}\n{}", shebang, formatted);
+        }
+        if path == STDIN && !check {
+            print!("{}", formatted);
+            continue;
+        }
+        if formatted == source {
+            continue;
+        }
+        if check {
+            println!("{}", source_name(&path));
+            failed = true;
+        } else if let Err(e) = std::fs::write(&path, formatted) {
+            eprintln!("cannot write {}: {}", path, e);
+            failed = true;
+        }
+    }
+    if unparsed {
+        std::process::exit(EXIT_PARSE);
+    }
+    if failed {
+        std::process::exit(EXIT_RUNTIME);
+    }
+}
+
+// `fmt --minify`, each script on a line of stdout
+fn minify_files(patterns: &[String], options: &MinifyOptions) {
+    let paths = expand_globs(patterns).unwrap_or_else(|e| usage_error(ErrorKind::InvalidValue, &e));
+    for path in &paths {
+        let path = path.display().to_string();
+        let source = read_script(&path);
+        match minify::minify_source(&source, options) {
+            Ok(minified) => println!("{}", minified),
+            Err(e) => {
+                eprint!("{}", e.render(&source, Some(source_name(&path))));
+                std::process::exit(EXIT_PARSE);
+            }
+        }
+    }
+}
+
+// the file at `path`, or else the code given with -e
+fn read_source(path: Option<&str>, expression: Option<&str>) -> String {
+    match (path, expression) {
+        (Some(path), _) => read_script(path),
+        (None, expression) => expression.unwrap_or_default().to_string(),
+    }
+}
+
+// `tokens`, one per line: its span, the token and the source text it was
+// read from
+fn print_tokens(path: Option<&str>, expression: Option<&str>) {
+    let source = read_source(path, expression);
+    for (token, span) in lexer::tokenize(&source) {
+        let text = String::from_utf8_lossy(&source.as_bytes()[span.start.offset..span.end.offset]);
+        let span = format!(
+            "{}:{}-{}:{}",
+            span.start.line, span.start.column, span.end.line, span.end.column
+        );
+        println!("{:<12} {:<20} {:?}", span, format!("{:?}", token), text);
+    }
+}
+
+// `ast`, either `path` or `expression` is given
+fn print_ast(path: Option<&str>, expression: Option<&str>, format: AstFormat) {
+    let source = read_source(path, expression);
+    let program = parse_or_exit(&source, path.map(source_name));
+    match format {
+        AstFormat::Text => print!("{}", dump::tree(&program)),
+        AstFormat::Json => println!("{}", serde_json::to_string_pretty(&program).unwrap()),
+    }
+}
+
+// `graph`
+fn print_graph(path: &str, format: GraphFormat) {
+    let source = read_source(Some(path), None);
+    let graph = CallGraph::new(&parse_or_exit(&source, Some(source_name(path))));
+    match format {
+        GraphFormat::Dot => print!("{}", graph.to_dot()),
+        GraphFormat::Json => println!("{}", serde_json::to_string_pretty(&graph).unwrap()),
+    }
+}
+
+// `run`, scripts are evaluated unless they are traced
+fn run_file(
+    path: &str,
+    profile: bool,
+    trace: bool,
+    warn_types: bool,
+    strict: bool,
+    args: Vec<String>,
+) {
+    if trace || path.ends_with(".monkeyc") {
+        return run_bytecode(path, trace, false, args);
+    }
+    let mut interpreter = Interpreter::with_options(EvalOptions {
+        type_warnings: warn_types,
+        ..Default::default()
+    });
+    interpreter.set_args(args);
+    if profile {
+        interpreter.enable_profiling();
+    }
+    if strict {
+        interpreter.enable_strict_mode();
+    }
+    run_script(interpreter, path);
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// `run --watch`, the script runs in a child process so it can be stopped
+// when the file changes and its exit does not end the watch
+fn watch_file(
+    path: &str,
+    profile: bool,
+    trace: bool,
+    warn_types: bool,
+    strict: bool,
+    args: &[String],
+) {
+    if path == STDIN {
+        usage_error(ErrorKind::ArgumentConflict, "--watch cannot watch stdin");
+    }
+    let exe = std::env::current_exe().unwrap_or_else(|e| {
+        eprintln!("cannot find the monkey_rust executable: {}", e);
+        std::process::exit(EXIT_RUNTIME);
+    });
+    loop {
+        let stamp = modified(path);
+        // clears the screen
+        print!("\x1b[2J\x1b[H");
+        let _ = std::io::stdout().flush();
+        let mut command = std::process::Command::new(&exe);
+        command.arg("run").arg(path);
+        if profile {
+            command.arg("--profile");
+        }
+        if trace {
+            command.arg("--trace");
+        }
+        if warn_types {
+            command.arg("--warn-types");
+        }
+        if strict {
+            command.arg("--strict");
+        }
+        let mut child = command.arg("--").args(args).spawn().ok();
+        if child.is_none() {
+            eprintln!("cannot run {}", exe.display());
+        }
+        while modified(path) == stamp {
+            if let Some(status) = child.as_mut().and_then(|c| c.try_wait().ok().flatten()) {
+                let status = match status.code() {
+                    Some(code) => format!("exited with {}", code),
+                    None => "killed".to_string(),
+                };
+                eprintln!("\n[{}] watching {} for changes", status, path);
+                child = None;
+            }
+            std::thread::sleep(WATCH_INTERVAL);
+        }
+        if let Some(mut child) = child {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+// colors the input as it is typed, see `highlight::highlight`
+struct ReplHelper;
+
+impl Highlighter for ReplHelper {
+    fn highlight<'l>(&self, line: &'l str, pos: usize) -> Cow<'l, str> {
+        Cow::Owned(highlight::highlight(line, pos))
+    }
+
+    // the matching bracket moves with the cursor
+    fn highlight_char(&self, _line: &str, _pos: usize, _kind: CmdKind) -> bool {
+        true
+    }
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+type ReplEditor = Editor<ReplHelper, DefaultHistory>;
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| Path::new(&home).join(HISTORY_FILE))
+}
+
+// Reads lines until they form a complete input, see `parser::is_incomplete`.
+// A blank line gives up on incomplete input, its parse error is then shown,
+// Ctrl+C drops it. Returns None at the end of input or on Ctrl+C at an empty
+// prompt.
+fn read_input(editor: &mut ReplEditor) -> Option<String> {
+    let mut buf = String::new();
+    loop {
+        let prompt = if buf.is_empty() {
+            PROMPT
+        } else {
+            CONTINUATION_PROMPT
+        };
+        let line = match editor.readline(prompt) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) if !buf.is_empty() => {
+                buf.clear();
+                continue;
+            }
+            Err(ReadlineError::Interrupted) => {
+                println!("Ctrl+C pressed, quiting");
+                return None;
+            }
+            Err(ReadlineError::Eof) => return Some(buf).filter(|buf| !buf.is_empty()),
+            Err(e) => {
+                eprintln!("cannot read input: {}", e);
+                return None;
+            }
+        };
+        let blank = line.trim().is_empty();
+        buf.push_str(&line);
+        buf.push('\n');
+        if blank || !parser::is_incomplete(&buf) {
+            if !blank {
+                let _ = editor.add_history_entry(buf.trim_end());
+            }
+            return Some(buf);
+        }
+    }
+}
+
+// `:paste`, lines are taken as they are until PASTE_END or Ctrl+D and then
+// evaluated at once. Ctrl+C drops them.
+fn read_paste(editor: &mut ReplEditor) -> Option<String> {
+    println!("paste mode, end with {} or Ctrl+D", PASTE_END);
+    let mut buf = String::new();
+    loop {
+        match editor.readline("") {
+            Ok(line) if line.trim() == PASTE_END => break,
+            Ok(line) => {
+                buf.push_str(&line);
+                buf.push('\n');
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(ReadlineError::Interrupted) => return None,
+            Err(e) => {
+                eprintln!("cannot read input: {}", e);
+                return None;
+            }
+        }
+    }
+    let _ = editor.add_history_entry(buf.trim_end());
+    Some(buf)
+}
+
+// `:type`, evaluates a single expression and prints what its value is,
+// `let`s are refused so nothing gets bound
+fn print_type(interpreter: &mut Interpreter, input: &str) {
+    match Parser::new(Lexer::new(input)).parse_program() {
+        Ok(program) if matches!(program.statements[..], [Statement::ExpressionStatement(..)]) => {}
+        Ok(_) => {
+            let message = ":type takes a single expression";
+            return eprint!("{}", diagnostics::render_message("error", message, None));
+        }
+        Err(e) => return eprint!("{}", e.render(input, None)),
+    }
+    match interpreter.eval(input) {
+        Ok(obj) => println!("{}", printer::summary(&obj)),
+        Err(e) => eprint!("{}", e.render(input, None)),
+    }
+}
+
+// what is wrong with a line starting with `:` that no command took
+fn command_error(line: &str) -> String {
+    let name = line.split_whitespace().next().unwrap_or(line);
+    match name {
+        ":paste" => "usage: :paste, then the code on the following lines".to_string(),
+        ":save" | ":load" => format!("usage: {} <path>", name),
+        ":type" => "usage: :type <expression>".to_string(),
+        ":time" => "usage: :time <code>".to_string(),
+        _ => format!(
+            "unknown command {}, the commands are :paste, :save, :load, :type and :time",
+            name
+        ),
+    }
+}
 
 #[async_std::main]
 async fn main() {
     env_logger::init();
+    diagnostics::set_color(diagnostics::color_supported());
+
+    let mut args = Args::try_parse().unwrap_or_else(|e| match e.kind() {
+        ErrorKind::DisplayHelp | ErrorKind::DisplayVersion => e.exit(),
+        _ => {
+            let _ = e.print();
+            std::process::exit(EXIT_USAGE);
+        }
+    });
+    // `echo 'puts(1)' | monkey_rust` runs what is piped in
+    if args.command.is_none() && args.script.is_none() && !std::io::stdin().is_terminal() {
+        args.script = Some(STDIN.to_string());
+    }
+    match (args.command, &args.script, args.emit) {
+        (
+            Some(Command::Run {
+                script,
+                profile,
+                trace,
+                watch: true,
+                warn_types,
+                strict,
+                args,
+                ..
+            }),
+            _,
+            _,
+        ) => return watch_file(&script, profile, trace, warn_types, strict, &args),
+        (
+            Some(Command::Run {
+                script,
+                coverage,
+                args,
+                ..
+            }),
+            _,
+            _,
+        ) if coverage.enabled() => return run_covered(&script, &coverage, args),
+        (
+            Some(Command::Run {
+                script,
+                profile,
+                trace,
+                warn_types,
+                strict,
+                args,
+                ..
+            }),
+            _,
+            _,
+        ) => return run_file(&script, profile, trace, warn_types, strict, args),
+        (Some(Command::Build { script, output }), _, _) => {
+            return emit_script(&script, Emit::Monkeyc, output.as_deref())
+        }
+        (
+            Some(Command::Debug {
+                script,
+                breakpoints,
+                args,
+            }),
+            _,
+            _,
+        ) => return debug_file(&script, &breakpoints, args),
+        (Some(Command::Exec { file, trace, args }), _, _) => {
+            return run_bytecode(&file, trace, true, args)
+        }
+        (Some(Command::Check { files, types }), _, _) => return check_files(&files, types),
+        (
+            Some(Command::Doc {
+                files,
+                format,
+                output,
+            }),
+            _,
+            _,
+        ) => return document_files(&files, format, output.as_deref()),
+        (Some(Command::Test { files, coverage }), _, _) => return test_files(&files, &coverage),
+        (Some(Command::Bench { files, iterations }), _, _) => {
+            return bench_files(&files, iterations as usize)
+        }
+        (Some(Command::Lint { files, allow, deny }), _, _) => {
+            return lint_files(&files, &allow, &deny)
+        }
+        (Some(Command::Tokens { script, expression }), _, _) => {
+            return print_tokens(script.as_deref(), expression.as_deref())
+        }
+        (
+            Some(Command::Fmt {
+                files,
+                check,
+                minify,
+                rename_locals,
+            }),
+            _,
+            _,
+        ) => {
+            if minify {
+                return minify_files(&files, &MinifyOptions { rename_locals });
+            }
+            return format_files(&files, check);
+        }
+        (Some(Command::Graph { script, format }), _, _) => return print_graph(&script, format),
+        (
+            Some(Command::Ast {
+                script,
+                expression,
+                format,
+            }),
+            _,
+            _,
+        ) => return print_ast(script.as_deref(), expression.as_deref(), format),
+        (None, Some(script), Some(emit)) => return emit_script(script, emit, None),
+        (None, Some(script), None) => {
+            return run_file(
+                script,
+                args.profile,
+                args.trace,
+                false,
+                false,
+                args.args.clone(),
+            )
+        }
+        (None, None, Some(Emit::Monkeyc)) => usage_error(
+            ErrorKind::MissingRequiredArgument,
+            "--emit=monkeyc needs a script",
+        ),
+        _ => {}
+    }
+    let mut interpreter = Interpreter::new();
+    if args.profile {
+        interpreter.enable_profiling();
+    }
+    // keeps the globals of the previous lines when printing or tracing
+    // bytecode
+    let mut compiler = Compiler::new();
+    let mut globals = vec![];
 
-    let ctrlc = CtrlC::new().expect("cannot create Ctrl+C handler!");
+    let mut ctrlc = CtrlC::new().expect("cannot create Ctrl+C handler!");
     println!("Welcome to Monkey Language REPL, press Ctrl+C to quit.");
 
-    ctrlc
-        .race(async {
-            let mut env = Environment::default();
-
-            loop {
-                print!("{}", PROMPT);
-                std::io::stdout().flush().unwrap();
-
-                let mut buf = String::new();
-                async_std::io::stdin().read_line(&mut buf).await.unwrap();
-
-                let lx = Lexer::new(buf);
-                let mut p = Parser::new(lx);
-                if let Err(e) = p.parse_program().and_then(|program| {
-                    let mut evaluator = Evaluator::with_env(&program.statements, env.clone());
-                    evaluator.eval().and_then(|obj| {
-                        println!("{:?}", obj);
-                        env = evaluator.get_env();
-                        Ok(())
-                    })
-                }) {
-                    eprintln!("{:?}", e);
+    // Ctrl+C interrupts the running script, or quits when waiting for input.
+    // Evaluation blocks this task, so the handler runs in its own.
+    let running = Arc::new(AtomicBool::new(false));
+    let cancel = interpreter.cancel_handle();
+    let is_running = running.clone();
+    async_std::task::spawn(async move {
+        loop {
+            (&mut ctrlc).await;
+            if is_running.load(Ordering::SeqCst) {
+                cancel.cancel();
+            } else {
+                println!("\nCtrl+C pressed, quiting");
+                std::process::exit(0);
+            }
+        }
+    });
+
+    let mut editor = match ReplEditor::new() {
+        Ok(editor) => editor,
+        Err(e) => {
+            eprintln!("cannot start the line editor: {}", e);
+            std::process::exit(1);
+        }
+    };
+    editor.set_helper(Some(ReplHelper));
+    let printer = ObjectPrinter::new();
+    let history = history_path();
+    if let Some(path) = &history {
+        // there is none yet on the first run
+        let _ = editor.load_history(path);
+    }
+    // what a script called `exit` with, the REPL ends after saving history
+    let mut exit_code = None;
+
+    while let Some(mut buf) = read_input(&mut editor) {
+        if buf.trim() == ":paste" {
+            match read_paste(&mut editor) {
+                Some(pasted) => buf = pasted,
+                None => continue,
+            }
+        }
+        if let Some(path) = buf.trim().strip_prefix(":save ") {
+            let (snapshot, skipped) = interpreter.env().snapshot();
+            if let Err(e) = std::fs::write(path.trim(), snapshot) {
+                eprintln!("cannot save snapshot to {}: {}", path.trim(), e);
+            } else if !skipped.is_empty() {
+                eprintln!(
+                    "not saved, their values cannot be written as literals: {}",
+                    skipped.join(", ")
+                );
+            }
+            continue;
+        }
+        if let Some(path) = buf.trim().strip_prefix(":load ") {
+            match std::fs::read_to_string(path.trim()) {
+                Ok(snapshot) => {
+                    if let Err(e) = interpreter.env_mut().restore(&snapshot) {
+                        eprintln!("{:?}", e);
+                    }
                 }
+                Err(e) => eprintln!("cannot load snapshot from {}: {}", path.trim(), e),
             }
-        })
-        .await;
-    println!("\nCtrl+C pressed, quiting")
+            continue;
+        }
+        if let Some(expression) = buf.trim().strip_prefix(":type ") {
+            print_type(&mut interpreter, expression);
+            continue;
+        }
+
+        // `:time` reports how long the evaluation took after its result, it
+        // is only measured when evaluating
+        let timed = buf.trim_start().starts_with(":time ");
+        if timed {
+            buf = buf.trim_start()[":time ".len()..].to_string();
+        }
+        // no code starts with a colon, the parser would only complain about it
+        if buf.trim_start().starts_with(':') {
+            let message = command_error(buf.trim());
+            eprint!("{}", diagnostics::render_message("error", &message, None));
+            continue;
+        }
+
+        if args.emit == Some(Emit::Asm) {
+            match compile_input(&mut compiler, &buf) {
+                Ok(bytecode) => print!("{}", bytecode.disassemble()),
+                Err(e) => eprint!("{}", e.render(&buf, None)),
+            }
+            continue;
+        }
+        if args.trace {
+            let ret = compile_input(&mut compiler, &buf).and_then(|bytecode| {
+                let mut vm = Vm::with_globals(bytecode, std::mem::take(&mut globals));
+                vm.set_options(vm_options(true));
+                let ret = vm.run().map(|_| vm.last_popped_stack_elem().clone());
+                exit_code = vm.exit_code();
+                globals = vm.into_globals();
+                ret
+            });
+            if exit_code.is_some() {
+                break;
+            }
+            match ret {
+                Ok(obj) => println!("{}", printer.print(&obj)),
+                Err(e) => eprint!("{}", e.render(&buf, None)),
+            }
+            continue;
+        }
+
+        running.store(true, Ordering::SeqCst);
+        let started = Instant::now();
+        let ret = interpreter.eval(&buf);
+        let elapsed = started.elapsed();
+        running.store(false, Ordering::SeqCst);
+        exit_code = interpreter.exit_code();
+        if exit_code.is_some() {
+            break;
+        }
+        match ret {
+            Ok(obj) => println!("{}", printer.print(&obj)),
+            Err(e) => {
+                eprint!("{}", e.render(&buf, None));
+                eprint!("{}", diagnostics::render_trace(e.trace(), None));
+            }
+        }
+        if timed {
+            println!("time: {:.3?}, {} steps", elapsed, interpreter.steps());
+        }
+        print_profile(&interpreter);
+    }
+
+    if let Some(path) = &history {
+        if let Err(e) = editor.save_history(path) {
+            eprintln!("cannot save history to {}: {}", path.display(), e);
+        }
+    }
+    if let Some(code) = exit_code {
+        std::process::exit(code);
+    }
 }
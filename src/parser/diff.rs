@@ -0,0 +1,357 @@
+use crate::lexer::span::Span;
+use crate::parser::program::{Expression, Ident, Program, Statement};
+use alloc::{vec, vec::Vec};
+use core::mem::discriminant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Statement,
+    Expression,
+}
+
+// Literals have no span, their changes are reported with the span of the
+// closest node around them that has one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AstChange {
+    // a node of the new program, with its span there
+    Inserted(NodeKind, Span),
+    // a node of the old program, with its span there
+    Removed(NodeKind, Span),
+    // a node replaced by a different one
+    Modified {
+        kind: NodeKind,
+        old: Span,
+        new: Span,
+    },
+}
+
+// How `new` differs from `old`, ignoring spans, comments and layout. Nodes
+// of the same kind at the same place are compared child by child so the
+// changes are the smallest nodes that differ, in the order of the source.
+pub fn diff(old: &Program, new: &Program) -> Vec<AstChange> {
+    let mut ret = vec![];
+    diff_block(&mut ret, &old.statements, &new.statements);
+    ret
+}
+
+enum Edit<'a, T> {
+    Same,
+    Changed(&'a T, &'a T),
+    Removed(&'a T),
+    Inserted(&'a T),
+}
+
+// Aligns two lists on their longest common subsequence. Between two
+// matches, the nodes that `comparable` pairs are changed, the rest removed
+// and inserted.
+fn align<'a, T>(
+    old: &'a [T],
+    new: &'a [T],
+    same: fn(&T, &T) -> bool,
+    comparable: fn(&T, &T) -> bool,
+) -> Vec<Edit<'a, T>> {
+    // lengths[i][j] is the length of the common subsequence of old[i..] and
+    // new[j..]
+    let mut lengths = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if same(&old[i], &new[j]) {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let mut ret = vec![];
+    let (mut i, mut j) = (0, 0);
+    let (mut removed, mut inserted) = (vec![], vec![]);
+    loop {
+        let done = i == old.len() && j == new.len();
+        if done || (i < old.len() && j < new.len() && same(&old[i], &new[j])) {
+            pair(&mut ret, &removed, &inserted, comparable);
+            removed.clear();
+            inserted.clear();
+            if done {
+                return ret;
+            }
+            ret.push(Edit::Same);
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && lengths[i + 1][j] >= lengths[i][j + 1]) {
+            removed.push(&old[i]);
+            i += 1;
+        } else {
+            inserted.push(&new[j]);
+            j += 1;
+        }
+    }
+}
+
+// pairs each removed node with the next inserted one it is comparable to,
+// the inserted nodes skipped over go before
+fn pair<'a, T>(
+    out: &mut Vec<Edit<'a, T>>,
+    removed: &[&'a T],
+    inserted: &[&'a T],
+    comparable: fn(&T, &T) -> bool,
+) {
+    let mut next = 0;
+    for old in removed {
+        match inserted[next..].iter().position(|new| comparable(old, new)) {
+            Some(skipped) => {
+                let new = next + skipped;
+                out.extend(inserted[next..new].iter().map(|new| Edit::Inserted(*new)));
+                out.push(Edit::Changed(old, inserted[new]));
+                next = new + 1;
+            }
+            None => out.push(Edit::Removed(old)),
+        }
+    }
+    out.extend(inserted[next..].iter().map(|new| Edit::Inserted(*new)));
+}
+
+fn diff_block(out: &mut Vec<AstChange>, old: &[Statement], new: &[Statement]) {
+    for edit in align(old, new, same_statement, comparable_statements) {
+        match edit {
+            Edit::Same => {}
+            Edit::Changed(old, new) => diff_statement(out, old, new),
+            Edit::Removed(old) => out.push(AstChange::Removed(NodeKind::Statement, old.span())),
+            Edit::Inserted(new) => out.push(AstChange::Inserted(NodeKind::Statement, new.span())),
+        }
+    }
+}
+
+// statements of the same kind, binding the same name for `let`
+fn comparable_statements(old: &Statement, new: &Statement) -> bool {
+    match (old, new) {
+        (Statement::LetStatement(old, _, _), Statement::LetStatement(new, _, _)) => old.0 == new.0,
+        _ => discriminant(old) == discriminant(new),
+    }
+}
+
+fn diff_statement(out: &mut Vec<AstChange>, old: &Statement, new: &Statement) {
+    match (old, new) {
+        (
+            Statement::LetStatement(_, old_value, old_span),
+            Statement::LetStatement(_, new_value, new_span),
+        )
+        | (
+            Statement::ReturnStatement(old_value, old_span),
+            Statement::ReturnStatement(new_value, new_span),
+        )
+        | (
+            Statement::ExpressionStatement(old_value, old_span),
+            Statement::ExpressionStatement(new_value, new_span),
+        ) => diff_expression(out, old_value, new_value, (*old_span, *new_span)),
+        _ => out.push(AstChange::Modified {
+            kind: NodeKind::Statement,
+            old: old.span(),
+            new: new.span(),
+        }),
+    }
+}
+
+// `parents` are the spans used for nodes without one
+fn diff_expression(
+    out: &mut Vec<AstChange>,
+    old: &Expression,
+    new: &Expression,
+    parents: (Span, Span),
+) {
+    if same_expression(old, new) {
+        return;
+    }
+    let spans = (
+        old.span().unwrap_or(parents.0),
+        new.span().unwrap_or(parents.1),
+    );
+    match (old, new) {
+        (
+            Expression::PrefixExpression(old_op, old_right, _),
+            Expression::PrefixExpression(new_op, new_right, _),
+        ) if old_op == new_op => diff_expression(out, old_right, new_right, spans),
+        (
+            Expression::InfixExpression(old_left, old_op, old_right, _),
+            Expression::InfixExpression(new_left, new_op, new_right, _),
+        ) if old_op == new_op => {
+            diff_expression(out, old_left, new_left, spans);
+            diff_expression(out, old_right, new_right, spans);
+        }
+        (
+            Expression::IfExpression(old_condition, old_consequence, old_alternative, _),
+            Expression::IfExpression(new_condition, new_consequence, new_alternative, _),
+        ) => {
+            diff_expression(out, old_condition, new_condition, spans);
+            diff_block(out, old_consequence, new_consequence);
+            diff_block(out, old_alternative, new_alternative);
+        }
+        (
+            Expression::FunctionExpression(old_params, old_body, _),
+            Expression::FunctionExpression(new_params, new_body, _),
+        ) if same_idents(old_params, new_params) => diff_block(out, old_body, new_body),
+        (
+            Expression::CallExpression(old_function, old_args, _),
+            Expression::CallExpression(new_function, new_args, _),
+        ) => {
+            diff_expression(out, old_function, new_function, spans);
+            diff_expressions(out, old_args, new_args, spans);
+        }
+        (Expression::ArrayLiteral(old_elements), Expression::ArrayLiteral(new_elements)) => {
+            diff_expressions(out, old_elements, new_elements, spans)
+        }
+        (Expression::HashLiteral(old_pairs), Expression::HashLiteral(new_pairs)) => {
+            diff_pairs(out, old_pairs, new_pairs, spans)
+        }
+        (
+            Expression::IndexExpression(old_left, old_index, _),
+            Expression::IndexExpression(new_left, new_index, _),
+        ) => {
+            diff_expression(out, old_left, new_left, spans);
+            diff_expression(out, old_index, new_index, spans);
+        }
+        _ => out.push(AstChange::Modified {
+            kind: NodeKind::Expression,
+            old: spans.0,
+            new: spans.1,
+        }),
+    }
+}
+
+fn comparable_expressions(old: &Expression, new: &Expression) -> bool {
+    discriminant(old) == discriminant(new)
+}
+
+fn diff_expressions(
+    out: &mut Vec<AstChange>,
+    old: &[Expression],
+    new: &[Expression],
+    parents: (Span, Span),
+) {
+    for edit in align(old, new, same_expression, comparable_expressions) {
+        match edit {
+            Edit::Same => {}
+            Edit::Changed(old, new) => diff_expression(out, old, new, parents),
+            Edit::Removed(old) => out.push(AstChange::Removed(
+                NodeKind::Expression,
+                old.span().unwrap_or(parents.0),
+            )),
+            Edit::Inserted(new) => out.push(AstChange::Inserted(
+                NodeKind::Expression,
+                new.span().unwrap_or(parents.1),
+            )),
+        }
+    }
+}
+
+type Pair = (Expression, Expression);
+
+fn same_pair(old: &Pair, new: &Pair) -> bool {
+    same_expression(&old.0, &new.0) && same_expression(&old.1, &new.1)
+}
+
+// pairs with the same key
+fn comparable_pairs(old: &Pair, new: &Pair) -> bool {
+    same_expression(&old.0, &new.0)
+}
+
+// a pair is reported with the span of its key
+fn diff_pairs(out: &mut Vec<AstChange>, old: &[Pair], new: &[Pair], parents: (Span, Span)) {
+    for edit in align(old, new, same_pair, comparable_pairs) {
+        match edit {
+            Edit::Same => {}
+            Edit::Changed(old, new) => diff_expression(out, &old.1, &new.1, parents),
+            Edit::Removed(old) => out.push(AstChange::Removed(
+                NodeKind::Expression,
+                old.0.span().unwrap_or(parents.0),
+            )),
+            Edit::Inserted(new) => out.push(AstChange::Inserted(
+                NodeKind::Expression,
+                new.0.span().unwrap_or(parents.1),
+            )),
+        }
+    }
+}
+
+fn same_idents(old: &[Ident], new: &[Ident]) -> bool {
+    old.len() == new.len() && old.iter().zip(new).all(|(old, new)| old.0 == new.0)
+}
+
+fn same_block(old: &[Statement], new: &[Statement]) -> bool {
+    old.len() == new.len()
+        && old
+            .iter()
+            .zip(new)
+            .all(|(old, new)| same_statement(old, new))
+}
+
+fn same_expressions(old: &[Expression], new: &[Expression]) -> bool {
+    old.len() == new.len()
+        && old
+            .iter()
+            .zip(new)
+            .all(|(old, new)| same_expression(old, new))
+}
+
+// the same tree, wherever it is
+fn same_statement(old: &Statement, new: &Statement) -> bool {
+    match (old, new) {
+        (
+            Statement::LetStatement(old_ident, old_value, _),
+            Statement::LetStatement(new_ident, new_value, _),
+        ) => old_ident.0 == new_ident.0 && same_expression(old_value, new_value),
+        (Statement::ReturnStatement(old, _), Statement::ReturnStatement(new, _))
+        | (Statement::ExpressionStatement(old, _), Statement::ExpressionStatement(new, _)) => {
+            same_expression(old, new)
+        }
+        _ => false,
+    }
+}
+
+fn same_expression(old: &Expression, new: &Expression) -> bool {
+    match (old, new) {
+        (Expression::Identifier(old), Expression::Identifier(new)) => old.0 == new.0,
+        (Expression::IntLiteral(_), Expression::IntLiteral(_))
+        | (Expression::FloatLiteral(_), Expression::FloatLiteral(_))
+        | (Expression::BoolLiteral(_), Expression::BoolLiteral(_))
+        | (Expression::StringLiteral(_), Expression::StringLiteral(_)) => old == new,
+        (
+            Expression::PrefixExpression(old_op, old_right, _),
+            Expression::PrefixExpression(new_op, new_right, _),
+        ) => old_op == new_op && same_expression(old_right, new_right),
+        (
+            Expression::InfixExpression(old_left, old_op, old_right, _),
+            Expression::InfixExpression(new_left, new_op, new_right, _),
+        ) => {
+            old_op == new_op
+                && same_expression(old_left, new_left)
+                && same_expression(old_right, new_right)
+        }
+        (
+            Expression::IfExpression(old_condition, old_consequence, old_alternative, _),
+            Expression::IfExpression(new_condition, new_consequence, new_alternative, _),
+        ) => {
+            same_expression(old_condition, new_condition)
+                && same_block(old_consequence, new_consequence)
+                && same_block(old_alternative, new_alternative)
+        }
+        (
+            Expression::FunctionExpression(old_params, old_body, _),
+            Expression::FunctionExpression(new_params, new_body, _),
+        ) => same_idents(old_params, new_params) && same_block(old_body, new_body),
+        (
+            Expression::CallExpression(old_function, old_args, _),
+            Expression::CallExpression(new_function, new_args, _),
+        ) => same_expression(old_function, new_function) && same_expressions(old_args, new_args),
+        (Expression::ArrayLiteral(old), Expression::ArrayLiteral(new)) => {
+            same_expressions(old, new)
+        }
+        (Expression::HashLiteral(old), Expression::HashLiteral(new)) => {
+            old.len() == new.len() && old.iter().zip(new).all(|(old, new)| same_pair(old, new))
+        }
+        (
+            Expression::IndexExpression(old_left, old_index, _),
+            Expression::IndexExpression(new_left, new_index, _),
+        ) => same_expression(old_left, new_left) && same_expression(old_index, new_index),
+        _ => false,
+    }
+}
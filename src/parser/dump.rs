@@ -0,0 +1,124 @@
+use crate::lexer::span::Span;
+use crate::parser::program::{Expression, Program, Statement};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+// Renders the syntax tree one node per line, children indented below their
+// parent and spans as `line:column-line:column`:
+//
+//   let x 1:1-1:11
+//     infix + 1:9-1:14
+//       int 1
+//       identifier y 1:13-1:14
+pub fn tree(program: &Program) -> String {
+    let mut ret = String::new();
+    for statement in &program.statements {
+        write_statement(&mut ret, statement, 0);
+    }
+    ret
+}
+
+fn line(out: &mut String, depth: usize, text: &str, span: Option<Span>) {
+    out.push_str(&"  ".repeat(depth));
+    out.push_str(text);
+    if let Some(span) = span {
+        write!(
+            out,
+            " {}:{}-{}:{}",
+            span.start.line, span.start.column, span.end.line, span.end.column
+        )
+        .unwrap();
+    }
+    out.push('\n');
+}
+
+fn write_block(out: &mut String, label: &str, statements: &[Statement], depth: usize) {
+    line(out, depth, label, None);
+    for statement in statements {
+        write_statement(out, statement, depth + 1);
+    }
+}
+
+fn write_statement(out: &mut String, statement: &Statement, depth: usize) {
+    match statement {
+        Statement::LetStatement(ident, value, span) => {
+            line(out, depth, &format!("let {}", ident.0), Some(*span));
+            write_expression(out, value, depth + 1);
+        }
+        Statement::ReturnStatement(value, span) => {
+            line(out, depth, "return", Some(*span));
+            write_expression(out, value, depth + 1);
+        }
+        Statement::ExpressionStatement(value, span) => {
+            line(out, depth, "expression", Some(*span));
+            write_expression(out, value, depth + 1);
+        }
+    }
+}
+
+fn write_expression(out: &mut String, expression: &Expression, depth: usize) {
+    let span = expression.span();
+    match expression {
+        Expression::Identifier(ident) => line(out, depth, &format!("identifier {}", ident.0), span),
+        Expression::IntLiteral(v) => line(out, depth, &format!("int {}", v), span),
+        Expression::FloatLiteral(v) => line(out, depth, &format!("float {:?}", v), span),
+        Expression::BoolLiteral(v) => line(out, depth, &format!("bool {}", v), span),
+        Expression::StringLiteral(v) => line(out, depth, &format!("string {:?}", v), span),
+        Expression::PrefixExpression(operator, right, _) => {
+            line(out, depth, &format!("prefix {}", operator), span);
+            write_expression(out, right, depth + 1);
+        }
+        Expression::InfixExpression(left, operator, right, _) => {
+            line(out, depth, &format!("infix {}", operator), span);
+            write_expression(out, left, depth + 1);
+            write_expression(out, right, depth + 1);
+        }
+        Expression::IfExpression(condition, consequence, alternative, _) => {
+            line(out, depth, "if", span);
+            write_expression(out, condition, depth + 1);
+            write_block(out, "then", consequence, depth + 1);
+            if !alternative.is_empty() {
+                write_block(out, "else", alternative, depth + 1);
+            }
+        }
+        Expression::FunctionExpression(params, body, _) => {
+            let params = params
+                .iter()
+                .map(|ident| ident.0.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ");
+            line(out, depth, &format!("fn({})", params), span);
+            for statement in body {
+                write_statement(out, statement, depth + 1);
+            }
+        }
+        Expression::CallExpression(function, args, _) => {
+            line(out, depth, "call", span);
+            write_expression(out, function, depth + 1);
+            for arg in args {
+                write_expression(out, arg, depth + 1);
+            }
+        }
+        Expression::ArrayLiteral(elements) => {
+            line(out, depth, "array", span);
+            for element in elements {
+                write_expression(out, element, depth + 1);
+            }
+        }
+        Expression::HashLiteral(pairs) => {
+            line(out, depth, "hash", span);
+            for (key, value) in pairs {
+                line(out, depth + 1, "pair", None);
+                write_expression(out, key, depth + 2);
+                write_expression(out, value, depth + 2);
+            }
+        }
+        Expression::IndexExpression(left, index, _) => {
+            line(out, depth, "index", span);
+            write_expression(out, left, depth + 1);
+            write_expression(out, index, depth + 1);
+        }
+    }
+}
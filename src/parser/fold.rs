@@ -0,0 +1,75 @@
+use crate::compiler::optimizer::fold_constant;
+use crate::eval::ObjectWrapper;
+use crate::parser::program::{Expression, Program, Statement};
+
+// Replaces the operators applied to literals by their value, `60 * 60`
+// becomes `3600`, for the tree-walking evaluator not to compute them again
+// on every run of a loop body. Only what cannot fail or have effects is
+// folded, the same as the compiler does, `1 / 0` is left to fail when it
+// runs. Evaluating the folded program takes fewer steps.
+pub fn fold(program: &mut Program) {
+    block(&mut program.statements);
+}
+
+fn block(statements: &mut [Statement]) {
+    for statement in statements {
+        match statement {
+            Statement::LetStatement(_, value, _)
+            | Statement::ReturnStatement(value, _)
+            | Statement::ExpressionStatement(value, _) => expression(value),
+        }
+    }
+}
+
+// the operands first, an operator is folded once they are literals
+fn expression(expression: &mut Expression) {
+    match expression {
+        Expression::Identifier(_)
+        | Expression::IntLiteral(_)
+        | Expression::FloatLiteral(_)
+        | Expression::BoolLiteral(_)
+        | Expression::StringLiteral(_) => {}
+        Expression::PrefixExpression(_, right, _) => {
+            self::expression(right);
+            fold_operator(expression);
+        }
+        Expression::InfixExpression(left, _, right, _) => {
+            self::expression(left);
+            self::expression(right);
+            fold_operator(expression);
+        }
+        Expression::IndexExpression(left, index, _) => {
+            self::expression(left);
+            self::expression(index);
+        }
+        Expression::IfExpression(condition, consequence, alternative, _) => {
+            self::expression(condition);
+            block(consequence);
+            block(alternative);
+        }
+        Expression::FunctionExpression(_, body, _) => block(body),
+        Expression::CallExpression(function, args, _) => {
+            self::expression(function);
+            args.iter_mut().for_each(self::expression);
+        }
+        Expression::ArrayLiteral(items) => items.iter_mut().for_each(self::expression),
+        Expression::HashLiteral(pairs) => {
+            for (key, value) in pairs {
+                self::expression(key);
+                self::expression(value);
+            }
+        }
+    }
+}
+
+fn fold_operator(expression: &mut Expression) {
+    let literal = match fold_constant(expression) {
+        Some(ObjectWrapper::Integer(v)) => Expression::IntLiteral(v),
+        // infinity and NaN have no literal
+        Some(ObjectWrapper::Float(v)) if v.is_finite() => Expression::FloatLiteral(v),
+        Some(ObjectWrapper::Boolean(v)) => Expression::BoolLiteral(v),
+        Some(ObjectWrapper::String(v)) => Expression::StringLiteral(v),
+        _ => return,
+    };
+    *expression = literal;
+}
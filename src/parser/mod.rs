@@ -1,57 +1,206 @@
+#[cfg(feature = "std")]
+use crate::diagnostics;
 use crate::lexer::lexer::Lexer;
-use crate::lexer::token::{Token, EOF_TOKEN};
+use crate::lexer::span::{Position, Span};
+use crate::lexer::token::{SourceToken, Token, EOF_TOKEN};
 use crate::parser::program::{Expression, Ident, Precedence, Program, Statement};
-use std::fmt::{Debug, Display, Formatter};
-
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::{vec, vec::Vec};
+use core::fmt::{Debug, Display, Formatter};
+
+pub mod diff;
+pub mod dump;
+#[cfg(feature = "std")]
+pub mod fold;
 pub mod program;
+pub mod resolve;
+pub mod visit;
 
 #[cfg(test)]
 mod test;
 
-pub type Result<T> = std::result::Result<T, ParseError>;
+pub type Result<T> = core::result::Result<T, ParseError>;
+
+// how deep expressions nest, operands of a chain of operators included
+pub const MAX_NESTING: usize = 256;
+
+// Whether more lines could still complete `input`: a bracket is left open, a
+// string is not terminated or it ends with an operator. The REPL keeps
+// reading then instead of reporting a parse error.
+pub fn is_incomplete(input: &str) -> bool {
+    let mut l = Lexer::new(input);
+    let mut depth = 0;
+    let mut last: SourceToken = Token::EOF;
+    loop {
+        let (token, span) = l.next_source_token();
+        match token {
+            Token::EOF => break,
+            Token::Illegal if input[span.start.offset..].starts_with('"') => return true,
+            Token::LParen | Token::LBracket | Token::LBrace => depth += 1,
+            Token::RParen | Token::RBracket | Token::RBrace => depth -= 1,
+            _ => {}
+        }
+        last = token;
+    }
+    depth > 0
+        || matches!(
+            last,
+            Token::Let
+                | Token::Function
+                | Token::If
+                | Token::Else
+                | Token::Assign
+                | Token::Plus
+                | Token::Minus
+                | Token::Bang
+                | Token::Asterisk
+                | Token::Slash
+                | Token::LT
+                | Token::GT
+                | Token::Comma
+                | Token::Colon
+                | Token::Eq
+                | Token::NotEq
+        )
+}
 
-pub struct Parser {
-    l: Lexer,
+pub struct Parser<'src> {
+    l: Lexer<'src>,
     cur_token: Token,
     peek_token: Token,
+    cur_span: Span,
+    peek_span: Span,
+    // brackets left open up to and including the current token
+    depth: usize,
+    // read from the lexer so far, traced
+    tokens: usize,
+    // depth of the expression being parsed, see MAX_NESTING
+    nesting: usize,
 }
 
+// Used for parse, compile and vm errors, `span` is where in the source the
+// error is if that is known, `trace` the calls of a runtime error. They are
+// boxed to keep results small, the evaluator's stack frames grow with them.
 pub struct ParseError {
     info: String,
+    span: Option<Box<Span>>,
+    // most errors have none, a `Vec` would take three words in all of them
+    #[allow(clippy::box_collection)]
+    trace: Option<Box<Vec<CallFrame>>>,
+}
+
+// a call a runtime error was raised in, see `ParseError::trace`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallFrame {
+    // the name it was called by, `<anonymous>` for a function called as it
+    // is defined or returned
+    pub function: String,
+    pub span: Span,
+}
+
+impl ParseError {
+    pub fn new<S: Into<String>>(info: S, span: Span) -> Self {
+        ParseError {
+            info: info.into(),
+            span: Some(Box::new(span)),
+            trace: None,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.info
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.span.as_deref().copied()
+    }
+
+    // keeps the span the error already has
+    pub fn or_span(mut self, span: Span) -> Self {
+        self.span.get_or_insert_with(|| Box::new(span));
+        self
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(Box::new(span));
+        self
+    }
+
+    // the calls the error was raised in, innermost first, each at the call
+    pub fn trace(&self) -> &[CallFrame] {
+        self.trace.as_deref().map_or(&[], |trace| trace)
+    }
+
+    pub fn in_call(mut self, frame: CallFrame) -> Self {
+        self.trace.get_or_insert_with(Default::default).push(frame);
+        self
+    }
+
+    // for an error reported at a call, what happened inside is left out
+    pub fn without_trace(mut self) -> Self {
+        self.trace = None;
+        self
+    }
+
+    // the message with the source line the error is at, see
+    // `diagnostics::render`
+    #[cfg(feature = "std")]
+    pub fn render(&self, source: &str, path: Option<&str>) -> String {
+        match self.span() {
+            Some(span) => diagnostics::render("error", &self.info, source, span, path),
+            None => diagnostics::render_message("error", &self.info, path),
+        }
+    }
 }
 
 impl From<&str> for ParseError {
     fn from(s: &str) -> Self {
-        ParseError { info: s.to_owned() }
+        ParseError {
+            info: s.to_owned(),
+            span: None,
+            trace: None,
+        }
     }
 }
 
 impl From<String> for ParseError {
     fn from(s: String) -> Self {
-        ParseError { info: s }
+        ParseError {
+            info: s,
+            span: None,
+            trace: None,
+        }
     }
 }
 
 impl Debug for ParseError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", self.info)
     }
 }
 
 impl Display for ParseError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", self.info)
     }
 }
 
-impl std::error::Error for ParseError {}
+impl core::error::Error for ParseError {}
 
-impl Parser {
-    pub fn new(l: Lexer) -> Self {
+impl<'src> Parser<'src> {
+    pub fn new(l: Lexer<'src>) -> Self {
         let mut ret = Parser {
             l,
             cur_token: EOF_TOKEN,
             peek_token: EOF_TOKEN,
+            cur_span: Span::default(),
+            peek_span: Span::default(),
+            depth: 0,
+            tokens: 0,
+            nesting: 0,
         };
         ret.next_token();
         ret.next_token();
@@ -60,8 +209,19 @@ impl Parser {
     }
 
     pub fn next_token(&mut self) {
-        std::mem::swap(&mut self.cur_token, &mut self.peek_token);
-        self.peek_token = self.l.next_token();
+        self.tokens += 1;
+        core::mem::swap(&mut self.cur_token, &mut self.peek_token);
+        self.cur_span = self.peek_span;
+        match self.cur_token {
+            Token::LParen | Token::LBracket | Token::LBrace => self.depth += 1,
+            Token::RParen | Token::RBracket | Token::RBrace => {
+                self.depth = self.depth.saturating_sub(1)
+            }
+            _ => {}
+        }
+        let (token, span) = self.l.next_token_with_span();
+        self.peek_token = token;
+        self.peek_span = span;
     }
 
     pub fn expect_peek(&mut self, token: Token) -> bool {
@@ -80,7 +240,20 @@ impl Parser {
         }
     }
 
+    // Moves the current token out for a node to own it, it is not to be
+    // looked at again before the next one is read.
+    fn take_token(&mut self) -> Token {
+        core::mem::replace(&mut self.cur_token, EOF_TOKEN)
+    }
+
+    // how many tokens were read so far
+    pub fn tokens(&self) -> usize {
+        self.tokens
+    }
+
     pub fn parse_program(&mut self) -> Result<Program> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("parse").entered();
         let mut ret = Program::default();
         loop {
             // println!("[parse loop] current token is {:?}", self.cur_token);
@@ -93,9 +266,67 @@ impl Parser {
 
             self.next_token();
         }
+        ret.comments = self.l.comments().to_vec();
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            tokens = self.tokens(),
+            statements = ret.statements.len(),
+            "parsed"
+        );
         Ok(ret)
     }
 
+    // Like `parse_program`, but goes on after an error with the next top level
+    // statement, so all of them are reported. The program is what could be
+    // parsed.
+    pub fn parse_program_with_errors(&mut self) -> (Program, Vec<ParseError>) {
+        let mut ret = Program::default();
+        let mut errors = vec![];
+        while !self.cur_token.is_eof() {
+            let start = self.cur_span.start;
+            match self.parse_statement() {
+                Ok(statement) => {
+                    ret.statements.push(statement);
+                    self.next_token();
+                }
+                Err(e) => {
+                    errors.push(e);
+                    self.skip_statement(start);
+                }
+            }
+        }
+        ret.comments = self.l.comments().to_vec();
+        (ret, errors)
+    }
+
+    // skips to the next top level `let` or `return`, or past the next top
+    // level semicolon, whichever comes first after `start`
+    fn skip_statement(&mut self, start: Position) {
+        while !self.cur_token.is_eof() {
+            if self.depth == 0 {
+                match self.cur_token {
+                    Token::Semicolon => {
+                        self.next_token();
+                        return;
+                    }
+                    Token::Let | Token::Return if self.cur_span.start > start => return,
+                    _ => {}
+                }
+            }
+            self.next_token();
+        }
+    }
+
+    // at the token the parser stopped on
+    fn cur_error<S: Into<String>>(&self, info: S) -> ParseError {
+        ParseError::new(info, self.cur_span)
+    }
+
+    // at the token the parser expected something else instead of
+    fn peek_error<S: Into<String>>(&self, info: S) -> ParseError {
+        ParseError::new(info, self.peek_span)
+    }
+
     fn parse_statement(&mut self) -> Result<Statement> {
         match self.cur_token {
             Token::Let => self.parse_let_statement(),
@@ -105,13 +336,14 @@ impl Parser {
     }
 
     fn parse_let_statement(&mut self) -> Result<Statement> {
+        let start = self.cur_span;
         if let Token::Ident(_) = &self.peek_token {
             self.next_token();
         }
         let identifier = self.parse_identifier()?;
 
         if !self.expect_peek(Token::Assign) {
-            return Err("no equal sign!".into());
+            return Err(self.peek_error("no equal sign!"));
         }
 
         self.next_token();
@@ -120,10 +352,15 @@ impl Parser {
         if self.peek_token == Token::Semicolon {
             self.next_token();
         }
-        Ok(Statement::LetStatement(identifier, value))
+        Ok(Statement::LetStatement(
+            identifier,
+            value,
+            start.to(self.cur_span),
+        ))
     }
 
     fn parse_return_statement(&mut self) -> Result<Statement> {
+        let start = self.cur_span;
         self.next_token();
 
         let ret = self.parse_expression(Precedence::Lowest)?;
@@ -131,26 +368,46 @@ impl Parser {
             self.next_token();
         }
 
-        Ok(Statement::ReturnStatement(ret))
+        Ok(Statement::ReturnStatement(ret, start.to(self.cur_span)))
     }
 
     fn parse_expression_statement(&mut self) -> Result<Statement> {
+        let start = self.cur_span;
         let ret = self.parse_expression(Precedence::Lowest)?;
         if self.peek_token == Token::Semicolon {
             self.next_token();
         }
 
-        Ok(Statement::ExpressionStatement(ret))
+        Ok(Statement::ExpressionStatement(ret, start.to(self.cur_span)))
     }
 
     fn parse_identifier(&mut self) -> Result<Ident> {
         match &self.cur_token {
-            Token::Ident(v) => Ok(Ident(v.clone())),
-            _ => Err("not a ident token".into()),
+            Token::Ident(v) => Ok(Ident::new(v.clone(), self.cur_span)),
+            _ => Err(self.cur_error("not a ident token")),
         }
     }
 
     fn parse_expression(&mut self, precedence: Precedence) -> Result<Expression> {
+        let nesting = self.nesting;
+        let ret = self.parse_nested_expression(precedence);
+        self.nesting = nesting;
+        ret
+    }
+
+    // deeper expressions are an error rather than a stack overflow here or
+    // where the tree is walked
+    fn nest(&mut self) -> Result<()> {
+        self.nesting += 1;
+        if self.nesting > MAX_NESTING {
+            return Err(self.cur_error("expression nested too deeply"));
+        }
+        Ok(())
+    }
+
+    fn parse_nested_expression(&mut self, precedence: Precedence) -> Result<Expression> {
+        self.nest()?;
+        let start = self.cur_span;
         // prefix
         let mut left = match &self.cur_token {
             Token::Ident(_) => {
@@ -158,15 +415,12 @@ impl Parser {
                 Ok(Expression::Identifier(ident))
             }
             Token::Int(_) => self.parse_int_literal(),
+            Token::Float(_) => self.parse_float_literal(),
             Token::Bool(_) => self.parse_bool_literal(),
             Token::String(_) => self.parse_string_literal(),
             Token::Bang | Token::Minus => {
                 if precedence > Precedence::Prefix {
-                    Err(format!(
-                        "'(' expected after prefix '{}'",
-                        &self.cur_token.to_string()
-                    )
-                    .into())
+                    Err(self.cur_error(format!("'(' expected after prefix '{}'", &self.cur_token)))
                 } else {
                     self.parse_prefix_expression()
                 }
@@ -176,9 +430,7 @@ impl Parser {
             Token::Function => self.parse_function_literal(),
             Token::LBracket => self.parse_array_literal(),
             Token::LBrace => self.parse_hash_literal(),
-            _ => Err(format!("no prefix parse function for {:?}", self.cur_token)
-                .as_str()
-                .into()),
+            _ => Err(self.cur_error(format!("no prefix parse function for {:?}", self.cur_token))),
         }?;
 
         // infix
@@ -190,6 +442,7 @@ impl Parser {
             }
 
             self.next_token();
+            self.nest()?;
             match self.cur_token {
                 Token::Eq
                 | Token::NotEq
@@ -198,9 +451,9 @@ impl Parser {
                 | Token::Plus
                 | Token::Minus
                 | Token::Slash
-                | Token::Asterisk => left = self.parse_infix_expression(left)?,
-                Token::LParen => left = self.parse_call_expression(left)?,
-                Token::LBracket => left = self.parse_index_expression(left)?,
+                | Token::Asterisk => left = self.parse_infix_expression(left, start)?,
+                Token::LParen => left = self.parse_call_expression(left, start)?,
+                Token::LBracket => left = self.parse_index_expression(left, start)?,
                 _ => return Ok(left),
             };
         }
@@ -212,7 +465,15 @@ impl Parser {
         if let Token::Int(v) = self.cur_token {
             Ok(Expression::IntLiteral(v))
         } else {
-            Err("Token::Int not found".into())
+            Err(self.cur_error("Token::Int not found"))
+        }
+    }
+
+    fn parse_float_literal(&self) -> Result<Expression> {
+        if let Token::Float(v) = self.cur_token {
+            Ok(Expression::FloatLiteral(v))
+        } else {
+            Err(self.cur_error("Token::Float not found"))
         }
     }
 
@@ -220,20 +481,21 @@ impl Parser {
         if let Token::Bool(v) = self.cur_token {
             Ok(Expression::BoolLiteral(v))
         } else {
-            Err("Token::Bool not found".into())
+            Err(self.cur_error("Token::Bool not found"))
         }
     }
 
-    fn parse_string_literal(&self) -> Result<Expression> {
-        if let Token::String(v) = &self.cur_token {
-            Ok(Expression::StringLiteral(v.clone()))
+    fn parse_string_literal(&mut self) -> Result<Expression> {
+        if let Token::String(v) = &mut self.cur_token {
+            Ok(Expression::StringLiteral(core::mem::take(v).into()))
         } else {
-            Err("Token::String not found".into())
+            Err(self.cur_error("Token::String not found"))
         }
     }
 
     fn parse_prefix_expression(&mut self) -> Result<Expression> {
-        let token = self.cur_token.clone();
+        let start = self.cur_span;
+        let token = self.take_token();
         let precedence = match &token {
             Token::Minus => Precedence::Prefix.add(1),
             _ => Precedence::Prefix,
@@ -241,23 +503,29 @@ impl Parser {
         self.next_token();
 
         let right = self.parse_expression(precedence)?;
-        Ok(Expression::PrefixExpression(token, Box::new(right)))
+        Ok(Expression::PrefixExpression(
+            token,
+            Box::new(right),
+            start.to(self.cur_span),
+        ))
     }
 
-    fn parse_infix_expression(&mut self, left: Expression) -> Result<Expression> {
+    fn parse_infix_expression(&mut self, left: Expression, start: Span) -> Result<Expression> {
         let precedence = Precedence::from_token(&self.cur_token);
-        let token = self.cur_token.clone();
+        let token = self.take_token();
         self.next_token();
 
-        let right = match &token {
-            // to make '+' right-associate
-            // Token::Plus => self.parse_expression(precedence.sub(1))?,
-            _ => self.parse_expression(precedence)?,
-        };
+        // to make '+' right-associate
+        // let right = match &token {
+        //     Token::Plus => self.parse_expression(precedence.sub(1))?,
+        //     _ => self.parse_expression(precedence)?,
+        // };
+        let right = self.parse_expression(precedence)?;
         Ok(Expression::InfixExpression(
             Box::new(left),
             token,
             Box::new(right),
+            start.to(self.cur_span),
         ))
     }
 
@@ -267,37 +535,45 @@ impl Parser {
         let exp = self.parse_expression(Precedence::Lowest)?;
 
         if !self.expect_peek(Token::RParen) {
-            return Err("Right parentheses expected".into());
+            return Err(self.peek_error("Right parentheses expected"));
         }
 
-        return Ok(exp);
+        Ok(exp)
     }
 
     fn parse_if_expression(&mut self) -> Result<Expression> {
+        let start = self.cur_span;
         if !self.expect_peek(Token::LParen) {
-            return Err("'(' expected after 'if'.".into());
+            return Err(self.peek_error("'(' expected after 'if'."));
         }
         self.next_token();
         let condition = self.parse_expression(Precedence::Lowest)?;
 
         if !self.expect_peek(Token::RParen) {
-            return Err("')' expected after if condition expression".into());
+            return Err(self.peek_error("')' expected after if condition expression"));
         }
 
         if !self.expect_peek(Token::LBrace) {
-            return Err("'{' expected for block.".into());
+            return Err(self.peek_error("'{' expected for block."));
         }
 
         let consequence = self.parse_block_statement()?;
 
-        let alternative = match self.peek_token {
-            _ => vec![],
+        let alternative = if self.peek_token == Token::Else {
+            self.next_token();
+            if !self.expect_peek(Token::LBrace) {
+                return Err(self.peek_error("'{' expected after 'else'."));
+            }
+            self.parse_block_statement()?
+        } else {
+            vec![]
         };
-        return Ok(Expression::IfExpression(
+        Ok(Expression::IfExpression(
             Box::new(condition),
             consequence,
             alternative,
-        ));
+            start.to(self.cur_span),
+        ))
     }
 
     fn parse_block_statement(&mut self) -> Result<Vec<Statement>> {
@@ -315,25 +591,30 @@ impl Parser {
 
     fn parse_function_literal(&mut self) -> Result<Expression> {
         // TODO! 支持function名称
+        let start = self.cur_span;
         if !self.expect_peek(Token::LParen) {
-            return Err("'(' expected for function expression".into());
+            return Err(self.peek_error("'(' expected for function expression"));
         }
 
         let params = self.parse_function_parameters()?;
 
         if !self.expect_peek(Token::LBrace) {
-            return Err("'{' expected for function body.".into());
+            return Err(self.peek_error("'{' expected for function body."));
         }
 
         let sts = self.parse_block_statement()?;
-        Ok(Expression::FunctionExpression(params, sts))
+        Ok(Expression::FunctionExpression(
+            params,
+            sts,
+            start.to(self.cur_span),
+        ))
     }
 
     fn parse_array_literal(&mut self) -> Result<Expression> {
         let elements = self.parse_expression_list(&Token::RBracket)?;
 
         if !self.expect_peek(Token::RBracket) {
-            return Err("']' expected for array definition.".into());
+            return Err(self.peek_error("']' expected for array definition."));
         }
 
         Ok(Expression::ArrayLiteral(elements))
@@ -346,20 +627,22 @@ impl Parser {
             let key = self.parse_expression(Precedence::Lowest)?;
 
             if !self.expect_peek(Token::Colon) {
-                return Err("':' expected in Hash element.".into());
+                return Err(self.peek_error("':' expected in Hash element."));
             }
 
             self.next_token();
             let value = self.parse_expression(Precedence::Lowest)?;
             ret.push((key, value));
 
-            if self.peek_token != Token::RBrace && self.peek_token != Token::Comma {
-                return Err("'}' or ',' expected in Hash element.".into());
+            if self.peek_token == Token::Comma {
+                self.next_token();
+            } else if self.peek_token != Token::RBrace {
+                return Err(self.peek_error("'}' or ',' expected in Hash element."));
             }
         }
 
         if !self.expect_peek(Token::RBrace) {
-            return Err("'}' expected for Hash end.".into());
+            return Err(self.peek_error("'}' expected for Hash end."));
         }
 
         Ok(Expression::HashLiteral(ret))
@@ -369,7 +652,6 @@ impl Parser {
         let mut ret = vec![];
 
         if self.peek_token.eq(end) {
-            self.next_token();
             return Ok(ret);
         }
 
@@ -378,6 +660,9 @@ impl Parser {
 
         while self.peek_token.eq(&Token::Comma) {
             self.next_token(); // comma
+            if self.peek_token.eq(end) {
+                break; // trailing comma
+            }
             self.next_token(); // next argument
             ret.push(self.parse_expression(Precedence::Lowest)?);
         }
@@ -396,7 +681,7 @@ impl Parser {
 
         loop {
             if let Token::Ident(v) = &self.cur_token {
-                ret.push(Ident(v.clone()));
+                ret.push(Ident::new(v.clone(), self.cur_span));
             }
 
             if self.peek_token != Token::Comma {
@@ -404,20 +689,25 @@ impl Parser {
             }
 
             self.next_token(); // comma
+            if self.peek_token == Token::RParen {
+                break; // trailing comma
+            }
             self.next_token(); // next ident
         }
 
         if !self.expect_peek(Token::RParen) {
-            return Err("')' expected for function parameters expression.".into());
+            return Err(self.peek_error("')' expected for function parameters expression."));
         }
 
         Ok(ret)
     }
 
-    fn parse_call_expression(&mut self, function: Expression) -> Result<Expression> {
+    fn parse_call_expression(&mut self, function: Expression, start: Span) -> Result<Expression> {
+        let arguments = self.parse_call_arguments()?;
         Ok(Expression::CallExpression(
             Box::new(function),
-            self.parse_call_arguments()?,
+            arguments,
+            start.to(self.cur_span),
         ))
     }
 
@@ -425,19 +715,23 @@ impl Parser {
         let ret = self.parse_expression_list(&Token::RParen)?;
 
         if !self.expect_peek(Token::RParen) {
-            return Err("')' expected for function call.".into());
+            return Err(self.peek_error("')' expected for function call."));
         }
 
         Ok(ret)
     }
 
-    fn parse_index_expression(&mut self, left: Expression) -> Result<Expression> {
+    fn parse_index_expression(&mut self, left: Expression, start: Span) -> Result<Expression> {
         self.next_token();
         let index = self.parse_expression(Precedence::Lowest)?;
 
         if !self.expect_peek(Token::RBracket) {
-            return Err("']' expected for index end.".into());
+            return Err(self.peek_error("']' expected for index end."));
         }
-        Ok(Expression::IndexExpression(Box::new(left), Box::new(index)))
+        Ok(Expression::IndexExpression(
+            Box::new(left),
+            Box::new(index),
+            start.to(self.cur_span),
+        ))
     }
 }
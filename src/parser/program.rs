@@ -1,66 +1,107 @@
+use crate::lexer::lexer::Comment;
+use crate::lexer::span::Span;
+use crate::lexer::symbol::Symbol;
 use crate::lexer::token::Token;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::{vec, vec::Vec};
+use core::fmt::{Display, Formatter};
+use serde::Serialize;
 
-#[derive(PartialEq, Debug, Clone, Eq)]
-pub struct Ident(pub String);
+#[derive(PartialEq, Debug, Clone, Eq, Serialize)]
+pub struct Ident(pub Symbol, pub Span, #[serde(skip)] pub Slot);
 
-#[derive(PartialEq, Debug, Clone, Eq)]
+impl Ident {
+    pub fn new(name: Symbol, span: Span) -> Self {
+        Ident(name, span, Slot::Name)
+    }
+}
+
+// Where the evaluator finds what a name is bound to, see `resolve`. Names
+// the resolver did not see are looked up by name.
+#[derive(PartialEq, Debug, Clone, Copy, Eq, Default)]
+pub enum Slot {
+    // a global, or not resolved
+    #[default]
+    Name,
+    // the `index`th local of the function call `depth` calls out
+    Local {
+        depth: u32,
+        index: u32,
+    },
+}
+
+// statements span from their first token up to the optional ';'
+#[derive(PartialEq, Debug, Clone, Eq, Serialize)]
 pub enum Statement {
-    LetStatement(Ident, Expression),
-    ReturnStatement(Expression),
-    ExpressionStatement(Expression),
+    LetStatement(Ident, Expression, Span),
+    ReturnStatement(Expression, Span),
+    ExpressionStatement(Expression, Span),
 }
 
 impl Statement {
-    pub fn to_string(&self) -> String {
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::LetStatement(_, _, span)
+            | Statement::ReturnStatement(_, span)
+            | Statement::ExpressionStatement(_, span) => *span,
+        }
+    }
+}
+
+impl Display for Statement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
-            Statement::LetStatement(ident, expression) => {
-                format!("let {} = {};", ident.0, expression.to_string())
+            Statement::LetStatement(ident, expression, _) => {
+                write!(f, "let {} = {};", ident.0, expression)
             }
-            Statement::ExpressionStatement(expr) => {
-                expr.to_string()
+            Statement::ReturnStatement(expr, _) => write!(f, "return {};", expr),
+            Statement::ExpressionStatement(expr, _) => {
+                write!(f, "{}", expr)
                 // format!("{};", expr.to_string())
             }
-            _ => "".to_string(),
         }
     }
 }
 
-// TODO 这个结构可能是多余的定义，也许可以直接使用Vec<Statement>代替，后面再确认
-#[derive(PartialEq, Debug, Clone, Eq)]
-pub struct BlockStatement {
-    statements: Vec<Statement>,
-}
-
-#[derive(PartialEq, Debug, Clone)]
+// literals carry no span, everything that can fail at runtime does
+#[derive(PartialEq, Debug, Clone, Serialize)]
 pub enum Expression {
     Identifier(Ident),
     IfExpression(
         Box<Expression>, /* condition */
         Vec<Statement>,  /* consequence */
         Vec<Statement>,  /* alternative */
+        Span,
     ),
     CallExpression(
         Box<Expression>, /* function */
         Vec<Expression>, /* parameters */
+        Span,
     ),
     FunctionExpression(
         Vec<Ident>,     /* arguments */
         Vec<Statement>, /* body */
+        Span,
     ),
     IntLiteral(i64),
     FloatLiteral(f64),
     BoolLiteral(bool),
-    StringLiteral(String),
-    PrefixExpression(Token, Box<Expression>),
+    StringLiteral(Arc<str>),
+    PrefixExpression(Token, Box<Expression>, Span),
     InfixExpression(
         Box<Expression>, /* left */
         Token,           /* operator */
         Box<Expression>, /* right */
+        Span,
     ),
     ArrayLiteral(Vec<Expression>),
     IndexExpression(
         Box<Expression>, /* left */
         Box<Expression>, /* index */
+        Span,
     ),
     HashLiteral(Vec<(Expression, Expression)>),
 }
@@ -68,55 +109,84 @@ pub enum Expression {
 impl Eq for Expression {}
 
 impl Expression {
-    pub fn to_string(&self) -> String {
+    pub fn span(&self) -> Option<Span> {
         match self {
-            Expression::Identifier(ident) => ident.0.clone(),
-            Expression::IntLiteral(v) => v.to_string(),
-            Expression::BoolLiteral(v) => v.to_string(),
-            Expression::PrefixExpression(prefix, right) => {
-                format!("({}{})", prefix.to_string(), right.to_string())
-            }
-            Expression::InfixExpression(left, operator, right) => {
-                format!(
-                    "({} {} {})",
-                    left.to_string(),
-                    operator.to_string(),
-                    right.to_string()
-                )
+            Expression::Identifier(ident) => Some(ident.1),
+            Expression::IfExpression(_, _, _, span)
+            | Expression::CallExpression(_, _, span)
+            | Expression::FunctionExpression(_, _, span)
+            | Expression::PrefixExpression(_, _, span)
+            | Expression::InfixExpression(_, _, _, span)
+            | Expression::IndexExpression(_, _, span) => Some(*span),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Expression::Identifier(ident) => f.write_str(&ident.0),
+            Expression::IntLiteral(v) => write!(f, "{}", v),
+            Expression::FloatLiteral(v) => write!(f, "{:?}", v),
+            Expression::BoolLiteral(v) => write!(f, "{}", v),
+            Expression::PrefixExpression(prefix, right, _) => write!(f, "({}{})", prefix, right),
+            Expression::InfixExpression(left, operator, right, _) => {
+                write!(f, "({} {} {})", left, operator, right)
             }
-            Expression::CallExpression(function, params) => {
+            Expression::CallExpression(function, params, _) => {
                 let params_str = params
                     .iter()
                     .map(|expr| expr.to_string())
                     .collect::<Vec<String>>()
                     .join(", ");
-                format!("{}({})", function.to_string(), params_str)
+                write!(f, "{}({})", function, params_str)
             }
-            Expression::StringLiteral(v) => v.clone(),
+            Expression::StringLiteral(v) => f.write_str(v),
             Expression::ArrayLiteral(array) => {
                 let array_str = array
                     .iter()
                     .map(|expr| expr.to_string())
                     .collect::<Vec<String>>()
                     .join(", ");
-                format!("[{}]", array_str)
-            }
-            Expression::IndexExpression(left, index) => {
-                format!("({}[{}])", left.to_string(), index.to_string())
+                write!(f, "[{}]", array_str)
             }
+            Expression::IndexExpression(left, index, _) => write!(f, "({}[{}])", left, index),
             Expression::HashLiteral(list) => {
                 let map_str = list
                     .iter()
-                    .map(|(left, right)| format!("{}: {}", left.to_string(), right.to_string()))
+                    .map(|(left, right)| format!("{}: {}", left, right))
                     .collect::<Vec<String>>()
                     .join(", ");
-                format!("{{{}}}", map_str)
+                write!(f, "{{{}}}", map_str)
+            }
+            Expression::IfExpression(condition, consequence, alternative, _) => {
+                write!(f, "if {} {{ {} }}", condition, block_str(consequence))?;
+                if !alternative.is_empty() {
+                    write!(f, " else {{ {} }}", block_str(alternative))?;
+                }
+                Ok(())
+            }
+            Expression::FunctionExpression(params, body, _) => {
+                let params_str = params
+                    .iter()
+                    .map(|ident| ident.0.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(", ");
+                write!(f, "fn({}) {{ {} }}", params_str, block_str(body))
             }
-            _ => "".to_string(),
         }
     }
 }
 
+fn block_str(statements: &[Statement]) -> String {
+    statements
+        .iter()
+        .map(|st| st.to_string())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
 #[derive(PartialEq, PartialOrd, Debug, Eq, Clone)]
 pub enum Precedence {
     Lowest,
@@ -129,18 +199,49 @@ pub enum Precedence {
     Index,       // array[index]
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize)]
 pub struct Program {
-    pub(crate) statements: Vec<Statement>,
+    pub statements: Vec<Statement>,
+    // every comment of the source, in order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<Comment>,
 }
 
 impl Program {
-    pub fn to_string(&self) -> String {
-        let mut ret = String::new();
+    // The `///` comments on the lines right above `statement`, one line of
+    // text each.
+    pub fn doc(&self, statement: &Statement) -> Option<String> {
+        let start = statement.span().start;
+        let mut line = start.line;
+        let mut ret = vec![];
+        let above = self
+            .comments
+            .iter()
+            .rev()
+            .filter(|comment| comment.span.start.offset < start.offset);
+        for comment in above {
+            match comment.doc() {
+                Some(text) if comment.after.is_none() && comment.span.start.line + 1 == line => {
+                    ret.push(text);
+                    line = comment.span.start.line;
+                }
+                _ => break,
+            }
+        }
+        if ret.is_empty() {
+            return None;
+        }
+        ret.reverse();
+        Some(ret.join("\n"))
+    }
+}
+
+impl Display for Program {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         for st in &self.statements {
-            ret.push_str(&st.to_string());
+            write!(f, "{}", st)?;
         }
-        return ret;
+        Ok(())
     }
 }
 
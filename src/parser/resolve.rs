@@ -0,0 +1,139 @@
+use crate::lexer::symbol::Symbol;
+use crate::parser::program::{Expression, Ident, Program, Slot, Statement};
+use crate::parser::visit::{walk_expression, walk_statement, Visitor};
+use alloc::{vec, vec::Vec};
+
+// Gives the locals of each function, its parameters and what its body binds
+// with `let`, a slot in the environment of a call, and the uses of a local
+// how many calls out it is bound and at which slot, so the evaluator indexes
+// them instead of hashing names. Blocks are not scopes, a `let` anywhere in
+// a function body binds a local of the call. Globals stay looked up by name,
+// they are shared with the host and between programs. A slot that is still
+// empty when it is read, its `let` did not run yet, makes the evaluator look
+// the name up outside the call as it would without slots.
+pub fn resolve(program: &mut Program) {
+    Resolver::default().block(&mut program.statements);
+}
+
+#[derive(Default)]
+struct Resolver {
+    // the locals of the functions being resolved, by slot, innermost last
+    scopes: Vec<Vec<Symbol>>,
+}
+
+impl Resolver {
+    fn block(&mut self, statements: &mut [Statement]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &mut Statement) {
+        match statement {
+            Statement::LetStatement(ident, value, _) => {
+                self.expression(value);
+                self.bind(ident);
+            }
+            Statement::ReturnStatement(value, _) | Statement::ExpressionStatement(value, _) => {
+                self.expression(value)
+            }
+        }
+    }
+
+    fn expression(&mut self, expression: &mut Expression) {
+        match expression {
+            Expression::Identifier(ident) => self.reference(ident),
+            Expression::IntLiteral(_)
+            | Expression::FloatLiteral(_)
+            | Expression::BoolLiteral(_)
+            | Expression::StringLiteral(_) => {}
+            Expression::PrefixExpression(_, right, _) => self.expression(right),
+            Expression::InfixExpression(left, _, right, _)
+            | Expression::IndexExpression(left, right, _) => {
+                self.expression(left);
+                self.expression(right);
+            }
+            Expression::IfExpression(condition, consequence, alternative, _) => {
+                self.expression(condition);
+                self.block(consequence);
+                self.block(alternative);
+            }
+            Expression::FunctionExpression(params, body, _) => {
+                // all the locals first, a closure in the body may use one
+                // bound after it
+                let mut scope = vec![];
+                let mut declarations = Declarations(&mut scope);
+                params
+                    .iter()
+                    .for_each(|param| declarations.declare(param.0.clone()));
+                declarations.visit_block(body);
+                self.scopes.push(scope);
+                params.iter_mut().for_each(|param| self.bind(param));
+                self.block(body);
+                self.scopes.pop();
+            }
+            Expression::CallExpression(function, args, _) => {
+                self.expression(function);
+                args.iter_mut().for_each(|arg| self.expression(arg));
+            }
+            Expression::ArrayLiteral(items) => {
+                items.iter_mut().for_each(|item| self.expression(item))
+            }
+            Expression::HashLiteral(pairs) => {
+                for (key, value) in pairs {
+                    self.expression(key);
+                    self.expression(value);
+                }
+            }
+        }
+    }
+
+    // a `let` or a parameter, declared in the innermost scope
+    fn bind(&mut self, ident: &mut Ident) {
+        if let Some(scope) = self.scopes.last() {
+            let index = scope.iter().position(|name| *name == ident.0).unwrap();
+            ident.2 = Slot::Local {
+                depth: 0,
+                index: index as u32,
+            };
+        }
+    }
+
+    fn reference(&mut self, ident: &mut Ident) {
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if let Some(index) = scope.iter().position(|name| *name == ident.0) {
+                ident.2 = Slot::Local {
+                    depth: depth as u32,
+                    index: index as u32,
+                };
+                return;
+            }
+        }
+    }
+}
+
+// the names bound by `let` in a function body, not in the functions there
+pub(crate) struct Declarations<'a>(pub(crate) &'a mut Vec<Symbol>);
+
+impl Declarations<'_> {
+    pub(crate) fn declare(&mut self, name: Symbol) {
+        if !self.0.contains(&name) {
+            self.0.push(name);
+        }
+    }
+}
+
+impl Visitor for Declarations<'_> {
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement);
+        if let Statement::LetStatement(ident, _, _) = statement {
+            self.declare(ident.0.clone());
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        if !matches!(expression, Expression::FunctionExpression(..)) {
+            walk_expression(self, expression)
+        }
+    }
+}
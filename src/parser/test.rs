@@ -1,12 +1,35 @@
+#[cfg(feature = "std")]
+use crate::fmt::format_program;
 use crate::lexer::lexer::Lexer;
+use crate::lexer::span::{Position, Span};
 use crate::lexer::token::Token;
-use crate::parser::program::{Expression, Ident, Statement};
-use crate::parser::Parser;
+use crate::parser::diff::{self, AstChange, NodeKind};
+#[cfg(feature = "std")]
+use crate::parser::fold::fold;
+use crate::parser::program::{Expression, Ident, Slot, Statement};
+use crate::parser::resolve::resolve;
+use crate::parser::visit::{walk_expression, walk_program, Visitor};
+use crate::parser::{dump, is_incomplete, Parser};
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+
+// span of a single-line range, columns are 1-based
+#[cfg(test)]
+fn span(line: usize, line_offset: usize, start_column: usize, end_column: usize) -> Span {
+    let position = |column: usize| Position {
+        offset: line_offset + column - 1,
+        line,
+        column,
+    };
+    Span::new(position(start_column), position(end_column))
+}
 
 #[cfg(test)]
 fn check_let_statement(st: &Statement, name_expect: &str, value_expected: &Expression) -> bool {
-    if let Statement::LetStatement(name, v) = st {
-        name_expect.eq(&name.0) && v.eq(value_expected)
+    if let Statement::LetStatement(name, v, _) = st {
+        name.0 == name_expect && v.eq(value_expected)
     } else {
         false
     }
@@ -23,8 +46,8 @@ fn check_let_statement(st: &Statement, name_expect: &str, value_expected: &Expre
 // }
 
 #[cfg(test)]
-fn check_function_expression(st: &Statement, expects: &Vec<&str>) -> bool {
-    if let Statement::ExpressionStatement(Expression::FunctionExpression(params, _)) = st {
+fn check_function_expression(st: &Statement, expects: &[&str]) -> bool {
+    if let Statement::ExpressionStatement(Expression::FunctionExpression(params, _, _), _) = st {
         assert_eq!(expects.len(), params.len());
         for (i, param) in params.iter().enumerate() {
             assert_eq!(param.0, expects[i]);
@@ -45,12 +68,15 @@ fn test_let_statements() {
     let y = true;
     let foobar = y;
     ";
-    let name_values = vec![
+    let name_values = [
         ("x", Expression::IntLiteral(5)),
         ("y", Expression::IntLiteral(10)),
         ("foobar", Expression::IntLiteral(838383)),
         ("y", Expression::BoolLiteral(true)),
-        ("foobar", Expression::Identifier(Ident("y".into()))),
+        (
+            "foobar",
+            Expression::Identifier(Ident::new("y".into(), span(6, 75, 18, 19))),
+        ),
     ];
 
     let l = Lexer::new(input);
@@ -78,15 +104,20 @@ fn test_infix_expression() {
     assert_eq!(program.statements.len(), 1);
     assert_eq!(
         program.statements[0],
-        Statement::ExpressionStatement(Expression::InfixExpression(
-            Box::new(Expression::IntLiteral(4)),
-            Token::Plus,
-            Box::new(Expression::InfixExpression(
-                Box::new(Expression::IntLiteral(5)),
+        Statement::ExpressionStatement(
+            Expression::InfixExpression(
+                Box::new(Expression::InfixExpression(
+                    Box::new(Expression::IntLiteral(4)),
+                    Token::Plus,
+                    Box::new(Expression::IntLiteral(5)),
+                    span(1, 0, 1, 6),
+                )),
                 Token::Plus,
-                Box::new(Expression::IntLiteral(10))
-            ))
-        ))
+                Box::new(Expression::IntLiteral(10)),
+                span(1, 0, 1, 11),
+            ),
+            span(1, 0, 1, 12),
+        )
     );
 }
 
@@ -113,6 +144,10 @@ fn test_operator_precedence() {
             "((3 + (4 * 5)) == ((3 * 1) + (4 * 5)))",
         ),
         ("a + add(b * c) + d", "((a + add((b * c))) + d)"),
+        ("add()", "add()"),
+        ("[]", "[]"),
+        ("{}", "{}"),
+        ("{1: a + b, \"c\": d}", "{1: (a + b), c: d}"),
         (
             "add(a, b, 1, 2 * 3, 4 + 5, add(6, 7 * 8))",
             "add(a, b, 1, (2 * 3), (4 + 5), add(6, (7 * 8)))",
@@ -179,6 +214,22 @@ fn test_call_expression() {
     println!("{:?}", program.statements[0]);
 }
 
+#[test]
+fn test_trailing_comma() {
+    let cases = [
+        ("f(1, 2,)", "f(1, 2)"),
+        ("[1,]", "[1]"),
+        ("{1: 2,}", "{1: 2}"),
+        ("fn(a, b,) { a }", "fn(a, b) { a }"),
+    ];
+    for (input, expect) in cases {
+        let trailing = Parser::new(Lexer::new(input)).parse_program().unwrap();
+        let plain = Parser::new(Lexer::new(expect)).parse_program().unwrap();
+        assert_eq!(trailing.to_string(), plain.to_string(), "{}", input);
+    }
+    assert!(Parser::new(Lexer::new("f(,)")).parse_program().is_err());
+}
+
 #[test]
 fn test_string_literal() {
     let input = "\"hello world\";";
@@ -188,13 +239,341 @@ fn test_string_literal() {
     let program = p.parse_program().unwrap();
 
     assert_eq!(program.statements.len(), 1);
-    if let Statement::ExpressionStatement(Expression::StringLiteral(v)) = &program.statements[0] {
-        assert_eq!(v, "hello world");
+    if let Statement::ExpressionStatement(Expression::StringLiteral(v), _) = &program.statements[0]
+    {
+        assert_eq!(&**v, "hello world");
     } else {
-        assert!(
-            false,
+        panic!(
             "expect a string literal, but a {:?}",
             &program.statements[0]
         );
     }
 }
+
+#[test]
+fn test_statement_spans() {
+    let input = "let a = 1;\nadd(a,\n  2)\nreturn a";
+
+    let l = Lexer::new(input);
+    let mut p = Parser::new(l);
+    let program = p.parse_program().unwrap();
+
+    let spans = program
+        .statements
+        .iter()
+        .map(|st| {
+            let span = st.span();
+            (
+                span.start.line,
+                span.start.column,
+                span.end.line,
+                span.end.column,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(spans, vec![(1, 1, 1, 11), (2, 1, 3, 5), (4, 1, 4, 9)]);
+
+    if let Statement::ExpressionStatement(call, _) = &program.statements[1] {
+        let span = call.span().unwrap();
+        assert_eq!(&input[span.start.offset..span.end.offset], "add(a,\n  2)");
+    } else {
+        panic!("expect a call, but a {:?}", &program.statements[1]);
+    }
+}
+
+#[test]
+fn test_error_spans() {
+    let cases = [
+        ("let x 5;", "no equal sign!", (1, 7)),
+        (
+            "let a = 1;\nlet b = ;",
+            "no prefix parse function for Semicolon",
+            (2, 9),
+        ),
+        ("1 +\n  let", "no prefix parse function for Let", (2, 3)),
+    ];
+    for (input, message, (line, column)) in cases {
+        let e = match Parser::new(Lexer::new(input)).parse_program() {
+            Ok(_) => panic!("expect a parse error for {}", input),
+            Err(e) => e,
+        };
+        assert_eq!(e.message(), message, "{}", input);
+        let span = e.span().expect("parse errors have a span");
+        assert_eq!(
+            (span.start.line, span.start.column),
+            (line, column),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_is_incomplete() {
+    let cases = [
+        ("", false),
+        ("let a = 1;", false),
+        ("let f = fn(x) {", true),
+        ("let f = fn(x) {\n  x\n}", false),
+        ("[1, 2,", true),
+        ("{\"a\": 1}", false),
+        ("add(1, 2", true),
+        ("let a = 1 +", true),
+        ("let a =", true),
+        ("if (x) { 1 } else", true),
+        ("let s = \"abc", true),
+        ("let s = \"abc\"", false),
+        ("let a = 1)", false),
+    ];
+    for (input, expect) in cases {
+        assert_eq!(is_incomplete(input), expect, "{}", input);
+    }
+}
+
+#[test]
+fn test_parse_program_with_errors() {
+    let cases = [
+        ("let a = 1; a", vec![], 2),
+        (
+            "let a = ;\nlet b = 2;\nlet c 3;\nc",
+            vec![(1, 9), (3, 7)],
+            2,
+        ),
+        // the rest of a broken function is skipped
+        (
+            "let f = fn() {\n  let x = ;\n  x\n};\nf()",
+            vec![(2, 11)],
+            1,
+        ),
+        ("1 +\nlet x = 1;", vec![(2, 1)], 1),
+        ("let = 5; 6", vec![(1, 1)], 1),
+    ];
+    for (input, expect, statements) in cases {
+        let (program, errors) = Parser::new(Lexer::new(input)).parse_program_with_errors();
+        let errors = errors
+            .iter()
+            .map(|e| {
+                let span = e.span().unwrap();
+                (span.start.line, span.start.column)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(errors, expect, "{}", input);
+        assert_eq!(program.statements.len(), statements, "{}", input);
+    }
+}
+
+#[test]
+fn test_dump_tree() {
+    let input = "let f = fn(x) { if (x > 1) { [x][0] } else { -x } };\nf({\"a\": 1.5})";
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+    assert_eq!(
+        dump::tree(&program),
+        "\
+let f 1:1-1:53
+  fn(x) 1:9-1:52
+    expression 1:17-1:50
+      if 1:17-1:50
+        infix > 1:21-1:26
+          identifier x 1:21-1:22
+          int 1
+        then
+          expression 1:30-1:36
+            index 1:30-1:36
+              array
+                identifier x 1:31-1:32
+              int 0
+        else
+          expression 1:46-1:48
+            prefix - 1:46-1:48
+              identifier x 1:47-1:48
+expression 2:1-2:14
+  call 2:1-2:14
+    identifier f 2:1-2:2
+    hash
+      pair
+        string \"a\"
+        float 1.5
+"
+    );
+}
+
+#[test]
+fn test_serialize_program() {
+    let program = Parser::new(Lexer::new("a;")).parse_program().unwrap();
+    let span = |start, end| {
+        serde_json::json!({
+            "start": {"offset": start - 1, "line": 1, "column": start},
+            "end": {"offset": end - 1, "line": 1, "column": end},
+        })
+    };
+    assert_eq!(
+        serde_json::to_value(&program).unwrap(),
+        serde_json::json!({
+            "statements": [
+                {"ExpressionStatement": [{"Identifier": ["a", span(1, 2)]}, span(1, 3)]}
+            ]
+        })
+    );
+}
+
+// the changes as `kind line:column-line:column`, both spans for modified
+#[cfg(test)]
+fn describe_changes(old: &str, new: &str) -> Vec<String> {
+    let parse = |input| Parser::new(Lexer::new(input)).parse_program().unwrap();
+    let at = |span: Span| {
+        format!(
+            "{}:{}-{}:{}",
+            span.start.line, span.start.column, span.end.line, span.end.column
+        )
+    };
+    let kind = |kind| match kind {
+        NodeKind::Statement => "statement",
+        NodeKind::Expression => "expression",
+    };
+    diff::diff(&parse(old), &parse(new))
+        .into_iter()
+        .map(|change| match change {
+            AstChange::Inserted(k, span) => format!("inserted {} {}", kind(k), at(span)),
+            AstChange::Removed(k, span) => format!("removed {} {}", kind(k), at(span)),
+            AstChange::Modified { kind: k, old, new } => {
+                format!("modified {} {} {}", kind(k), at(old), at(new))
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_diff() {
+    let cases = vec![
+        ("let a = 1;\na;", "let a = 1;\n\n  a", vec![]),
+        ("let a = 1; // one", "let a = 1;", vec![]),
+        (
+            "let a = 1;",
+            "let a = 2;",
+            vec!["modified expression 1:1-1:11 1:1-1:11"],
+        ),
+        (
+            "let a = 1;\nlet c = 3;",
+            "let a = 1;\nlet b = 2;\nlet c = 3;",
+            vec!["inserted statement 2:1-2:11"],
+        ),
+        (
+            "let a = 1;\nlet b = 2;",
+            "let b = 2;",
+            vec!["removed statement 1:1-1:11"],
+        ),
+        (
+            "let a = 1;",
+            "let b = 1;",
+            vec!["removed statement 1:1-1:11", "inserted statement 1:1-1:11"],
+        ),
+        (
+            "let a = 1;",
+            "return 1;",
+            vec!["removed statement 1:1-1:11", "inserted statement 1:1-1:10"],
+        ),
+        (
+            "let f = fn(x) { x + y };",
+            "let f = fn(x) {\n  x * z\n};",
+            vec!["modified expression 1:17-1:22 2:3-2:8"],
+        ),
+        (
+            "let f = fn(x) { x + y };",
+            "let f = fn(x) { x + z };",
+            vec!["modified expression 1:21-1:22 1:21-1:22"],
+        ),
+        (
+            "let f = fn(x) { x };",
+            "let f = fn(x, y) { x };",
+            vec!["modified expression 1:9-1:20 1:9-1:23"],
+        ),
+        (
+            "if (a) { 1 } else { 2; 3 }",
+            "if (a) { 1 } else { 3 }",
+            vec!["removed statement 1:21-1:23"],
+        ),
+        ("f(a, c)", "f(a, b, c)", vec!["inserted expression 1:6-1:7"]),
+        ("[1, 2]", "[1, 2, 3]", vec!["inserted expression 1:1-1:10"]),
+        (
+            "{\"a\": 1, \"b\": 2}",
+            "{\"a\": 1, \"b\": x}",
+            vec!["modified expression 1:1-1:17 1:15-1:16"],
+        ),
+    ];
+    for (old, new, expected) in cases {
+        assert_eq!(
+            describe_changes(old, new),
+            expected,
+            "{:?} -> {:?}",
+            old,
+            new
+        );
+    }
+}
+
+#[test]
+fn test_token_count() {
+    let mut parser = Parser::new(Lexer::new("let a = 1; a"));
+    parser.parse_program().unwrap();
+    // `let a = 1 ; a`, then the end of the source as current and next token
+    assert_eq!(parser.tokens(), 8);
+}
+
+#[test]
+fn test_resolve() {
+    let mut program = Parser::new(Lexer::new(
+        "let g = 1; let f = fn(a) { let b = a; fn(c) { [a, b, c, g] } };",
+    ))
+    .parse_program()
+    .unwrap();
+    resolve(&mut program);
+    struct Slots(Vec<(String, Slot)>);
+    impl Visitor for Slots {
+        fn visit_expression(&mut self, expression: &Expression) {
+            if let Expression::Identifier(ident) = expression {
+                self.0.push((ident.0.to_string(), ident.2));
+            }
+            walk_expression(self, expression)
+        }
+    }
+    let mut slots = Slots(vec![]);
+    walk_program(&mut slots, &program);
+    let local = |depth, index| Slot::Local { depth, index };
+    assert_eq!(
+        slots.0,
+        [
+            ("a".to_string(), local(0, 0)),
+            ("a".to_string(), local(1, 0)),
+            ("b".to_string(), local(1, 1)),
+            ("c".to_string(), local(0, 0)),
+            ("g".to_string(), Slot::Name),
+        ]
+    );
+}
+
+// folding and formatting need std
+#[cfg(feature = "std")]
+#[test]
+fn test_fold() {
+    let cases = [
+        ("60 * 60 * x", "3600 * x;\n"),
+        ("x * 60 * 60", "x * 60 * 60;\n"),
+        ("-(1 + 2) < 0", "true;\n"),
+        ("\"a\" + \"b\" + 1", "\"ab1\";\n"),
+        ("1.5 * 2.0", "3.0;\n"),
+        ("f(1 + 1)[2 - 2]", "f(2)[0];\n"),
+        (
+            "let g = fn(n) { if (n > 2 * 5) { [n, !true] } }",
+            "let g = fn(n) {\n    if (n > 10) {\n        [n, false]\n    }\n};\n",
+        ),
+        // failing or without a literal, left as they are
+        ("1 / 0", "1 / 0;\n"),
+        ("9223372036854775807 + 1", "9223372036854775807 + 1;\n"),
+        ("1.0 / 0.0", "1.0 / 0.0;\n"),
+    ];
+    for (input, expect) in cases {
+        let mut program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+        fold(&mut program);
+        assert_eq!(format_program(&program), expect, "{}", input);
+    }
+}
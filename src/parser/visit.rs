@@ -0,0 +1,79 @@
+use crate::parser::program::{Expression, Program, Statement};
+
+// Walks the syntax tree. Each method visits the children of its node by
+// default, an implementation overrides the ones it is interested in and
+// calls the matching `walk_*` function to keep going down.
+pub trait Visitor {
+    // the statements of the program, a block or a function body
+    fn visit_block(&mut self, statements: &[Statement]) {
+        walk_block(self, statements)
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement)
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression)
+    }
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    visitor.visit_block(&program.statements);
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, statements: &[Statement]) {
+    for statement in statements {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    match statement {
+        Statement::LetStatement(_, value, _)
+        | Statement::ReturnStatement(value, _)
+        | Statement::ExpressionStatement(value, _) => visitor.visit_expression(value),
+    }
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &Expression) {
+    match expression {
+        Expression::Identifier(_)
+        | Expression::IntLiteral(_)
+        | Expression::FloatLiteral(_)
+        | Expression::BoolLiteral(_)
+        | Expression::StringLiteral(_) => {}
+        Expression::PrefixExpression(_, right, _) => visitor.visit_expression(right),
+        Expression::InfixExpression(left, _, right, _) => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(right);
+        }
+        Expression::IfExpression(condition, consequence, alternative, _) => {
+            visitor.visit_expression(condition);
+            visitor.visit_block(consequence);
+            visitor.visit_block(alternative);
+        }
+        Expression::FunctionExpression(_, body, _) => visitor.visit_block(body),
+        Expression::CallExpression(function, args, _) => {
+            visitor.visit_expression(function);
+            for arg in args {
+                visitor.visit_expression(arg);
+            }
+        }
+        Expression::ArrayLiteral(elements) => {
+            for element in elements {
+                visitor.visit_expression(element);
+            }
+        }
+        Expression::HashLiteral(pairs) => {
+            for (key, value) in pairs {
+                visitor.visit_expression(key);
+                visitor.visit_expression(value);
+            }
+        }
+        Expression::IndexExpression(left, index, _) => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(index);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+// Builtins shipped as shared libraries. A plugin is a `cdylib` depending on
+// this crate with a function registering its builtins, exported with
+// `monkey::export_plugin!(register)`. The application loads it with `load`
+// when it creates its interpreter. The `Interpreter` is passed as a Rust
+// type, so the plugin must be built with the same version of this crate and
+// the same compiler as the application, the version is checked.
+#![allow(clippy::missing_safety_doc)]
+
+use crate::interpreter::Interpreter;
+use crate::parser::Result;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::path::Path;
+
+#[cfg(test)]
+mod test;
+
+// the version of the crate a plugin was built with, nul-terminated
+pub const VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "\0");
+
+pub type VersionFn = unsafe extern "C" fn() -> *const c_char;
+pub type RegisterFn = unsafe extern "C" fn(&mut Interpreter);
+
+const VERSION_SYMBOL: &str = "monkey_plugin_version";
+const REGISTER_SYMBOL: &str = "monkey_plugin_register";
+
+// exports `register`, a `fn(&mut Interpreter)`, as the entry point of a
+// plugin
+#[macro_export]
+macro_rules! export_plugin {
+    ($register:path) => {
+        #[no_mangle]
+        pub extern "C" fn monkey_plugin_version() -> *const ::std::os::raw::c_char {
+            $crate::plugin::VERSION.as_ptr() as *const ::std::os::raw::c_char
+        }
+
+        #[no_mangle]
+        pub extern "C" fn monkey_plugin_register(interpreter: &mut $crate::Interpreter) {
+            $register(interpreter)
+        }
+    };
+}
+
+// Loads the plugin at `path` and lets it register its builtins with
+// `interpreter`. The library stays loaded until the process exits, the
+// functions it registered may outlive the interpreter. Loading runs the
+// code of the library, it must be trusted.
+pub unsafe fn load<P: AsRef<Path>>(interpreter: &mut Interpreter, path: P) -> Result<()> {
+    let path = path.as_ref();
+    let library = libloading::Library::new(path)
+        .map_err(|e| format!("cannot load {}: {}", path.display(), e))?;
+    let symbol = |name: &str| format!("{} is not a plugin, it has no {}", path.display(), name);
+    let version = library
+        .get::<VersionFn>(VERSION_SYMBOL.as_bytes())
+        .map_err(|_| symbol(VERSION_SYMBOL))?;
+    let version = CStr::from_ptr(version()).to_string_lossy();
+    let expect = VERSION.trim_end_matches('\0');
+    if version != expect {
+        return Err(format!(
+            "{} was built for monkey {}, expect {}",
+            path.display(),
+            version,
+            expect
+        )
+        .into());
+    }
+    let register = library
+        .get::<RegisterFn>(REGISTER_SYMBOL.as_bytes())
+        .map_err(|_| symbol(REGISTER_SYMBOL))?;
+    register(interpreter);
+    std::mem::forget(library);
+    Ok(())
+}
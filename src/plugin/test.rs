@@ -0,0 +1,41 @@
+use crate::eval::ObjectWrapper;
+use crate::interpreter::Interpreter;
+use crate::plugin::{self, VERSION};
+use std::ffi::CStr;
+
+fn register(interpreter: &mut Interpreter) {
+    interpreter.register_typed_fn("triple", |n: i64| n * 3);
+}
+
+crate::export_plugin!(register);
+
+#[test]
+fn test_export_plugin() {
+    let version = unsafe { CStr::from_ptr(monkey_plugin_version()) };
+    assert_eq!(version.to_bytes_with_nul(), VERSION.as_bytes());
+    let mut interpreter = Interpreter::new();
+    monkey_plugin_register(&mut interpreter);
+    assert_eq!(
+        interpreter.eval("triple(2)").unwrap(),
+        ObjectWrapper::Integer(6)
+    );
+}
+
+#[test]
+fn test_load_errors() {
+    let mut interpreter = Interpreter::new();
+    let e = unsafe { plugin::load(&mut interpreter, "/nonexistent/plugin.so") }.unwrap_err();
+    assert!(
+        e.message()
+            .starts_with("cannot load /nonexistent/plugin.so: "),
+        "{}",
+        e
+    );
+    if cfg!(target_os = "linux") {
+        let e = unsafe { plugin::load(&mut interpreter, "libc.so.6") }.unwrap_err();
+        assert_eq!(
+            e.message(),
+            "libc.so.6 is not a plugin, it has no monkey_plugin_version"
+        );
+    }
+}
@@ -0,0 +1,163 @@
+// `import monkey` from Python. Build the extension with `cargo rustc
+// --release --lib --crate-type cdylib --features python` and copy
+// libmonkey.so to monkey.so where Python finds it.
+use crate::diagnostics;
+use crate::eval::{HashMap, ObjectWrapper};
+use crate::parser::ParseError;
+use pyo3::exceptions::{PyException, PyTypeError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
+use pyo3::{create_exception, IntoPyObjectExt};
+use std::sync::Arc;
+
+#[cfg(test)]
+mod test;
+
+create_exception!(
+    monkey,
+    MonkeyError,
+    PyException,
+    "A script failed to parse or to run."
+);
+
+// Objects to and from Python: None, bools, ints, floats, strings, lists and
+// dicts with int, bool or string keys. Tuples become arrays too. Ints beyond
+// `i64`, functions and handles do not convert.
+
+impl<'py> IntoPyObject<'py> for ObjectWrapper {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> PyResult<Self::Output> {
+        match self {
+            ObjectWrapper::Null => Ok(py.None().into_bound(py)),
+            ObjectWrapper::Boolean(v) => v.into_bound_py_any(py),
+            ObjectWrapper::Integer(v) => v.into_bound_py_any(py),
+            ObjectWrapper::Float(v) => v.into_bound_py_any(py),
+            ObjectWrapper::String(v) => v.into_bound_py_any(py),
+            ObjectWrapper::Array(v) => {
+                PyList::new(py, Arc::unwrap_or_clone(v))?.into_bound_py_any(py)
+            }
+            ObjectWrapper::Hash(v) => {
+                let dict = PyDict::new(py);
+                // dicts keep their insertion order, sorted like printed
+                let mut pairs = v.into_iter().collect::<Vec<_>>();
+                pairs.sort_by(|a, b| a.0.cmp(&b.0));
+                for (key, value) in pairs {
+                    dict.set_item(ObjectWrapper::from(key), value)?;
+                }
+                dict.into_bound_py_any(py)
+            }
+            ObjectWrapper::ErrorObject(message) => Err(MonkeyError::new_err(message)),
+            obj => Err(PyTypeError::new_err(format!(
+                "cannot convert {} to python",
+                obj.type_str()
+            ))),
+        }
+    }
+}
+
+impl<'a, 'py> FromPyObject<'a, 'py> for ObjectWrapper {
+    type Error = PyErr;
+
+    fn extract(obj: Borrowed<'a, 'py, PyAny>) -> PyResult<Self> {
+        if obj.is_none() {
+            Ok(ObjectWrapper::Null)
+        } else if obj.is_instance_of::<PyBool>() {
+            Ok(ObjectWrapper::Boolean(obj.extract()?))
+        } else if obj.is_instance_of::<PyInt>() {
+            Ok(ObjectWrapper::Integer(obj.extract()?))
+        } else if obj.is_instance_of::<PyFloat>() {
+            Ok(ObjectWrapper::Float(obj.extract()?))
+        } else if obj.is_instance_of::<PyString>() {
+            Ok(ObjectWrapper::from(obj.extract::<String>()?))
+        } else if obj.is_instance_of::<PyList>() || obj.is_instance_of::<PyTuple>() {
+            obj.try_iter()?
+                .map(|item| item?.extract())
+                .collect::<PyResult<Vec<_>>>()
+                .map(|items| ObjectWrapper::Array(items.into()))
+        } else if let Ok(dict) = obj.cast::<PyDict>() {
+            let mut hash = HashMap::with_capacity_and_hasher(dict.len(), Default::default());
+            for (key, value) in dict.iter() {
+                let key = key
+                    .extract::<ObjectWrapper>()?
+                    .hash_key()
+                    .map_err(|e| PyTypeError::new_err(e.message().to_string()))?;
+                hash.insert(key, value.extract()?);
+            }
+            Ok(ObjectWrapper::Hash(hash))
+        } else {
+            Err(PyTypeError::new_err(format!(
+                "cannot convert {} to monkey",
+                obj.get_type().name()?
+            )))
+        }
+    }
+}
+
+// An interpreter keeping its bindings between `eval` calls, with Python
+// functions as builtins.
+#[pyclass(name = "Interpreter")]
+#[derive(Default)]
+pub struct PyInterpreter {
+    interpreter: crate::Interpreter,
+}
+
+#[pymethods]
+impl PyInterpreter {
+    #[new]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // what `source` evaluates to, MonkeyError with the rendered error if it
+    // fails
+    pub fn eval(&mut self, source: &str) -> PyResult<ObjectWrapper> {
+        match self.interpreter.eval(source) {
+            Ok(ObjectWrapper::ErrorObject(e)) => Err(MonkeyError::new_err(
+                diagnostics::render_message("error", &e, None),
+            )),
+            Ok(obj) => Ok(obj),
+            Err(e) => Err(MonkeyError::new_err(e.render(source, None))),
+        }
+    }
+
+    pub fn set_global(&mut self, name: &str, value: ObjectWrapper) {
+        self.interpreter.set_global(name, value);
+    }
+
+    pub fn get_global(&self, name: &str) -> PyResult<ObjectWrapper> {
+        self.interpreter
+            .env()
+            .get(name)
+            .ok_or_else(|| MonkeyError::new_err(format!("identifier not found: {}", name)))
+    }
+
+    // Makes `function` a builtin taking `arity` arguments. What it raises
+    // becomes an error of the script.
+    pub fn register(&mut self, name: &str, arity: usize, function: Py<PyAny>) {
+        self.interpreter.register_fn(name, arity, move |args| {
+            Python::attach(|py| {
+                PyTuple::new(py, args)
+                    .and_then(|args| function.call1(py, args))
+                    .and_then(|ret| ret.extract(py))
+                    .map_err(|e| ParseError::from(e.to_string()))
+            })
+        });
+    }
+}
+
+// `monkey.eval(source)`, evaluated by a new interpreter
+#[pyfunction]
+pub fn eval(source: &str) -> PyResult<ObjectWrapper> {
+    PyInterpreter::new().eval(source)
+}
+
+#[pymodule]
+fn monkey(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(eval, module)?)?;
+    module.add_class::<PyInterpreter>()?;
+    module.add("MonkeyError", module.py().get_type::<MonkeyError>())?;
+    Ok(())
+}
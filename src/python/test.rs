@@ -0,0 +1,57 @@
+use crate::python::monkey;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3::wrap_pymodule;
+use std::ffi::CString;
+
+#[test]
+fn test_python_module() {
+    Python::initialize();
+    Python::attach(|py| {
+        let globals = PyDict::new(py);
+        globals
+            .set_item("monkey", wrap_pymodule!(monkey)(py))
+            .unwrap();
+        let script = CString::new(
+            "
+assert monkey.eval('1 + 2') == 3
+assert monkey.eval('[1, \"a\", true, 1.5, {\"k\": [], 2: false}]') == [
+    1, 'a', True, 1.5, {'k': [], 2: False}
+]
+
+i = monkey.Interpreter()
+i.register('mul', 2, lambda a, b: a * b)
+i.register('fail', 0, lambda: 1 / 0)
+i.set_global('xs', (1, None, {'a': 'b'}))
+assert i.eval('let f = fn(x) { mul(x, 10) }; f(len(xs))') == 30
+assert i.get_global('xs') == [1, None, {'a': 'b'}]
+
+for source, message in [
+    ('let = 1;', 'error: not a ident token'),
+    ('1 / 0', 'error: division by zero'),
+    ('fail()', 'error: ZeroDivisionError: division by zero'),
+]:
+    try:
+        i.eval(source)
+        raise AssertionError(source)
+    except monkey.MonkeyError as e:
+        assert str(e).startswith(message), str(e)
+
+for convert, message in [
+    (lambda: i.set_global('o', object()), 'cannot convert object to monkey'),
+    (lambda: i.set_global('h', {1.5: 1}), 'unusable as hash key: float'),
+    (lambda: i.get_global('f'), 'cannot convert function to python'),
+]:
+    try:
+        convert()
+        raise AssertionError(message)
+    except TypeError as e:
+        assert str(e) == message, str(e)
+",
+        )
+        .unwrap();
+        if let Err(e) = py.run(&script, Some(&globals), None) {
+            panic!("{}", e);
+        }
+    });
+}
@@ -0,0 +1,97 @@
+use crate::lexer::lexer::Lexer;
+use crate::lexer::span::{Position, Span};
+use crate::lexer::token::Token;
+use crate::parser::{ParseError, Parser, Result};
+use crate::symbols::SymbolIndex;
+
+#[cfg(test)]
+mod test;
+
+// replaces the text of `span` with `text`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub span: Span,
+    pub text: String,
+}
+
+// the source with the edits made, they are in order and do not overlap
+pub fn apply(source: &str, edits: &[TextEdit]) -> String {
+    let mut ret = String::with_capacity(source.len());
+    let mut offset = 0;
+    for edit in edits {
+        ret.push_str(&source[offset..edit.span.start.offset]);
+        ret.push_str(&edit.text);
+        offset = edit.span.end.offset;
+    }
+    ret.push_str(&source[offset..]);
+    ret
+}
+
+// `source` with the binding at `position` and its references renamed, see
+// `rename_edits`
+pub fn rename(source: &str, position: Position, new_name: &str) -> Result<String> {
+    Ok(apply(source, &rename_edits(source, position, new_name)?))
+}
+
+// The edits renaming the binding defined or referred to at `position` to
+// `new_name`, in order. Other bindings of the same name are left alone. It
+// fails when the renamed code would not mean the same: the new name hides
+// or is hidden by another binding some identifier refers to, or a builtin
+// in use.
+pub fn rename_edits(source: &str, position: Position, new_name: &str) -> Result<Vec<TextEdit>> {
+    if !is_identifier(new_name) {
+        return Err(format!("not an identifier: {}", new_name).into());
+    }
+    let program = Parser::new(Lexer::new(source)).parse_program()?;
+    let index = SymbolIndex::new(&program);
+    let symbol = match index.symbol_at(position) {
+        Some(symbol) => symbol,
+        None => {
+            let message = format!("no binding at {}", position);
+            return Err(ParseError::new(message, Span::new(position, position)));
+        }
+    };
+    let mut spans = vec![symbol.span];
+    spans.extend(&symbol.references);
+    spans.sort();
+    let edits = spans
+        .iter()
+        .map(|span| TextEdit {
+            span: *span,
+            text: new_name.to_string(),
+        })
+        .collect::<Vec<TextEdit>>();
+
+    let renamed = apply(source, &edits);
+    let program = Parser::new(Lexer::new(&renamed)).parse_program()?;
+    // the edits only change names, the bindings are found in the same order
+    let moved = |span: &Span| {
+        let before = edits
+            .iter()
+            .filter(|edit| edit.span.start.offset < span.start.offset);
+        before.fold(span.start.offset, |offset, edit| {
+            offset + edit.text.len() - (edit.span.end.offset - edit.span.start.offset)
+        })
+    };
+    let same = SymbolIndex::new(&program)
+        .symbols()
+        .iter()
+        .zip(index.symbols())
+        .all(|(new, old)| {
+            let starts = new.references.iter().map(|span| span.start.offset);
+            starts.eq(old.references.iter().map(moved))
+        });
+    if !same {
+        let message = format!(
+            "renaming `{}` to `{}` would change what an identifier refers to",
+            symbol.name, new_name
+        );
+        return Err(ParseError::new(message, symbol.span));
+    }
+    Ok(edits)
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut l = Lexer::new(name);
+    l.next_source_token().0 == Token::Ident(name) && l.next_source_token().0 == Token::EOF
+}
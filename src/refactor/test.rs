@@ -0,0 +1,76 @@
+use crate::lexer::span::Position;
+use crate::refactor::rename;
+
+// the position of the first occurrence of `text`
+#[cfg(test)]
+fn position(input: &str, text: &str) -> Position {
+    let offset = input.find(text).unwrap();
+    let line = input[..offset].matches('\n').count() + 1;
+    let column = offset - input[..offset].rfind('\n').map_or(0, |i| i + 1) + 1;
+    Position {
+        offset,
+        line,
+        column,
+    }
+}
+
+#[test]
+fn test_rename() {
+    let cases = vec![
+        // from the definition or a reference
+        (
+            "let a = 1; a + a",
+            "a = ",
+            "count",
+            Ok("let count = 1; count + count"),
+        ),
+        ("let a = 1; a + a", "a + a", "b", Ok("let b = 1; b + b")),
+        // shadowed and unrelated bindings are left alone
+        (
+            "let x = 1;\nlet f = fn(x) { x };\nf(x)",
+            "x = 1",
+            "y",
+            Ok("let y = 1;\nlet f = fn(x) { x };\nf(y)"),
+        ),
+        (
+            "let x = 1;\nlet f = fn(x) { x };\nf(x)",
+            "x)",
+            "n",
+            Ok("let x = 1;\nlet f = fn(n) { n };\nf(x)"),
+        ),
+        (
+            "let f = fn() { g() };\nlet g = fn() { 1 };",
+            "g = fn",
+            "h",
+            Ok("let f = fn() { h() };\nlet h = fn() { 1 };"),
+        ),
+        // the new name would capture or be captured
+        (
+            "let a = 1;\nlet f = fn(b) { a + b };",
+            "b)",
+            "a",
+            Err("renaming `b` to `a` would change what an identifier refers to"),
+        ),
+        (
+            "let a = 1;\nlet f = fn(b) { a + b };",
+            "a = ",
+            "b",
+            Err("renaming `a` to `b` would change what an identifier refers to"),
+        ),
+        (
+            "let a = [1];\nlen(a)",
+            "a = ",
+            "len",
+            Err("renaming `a` to `len` would change what an identifier refers to"),
+        ),
+        ("let a = 1;", "a", "if", Err("not an identifier: if")),
+        ("let a = 1;", "a", "b c", Err("not an identifier: b c")),
+        ("let a = 1;", "1", "b", Err("no binding at 1:9")),
+        ("len([1])", "len", "size", Err("no binding at 1:1")),
+    ];
+    for (input, at, new_name, expected) in cases {
+        let renamed = rename(input, position(input, at), new_name);
+        let renamed = renamed.as_deref().map_err(|e| e.message());
+        assert_eq!(renamed, expected, "{} at {}", input, at);
+    }
+}
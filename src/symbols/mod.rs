@@ -0,0 +1,198 @@
+use crate::lexer::span::{Position, Span};
+use crate::parser::program::{Expression, Ident, Program, Statement};
+use crate::parser::visit::{self, Visitor};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    // a `let` of a function literal
+    Function,
+    Variable,
+    Parameter,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    // of the name where it is defined
+    pub span: Span,
+    // the function it is local to, None for the program
+    pub scope: Option<Span>,
+    // the offset it can be referred to from in its scope: the name for
+    // functions, which can call themselves, and parameters, the end of the
+    // statement for the other bindings
+    pub visible_from: usize,
+    // the identifiers referring to it, in order
+    pub references: Vec<Span>,
+}
+
+impl Symbol {
+    fn contains(&self, offset: usize) -> bool {
+        std::iter::once(&self.span)
+            .chain(&self.references)
+            .any(|span| span.start.offset <= offset && offset < span.end.offset)
+    }
+}
+
+// Every definition of a program with the identifiers referring to it. The
+// program and function bodies have a scope each, blocks of `if` do not. A
+// function can refer to the globals defined after it, as it runs once they
+// are. Identifiers bound nowhere, builtins included, are not in the index.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolIndex {
+    symbols: Vec<Symbol>,
+    functions: Vec<Span>,
+}
+
+impl SymbolIndex {
+    pub fn new(program: &Program) -> Self {
+        let mut indexer = Indexer {
+            index: SymbolIndex::default(),
+            scopes: vec![(None, vec![])],
+            later_globals: vec![],
+        };
+        visit::walk_program(&mut indexer, program);
+        let mut index = indexer.index;
+        let globals = &indexer.scopes[0].1;
+        for (name, span) in indexer.later_globals {
+            let symbol = globals
+                .iter()
+                .rev()
+                .find(|i| index.symbols[**i].name == name);
+            if let Some(i) = symbol {
+                index.symbols[*i].references.push(span);
+            }
+        }
+        for symbol in &mut index.symbols {
+            symbol.references.sort();
+        }
+        index
+    }
+
+    // in the order they are defined
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    // the symbol defined or referred to at `position`
+    pub fn symbol_at(&self, position: Position) -> Option<&Symbol> {
+        self.symbols
+            .iter()
+            .find(|symbol| symbol.contains(position.offset))
+    }
+
+    // The symbol `name` refers to at `position`: the one defined or referred
+    // to there, else the innermost binding visible from there.
+    pub fn lookup(&self, name: &str, position: Position) -> Option<&Symbol> {
+        let offset = position.offset;
+        let named = || self.symbols.iter().filter(|symbol| symbol.name == name);
+        if let Some(symbol) = named().find(|symbol| symbol.contains(offset)) {
+            return Some(symbol);
+        }
+        let in_function = self.functions.iter().any(|span| contains(*span, offset));
+        named()
+            .filter(|symbol| match symbol.scope {
+                Some(scope) => contains(scope, offset) && symbol.visible_from <= offset,
+                None => in_function || symbol.visible_from <= offset,
+            })
+            // the innermost scope, then the last binding before `position`,
+            // then the last one after it as for references
+            .max_by_key(|symbol| {
+                let size = symbol
+                    .scope
+                    .map_or(usize::MAX, |scope| scope.end.offset - scope.start.offset);
+                let before = symbol.visible_from <= offset;
+                (std::cmp::Reverse(size), before, symbol.visible_from)
+            })
+    }
+
+    // the definition of what `name` refers to at `position`, then its
+    // references
+    pub fn find_references(&self, name: &str, position: Position) -> Vec<Span> {
+        match self.lookup(name, position) {
+            Some(symbol) => std::iter::once(symbol.span)
+                .chain(symbol.references.iter().copied())
+                .collect(),
+            None => vec![],
+        }
+    }
+}
+
+fn contains(span: Span, offset: usize) -> bool {
+    span.start.offset <= offset && offset < span.end.offset
+}
+
+struct Indexer {
+    index: SymbolIndex,
+    // the function of each scope with its symbols, innermost last
+    scopes: Vec<(Option<Span>, Vec<usize>)>,
+    // references in functions to names not bound yet
+    later_globals: Vec<(String, Span)>,
+}
+
+impl Indexer {
+    fn declare(&mut self, ident: &Ident, kind: SymbolKind, visible_from: usize) {
+        let (scope, symbols) = self.scopes.last_mut().unwrap();
+        symbols.push(self.index.symbols.len());
+        self.index.symbols.push(Symbol {
+            name: ident.0.to_string(),
+            kind,
+            span: ident.1,
+            scope: *scope,
+            visible_from,
+            references: vec![],
+        });
+    }
+
+    fn refer(&mut self, ident: &Ident) {
+        let symbols = &mut self.index.symbols;
+        let symbol = self
+            .scopes
+            .iter()
+            .flat_map(|(_, scope)| scope)
+            .rev()
+            .find(|i| ident.0 == *symbols[**i].name);
+        match symbol {
+            Some(i) => symbols[*i].references.push(ident.1),
+            None if self.scopes.len() > 1 => {
+                self.later_globals.push((ident.0.to_string(), ident.1))
+            }
+            None => {}
+        }
+    }
+}
+
+impl Visitor for Indexer {
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::LetStatement(ident, value @ Expression::FunctionExpression(..), _) => {
+                self.declare(ident, SymbolKind::Function, ident.1.start.offset);
+                self.visit_expression(value);
+            }
+            Statement::LetStatement(ident, value, span) => {
+                self.visit_expression(value);
+                self.declare(ident, SymbolKind::Variable, span.end.offset);
+            }
+            _ => visit::walk_statement(self, statement),
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Identifier(ident) => self.refer(ident),
+            Expression::FunctionExpression(params, body, span) => {
+                self.index.functions.push(*span);
+                self.scopes.push((Some(*span), vec![]));
+                for param in params {
+                    self.declare(param, SymbolKind::Parameter, span.start.offset);
+                }
+                self.visit_block(body);
+                self.scopes.pop();
+            }
+            _ => visit::walk_expression(self, expression),
+        }
+    }
+}
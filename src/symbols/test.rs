@@ -0,0 +1,93 @@
+use crate::lexer::lexer::Lexer;
+use crate::lexer::span::{Position, Span};
+use crate::parser::Parser;
+use crate::symbols::{SymbolIndex, SymbolKind};
+
+#[cfg(test)]
+fn index(input: &str) -> SymbolIndex {
+    SymbolIndex::new(&Parser::new(Lexer::new(input)).parse_program().unwrap())
+}
+
+// the position of the `n`th occurrence of `text`, counting from 0
+#[cfg(test)]
+fn position(input: &str, text: &str, n: usize) -> Position {
+    let offset = input.match_indices(text).nth(n).unwrap().0;
+    let line = input[..offset].matches('\n').count() + 1;
+    let column = offset - input[..offset].rfind('\n').map_or(0, |i| i + 1) + 1;
+    Position {
+        offset,
+        line,
+        column,
+    }
+}
+
+#[cfg(test)]
+fn texts<'a>(input: &'a str, spans: &[Span]) -> Vec<(usize, &'a str)> {
+    spans
+        .iter()
+        .map(|span| (span.start.line, &input[span.start.offset..span.end.offset]))
+        .collect()
+}
+
+#[test]
+fn test_symbols() {
+    let input = "let x = 1;
+let add = fn(a, b) { a + b + x };
+let x = add(x, 2);
+len(x);";
+    let index = index(input);
+    let symbols = index
+        .symbols()
+        .iter()
+        .map(|symbol| {
+            (
+                symbol.name.as_str(),
+                symbol.kind,
+                symbol.scope.is_some(),
+                texts(input, &symbol.references),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        symbols,
+        vec![
+            ("x", SymbolKind::Variable, false, vec![(2, "x"), (3, "x")]),
+            ("add", SymbolKind::Function, false, vec![(3, "add")]),
+            ("a", SymbolKind::Parameter, true, vec![(2, "a")]),
+            ("b", SymbolKind::Parameter, true, vec![(2, "b")]),
+            ("x", SymbolKind::Variable, false, vec![(4, "x")]),
+        ]
+    );
+}
+
+#[test]
+fn test_find_references() {
+    let input = "let f = fn(n) {
+  if (n < 1) { g(n) } else { f(n - 1) }
+};
+let n = 3;
+let g = fn(x) { let n = x; n };
+f(n);";
+    let cases = vec![
+        // on the definition or a reference
+        (("f", "f", 0), vec![(1, "f"), (2, "f"), (6, "f")]),
+        (("f", "f(n - 1", 0), vec![(1, "f"), (2, "f"), (6, "f")]),
+        (("n", "n)", 0), vec![(1, "n"), (2, "n"), (2, "n"), (2, "n")]),
+        // a function refers to the globals defined after it
+        (("g", "g", 0), vec![(5, "g"), (2, "g")]),
+        // the innermost binding visible from a position
+        (
+            ("n", "else", 0),
+            vec![(1, "n"), (2, "n"), (2, "n"), (2, "n")],
+        ),
+        (("n", " n }", 0), vec![(5, "n"), (5, "n")]),
+        (("n", "f(n);", 0), vec![(4, "n"), (6, "n")]),
+        (("x", "f(n);", 0), vec![]),
+        (("len", "f(n);", 0), vec![]),
+    ];
+    let index = index(input);
+    for ((name, text, n), expected) in cases {
+        let spans = index.find_references(name, position(input, text, n));
+        assert_eq!(texts(input, &spans), expected, "{} at {:?}", name, text);
+    }
+}
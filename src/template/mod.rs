@@ -0,0 +1,349 @@
+use crate::eval::output::format_value;
+use crate::eval::ObjectWrapper;
+use crate::interpreter::Interpreter;
+use crate::lexer::lexer::Lexer;
+use crate::lexer::span::{Position, Span};
+use crate::lexer::token::Token;
+use crate::parser::program::{Program, Statement};
+use crate::parser::{ParseError, Parser, Result};
+
+#[cfg(test)]
+mod test;
+
+// Text with Monkey expressions in it. `{{ expression }}` is replaced by
+// its value as `puts` prints it, with the characters HTML gives a meaning
+// to escaped, `{{{ expression }}}` by the value as is. `{% if condition %}`,
+// an optional
+// `{% else %}` and `{% endif %}` keep one part or the other, and
+// `{% for name in array %}` to `{% endfor %}` repeats its part for each
+// item with `name` bound to it as by a `let`.
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+enum Node {
+    Text(String),
+    Value(Code),
+    Raw(Code),
+    If(Code, Vec<Node>, Vec<Node>),
+    For(String, Code, Vec<Node>),
+}
+
+// an expression of the template, parsed once
+struct Code {
+    program: Program,
+    span: Span,
+}
+
+// the position in the template of one in the code starting at `start`
+fn shift(position: Position, start: Position) -> Position {
+    Position {
+        offset: start.offset + position.offset,
+        line: start.line + position.line - 1,
+        column: if position.line == 1 {
+            start.column + position.column - 1
+        } else {
+            position.column
+        },
+    }
+}
+
+impl Code {
+    fn parse(source: &str, span: Span) -> Result<Self> {
+        let shifted = |e: ParseError| match e.span() {
+            Some(s) => ParseError::new(
+                e.message().to_string(),
+                Span::new(shift(s.start, span.start), shift(s.end, span.start)),
+            ),
+            None => e.or_span(span),
+        };
+        let program = Parser::new(Lexer::new(source))
+            .parse_program()
+            .map_err(shifted)?;
+        match program.statements[..] {
+            [Statement::ExpressionStatement(..)] => Ok(Code { program, span }),
+            _ => Err(ParseError::new(
+                format!("expect an expression, got `{}`", source),
+                span,
+            )),
+        }
+    }
+
+    // a runtime error, or an error value, at its span in the template
+    fn eval(&self, interpreter: &mut Interpreter) -> Result<ObjectWrapper> {
+        let span = |e: Option<Span>| match e {
+            Some(s) => Span::new(
+                shift(s.start, self.span.start),
+                shift(s.end, self.span.start),
+            ),
+            None => self.span,
+        };
+        match interpreter.eval_program(&self.program) {
+            Ok(ObjectWrapper::ErrorObject(message)) => Err(ParseError::new(message, self.span)),
+            Ok(obj) => Ok(obj),
+            Err(e) => Err(ParseError::new(e.message().to_string(), span(e.span()))),
+        }
+    }
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut parser = TemplateParser {
+            source,
+            offset: 0,
+            position: Position {
+                offset: 0,
+                line: 1,
+                column: 1,
+            },
+        };
+        let (nodes, end) = parser.nodes()?;
+        match end {
+            None => Ok(Template { nodes }),
+            Some((tag, span)) => Err(ParseError::new(format!("unexpected `{}`", tag), span)),
+        }
+    }
+
+    // the text with the values the expressions have in `interpreter`, the
+    // error of the first failing one with its span in the template
+    pub fn render(&self, interpreter: &mut Interpreter) -> Result<String> {
+        let mut ret = String::new();
+        render_nodes(&self.nodes, interpreter, &mut ret)?;
+        Ok(ret)
+    }
+}
+
+// parses and renders `source`, see `Template`
+pub fn render(source: &str, interpreter: &mut Interpreter) -> Result<String> {
+    Template::parse(source)?.render(interpreter)
+}
+
+fn render_nodes(nodes: &[Node], interpreter: &mut Interpreter, out: &mut String) -> Result<()> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Value(code) => escape_html(&format_value(&code.eval(interpreter)?), out),
+            Node::Raw(code) => out.push_str(&format_value(&code.eval(interpreter)?)),
+            Node::If(condition, consequence, alternative) => {
+                match eval_condition(condition, interpreter)? {
+                    true => render_nodes(consequence, interpreter, out)?,
+                    false => render_nodes(alternative, interpreter, out)?,
+                }
+            }
+            Node::For(name, items, body) => match items.eval(interpreter)? {
+                ObjectWrapper::Array(items) => {
+                    // the loop variable is a global while the body renders,
+                    // the one it shadows is put back after
+                    let outer = interpreter.remove_global(name);
+                    let ret = items.iter().try_for_each(|item| {
+                        interpreter.set_global(name, item.clone());
+                        render_nodes(body, interpreter, out)
+                    });
+                    interpreter.remove_global(name);
+                    if let Some(outer) = outer {
+                        interpreter.set_global(name, outer);
+                    }
+                    ret?;
+                }
+                obj => {
+                    let message = format!("cannot loop over {}", obj.type_str());
+                    return Err(ParseError::new(message, items.span));
+                }
+            },
+        }
+    }
+    Ok(())
+}
+
+// `text` safe to put in an element or a quoted attribute
+fn escape_html(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+}
+
+// conditions are booleans, as for `if` expressions
+fn eval_condition(code: &Code, interpreter: &mut Interpreter) -> Result<bool> {
+    match code.eval(interpreter)? {
+        ObjectWrapper::Boolean(v) => Ok(v),
+        _ => Err(ParseError::new("Invalid 'if' condition.", code.span)),
+    }
+}
+
+// the `else`, `endif` or `endfor` some nodes end at, with its span
+type EndTag = Option<(String, Span)>;
+
+struct TemplateParser<'a> {
+    source: &'a str,
+    offset: usize,
+    position: Position,
+}
+
+impl TemplateParser<'_> {
+    // moves to `offset` and returns where it is
+    fn advance(&mut self, offset: usize) -> Position {
+        for c in self.source[self.offset..offset].chars() {
+            if c == '\n' {
+                self.position.line += 1;
+                self.position.column = 1;
+            } else {
+                self.position.column += 1;
+            }
+        }
+        self.position.offset = offset;
+        self.offset = offset;
+        self.position
+    }
+
+    // The nodes up to the end of the source or the first `else`, `endif` or
+    // `endfor`, returned with its span.
+    fn nodes(&mut self) -> Result<(Vec<Node>, EndTag)> {
+        let mut ret = vec![];
+        loop {
+            let rest = &self.source[self.offset..];
+            let open = match (rest.find("{{"), rest.find("{%")) {
+                (Some(a), Some(b)) => a.min(b),
+                (Some(a), None) | (None, Some(a)) => a,
+                (None, None) => {
+                    if !rest.is_empty() {
+                        ret.push(Node::Text(rest.to_string()));
+                    }
+                    self.advance(self.source.len());
+                    return Ok((ret, None));
+                }
+            };
+            if open > 0 {
+                ret.push(Node::Text(rest[..open].to_string()));
+            }
+            let is_raw = rest[open..].starts_with("{{{");
+            let is_value = rest[open..].starts_with("{{");
+            let start = self.advance(self.offset + open);
+            let (opener, close) = match (is_raw, is_value) {
+                (true, _) => ("{{{", "}}}"),
+                (false, true) => ("{{", "}}"),
+                (false, false) => ("{%", "%}"),
+            };
+            let inner_offset = self.offset + opener.len();
+            let inner_len = match self.code_len(inner_offset, close) {
+                Some(len) => len,
+                None => {
+                    let message = format!("unclosed `{}`", opener);
+                    let end = self.advance(inner_offset);
+                    return Err(ParseError::new(message, Span::new(start, end)));
+                }
+            };
+            let inner = &self.source[inner_offset..inner_offset + inner_len];
+            // the span of the text trimmed
+            let trimmed = inner.trim();
+            let code_start = inner_offset + (inner.len() - inner.trim_start().len());
+            let code_span = Span::new(
+                self.advance(code_start),
+                self.advance(code_start + trimmed.len()),
+            );
+            let end = self.advance(inner_offset + inner_len + close.len());
+            let tag_span = Span::new(start, end);
+            if is_raw {
+                ret.push(Node::Raw(Code::parse(trimmed, code_span)?));
+                continue;
+            }
+            if is_value {
+                ret.push(Node::Value(Code::parse(trimmed, code_span)?));
+                continue;
+            }
+            let (keyword, argument) = match trimmed.find(char::is_whitespace) {
+                Some(i) => (&trimmed[..i], trimmed[i..].trim_start()),
+                None => (trimmed, ""),
+            };
+            // where `argument` starts in the template
+            let argument_span = |argument: &str| {
+                let offset = code_span.start.offset + trimmed.len() - argument.len();
+                let mut parser = TemplateParser {
+                    source: self.source,
+                    offset: code_span.start.offset,
+                    position: code_span.start,
+                };
+                Span::new(parser.advance(offset), code_span.end)
+            };
+            match keyword {
+                "if" => {
+                    let condition = Code::parse(argument, argument_span(argument))?;
+                    let (consequence, end) = self.nodes()?;
+                    let (alternative, end) = match end {
+                        Some((tag, _)) if tag == "else" => self.nodes()?,
+                        end => (vec![], end),
+                    };
+                    self.expect_end(end, "endif", tag_span)?;
+                    ret.push(Node::If(condition, consequence, alternative));
+                }
+                "for" => {
+                    let (name, items) = match argument.split_once(" in ") {
+                        Some((name, items)) if is_name(name.trim()) => {
+                            (name.trim(), items.trim_start())
+                        }
+                        _ => {
+                            let message = "expect `{% for name in array %}`";
+                            return Err(ParseError::new(message, tag_span));
+                        }
+                    };
+                    let items = Code::parse(items, argument_span(items))?;
+                    let (body, end) = self.nodes()?;
+                    self.expect_end(end, "endfor", tag_span)?;
+                    ret.push(Node::For(name.to_string(), items, body));
+                }
+                "else" | "endif" | "endfor" if argument.is_empty() => {
+                    return Ok((ret, Some((keyword.to_string(), tag_span))));
+                }
+                _ => {
+                    let message = format!("unknown tag `{}`", trimmed);
+                    return Err(ParseError::new(message, tag_span));
+                }
+            }
+        }
+    }
+
+    // The length of the code of a tag from `offset` to its `close`. The code
+    // is lexed, a `}}` in a string or closing nested braces does not end it.
+    fn code_len(&self, offset: usize, close: &str) -> Option<usize> {
+        let code = &self.source[offset..];
+        let mut lexer = Lexer::new(code);
+        let mut depth = 0usize;
+        loop {
+            let (token, span) = lexer.next_source_token();
+            let start = span.start.offset;
+            match token {
+                Token::EOF => return None,
+                _ if depth == 0 && code[start..].starts_with(close) => return Some(start),
+                Token::LBrace => depth += 1,
+                Token::RBrace => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+    }
+
+    fn expect_end(&self, end: EndTag, expect: &str, open: Span) -> Result<()> {
+        match end {
+            Some((tag, _)) if tag == expect => Ok(()),
+            Some((tag, span)) => Err(ParseError::new(
+                format!("expect `{{% {} %}}`, got `{}`", expect, tag),
+                span,
+            )),
+            None => Err(ParseError::new(
+                format!("missing `{{% {} %}}`", expect),
+                open,
+            )),
+        }
+    }
+}
+
+fn is_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
@@ -0,0 +1,120 @@
+use crate::interpreter::Interpreter;
+use crate::template::{render, Template};
+
+#[test]
+fn test_render() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .eval(r#"let user = {"name": "Ann", "admin": true}; let items = ["a", "b"];"#)
+        .unwrap();
+    interpreter.set_global("page", "<script>&\"'");
+    let cases = [
+        ("no tags", "no tags"),
+        ("Hello {{ user[\"name\"] }}!", "Hello Ann!"),
+        ("{{1 + 2}}{{ [1, \"x\"] }}", "3[1, &quot;x&quot;]"),
+        // values are escaped for HTML unless written in triple braces
+        (
+            "<p title=\"{{ page }}\">{{ page }}</p>",
+            "<p title=\"&lt;script&gt;&amp;&quot;&#39;\">&lt;script&gt;&amp;&quot;&#39;</p>",
+        ),
+        ("{{{ page }}}", "<script>&\"'"),
+        ("{{{ {\"a\": page}[\"a\"] }}}", "<script>&\"'"),
+        (
+            "{% if user[\"admin\"] %}admin{% else %}user{% endif %}",
+            "admin",
+        ),
+        ("{% if len(items) > 5 %}many{% endif %}.", "."),
+        (
+            "{% if user[\"name\"] == \"Ann\" %}hi Ann{% else %}who?{% endif %}",
+            "hi Ann",
+        ),
+        (
+            "{% for item in items %}{% if item != \"a\" %}{{ item }}{% endif %}{% endfor %}",
+            "b",
+        ),
+        (
+            "<ul>{% for item in items %}<li>{{ item }}</li>{% endfor %}</ul>",
+            "<ul><li>a</li><li>b</li></ul>",
+        ),
+        (
+            "{% for i in [1, 2] %}{% for j in [3] %}{{ i * j }} {% endfor %}{% endfor %}",
+            "3 6 ",
+        ),
+        // the tags end where the code does
+        ("{{ {\"a\": {\"b\": 1}}[\"a\"][\"b\"] }}", "1"),
+        ("{{ \"}}\" }}!", "}}!"),
+        ("{% if len(\"%}\") == 2 %}yes{% endif %}", "yes"),
+    ];
+    for (source, expect) in cases {
+        assert_eq!(
+            render(source, &mut interpreter).unwrap(),
+            expect,
+            "{}",
+            source
+        );
+    }
+
+    // parsed once, rendered with the bindings of the moment
+    let template = Template::parse("{{ count }}").unwrap();
+    interpreter.eval("let count = 1;").unwrap();
+    assert_eq!(template.render(&mut interpreter).unwrap(), "1");
+    interpreter.eval("let count = count + 1;").unwrap();
+    assert_eq!(template.render(&mut interpreter).unwrap(), "2");
+
+    // the loop variable does not outlive the loop
+    interpreter.set_global("x", 42);
+    let source = "{% for x in [1, 2] %}{{ x }}{% endfor %}{% for y in [3] %}{% endfor %}";
+    assert_eq!(render(source, &mut interpreter).unwrap(), "12");
+    assert_eq!(interpreter.get_global::<i64>("x").unwrap(), 42);
+    assert!(interpreter.get_global::<i64>("y").is_err());
+    assert!(render(
+        "{% for x in [1] %}{{ 1 / 0 }}{% endfor %}",
+        &mut interpreter
+    )
+    .is_err());
+    assert_eq!(interpreter.get_global::<i64>("x").unwrap(), 42);
+}
+
+#[test]
+fn test_render_errors() {
+    let cases = [
+        ("a {{ 1 + }}", "no prefix parse function for EOF", "1:9"),
+        ("{{ 1 }", "unclosed `{{`", "1:1"),
+        ("{{{ 1 }}", "unclosed `{{{`", "1:1"),
+        ("{% if true %}x", "missing `{% endif %}`", "1:1"),
+        (
+            "{% for x %}{% endfor %}",
+            "expect `{% for name in array %}`",
+            "1:1",
+        ),
+        ("x\n{% endfor %}", "unexpected `endfor`", "2:1"),
+        (
+            "{% if true %}{% endfor %}",
+            "expect `{% endif %}`, got `endfor`",
+            "1:14",
+        ),
+        ("{% while true %}", "unknown tag `while true`", "1:1"),
+        (
+            "{{ let a = 1; }}",
+            "expect an expression, got `let a = 1;`",
+            "1:4",
+        ),
+        ("\n  {{ 1 / 0 }}", "division by zero", "2:6"),
+        ("{% if 1 %}{% endif %}", "Invalid 'if' condition.", "1:7"),
+        (
+            "{% for x in 1 %}{% endfor %}",
+            "cannot loop over int",
+            "1:13",
+        ),
+        ("{{ f(1) }}", "identifier not found: f", "1:4"),
+    ];
+    for (source, message, position) in cases {
+        let e = render(source, &mut Interpreter::new()).unwrap_err();
+        assert_eq!(
+            (e.message(), e.span().map(|span| span.start.to_string())),
+            (message, Some(position.to_string())),
+            "{}",
+            source
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use crate::fmt;
+use crate::lexer::lexer::Lexer;
+use crate::lexer::span::Span;
+use crate::lexer::symbol::Symbol;
+use crate::lexer::token::Token;
+use crate::parser::diff::diff;
+use crate::parser::program::{Expression, Ident, Program, Statement};
+use crate::parser::Parser;
+
+// how deep generated expressions nest, well within what the parser takes
+const MAX_DEPTH: usize = 6;
+
+const NAMES: &[&str] = &["a", "b", "x", "total", "_tmp", "f2", "len", "puts"];
+
+const INFIX: &[Token] = &[
+    Token::Plus,
+    Token::Minus,
+    Token::Asterisk,
+    Token::Slash,
+    Token::LT,
+    Token::GT,
+    Token::Eq,
+    Token::NotEq,
+];
+
+// pieces of source random inputs are made of, so they get past the lexer
+const FRAGMENTS: &[&str] = &[
+    "let ", "fn", "if", "else", "return ", "true", "false", "(", ")", "{", "}", "[", "]", ",", ";",
+    ":", "=", "==", "!=", "!", "-", "+", "*", "/", "<", ">", "\"", "a", "1", "2.5", " ", "\n",
+    "//", "é",
+];
+
+// A xorshift generator, the same seed gives the same cases so a failure
+// can be replayed.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // xorshift is stuck at 0
+        Rng(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    // in `0..n`, `n` is not 0
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+// A random program the parser accepts, of about `size` statements. Nodes
+// have no spans, only what the source says is generated: literals are not
+// negative, `-1` is a prefix expression.
+pub fn program(rng: &mut Rng, size: usize) -> Program {
+    Program {
+        statements: (0..size).map(|_| statement(rng, 0)).collect(),
+        comments: vec![],
+    }
+}
+
+fn ident(rng: &mut Rng) -> Ident {
+    let name = *rng.pick(NAMES);
+    Ident::new(Symbol::intern(name), Span::default())
+}
+
+fn block(rng: &mut Rng, depth: usize) -> Vec<Statement> {
+    (0..rng.below(3)).map(|_| statement(rng, depth)).collect()
+}
+
+fn statement(rng: &mut Rng, depth: usize) -> Statement {
+    let span = Span::default();
+    match rng.below(4) {
+        0 => Statement::LetStatement(ident(rng), expression(rng, depth), span),
+        1 => Statement::ReturnStatement(expression(rng, depth), span),
+        _ => Statement::ExpressionStatement(expression(rng, depth), span),
+    }
+}
+
+fn expression(rng: &mut Rng, depth: usize) -> Expression {
+    let span = Span::default();
+    // leaves only, past the deepest nesting
+    let kinds = if depth >= MAX_DEPTH { 5 } else { 13 };
+    let depth = depth + 1;
+    match rng.below(kinds) {
+        0 => Expression::Identifier(ident(rng)),
+        1 => Expression::IntLiteral(rng.below(1000) as i64),
+        // eighths print exactly
+        2 => Expression::FloatLiteral(rng.below(800) as f64 / 8.0),
+        3 => Expression::BoolLiteral(rng.below(2) == 0),
+        4 => {
+            let len = rng.below(6);
+            let text = (0..len)
+                .map(|_| *rng.pick(&['a', 'Z', '0', ' ', '_', 'é', '+', '{']))
+                .collect::<String>();
+            Expression::StringLiteral(text.into())
+        }
+        5 => {
+            let operator = if rng.below(2) == 0 {
+                Token::Bang
+            } else {
+                Token::Minus
+            };
+            Expression::PrefixExpression(operator, Box::new(expression(rng, depth)), span)
+        }
+        6 | 7 => Expression::InfixExpression(
+            Box::new(expression(rng, depth)),
+            rng.pick(INFIX).clone(),
+            Box::new(expression(rng, depth)),
+            span,
+        ),
+        8 => Expression::IfExpression(
+            Box::new(expression(rng, depth)),
+            block(rng, depth),
+            block(rng, depth),
+            span,
+        ),
+        9 => Expression::FunctionExpression(
+            (0..rng.below(3)).map(|_| ident(rng)).collect(),
+            block(rng, depth),
+            span,
+        ),
+        10 => Expression::CallExpression(
+            Box::new(expression(rng, depth)),
+            (0..rng.below(3)).map(|_| expression(rng, depth)).collect(),
+            span,
+        ),
+        11 => Expression::IndexExpression(
+            Box::new(expression(rng, depth)),
+            Box::new(expression(rng, depth)),
+            span,
+        ),
+        _ => {
+            if rng.below(2) == 0 {
+                Expression::ArrayLiteral(
+                    (0..rng.below(4)).map(|_| expression(rng, depth)).collect(),
+                )
+            } else {
+                Expression::HashLiteral(
+                    (0..rng.below(3))
+                        .map(|_| (expression(rng, depth), expression(rng, depth)))
+                        .collect(),
+                )
+            }
+        }
+    }
+}
+
+// Random input for the parser: source fragments most of the time, so it
+// gets past the lexer, and arbitrary bytes, not all of it UTF-8.
+pub fn bytes(rng: &mut Rng, len: usize) -> Vec<u8> {
+    let mut ret = vec![];
+    while ret.len() < len {
+        if rng.below(4) == 0 {
+            ret.push(rng.next_u64() as u8);
+        } else {
+            ret.extend_from_slice(rng.pick(FRAGMENTS).as_bytes());
+        }
+    }
+    ret
+}
+
+// The source `program` prints to with `fmt::format_program`, or how what
+// that source parses back to differs from `program`, spans aside.
+pub fn round_trip(program: &Program) -> Result<String, String> {
+    let source = fmt::format_program(program);
+    let reparsed = Parser::new(Lexer::new(&source))
+        .parse_program()
+        .map_err(|e| format!("{:?} does not parse: {}", source, e))?;
+    if diff(program, &reparsed).is_empty() {
+        Ok(source)
+    } else {
+        Err(format!(
+            "{:?} parses back as {:?}, not {:?}",
+            source,
+            reparsed.to_string(),
+            program.to_string()
+        ))
+    }
+}
+
+// Parses `bytes` as source, the invalid UTF-8 replaced, a panic of the
+// lexer or the parser goes on to the caller.
+pub fn parse_bytes(bytes: &[u8]) {
+    let source = String::from_utf8_lossy(bytes);
+    let _ = Parser::new(Lexer::new(&source)).parse_program();
+    let _ = Parser::new(Lexer::new(&source)).parse_program_with_errors();
+}
@@ -0,0 +1,200 @@
+use crate::eval::benchmark::Stats;
+use crate::eval::coverage::Coverage;
+use crate::eval::environment::Environment;
+use crate::eval::observer::EvalObserver;
+use crate::eval::output::OutputBuffer;
+use crate::eval::ObjectWrapper;
+use crate::interpreter::Interpreter;
+use crate::lexer::lexer::Lexer;
+use crate::lexer::span::Span;
+use crate::parser::program::{Expression, Program, Statement};
+use crate::parser::{Parser, Result};
+
+pub mod grammar;
+#[cfg(test)]
+mod test;
+
+// test files are named like `math_test.mk`
+pub const TEST_FILE_SUFFIX: &str = "_test.mk";
+// their cases are top-level functions without parameters named like
+// `test_add`
+pub const TEST_PREFIX: &str = "test_";
+// benchmarks go in files like `sort_bench.mk`, in functions without
+// parameters named like `bench_sort`
+pub const BENCH_FILE_SUFFIX: &str = "_bench.mk";
+pub const BENCH_PREFIX: &str = "bench_";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestCase {
+    pub name: String,
+    // of the `let` defining it
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    pub message: String,
+    // the innermost statement that failed, None if it is not in the file
+    pub span: Option<Span>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    pub case: TestCase,
+    // None when the test passed
+    pub failure: Option<Failure>,
+    // what the test printed with `puts`
+    pub output: String,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchResult {
+    pub case: TestCase,
+    pub stats: std::result::Result<Stats, Failure>,
+    // what the benchmark printed with `puts`
+    pub output: String,
+}
+
+// the cases of a test file in the order they are defined
+pub fn discover(program: &Program) -> Vec<TestCase> {
+    discover_prefixed(program, TEST_PREFIX)
+}
+
+// the benchmarks of a file in the order they are defined
+pub fn discover_benches(program: &Program) -> Vec<TestCase> {
+    discover_prefixed(program, BENCH_PREFIX)
+}
+
+fn discover_prefixed(program: &Program, prefix: &str) -> Vec<TestCase> {
+    program
+        .statements
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::LetStatement(ident, Expression::FunctionExpression(params, _, _), span)
+                if ident.0.starts_with(prefix) && params.is_empty() =>
+            {
+                Some(TestCase {
+                    name: ident.0.to_string(),
+                    span: *span,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// Runs every case of a test file. Each one gets a fresh interpreter that
+// evaluates the whole file and then calls the case, so cases cannot see
+// what the others did. The lines they run are added to `coverage`. The
+// error is the file's if it does not parse.
+pub fn run_tests(source: &str, mut coverage: Option<&mut Coverage>) -> Result<Vec<TestResult>> {
+    let program = Parser::new(Lexer::new(source)).parse_program()?;
+    let mut ret = vec![];
+    for case in discover(&program) {
+        ret.push(run_case(source, case, coverage.as_deref_mut()));
+    }
+    Ok(ret)
+}
+
+fn run_case(source: &str, case: TestCase, coverage: Option<&mut Coverage>) -> TestResult {
+    let output = OutputBuffer::new();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(output.clone());
+    let mut locator = FailureLocator {
+        len: source.len(),
+        span: None,
+        coverage,
+    };
+    // the call goes after the file so the spans of its statements are the
+    // file's
+    let input = format!("{}\n{}();", source, case.name);
+    let failure = match interpreter.eval_observed(&input, &mut locator) {
+        Ok(ObjectWrapper::ErrorObject(message)) => Some(Failure {
+            message,
+            span: locator.span,
+        }),
+        Ok(_) => None,
+        // a parse error has not run any statement
+        Err(e) => Some(Failure {
+            message: e.message().to_string(),
+            span: locator.span.or(e.span()),
+        }),
+    };
+    TestResult {
+        case,
+        failure,
+        output: output.contents(),
+    }
+}
+
+// Runs every benchmark of a file with the `benchmark` builtin, each in a
+// fresh interpreter as for tests. The error is the file's if it does not
+// parse.
+pub fn run_benches(source: &str, iterations: usize) -> Result<Vec<BenchResult>> {
+    let program = Parser::new(Lexer::new(source)).parse_program()?;
+    let mut ret = vec![];
+    for case in discover_benches(&program) {
+        let output = OutputBuffer::new();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_stdout(output.clone());
+        let mut locator = FailureLocator {
+            len: source.len(),
+            span: None,
+            coverage: None,
+        };
+        let input = format!("{}\nbenchmark({}, {});", source, case.name, iterations);
+        let stats = match interpreter.eval_observed(&input, &mut locator) {
+            Ok(ObjectWrapper::ErrorObject(message)) => Err(Failure {
+                message,
+                span: locator.span,
+            }),
+            // `benchmark` returns stats or fails
+            Ok(obj) => Ok(Stats::from_object(&obj).unwrap()),
+            // the error goes out from the appended call
+            Err(e) => Err(Failure {
+                message: e.message().to_string(),
+                span: locator.span,
+            }),
+        };
+        ret.push(BenchResult {
+            case,
+            stats,
+            output: output.contents(),
+        });
+    }
+    Ok(ret)
+}
+
+// remembers the first statement of the file to fail, errors go out from
+// the innermost one
+struct FailureLocator<'a> {
+    len: usize,
+    span: Option<Span>,
+    coverage: Option<&'a mut Coverage>,
+}
+
+impl EvalObserver for FailureLocator<'_> {
+    fn before_statement(&mut self, statement: &Statement, span: Span, env: &Environment) {
+        if let Some(coverage) = self.coverage.as_deref_mut() {
+            coverage.before_statement(statement, span, env);
+        }
+    }
+
+    fn after_statement(
+        &mut self,
+        _statement: &Statement,
+        span: Span,
+        _env: &Environment,
+        result: &Result<ObjectWrapper>,
+    ) {
+        if result.is_err() && self.span.is_none() && span.end.offset <= self.len {
+            self.span = Some(span);
+        }
+    }
+}
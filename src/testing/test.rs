@@ -0,0 +1,124 @@
+use crate::testing::grammar::{self, Rng};
+use crate::testing::{run_benches, run_tests};
+
+#[test]
+fn test_run_tests() {
+    let source = r#"let double = fn(x) { x * 2 };
+let calls = [];
+let test_double = fn() {
+    assert_eq(double(2), 4);
+    puts("doubled");
+};
+let test_wrong = fn() {
+    let a = double(1);
+    assert_eq(a, 3);
+    puts("not reached");
+};
+let test_assert = fn() { assert(len(calls) == 0, "calls is empty") };
+let test_message = fn() { assert(false, "always fails") };
+let helper = fn() { assert(false, "not a test") };
+let test_with_params = fn(x) { x };
+"#;
+    let results = run_tests(source, None).unwrap();
+    let summary = results
+        .iter()
+        .map(|result| {
+            let failure = result.failure.as_ref();
+            (
+                result.case.name.as_str(),
+                result.case.span.start.line,
+                failure.map(|failure| failure.message.as_str()),
+                failure
+                    .and_then(|failure| failure.span)
+                    .map(|span| span.start.line),
+                result.output.as_str(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        vec![
+            ("test_double", 3, None, None, "doubled\n"),
+            (
+                "test_wrong",
+                7,
+                Some("assertion failed: expect 3, got 2"),
+                Some(9),
+                ""
+            ),
+            ("test_assert", 12, None, None, ""),
+            (
+                "test_message",
+                13,
+                Some("assertion failed: always fails"),
+                Some(13),
+                ""
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_run_tests_setup_failure() {
+    // the file itself fails before the case is called
+    let results = run_tests("let x = 1 / 0;\nlet test_a = fn() { 1 };", None).unwrap();
+    assert_eq!(results.len(), 1);
+    let failure = results[0].failure.as_ref().unwrap();
+    assert_eq!(failure.message, "division by zero");
+    assert_eq!(failure.span.map(|span| span.start.line), Some(1));
+
+    assert!(run_tests("let test_a = fn( { 1 };", None).is_err());
+}
+
+#[test]
+fn test_run_benches() {
+    let source = r#"let double = fn(x) { x * 2 };
+let bench_double = fn() { puts(double(3)) };
+let bench_broken = fn() {
+    double("a") < 1
+};
+let test_double = fn() { double(1) };
+"#;
+    let results = run_benches(source, 4).unwrap();
+    let summary = results
+        .iter()
+        .map(|result| match &result.stats {
+            Ok(stats) => (result.case.name.as_str(), Some(stats.iterations), None),
+            Err(failure) => (
+                result.case.name.as_str(),
+                None,
+                failure.span.map(|span| span.start.line),
+            ),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        vec![
+            ("bench_double", Some(4), None),
+            ("bench_broken", None, Some(1))
+        ]
+    );
+    assert_eq!(results[0].output, "6\n".repeat(4));
+
+    assert!(run_benches("let bench_a = fn( { 1 };", 1).is_err());
+}
+
+#[test]
+fn test_grammar_round_trip() {
+    let mut rng = Rng::new(0x5eed);
+    for _ in 0..500 {
+        let program = grammar::program(&mut rng, 3);
+        if let Err(e) = grammar::round_trip(&program) {
+            panic!("{}", e);
+        }
+    }
+}
+
+#[test]
+fn test_parse_random_bytes() {
+    let mut rng = Rng::new(0x5eed);
+    for _ in 0..2000 {
+        let len = rng.below(64);
+        grammar::parse_bytes(&grammar::bytes(&mut rng, len));
+    }
+}
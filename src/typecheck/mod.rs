@@ -0,0 +1,477 @@
+use crate::compiler::Warning;
+use crate::eval::builtins::Builtins;
+use crate::eval::ObjectWrapper;
+use crate::lexer::span::Span;
+use crate::lexer::symbol::Symbol;
+use crate::lexer::token::Token;
+use crate::parser::program::{Expression, Ident, Program, Statement};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+#[cfg(test)]
+mod test;
+
+// What an expression is found to evaluate to. Parameters are not annotated,
+// they and whatever depends on them are `Unknown`, which is never warned
+// about, unless the function is bound by a `let` and called by its name:
+// its body is then checked again with the types of the arguments.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Type {
+    Int,
+    Float,
+    Bool,
+    String,
+    Null,
+    Array(Box<Type>),
+    Hash,
+    Function(Vec<Type>, Box<Type>),
+    Unknown,
+}
+
+impl Type {
+    // what the evaluator names the type in its errors, the elements and
+    // signatures left out
+    fn kind(&self) -> &'static str {
+        match self {
+            Type::Int => "int",
+            Type::Float => "float",
+            Type::Bool => "bool",
+            Type::String => "string",
+            Type::Null => "null",
+            Type::Array(_) => "array",
+            Type::Hash => "hash",
+            Type::Function(..) => "function",
+            Type::Unknown => "unknown",
+        }
+    }
+
+    fn is_number(&self) -> bool {
+        matches!(self, Type::Int | Type::Float)
+    }
+
+    // the type of a value that is either `self` or `other`
+    fn join(self, other: Type) -> Type {
+        match (self, other) {
+            (Type::Array(a), Type::Array(b)) => Type::Array(Box::new(a.join(*b))),
+            (a, b) if a == b => a,
+            _ => Type::Unknown,
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Array(element) => write!(f, "array<{}>", element),
+            Type::Function(params, ret) => {
+                f.write_str("fn(")?;
+                for (i, param) in params.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", param)?;
+                }
+                write!(f, ") -> {}", ret)
+            }
+            _ => f.write_str(self.kind()),
+        }
+    }
+}
+
+// The probable type errors of `program`, in source order: operators,
+// calls, indexes and `if` conditions on values of types the evaluator
+// fails on. The types are inferred in the order the program is written, a
+// function sees the bindings made before it. A call to a function by the
+// name a `let` bound it to is warned about when its body fails on the
+// arguments.
+pub fn check(program: &Program) -> Vec<Warning> {
+    let mut checker = Checker {
+        scopes: vec![HashMap::new()],
+        literals: vec![HashMap::new()],
+        calls: HashMap::new(),
+        calling: vec![],
+        returns: vec![],
+        statement: Span::default(),
+        warnings: vec![],
+    };
+    checker.block(&program.statements);
+    checker.warnings.sort_by_key(|warning| warning.span.start);
+    checker.warnings
+}
+
+// what a function returns for a call, with the warnings of its body
+type Call = (Type, Vec<Warning>);
+
+struct Checker<'p> {
+    // the types of the bindings of the top level and of the functions being
+    // checked, innermost last
+    scopes: Vec<HashMap<Symbol, Type>>,
+    // the function literals bound in each of the scopes
+    literals: Vec<HashMap<Symbol, &'p Expression>>,
+    // the calls checked, by function literal and types of the arguments
+    calls: HashMap<(*const Expression, Vec<Type>), Call>,
+    // the function literals whose bodies are checked for a call, the calls
+    // they make to themselves are not checked again
+    calling: Vec<*const Expression>,
+    // the types the functions being checked `return`, innermost last
+    returns: Vec<Vec<Type>>,
+    // where the statement being checked is, for expressions without a span
+    statement: Span,
+    warnings: Vec<Warning>,
+}
+
+impl<'p> Checker<'p> {
+    fn warn(&mut self, span: Option<Span>, message: String) {
+        let span = span.unwrap_or(self.statement);
+        self.warnings.push(Warning { span, message });
+    }
+
+    fn bind(&mut self, name: Symbol, ty: Type) {
+        self.scopes.last_mut().unwrap().insert(name, ty);
+    }
+
+    fn lookup(&self, name: &Symbol) -> Type {
+        if let Some(ty) = self.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+            return ty.clone();
+        }
+        match Builtins::instance_ref().get(name) {
+            Some(ObjectWrapper::BuiltinFn(arity, _)) => Type::Function(
+                vec![Type::Unknown; arity],
+                Box::new(builtin_result(name, &[])),
+            ),
+            Some(ObjectWrapper::Float(_)) => Type::Float,
+            _ => Type::Unknown,
+        }
+    }
+
+    // the function literal `name` is bound to, with the depth of its scope
+    fn literal(&self, name: &Symbol) -> Option<(usize, &'p Expression)> {
+        let depth = self
+            .scopes
+            .iter()
+            .rposition(|scope| scope.contains_key(name))?;
+        Some((depth, *self.literals[depth].get(name)?))
+    }
+
+    // the type of the value of a block, the one of its last expression
+    fn block(&mut self, statements: &'p [Statement]) -> Type {
+        let mut ty = Type::Null;
+        for statement in statements {
+            self.statement = statement.span();
+            ty = match statement {
+                Statement::LetStatement(ident, value, _) => {
+                    let literals = self.literals.last_mut().unwrap();
+                    if let Expression::FunctionExpression(params, ..) = value {
+                        literals.insert(ident.0.clone(), value);
+                        // a recursive call is checked against the arity
+                        let params = vec![Type::Unknown; params.len()];
+                        self.bind(
+                            ident.0.clone(),
+                            Type::Function(params, Box::new(Type::Unknown)),
+                        );
+                    } else {
+                        literals.remove(&ident.0);
+                    }
+                    let value = self.expression(value);
+                    self.bind(ident.0.clone(), value);
+                    Type::Unknown
+                }
+                Statement::ReturnStatement(value, _) => {
+                    let value = self.expression(value);
+                    if let Some(returns) = self.returns.last_mut() {
+                        returns.push(value.clone());
+                    }
+                    value
+                }
+                Statement::ExpressionStatement(value, _) => self.expression(value),
+            };
+        }
+        ty
+    }
+
+    fn expression(&mut self, expression: &'p Expression) -> Type {
+        match expression {
+            Expression::Identifier(ident) => self.lookup(&ident.0),
+            Expression::IntLiteral(_) => Type::Int,
+            Expression::FloatLiteral(_) => Type::Float,
+            Expression::BoolLiteral(_) => Type::Bool,
+            Expression::StringLiteral(_) => Type::String,
+            Expression::PrefixExpression(operator, right, span) => {
+                let right = self.expression(right);
+                match operator {
+                    Token::Bang => Type::Bool,
+                    Token::Minus if right.is_number() || right == Type::Unknown => right,
+                    Token::Minus => {
+                        self.warn(Some(*span), format!("`-` is not supported on {}", right));
+                        Type::Unknown
+                    }
+                    _ => Type::Unknown,
+                }
+            }
+            Expression::InfixExpression(left, operator, right, span) => {
+                let left = self.expression(left);
+                let right = self.expression(right);
+                match infix(&left, operator, &right) {
+                    Some(ty) => ty,
+                    None => {
+                        let message = format!(
+                            "`{}` is not supported between {} and {}",
+                            operator, left, right
+                        );
+                        self.warn(Some(*span), message);
+                        Type::Unknown
+                    }
+                }
+            }
+            Expression::IfExpression(condition, consequence, alternative, span) => {
+                let condition_span = condition.span().or(Some(*span));
+                let condition = self.expression(condition);
+                if !matches!(condition, Type::Bool | Type::Unknown) {
+                    let message = format!("the condition is {}, `if` only takes bool", condition);
+                    self.warn(condition_span, message);
+                }
+                let statement = self.statement;
+                let consequence = self.block(consequence);
+                let alternative = self.block(alternative);
+                self.statement = statement;
+                consequence.join(alternative)
+            }
+            Expression::FunctionExpression(params, body, _) => {
+                let ret = self.function(params, body, vec![Type::Unknown; params.len()]);
+                Type::Function(vec![Type::Unknown; params.len()], Box::new(ret))
+            }
+            Expression::CallExpression(function, args, span) => {
+                let callee = self.expression(function);
+                let args = args
+                    .iter()
+                    .map(|arg| self.expression(arg))
+                    .collect::<Vec<_>>();
+                self.call(function, callee, &args, *span)
+            }
+            Expression::IndexExpression(left, index, span) => {
+                let left = self.expression(left);
+                let index = self.expression(index);
+                match (left, index) {
+                    (Type::Array(element), Type::Int | Type::Unknown) => *element,
+                    (Type::Hash, Type::Int | Type::Bool | Type::String | Type::Unknown)
+                    | (Type::Unknown, _) => Type::Unknown,
+                    (left, index) => {
+                        let message = format!("{} cannot be indexed with {}", left, index);
+                        self.warn(Some(*span), message);
+                        Type::Unknown
+                    }
+                }
+            }
+            Expression::ArrayLiteral(items) => {
+                let mut items = items.iter().map(|item| self.expression(item));
+                let element = match items.next() {
+                    Some(first) => items.fold(first, Type::join),
+                    None => Type::Unknown,
+                };
+                Type::Array(Box::new(element))
+            }
+            Expression::HashLiteral(pairs) => {
+                for (key, value) in pairs {
+                    let span = key.span();
+                    let key = self.expression(key);
+                    if !matches!(key, Type::Int | Type::Bool | Type::String | Type::Unknown) {
+                        self.warn(span, format!("{} cannot be a hash key", key));
+                    }
+                    self.expression(value);
+                }
+                Type::Hash
+            }
+        }
+    }
+
+    // what a function returns when its parameters have the types `args`
+    fn function(&mut self, params: &[Ident], body: &'p [Statement], args: Vec<Type>) -> Type {
+        let statement = self.statement;
+        self.scopes.push(
+            params
+                .iter()
+                .map(|param| param.0.clone())
+                .zip(args)
+                .collect(),
+        );
+        self.literals.push(HashMap::new());
+        self.returns.push(vec![]);
+        let last = self.block(body);
+        let ret = self
+            .returns
+            .pop()
+            .unwrap()
+            .into_iter()
+            .fold(last, Type::join);
+        self.literals.pop();
+        self.scopes.pop();
+        self.statement = statement;
+        ret
+    }
+
+    // Checks the body of the function literal bound at `depth` for a call
+    // with arguments of types `args`, seeing the bindings it sees where it
+    // is written. None for a call the literal makes to itself.
+    fn call_literal(
+        &mut self,
+        depth: usize,
+        literal: &'p Expression,
+        args: &[Type],
+    ) -> Option<Call> {
+        let (params, body) = match literal {
+            Expression::FunctionExpression(params, body, _) => (params, body),
+            _ => return None,
+        };
+        let key = (literal as *const Expression, args.to_vec());
+        if self.calling.contains(&key.0) {
+            return None;
+        }
+        if let Some(call) = self.calls.get(&key) {
+            return Some(call.clone());
+        }
+        self.calling.push(key.0);
+        let scopes = self.scopes.split_off(depth + 1);
+        let literals = self.literals.split_off(depth + 1);
+        let warnings = std::mem::take(&mut self.warnings);
+        let ret = self.function(params, body, args.to_vec());
+        let found = std::mem::replace(&mut self.warnings, warnings);
+        self.scopes.extend(scopes);
+        self.literals.extend(literals);
+        self.calling.pop();
+        self.calls.insert(key, (ret.clone(), found.clone()));
+        Some((ret, found))
+    }
+
+    fn call(&mut self, function: &Expression, callee: Type, args: &[Type], span: Span) -> Type {
+        let (params, ret) = match callee {
+            Type::Function(params, ret) => (params, ret),
+            Type::Unknown => return Type::Unknown,
+            callee => {
+                self.warn(
+                    Some(span),
+                    format!("{} is called, it is not a function", callee),
+                );
+                return Type::Unknown;
+            }
+        };
+        let name = match function {
+            Expression::Identifier(ident) => format!("`{}`", ident.0),
+            _ => "the function".to_string(),
+        };
+        if params.len() != args.len() {
+            let message = format!(
+                "{} takes {} argument(s), got {}",
+                name,
+                params.len(),
+                args.len()
+            );
+            self.warn(Some(span), message);
+            return Type::Unknown;
+        }
+        // a builtin, unless a binding hides it
+        match function {
+            Expression::Identifier(ident)
+                if !self.scopes.iter().any(|scope| scope.contains_key(&ident.0)) =>
+            {
+                if let Some(message) = builtin_argument(&ident.0, args) {
+                    self.warn(Some(span), format!("{} {}", name, message));
+                    return Type::Unknown;
+                }
+                builtin_result(&ident.0, args)
+            }
+            Expression::Identifier(ident) if args.iter().any(|arg| *arg != Type::Unknown) => {
+                let (depth, literal) = match self.literal(&ident.0) {
+                    Some(literal) => literal,
+                    None => return *ret,
+                };
+                let unknown = vec![Type::Unknown; args.len()];
+                let (ret, found, known) = match (
+                    self.call_literal(depth, literal, args),
+                    self.call_literal(depth, literal, &unknown),
+                ) {
+                    (Some((ret, found)), Some((_, known))) => (ret, found, known),
+                    _ => return *ret,
+                };
+                // the warnings of the body whatever the arguments are were
+                // reported where they are written
+                let args = args
+                    .iter()
+                    .map(Type::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                for warning in found.into_iter().filter(|w| !known.contains(w)) {
+                    let message = format!(
+                        "{} called with {}: {} at {}",
+                        name, args, warning.message, warning.span.start
+                    );
+                    self.warn(Some(span), message);
+                }
+                ret
+            }
+            _ => *ret,
+        }
+    }
+}
+
+// the type of `left operator right`, None if the evaluator fails on it
+fn infix(left: &Type, operator: &Token, right: &Type) -> Option<Type> {
+    use Type::*;
+    let unknown = *left == Unknown || *right == Unknown;
+    match operator {
+        Token::Eq | Token::NotEq if unknown || left.kind() == right.kind() => Some(Bool),
+        Token::LT | Token::GT if unknown => Some(Bool),
+        _ if unknown => Some(Unknown),
+        Token::Plus | Token::Minus => match (left, right) {
+            (Int, Int) => Some(Int),
+            (l, r) if l.is_number() && r.is_number() => Some(Float),
+            (String, String | Int | Float | Bool) if *operator == Token::Plus => Some(String),
+            _ => None,
+        },
+        Token::Asterisk | Token::Slash => match (left, right) {
+            (Int, Int) => Some(Int),
+            (Float, Float) => Some(Float),
+            _ => None,
+        },
+        Token::LT | Token::GT => match (left, right) {
+            (Int, Int) | (Float, Float) => Some(Bool),
+            _ => None,
+        },
+        Token::Eq | Token::NotEq => None,
+        _ => Some(Unknown),
+    }
+}
+
+// why the builtin fails on arguments of these types, None if it may not
+fn builtin_argument(name: &str, args: &[Type]) -> Option<String> {
+    let arg = args.first()?;
+    let fails = match name {
+        "len" => !matches!(
+            arg,
+            Type::String | Type::Array(_) | Type::Hash | Type::Unknown
+        ),
+        "first" | "last" | "rest" | "push" => !matches!(arg, Type::Array(_) | Type::Unknown),
+        _ => false,
+    };
+    if fails {
+        Some(format!("does not take {}", arg))
+    } else {
+        None
+    }
+}
+
+// what the builtins computing on their arguments return
+fn builtin_result(name: &str, args: &[Type]) -> Type {
+    match (name, args) {
+        ("len", _) => Type::Int,
+        ("first" | "last", [Type::Array(element)]) => (**element).clone(),
+        ("rest", [array @ Type::Array(_)]) => array.clone(),
+        ("push", [Type::Array(element), item]) => {
+            Type::Array(Box::new((**element).clone().join(item.clone())))
+        }
+        ("puts" | "eputs", _) => Type::Null,
+        ("is_error", _) => Type::Bool,
+        ("message", _) => Type::String,
+        ("args", _) => Type::Array(Box::new(Type::String)),
+        _ => Type::Unknown,
+    }
+}
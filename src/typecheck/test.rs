@@ -0,0 +1,109 @@
+use crate::lexer::lexer::Lexer;
+use crate::parser::Parser;
+use crate::typecheck::{check, Type};
+
+#[cfg(test)]
+fn test_check(input: &str) -> Vec<String> {
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+    check(&program)
+        .into_iter()
+        .map(|warning| format!("{} {}", warning.span.start, warning.message))
+        .collect()
+}
+
+#[test]
+fn test_types() {
+    let cases = [
+        ("let a = 1 + 2.5; a * 2.0;", vec![]),
+        ("\"n = \" + 1; 1 + 2 == 3", vec![]),
+        (
+            "let a = 1; let b = \"x\"; a - b;",
+            vec!["1:25 `-` is not supported between int and string"],
+        ),
+        (
+            "1 == true",
+            vec!["1:1 `==` is not supported between int and bool"],
+        ),
+        // what a function returns is inferred, for the arguments of a call
+        // by its name
+        (
+            "let f = fn(x) { if (x) { return 1; }; 2 }; f(true) + \"a\"",
+            vec!["1:44 `+` is not supported between int and string"],
+        ),
+        (
+            "let f = fn(x) { x + 1 }; f(\"a\") * 2",
+            vec!["1:26 `*` is not supported between string and int"],
+        ),
+        (
+            "let f = fn(x) { 1 - x }; f(\"s\"); f(2)",
+            vec![
+                "1:26 `f` called with string: `-` is not supported between int and string at 1:17",
+            ],
+        ),
+        (
+            "let f = fn(x) { len(1); x }; f(2)",
+            vec!["1:17 `len` does not take int"],
+        ),
+        // the body sees the bindings where it is written
+        (
+            "let y = 1; let f = fn(x) { x * y }; let g = fn() { let y = \"s\"; f(2) }; g()",
+            vec![],
+        ),
+        (
+            "let f = fn(n) { if (n < 1) { 0 } else { f(n - 1) } }; f(3) + f(true)",
+            vec![
+                "1:62 `f` called with bool: `<` is not supported between bool and int at 1:21",
+                "1:62 `f` called with bool: `-` is not supported between bool and int at 1:43",
+            ],
+        ),
+        (
+            "let f = fn(n) { f(n, 1) }; f(1)",
+            vec!["1:17 `f` takes 1 argument(s), got 2"],
+        ),
+        (
+            "let a = [1, 2]; a[0] < \"b\"; a[\"0\"]",
+            vec![
+                "1:17 `<` is not supported between int and string",
+                "1:29 array<int> cannot be indexed with string",
+            ],
+        ),
+        (
+            "len(1); first(\"ab\"); len([1])",
+            vec![
+                "1:1 `len` does not take int",
+                "1:9 `first` does not take string",
+            ],
+        ),
+        ("let len = fn(x) { x }; len(1)", vec![]),
+        (
+            "let x = 5; x(); if (x) { 1 }",
+            vec![
+                "1:12 int is called, it is not a function",
+                "1:21 the condition is int, `if` only takes bool",
+            ],
+        ),
+        (
+            "{[1]: 2, \"a\": -true}",
+            vec![
+                "1:1 array<int> cannot be a hash key",
+                "1:15 `-` is not supported on bool",
+            ],
+        ),
+        // unknown values are never warned about
+        ("let f = fn(a) { a[0] + a(1) - -a }; undefined + 1", vec![]),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(test_check(input), expected, "{}", input);
+    }
+}
+
+#[test]
+fn test_type_display() {
+    let program = Parser::new(Lexer::new("")).parse_program().unwrap();
+    assert!(check(&program).is_empty());
+    let ty = Type::Function(
+        vec![Type::Array(Box::new(Type::Int)), Type::Unknown],
+        Box::new(Type::Hash),
+    );
+    assert_eq!(ty.to_string(), "fn(array<int>, unknown) -> hash");
+}
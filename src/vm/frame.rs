@@ -0,0 +1,24 @@
+use crate::compiler::Closure;
+use std::sync::Arc;
+
+// a function being executed, its locals live on the stack above `base_pointer`
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub closure: Arc<Closure>,
+    pub ip: usize,
+    pub base_pointer: usize,
+}
+
+impl Frame {
+    pub fn new(closure: Arc<Closure>, base_pointer: usize) -> Self {
+        Frame {
+            closure,
+            ip: 0,
+            base_pointer,
+        }
+    }
+
+    pub fn instructions(&self) -> &[u8] {
+        &self.closure.func.instructions
+    }
+}
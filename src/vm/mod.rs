@@ -0,0 +1,573 @@
+use crate::compiler::code::{describe_value, format_instruction, read_u16, Opcode};
+use crate::compiler::{span_at, Bytecode, Closure, CompiledFunction};
+use crate::eval::builtins::Builtins;
+use crate::eval::evaluator::{CancelHandle, EvalOptions, Evaluator};
+use crate::eval::output::Output;
+use crate::eval::{HashMap, ObjectWrapper};
+use crate::parser::{ParseError, Result};
+use crate::vm::frame::Frame;
+use std::sync::Arc;
+
+pub mod frame;
+
+#[cfg(test)]
+mod test;
+
+pub const STACK_SIZE: usize = 2048;
+pub const GLOBALS_SIZE: usize = 65536;
+pub const MAX_FRAMES: usize = 1024;
+
+// Limits of a vm, exceeding them is a runtime error.
+#[derive(Debug, Clone)]
+pub struct VmOptions {
+    // number of values on the stack, the locals of every active call included
+    pub stack_size: usize,
+    pub globals_size: usize,
+    // depth of nested calls
+    pub max_frames: usize,
+    // write every executed instruction with the top of the stack after it
+    // to stderr
+    pub trace: bool,
+    // every instruction costs one step against `max_steps`, the arrays,
+    // hashes and strings the vm creates count against `max_memory`. Builtins
+    // are called with these options.
+    pub eval: EvalOptions,
+    // checked before every instruction, see `CancelHandle`
+    pub cancel: CancelHandle,
+}
+
+impl Default for VmOptions {
+    fn default() -> Self {
+        VmOptions {
+            stack_size: STACK_SIZE,
+            globals_size: GLOBALS_SIZE,
+            max_frames: MAX_FRAMES,
+            trace: false,
+            eval: EvalOptions::default(),
+            cancel: CancelHandle::default(),
+        }
+    }
+}
+
+// Stack machine running the compiler's bytecode. Values and errors are the
+// same as the evaluator's.
+pub struct Vm {
+    constants: Arc<Vec<ObjectWrapper>>,
+    // grows up to `VmOptions::stack_size` as values are pushed
+    stack: Vec<ObjectWrapper>,
+    // the next free slot, the top of the stack is at `sp - 1`
+    sp: usize,
+    // shared with the closures builtins call while they run, copied if they
+    // still hold them when a global is set
+    globals: Arc<Vec<ObjectWrapper>>,
+    frames: Vec<Frame>,
+    last_popped: ObjectWrapper,
+    steps: u64,
+    // builtins are written against the evaluator, they are called with this
+    // one. It also takes the steps of the vm and counts the values it
+    // creates against `max_memory`. The closures builtins call back, like
+    // `rescue` does, run on vms of their own, see `Callbacks`.
+    evaluator: Evaluator<'static>,
+    options: VmOptions,
+    output: Output,
+}
+
+impl Vm {
+    pub fn new(bytecode: Bytecode) -> Self {
+        Self::with_globals(bytecode, vec![])
+    }
+
+    // keeps the globals of a previous run, for the REPL
+    pub fn with_globals(bytecode: Bytecode, globals: Vec<ObjectWrapper>) -> Self {
+        let main = CompiledFunction {
+            instructions: bytecode.instructions,
+            num_locals: 0,
+            num_parameters: 0,
+            spans: bytecode.spans,
+        };
+        let options = VmOptions::default();
+        let mut frames = Vec::new();
+        let main = Closure {
+            func: Arc::new(main),
+            free: vec![],
+        };
+        frames.push(Frame::new(Arc::new(main), 0));
+        Vm {
+            constants: Arc::new(bytecode.constants),
+            stack: vec![],
+            sp: 0,
+            globals: Arc::new(globals),
+            frames,
+            last_popped: ObjectWrapper::Null,
+            steps: 0,
+            evaluator: Evaluator::new(&[]),
+            options,
+            output: Output::default(),
+        }
+    }
+
+    // where builtins print to and the trace goes
+    pub fn set_output(&mut self, output: Output) {
+        self.evaluator.set_output(output.clone());
+        self.output = output;
+    }
+
+    // what `args` returns
+    pub fn set_args(&mut self, args: Vec<String>) {
+        self.evaluator.set_args(args);
+    }
+
+    pub fn set_options(&mut self, options: VmOptions) {
+        self.evaluator.set_options(options.eval.clone());
+        self.evaluator.set_cancel_handle(options.cancel.clone());
+        self.options = options;
+    }
+
+    // the instructions run so far
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn into_globals(self) -> Vec<ObjectWrapper> {
+        Arc::unwrap_or_clone(self.globals)
+    }
+
+    // what the script called `exit` with, `run` then failed
+    pub fn exit_code(&self) -> Option<i32> {
+        self.evaluator.exit_code()
+    }
+
+    // the value of the last expression statement that was run
+    pub fn last_popped_stack_elem(&self) -> &ObjectWrapper {
+        &self.last_popped
+    }
+
+    pub fn run(&mut self) -> Result<()> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("run").entered();
+        let ret = self.run_instructions();
+        self.options.cancel.stopped();
+        ret
+    }
+
+    fn run_instructions(&mut self) -> Result<()> {
+        loop {
+            let frame = self.frames.last_mut().unwrap();
+            if frame.ip >= frame.instructions().len() {
+                return Ok(());
+            }
+            let offset = frame.ip;
+            let byte = frame.instructions()[offset];
+            frame.ip += 1;
+            let op = Opcode::from_byte(byte).ok_or(format!("unknown opcode: {}", byte))?;
+
+            let depth = self.frames.len() - 1;
+            self.step().map_err(|e| self.locate(e, depth, offset))?;
+            if !self.options.trace {
+                if !self
+                    .execute(op)
+                    .map_err(|e| self.locate(e, depth, offset))?
+                {
+                    return Ok(());
+                }
+                continue;
+            }
+            let closure = self.current_frame().closure.clone();
+            let running = self
+                .execute(op)
+                .map_err(|e| self.locate(e, depth, offset))?;
+            self.trace(depth, &closure.func.instructions, offset)?;
+            if !running {
+                return Ok(());
+            }
+        }
+    }
+
+    fn step(&mut self) -> Result<()> {
+        self.steps += 1;
+        self.evaluator.step()
+    }
+
+    // points an error without a span at the source of the instruction at
+    // `offset` of the frame at `depth`
+    fn locate(&self, e: ParseError, depth: usize, offset: usize) -> ParseError {
+        let span = self
+            .frames
+            .get(depth)
+            .and_then(|frame| span_at(&frame.closure.func.spans, offset));
+        match span {
+            Some(span) => e.or_span(span),
+            None => e,
+        }
+    }
+
+    // runs one instruction, false when the program is done
+    fn execute(&mut self, op: Opcode) -> Result<bool> {
+        match op {
+            Opcode::Constant => {
+                let index = self.read_u16_operand()?;
+                let obj = self
+                    .constants
+                    .get(index)
+                    .cloned()
+                    .ok_or(format!("unknown constant: {}", index))?;
+                self.push(obj)?;
+            }
+            Opcode::Pop => self.last_popped = self.pop()?,
+            Opcode::Add
+            | Opcode::Sub
+            | Opcode::Mul
+            | Opcode::Div
+            | Opcode::Equal
+            | Opcode::NotEqual
+            | Opcode::GreaterThan
+            | Opcode::LessThan => self.execute_binary_operation(op)?,
+            Opcode::True => self.push(ObjectWrapper::Boolean(true))?,
+            Opcode::False => self.push(ObjectWrapper::Boolean(false))?,
+            Opcode::Null => self.push(ObjectWrapper::Null)?,
+            Opcode::Minus => {
+                let obj = self.pop()?.neg()?;
+                self.push(obj)?;
+            }
+            Opcode::Bang => {
+                let obj = match self.pop()? {
+                    ObjectWrapper::Boolean(v) => ObjectWrapper::Boolean(!v),
+                    ObjectWrapper::Integer(v) => ObjectWrapper::Boolean(v == 0),
+                    _ => ObjectWrapper::Boolean(false),
+                };
+                self.push(obj)?;
+            }
+            Opcode::JumpNotTruthy => {
+                let position = self.read_u16_operand()?;
+                match self.pop()? {
+                    ObjectWrapper::Boolean(true) => {}
+                    ObjectWrapper::Boolean(false) => self.current_frame().ip = position,
+                    _ => return Err("Invalid 'if' condition.".into()),
+                }
+            }
+            Opcode::Jump => {
+                let position = self.read_u16_operand()?;
+                self.current_frame().ip = position;
+            }
+            Opcode::SetGlobal => {
+                let index = self.read_u16_operand()?;
+                if index >= self.options.globals_size {
+                    return Err("too many globals".into());
+                }
+                let obj = self.pop()?;
+                let globals = Arc::make_mut(&mut self.globals);
+                if index >= globals.len() {
+                    globals.resize(index + 1, ObjectWrapper::Null);
+                }
+                globals[index] = obj;
+            }
+            Opcode::GetGlobal => {
+                let index = self.read_u16_operand()?;
+                let obj = self
+                    .globals
+                    .get(index)
+                    .cloned()
+                    .unwrap_or(ObjectWrapper::Null);
+                self.push(obj)?;
+            }
+            Opcode::SetLocal => {
+                let slot = self.read_local_operand()?;
+                self.stack[slot] = self.pop()?;
+            }
+            Opcode::GetLocal => {
+                let slot = self.read_local_operand()?;
+                self.push(self.stack[slot].clone())?;
+            }
+            Opcode::GetBuiltin => {
+                let index = self.read_u8_operand()?;
+                let builtin = Builtins::instance_ref()
+                    .get_by_index(index)
+                    .ok_or(format!("unknown builtin: {}", index))?;
+                self.push(builtin)?;
+            }
+            Opcode::GetFree => {
+                let index = self.read_u8_operand()?;
+                let obj = self
+                    .current_frame()
+                    .closure
+                    .free
+                    .get(index)
+                    .cloned()
+                    .ok_or(format!("unknown free variable: {}", index))?;
+                self.push(obj)?;
+            }
+            Opcode::CurrentClosure => {
+                let closure = self.current_frame().closure.clone();
+                self.push(ObjectWrapper::Closure(closure))?;
+            }
+            Opcode::Closure => {
+                let index = self.read_u16_operand()?;
+                let num_free = self.read_u8_operand()?;
+                self.push_closure(index, num_free)?;
+            }
+            Opcode::Array => {
+                let len = self.read_u16_operand()?;
+                let elements = self.take_stack_values(len)?;
+                let array = self
+                    .evaluator
+                    .track(ObjectWrapper::Array(elements.into()))?;
+                self.push(array)?;
+            }
+            Opcode::Hash => {
+                let len = self.read_u16_operand()?;
+                let elements = self.take_stack_values(len)?;
+                let mut hash = HashMap::with_capacity_and_hasher(len / 2, Default::default());
+                let mut elements = elements.into_iter();
+                while let (Some(key), Some(value)) = (elements.next(), elements.next()) {
+                    hash.insert(key.hash_key()?, value);
+                }
+                let hash = self.evaluator.track(ObjectWrapper::Hash(hash))?;
+                self.push(hash)?;
+            }
+            Opcode::Index => {
+                let index = self.pop()?;
+                let left = self.pop()?;
+                self.push(left.index(&index)?)?;
+            }
+            Opcode::Call => {
+                let num_args = self.read_u8_operand()?;
+                self.call_function(num_args)?;
+            }
+            Opcode::ReturnValue => {
+                let ret = self.pop()?;
+                let frame = self.frames.pop().unwrap();
+                if self.frames.is_empty() {
+                    // `return` at the top level ends the program
+                    self.last_popped = ret;
+                    return Ok(false);
+                }
+                self.sp = frame.base_pointer - 1;
+                self.push(ret)?;
+            }
+            Opcode::Return => {
+                let frame = self.frames.pop().unwrap();
+                if self.frames.is_empty() {
+                    self.last_popped = ObjectWrapper::Null;
+                    return Ok(false);
+                }
+                self.sp = frame.base_pointer - 1;
+                self.push(ObjectWrapper::Null)?;
+            }
+        }
+        Ok(true)
+    }
+
+    fn trace(&self, depth: usize, instructions: &[u8], offset: usize) -> Result<()> {
+        let (text, _) = format_instruction(instructions, offset, &self.constants);
+        let top = match self.sp {
+            0 => "<empty>".to_string(),
+            sp => describe_value(&self.stack[sp - 1]),
+        };
+        let line = format!("[frame {}] {:04} {:<32} top: {}", depth, offset, text, top);
+        self.output
+            .stderr
+            .write_line(&line)
+            .map_err(|e| format!("cannot write trace: {}", e).into())
+    }
+
+    fn current_frame(&mut self) -> &mut Frame {
+        self.frames.last_mut().unwrap()
+    }
+
+    // operands, locals and constants are checked, bytecode read from a file
+    // may not come from the compiler
+    fn read_u16_operand(&mut self) -> Result<usize> {
+        let frame = self.current_frame();
+        let operand = match frame.instructions().get(frame.ip..frame.ip + 2) {
+            Some(ins) => read_u16(ins) as usize,
+            None => return Err("truncated instruction".into()),
+        };
+        frame.ip += 2;
+        Ok(operand)
+    }
+
+    fn read_u8_operand(&mut self) -> Result<usize> {
+        let frame = self.current_frame();
+        let operand = *frame
+            .instructions()
+            .get(frame.ip)
+            .ok_or("truncated instruction")? as usize;
+        frame.ip += 1;
+        Ok(operand)
+    }
+
+    // the stack slot of the local the operand refers to
+    fn read_local_operand(&mut self) -> Result<usize> {
+        let index = self.read_u8_operand()?;
+        let frame = self.current_frame();
+        if index >= frame.closure.func.num_locals {
+            return Err(format!("unknown local: {}", index).into());
+        }
+        Ok(frame.base_pointer + index)
+    }
+
+    fn execute_binary_operation(&mut self, op: Opcode) -> Result<()> {
+        let right = self.pop()?;
+        let left = self.pop()?;
+        let obj = match op {
+            Opcode::Add => left.add(&right),
+            Opcode::Sub => left.sub(&right),
+            Opcode::Mul => left.multi(&right),
+            Opcode::Div => left.divide(&right),
+            Opcode::Equal => left.eq(&right),
+            Opcode::NotEqual => left.not_eq(&right),
+            Opcode::GreaterThan => left.great_than(&right),
+            Opcode::LessThan => left.less_than(&right),
+            _ => Err(format!("unknown binary operator: {:?}", op).into()),
+        }?;
+        let obj = self.evaluator.track(obj)?;
+        self.push(obj)
+    }
+
+    fn push_closure(&mut self, index: usize, num_free: usize) -> Result<()> {
+        let func = match self.constants.get(index) {
+            Some(ObjectWrapper::CompiledFn(func)) => func.clone(),
+            Some(obj) => return Err(format!("not a function: {}", obj.type_str()).into()),
+            None => return Err(format!("unknown constant: {}", index).into()),
+        };
+        let free = self.take_stack_values(num_free)?;
+        self.push(ObjectWrapper::Closure(Arc::new(Closure { func, free })))
+    }
+
+    fn call_function(&mut self, num_args: usize) -> Result<()> {
+        let callee = match self.sp.checked_sub(1 + num_args) {
+            Some(index) => self.stack[index].clone(),
+            None => return Err("stack underflow".into()),
+        };
+        match callee {
+            ObjectWrapper::Closure(closure) => {
+                let func = &closure.func;
+                if num_args != func.num_parameters {
+                    return Err(format!(
+                        "Invalid params, expect {} got {}",
+                        func.num_parameters, num_args
+                    )
+                    .into());
+                }
+                if self.frames.len() >= self.options.max_frames {
+                    return Err("stack overflow".into());
+                }
+                let base_pointer = self.sp - num_args;
+                let sp = base_pointer + func.num_locals;
+                if sp >= self.options.stack_size {
+                    return Err("stack overflow".into());
+                }
+                if self.stack.len() < sp {
+                    self.stack.resize(sp, ObjectWrapper::Null);
+                }
+                for slot in &mut self.stack[self.sp..sp] {
+                    *slot = ObjectWrapper::Null;
+                }
+                self.sp = sp;
+                self.frames.push(Frame::new(closure, base_pointer));
+                Ok(())
+            }
+            ObjectWrapper::BuiltinFn(..)
+            | ObjectWrapper::HostFn(_)
+            | ObjectWrapper::Memoized(_) => {
+                let args = self.take_stack_values(num_args)?;
+                let callbacks = Callbacks {
+                    constants: self.constants.clone(),
+                    globals: self.globals.clone(),
+                    // what is left to them
+                    options: VmOptions {
+                        stack_size: self.options.stack_size - self.sp,
+                        max_frames: self.options.max_frames - self.frames.len(),
+                        ..self.options.clone()
+                    },
+                };
+                self.evaluator.set_callbacks(Some(Arc::new(callbacks)));
+                let ret = self.evaluator.apply_function(&callee, args);
+                self.evaluator.set_callbacks(None);
+                let ret = ret?;
+                // replaces the callee
+                self.sp -= 1;
+                self.push(ret)
+            }
+            _ => Err(format!("not a function: {}", callee.type_str()).into()),
+        }
+    }
+
+    // removes the top `len` values, the deepest first
+    fn take_stack_values(&mut self, len: usize) -> Result<Vec<ObjectWrapper>> {
+        let start = self.sp.checked_sub(len).ok_or("stack underflow")?;
+        let values = self.stack[start..self.sp]
+            .iter_mut()
+            .map(|slot| std::mem::replace(slot, ObjectWrapper::Null))
+            .collect();
+        self.sp = start;
+        Ok(values)
+    }
+
+    fn push(&mut self, obj: ObjectWrapper) -> Result<()> {
+        if self.sp >= self.options.stack_size {
+            return Err("stack overflow".into());
+        }
+        if self.sp == self.stack.len() {
+            self.stack.push(obj);
+        } else {
+            self.stack[self.sp] = obj;
+        }
+        self.sp += 1;
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<ObjectWrapper> {
+        if self.sp == 0 {
+            return Err("stack underflow".into());
+        }
+        self.sp -= 1;
+        Ok(std::mem::replace(
+            &mut self.stack[self.sp],
+            ObjectWrapper::Null,
+        ))
+    }
+}
+
+// What builtins called by a vm need to call its closures back. The globals
+// are those of when the builtin was called, functions cannot set globals.
+pub(crate) struct Callbacks {
+    constants: Arc<Vec<ObjectWrapper>>,
+    globals: Arc<Vec<ObjectWrapper>>,
+    options: VmOptions,
+}
+
+impl Callbacks {
+    // `closure` called with `args` on a vm of its own, what it takes counts
+    // against the limits of `ev`
+    pub(crate) fn call(
+        &self,
+        ev: &mut Evaluator,
+        closure: Arc<Closure>,
+        args: Vec<ObjectWrapper>,
+    ) -> Result<ObjectWrapper> {
+        let mut vm = Vm {
+            constants: self.constants.clone(),
+            stack: vec![],
+            sp: 0,
+            globals: self.globals.clone(),
+            frames: vec![],
+            last_popped: ObjectWrapper::Null,
+            steps: 0,
+            evaluator: ev.worker().evaluator(),
+            options: self.options.clone(),
+            output: ev.output().clone(),
+        };
+        let num_args = args.len();
+        vm.push(ObjectWrapper::Closure(closure))?;
+        for arg in args {
+            vm.push(arg)?;
+        }
+        // ends once the closure returned
+        let ret = vm
+            .call_function(num_args)
+            .and_then(|_| vm.run_instructions());
+        ev.absorb(vm.evaluator.steps(), vm.evaluator.take_heap())?;
+        ret.map(|_| vm.last_popped)
+    }
+}
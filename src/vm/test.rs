@@ -0,0 +1,545 @@
+use crate::compiler::code::{make, Opcode};
+use crate::compiler::{Bytecode, CompiledFunction, Compiler};
+use crate::eval::capabilities::Capabilities;
+use crate::eval::evaluator::{CancelHandle, EvalOptions};
+use crate::eval::output::{Output, OutputBuffer, Sink};
+use crate::eval::{HashKey, HashMap, ObjectWrapper};
+use crate::lexer::lexer::Lexer;
+use crate::parser::Parser;
+use crate::parser::Result;
+use crate::vm::{Vm, VmOptions};
+use std::sync::Arc;
+
+#[cfg(test)]
+fn run_vm(input: &str) -> Result<ObjectWrapper> {
+    let program = Parser::new(Lexer::new(input)).parse_program()?;
+    let mut compiler = Compiler::new();
+    compiler.compile(&program)?;
+    let mut vm = Vm::new(compiler.bytecode());
+    vm.run()?;
+    Ok(vm.last_popped_stack_elem().clone())
+}
+
+#[test]
+fn test_integer_arithmetic() {
+    let cases = [
+        ("1", 1),
+        ("1 + 2", 3),
+        ("50 / 2 * 2 + 10 - 5", 55),
+        ("5 * (2 + 10)", 60),
+        ("-50 + 100 + -50", 0),
+        ("(5 + 10 * 2 + 15 / 3) * 2 + -10", 50),
+    ];
+    for (input, expect) in cases {
+        assert_eq!(
+            run_vm(input).unwrap(),
+            ObjectWrapper::Integer(expect),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_boolean_expressions() {
+    let cases = [
+        ("true", true),
+        ("1 < 2", true),
+        ("1 > 2", false),
+        ("1 == 1", true),
+        ("(1 < 2) == true", true),
+        ("\"a\" == \"a\"", true),
+        ("\"a\" == \"b\"", false),
+        ("\"a\" != \"a\"", false),
+        ("\"a\" + \"b\" != \"ab\"", false),
+        ("!true", false),
+        ("!!true", true),
+        ("!5", false),
+        ("!(if (false) { 5; })", false),
+    ];
+    for (input, expect) in cases {
+        assert_eq!(
+            run_vm(input).unwrap(),
+            ObjectWrapper::Boolean(expect),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_conditionals_and_globals() {
+    let cases = [
+        ("if (true) { 10 }", ObjectWrapper::Integer(10)),
+        ("if (1 > 2) { 10 } else { 20 }", ObjectWrapper::Integer(20)),
+        ("if (1 > 2) { 10 }", ObjectWrapper::Null),
+        (
+            "let one = 1; let two = one + one; one + two",
+            ObjectWrapper::Integer(3),
+        ),
+        ("let one = 1;", ObjectWrapper::Null),
+        ("return 7; 8", ObjectWrapper::Integer(7)),
+        ("\"mon\" + \"key\"", ObjectWrapper::String("monkey".into())),
+    ];
+    for (input, expect) in cases {
+        assert_eq!(run_vm(input).unwrap(), expect, "{}", input);
+    }
+}
+
+#[test]
+fn test_collections() {
+    let mut hash = HashMap::default();
+    hash.insert(HashKey::Integer(1), ObjectWrapper::Integer(2));
+    hash.insert(HashKey::String("a".into()), ObjectWrapper::Integer(12));
+    let cases = [
+        ("[]", ObjectWrapper::Array(vec![].into())),
+        (
+            "[1, 2 * 3]",
+            ObjectWrapper::Array(vec![ObjectWrapper::Integer(1), ObjectWrapper::Integer(6)].into()),
+        ),
+        ("{1: 2, \"a\": 3 * 4}", ObjectWrapper::Hash(hash)),
+        ("[1, 2, 3][1]", ObjectWrapper::Integer(2)),
+        ("{1: 2}[1]", ObjectWrapper::Integer(2)),
+        ("{1: 2}[0]", ObjectWrapper::Null),
+    ];
+    for (input, expect) in cases {
+        assert_eq!(run_vm(input).unwrap(), expect, "{}", input);
+    }
+}
+
+#[test]
+fn test_calling_functions() {
+    let cases = [
+        ("let f = fn() { 5 + 10; }; f();", ObjectWrapper::Integer(15)),
+        (
+            "let f = fn() { return 1; 2 }; f();",
+            ObjectWrapper::Integer(1),
+        ),
+        ("let f = fn() { }; f();", ObjectWrapper::Null),
+        (
+            "let sum = fn(a, b) { let c = a + b; c }; sum(1, 2) + sum(3, 4)",
+            ObjectWrapper::Integer(10),
+        ),
+        (
+            "let global = 10; let f = fn(a) { let local = 1; a + local + global }; f(1) + f(2)",
+            ObjectWrapper::Integer(25),
+        ),
+        (
+            "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(15)",
+            ObjectWrapper::Integer(610),
+        ),
+        (
+            "let f = fn() { if (true) { return 1; } 2 }; f()",
+            ObjectWrapper::Integer(1),
+        ),
+        // the last of repeated parameters, like the evaluator
+        ("let f = fn(a, a) { a }; f(1, 2)", ObjectWrapper::Integer(2)),
+        (
+            "let f = fn(a, b, a) { let c = a; b + c }; f(1, 2, 3)",
+            ObjectWrapper::Integer(5),
+        ),
+    ];
+    for (input, expect) in cases {
+        assert_eq!(run_vm(input).unwrap(), expect, "{}", input);
+    }
+}
+
+#[test]
+fn test_closures() {
+    let cases = [
+        (
+            "let adder = fn(a) { fn(b) { a + b } }; let add2 = adder(2); add2(3)",
+            ObjectWrapper::Integer(5),
+        ),
+        (
+            "let f = fn(a, b) { let c = a + b; fn(d) { fn(e) { c + d + e } } }; f(1, 2)(3)(4)",
+            ObjectWrapper::Integer(10),
+        ),
+        (
+            "let wrapper = fn() { let countdown = fn(x) { if (x == 0) { 0 } else { countdown(x - 1) } }; countdown(5) }; wrapper()",
+            ObjectWrapper::Integer(0),
+        ),
+        (
+            "let a = 1; let f = fn() { let a = 2; fn() { a } }; f()() + a",
+            ObjectWrapper::Integer(3),
+        ),
+    ];
+    for (input, expect) in cases {
+        assert_eq!(run_vm(input).unwrap(), expect, "{}", input);
+    }
+}
+
+#[test]
+fn test_builtin_functions() {
+    let cases = [
+        ("len(\"four\")", ObjectWrapper::Integer(4)),
+        ("len([1, 2, 3])", ObjectWrapper::Integer(3)),
+        (
+            "let f = fn(xs) { first(xs) }; f([7, 8])",
+            ObjectWrapper::Integer(7),
+        ),
+        ("let len = fn(x) { 0 }; len([1])", ObjectWrapper::Integer(0)),
+        // builtins call closures back
+        (
+            "rescue(fn() { 1 / 0 }, fn(e) { 5 })",
+            ObjectWrapper::Integer(5),
+        ),
+        (
+            "rescue(fn() { error(\"boom\") }, fn(e) { \"caught: \" + message(e) })",
+            ObjectWrapper::String("caught: boom".into()),
+        ),
+        (
+            "let k = 10; let f = fn(x) { pmap([x, x + 1], fn(y) { y + k }) }; f(1)",
+            ObjectWrapper::Array(Arc::new(vec![
+                ObjectWrapper::Integer(11),
+                ObjectWrapper::Integer(12),
+            ])),
+        ),
+        (
+            "let k = 2; let f = memoize(fn(x) { x * k }); f(3) + f(3)",
+            ObjectWrapper::Integer(12),
+        ),
+        (
+            "let f = fn(n) { if (n < 1) { 0 } else { rescue(fn() { f(n - 1) }, fn(e) { e }) + 1 } }; f(10)",
+            ObjectWrapper::Integer(10),
+        ),
+    ];
+    for (input, expect) in cases {
+        assert_eq!(run_vm(input).unwrap(), expect, "{}", input);
+    }
+
+    let cases = [
+        ("rescue(fn() { 1 / 0 }, 1)", "not a function: int"),
+        (
+            "pmap([1], fn(x, y) { x })",
+            "Invalid params, expect 2 got 1",
+        ),
+    ];
+    for (input, expect) in cases {
+        let e = run_vm(input).unwrap_err();
+        assert_eq!(e.to_string(), expect, "{}", input);
+    }
+
+    // the limits are those of the vm calling the builtin
+    let cases = [
+        (
+            VmOptions {
+                max_frames: 10,
+                ..Default::default()
+            },
+            "let f = fn(n) { if (n < 1) { 0 } else { pmap([n - 1], f)[0] } }; f(20)",
+            "stack overflow",
+        ),
+        (
+            VmOptions {
+                eval: EvalOptions {
+                    max_steps: Some(1000),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            "let f = fn(n) { if (n < 1) { 0 } else { 1 + f(n - 1) } }; pmap([50, 50, 50, 50], f)",
+            "script exceeded execution limit",
+        ),
+    ];
+    for (options, input, expect) in cases {
+        let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+        let mut compiler = Compiler::new();
+        compiler.compile(&program).unwrap();
+        let mut vm = Vm::new(compiler.bytecode());
+        vm.set_options(options);
+        assert_eq!(vm.run().unwrap_err().to_string(), expect, "{}", input);
+    }
+}
+
+#[test]
+fn test_runtime_errors() {
+    let cases = [
+        ("1 / 0", "division by zero"),
+        ("if (1) { 2 }", "Invalid 'if' condition."),
+        ("let f = fn(a) { a }; f()", "Invalid params, expect 1 got 0"),
+        ("1()", "not a function: int"),
+        ("[1][5]", "Index out of range. expect [0, 1), got 5"),
+        ("let f = fn() { f() }; f()", "stack overflow"),
+        ("len(1, 2)", "Wrong number of arguments, expect 1 got 2"),
+        ("len(1)", "Argument to `len` not supported, got int"),
+        ("9223372036854775807 * 2", "integer overflow"),
+        ("-(-9223372036854775807 - 1)", "integer overflow"),
+    ];
+    for (input, expect) in cases {
+        assert_eq!(run_vm(input).unwrap_err().to_string(), expect, "{}", input);
+    }
+}
+
+// runtime errors point at the expression that failed, also in functions and
+// in bytecode read back from a file
+#[test]
+fn test_runtime_error_spans() {
+    let cases = [
+        ("let a = 0;\n1 + 2 / a", "2:5"),
+        ("let a = [1];\nlet b = a[5];", "2:9"),
+        ("let f = fn(x) {\n  let y = x;\n  -y\n};\nf(\"a\")", "3:3"),
+        (
+            "let f = fn(a) { a };\nlet g = fn() {\n  f()\n};\ng()",
+            "3:3",
+        ),
+        ("1;\n2();", "2:1"),
+    ];
+    for (input, expect) in cases {
+        let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+        let mut compiler = Compiler::new();
+        compiler.compile(&program).unwrap();
+        let bytecode = compiler.bytecode();
+        let read = Bytecode::from_bytes(&bytecode.to_bytes().unwrap()).unwrap();
+        for bytecode in [bytecode, read] {
+            let e = Vm::new(bytecode).run().unwrap_err();
+            let span = e.span().map(|span| span.to_string());
+            assert_eq!(span.as_deref(), Some(expect), "{}", input);
+        }
+    }
+}
+
+// bytecode from a file may not come from the compiler
+#[test]
+fn test_invalid_bytecode() {
+    let function = ObjectWrapper::CompiledFn(Arc::new(CompiledFunction {
+        instructions: [make(Opcode::GetLocal, &[3]), make(Opcode::ReturnValue, &[])].concat(),
+        num_locals: 1,
+        num_parameters: 0,
+        spans: vec![],
+    }));
+    let cases = [
+        (vec![0, 0xff, 0xff], vec![], "unknown constant: 65535"),
+        (vec![0, 0], vec![], "truncated instruction"),
+        (make(Opcode::Pop, &[]), vec![], "stack underflow"),
+        (make(Opcode::Add, &[]), vec![], "stack underflow"),
+        (make(Opcode::Array, &[3]), vec![], "stack underflow"),
+        (make(Opcode::Call, &[2]), vec![], "stack underflow"),
+        (make(Opcode::GetLocal, &[0]), vec![], "unknown local: 0"),
+        (
+            make(Opcode::GetFree, &[1]),
+            vec![],
+            "unknown free variable: 1",
+        ),
+        (
+            make(Opcode::Closure, &[0, 0]),
+            vec![ObjectWrapper::Integer(1)],
+            "not a function: int",
+        ),
+        (
+            [make(Opcode::Closure, &[0, 0]), make(Opcode::Call, &[0])].concat(),
+            vec![function],
+            "unknown local: 3",
+        ),
+    ];
+    for (instructions, constants, expect) in cases {
+        let bytecode = Bytecode {
+            instructions,
+            constants,
+            spans: vec![],
+        };
+        let bytecode = Bytecode::from_bytes(&bytecode.to_bytes().unwrap()).unwrap();
+        let e = Vm::new(bytecode).run().unwrap_err();
+        assert_eq!(e.to_string(), expect);
+    }
+}
+
+#[test]
+fn test_vm_options() {
+    let cases = [
+        (
+            VmOptions {
+                max_frames: 10,
+                ..Default::default()
+            },
+            "let f = fn(n) { if (n == 0) { 0 } else { f(n - 1) } }; f(8)",
+            Ok(ObjectWrapper::Integer(0)),
+        ),
+        (
+            VmOptions {
+                max_frames: 10,
+                ..Default::default()
+            },
+            "let f = fn(n) { if (n == 0) { 0 } else { f(n - 1) } }; f(9)",
+            Err("stack overflow"),
+        ),
+        (
+            VmOptions {
+                stack_size: 4,
+                ..Default::default()
+            },
+            "[1, 2, 3, 4, 5]",
+            Err("stack overflow"),
+        ),
+        (
+            VmOptions {
+                stack_size: 8,
+                ..Default::default()
+            },
+            "let f = fn(a) { let b = 1; let c = 2; a + b + c }; f(f(1))",
+            Ok(ObjectWrapper::Integer(7)),
+        ),
+        (
+            VmOptions {
+                stack_size: 4,
+                ..Default::default()
+            },
+            "let f = fn(a) { let b = 1; let c = 2; let d = 3; a }; f(1)",
+            Err("stack overflow"),
+        ),
+        (
+            VmOptions {
+                globals_size: 2,
+                ..Default::default()
+            },
+            "let a = 1; let b = 2; let c = 3;",
+            Err("too many globals"),
+        ),
+        (
+            VmOptions {
+                eval: EvalOptions {
+                    max_steps: Some(100),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            "let f = fn(n) { f(n + 1) }; f(0)",
+            Err("script exceeded execution limit"),
+        ),
+        (
+            VmOptions {
+                eval: EvalOptions {
+                    capabilities: Capabilities::ALL - Capabilities::CLOCK,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            "time()",
+            Err("permission denied: 'time' needs the clock capability"),
+        ),
+        (
+            VmOptions {
+                eval: EvalOptions {
+                    max_memory: Some(1024),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            "let s = \"xxxxxxxxxxxxxxxx\"; let double = fn(s, n) { if (n < 1) { s } else { double(s + s, n - 1) } }; double(s, 10)",
+            Err("out of memory: script allocated more than 1024 bytes"),
+        ),
+        (
+            VmOptions {
+                eval: EvalOptions {
+                    max_memory: Some(1024),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            "let f = fn(n) { if (n < 1) { 0 } else { [n, n, n, n]; {n: n}; f(n - 1) } }; f(100)",
+            Err("out of memory: script allocated more than 1024 bytes"),
+        ),
+        (
+            VmOptions {
+                eval: EvalOptions {
+                    max_memory: Some(1024),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            "let f = fn(n) { if (n < 1) { 0 } else { [n, n]; f(n - 1) } }; f(2)",
+            Ok(ObjectWrapper::Integer(0)),
+        ),
+        // the arrays are released once popped, together they take more
+        (
+            VmOptions {
+                eval: EvalOptions {
+                    max_memory: Some(1024),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            "let f = fn(n) { if (n < 1) { 0 } else { [n, n, n, n]; f(n - 1) } }; f(100)",
+            Ok(ObjectWrapper::Integer(0)),
+        ),
+    ];
+    for (options, input, expect) in cases {
+        let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+        let mut compiler = Compiler::new();
+        compiler.compile(&program).unwrap();
+        let mut vm = Vm::new(compiler.bytecode());
+        vm.set_options(options);
+        let ret = vm
+            .run()
+            .map(|_| vm.last_popped_stack_elem().clone())
+            .map_err(|e| e.to_string());
+        assert_eq!(ret, expect.map_err(|e| e.to_string()), "{}", input);
+    }
+}
+
+#[cfg(test)]
+fn run_vm_with(input: &str, options: VmOptions) -> (String, String) {
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+    let mut compiler = Compiler::new();
+    compiler.compile(&program).unwrap();
+    let mut vm = Vm::new(compiler.bytecode());
+    vm.set_options(options);
+    let stdout = OutputBuffer::new();
+    let stderr = OutputBuffer::new();
+    vm.set_output(Output {
+        stdout: Sink::new(stdout.clone()),
+        stderr: Sink::new(stderr.clone()),
+    });
+    vm.run().unwrap();
+    (stdout.contents(), stderr.contents())
+}
+
+#[test]
+fn test_builtin_output() {
+    let (stdout, stderr) = run_vm_with("puts(1 + 2); eputs(\"oops\")", VmOptions::default());
+    assert_eq!(stdout, "3\n");
+    assert_eq!(stderr, "oops\n");
+}
+
+#[test]
+fn test_trace() {
+    let options = VmOptions {
+        trace: true,
+        ..Default::default()
+    };
+    let (_, trace) = run_vm_with("let f = fn(a) { a * 2 }; f(3)", options);
+    let expect = "\
+[frame 0] 0000 OpClosure 1 0 ; fn 1             top: <function>
+[frame 0] 0004 OpSetGlobal 0                    top: <empty>
+[frame 0] 0007 OpGetGlobal 0                    top: <function>
+[frame 0] 0010 OpConstant 2 ; 3                 top: 3
+[frame 0] 0013 OpCall 1                         top: 3
+[frame 1] 0000 OpGetLocal 0                     top: 3
+[frame 1] 0002 OpConstant 0 ; 2                 top: 2
+[frame 1] 0005 OpMul                            top: 6
+[frame 1] 0006 OpReturnValue                    top: 6
+[frame 0] 0015 OpPop                            top: <empty>
+";
+    assert_eq!(trace, expect);
+}
+
+#[test]
+fn test_vm_cancel() {
+    let cancel = CancelHandle::default();
+    let run = |input: &str| {
+        let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+        let mut compiler = Compiler::new();
+        compiler.compile(&program).unwrap();
+        let mut vm = Vm::new(compiler.bytecode());
+        vm.set_options(VmOptions {
+            cancel: cancel.clone(),
+            ..Default::default()
+        });
+        vm.run().map(|_| vm.last_popped_stack_elem().clone())
+    };
+
+    // sent before the run, it stops the next one only
+    cancel.cancel();
+    assert_eq!(run("1 + 2").unwrap_err().to_string(), "script cancelled");
+    assert_eq!(run("1 + 2").unwrap(), ObjectWrapper::Integer(3));
+}
@@ -0,0 +1,77 @@
+use crate::diagnostics;
+use crate::eval::capabilities::Capabilities;
+use crate::eval::evaluator::EvalOptions;
+use crate::eval::output::OutputBuffer;
+use crate::eval::printer::ObjectPrinter;
+use crate::eval::ObjectWrapper;
+use crate::lexer::lexer::Lexer;
+use crate::parser::Parser;
+use wasm_bindgen::prelude::*;
+
+#[cfg(test)]
+mod test;
+
+// Browsers have no files, processes, environment or stdin, and the std
+// clock and random seed panic on wasm32-unknown-unknown, so scripts only
+// get their output, which is captured.
+const CAPABILITIES: Capabilities = Capabilities::OUTPUT;
+
+// the program in `source` as json, or the rendered parse error
+#[wasm_bindgen]
+pub fn parse(source: &str) -> Result<String, String> {
+    match Parser::new(Lexer::new(source)).parse_program() {
+        Ok(program) => Ok(serde_json::to_string(&program).unwrap()),
+        Err(e) => Err(e.render(source, None)),
+    }
+}
+
+// An interpreter for the playground, keeping its bindings between `eval`
+// calls like the REPL. What scripts print is kept until `take_output`.
+#[wasm_bindgen(js_name = Interpreter)]
+pub struct WasmInterpreter {
+    interpreter: crate::Interpreter,
+    output: OutputBuffer,
+}
+
+#[wasm_bindgen(js_class = Interpreter)]
+impl WasmInterpreter {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        let mut interpreter = crate::Interpreter::with_options(EvalOptions {
+            capabilities: CAPABILITIES,
+            ..Default::default()
+        });
+        let output = OutputBuffer::new();
+        interpreter.set_stdout(output.clone());
+        interpreter.set_stderr(output.clone());
+        WasmInterpreter {
+            interpreter,
+            output,
+        }
+    }
+
+    // the value of `source` printed as in the REPL, or the rendered error
+    pub fn eval(&mut self, source: &str) -> Result<String, String> {
+        match self.interpreter.eval(source) {
+            Ok(ObjectWrapper::ErrorObject(e)) => {
+                Err(diagnostics::render_message("error", &e, None))
+            }
+            Ok(obj) => Ok(ObjectPrinter::new().print(&obj)),
+            Err(e) => Err(e.render(source, None)),
+        }
+    }
+
+    // what was printed since the last call
+    #[wasm_bindgen(js_name = takeOutput)]
+    pub fn take_output(&mut self) -> String {
+        let ret = self.output.contents();
+        self.output.clear();
+        ret
+    }
+}
+
+impl Default for WasmInterpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
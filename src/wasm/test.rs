@@ -0,0 +1,43 @@
+use crate::wasm::{parse, WasmInterpreter};
+
+#[test]
+fn test_parse() {
+    let json = parse("let a = 1;").unwrap();
+    assert!(
+        json.starts_with("{\"statements\":[{\"LetStatement\""),
+        "{}",
+        json
+    );
+    let e = parse("let = 1;").unwrap_err();
+    assert!(
+        e.starts_with("error: not a ident token\n  --> 1:1"),
+        "{}",
+        e
+    );
+}
+
+#[test]
+fn test_wasm_interpreter() {
+    let mut interpreter = WasmInterpreter::new();
+    assert_eq!(
+        interpreter.eval("let add = fn(a, b) { a + b }; puts(add(1, 2));"),
+        Ok("null".to_string())
+    );
+    assert_eq!(
+        interpreter.eval("add(\"a\", \"b\")"),
+        Ok("\"ab\"".to_string())
+    );
+    assert_eq!(interpreter.take_output(), "3\n");
+    assert_eq!(interpreter.take_output(), "");
+
+    let cases = [
+        "1 / 0",
+        "time()",
+        "read_file(\"/etc/passwd\")",
+        "random()",
+        "let = 1;",
+    ];
+    for input in cases {
+        assert!(interpreter.eval(input).is_err(), "{}", input);
+    }
+}
@@ -0,0 +1,66 @@
+// The exit status of the `monkey_rust` binary, as its `--help` documents it.
+use std::path::PathBuf;
+use std::process::Command;
+
+// `source` written to a file of its own for `name`
+fn script(name: &str, source: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("monkey_cli_{}_{}", std::process::id(), name));
+    std::fs::write(&path, source).unwrap();
+    path
+}
+
+fn status(args: &[&str]) -> Option<i32> {
+    Command::new(env!("CARGO_BIN_EXE_monkey_rust"))
+        .args(args)
+        .output()
+        .unwrap()
+        .status
+        .code()
+}
+
+#[test]
+fn test_exit_codes() {
+    let ok = script("ok.mk", "1 + 1");
+    let runtime = script("runtime.mk", "let x = 1; x()");
+    let returned = script("returned.mk", "error(\"boom\")");
+    let exited = script("exited.mk", "exit(5)");
+    let unparsed = script("unparsed.mk", "let = 1;");
+    let undefined = script("undefined.mk", "let f = fn() { y }; 1");
+    let out = std::env::temp_dir().join(format!("monkey_cli_{}.monkeyc", std::process::id()));
+    let missing = std::env::temp_dir().join("monkey_cli_missing.mk");
+    let path = |path: &PathBuf| path.to_str().unwrap().to_string();
+    let cases = [
+        (vec!["run".to_string(), path(&ok)], 0),
+        (vec![path(&ok)], 0),
+        (vec!["run".to_string(), path(&runtime)], 1),
+        (vec!["run".to_string(), path(&returned)], 1),
+        (vec!["run".to_string(), path(&exited)], 5),
+        (vec!["run".to_string(), path(&unparsed)], 2),
+        // fails once `f` is called without --strict
+        (vec!["run".to_string(), path(&undefined)], 0),
+        (
+            vec!["run".to_string(), "--strict".to_string(), path(&undefined)],
+            2,
+        ),
+        (
+            vec![
+                "build".to_string(),
+                path(&undefined),
+                "-o".to_string(),
+                path(&out),
+            ],
+            2,
+        ),
+        (vec!["run".to_string(), path(&missing)], 3),
+        (vec!["build".to_string(), path(&missing)], 3),
+        (vec!["exec".to_string(), path(&missing)], 3),
+        (vec!["run".to_string(), "--no-such-flag".to_string()], 3),
+    ];
+    for (args, expect) in cases {
+        let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(status(&args), Some(expect), "{:?}", args);
+    }
+    for path in [ok, runtime, returned, exited, unparsed, undefined] {
+        std::fs::remove_file(path).unwrap();
+    }
+}